@@ -0,0 +1,124 @@
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::http::StatusCode;
+use actix_web::rt::time;
+use actix_web::web::Bytes;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use futures::{stream, StreamExt};
+use std::time::Duration;
+
+use libfinnkino_actix::extract::{LastEventId, PathId};
+use libfinnkino_actix::{get_areas, get_event, get_show, get_shows, get_upcoming_shows};
+use libfinnkino_core::api::v1;
+use libfinnkino_core::ical::Calendar;
+use libfinnkino_core::id::{EventId, ShowId};
+use libfinnkino_core::live::{self, Hub};
+use libfinnkino_core::schedule::ShowFilter;
+
+use crate::response::{calendar_response, error_response, json_response};
+
+#[get("/areas")]
+async fn areas() -> impl Responder {
+  match get_areas().await {
+    Err(error) => error_response(error),
+    Ok(areas) => {
+      let areas: Vec<v1::Area> = areas.into_iter().map(v1::Area::from).collect();
+      json_response(StatusCode::OK, &areas)
+    }
+  }
+}
+
+#[get("/shows")]
+async fn shows(req: HttpRequest) -> impl Responder {
+  let filter = match ShowFilter::from_query(req.query_string()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(filter) => filter,
+  };
+  match get_shows(&filter).await {
+    Err(error) => error_response(error),
+    Ok(shows) => {
+      let shows: Vec<v1::Show> = shows.into_iter().map(v1::Show::from).collect();
+      json_response(StatusCode::OK, &shows)
+    }
+  }
+}
+
+#[get("/shows/stream")]
+async fn shows_stream(
+  req: HttpRequest,
+  last_event_id: LastEventId,
+  hub: web::Data<Hub>,
+) -> impl Responder {
+  let area = match live::parse_query(req.query_string()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(area) => area,
+  };
+  let LastEventId(last_event_id) = last_event_id;
+  let (backlog, receiver) = hub.subscribe(area, last_event_id);
+  if !hub.has_schedule(area) {
+    match get_shows(&live::filter(area)).await {
+      Err(error) => return error_response(error),
+      Ok(current) => hub.update(area, current),
+    }
+  }
+  let changes = stream::iter(backlog)
+    .chain(receiver)
+    .map(|change| change.to_sse());
+  let interval = time::interval(Duration::from_secs(live::HEARTBEAT_SECS));
+  let heartbeats = stream::unfold(interval, |mut interval| async move {
+    interval.tick().await;
+    Some((live::HEARTBEAT.to_string(), interval))
+  })
+  .skip(1);
+  let events = stream::select(changes, heartbeats)
+    .map(|message| Ok::<_, actix_web::Error>(Bytes::from(message)));
+  HttpResponse::Ok()
+    .content_type(live::CONTENT_TYPE)
+    .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+    .streaming(events)
+}
+
+#[get("/shows.ics")]
+async fn shows_calendar(req: HttpRequest) -> impl Responder {
+  let filter = match ShowFilter::from_query(req.query_string()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(filter) => filter,
+  };
+  match get_shows(&filter).await {
+    Err(error) => error_response(error),
+    Ok(matching) => calendar_response(&Calendar::new(matching)),
+  }
+}
+
+#[get("/shows/{id}.ics")]
+async fn show_calendar(id: PathId<ShowId>) -> impl Responder {
+  let PathId(id) = id;
+  match get_show(id).await {
+    Err(error) => error_response(error),
+    Ok(show) => calendar_response(&Calendar::new(vec![show])),
+  }
+}
+
+#[get("/events/{id}")]
+async fn event(id: PathId<EventId>) -> impl Responder {
+  let PathId(id) = id;
+  match futures::try_join!(get_event(id), get_upcoming_shows(id)) {
+    Err(error) => error_response(error),
+    Ok((details, upcoming)) => {
+      json_response(StatusCode::OK, &v1::EventDetail::new(details, upcoming))
+    }
+  }
+}
+
+pub fn v1(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(areas)
+    .service(shows)
+    .service(shows_stream)
+    .service(shows_calendar)
+    .service(show_calendar)
+    .service(event)
+    .configure(crate::webhooks::configure)
+    .configure(crate::watchlists::configure)
+    .configure(crate::archive::configure)
+    .configure(crate::changes::configure);
+}
@@ -0,0 +1,59 @@
+use actix_web::http::StatusCode;
+use actix_web::rt::time;
+use actix_web::{get, web, HttpRequest, Responder};
+use std::time::Duration;
+
+use libfinnkino_actix::extract::PathId;
+use libfinnkino_actix::{get_areas, get_events, get_shows};
+use libfinnkino_core::archive::{self, ArchiveQuery};
+use libfinnkino_core::events::EventQuery;
+use libfinnkino_core::id::EventId;
+use libfinnkino_core::storage::{database_error, not_found, Store};
+use libfinnkino_core::time::{now, today};
+
+use crate::response::json_response;
+
+#[get("/archive/shows")]
+async fn archived_shows(req: HttpRequest, store: web::Data<Store>) -> impl Responder {
+  let query = match ArchiveQuery::from_query(req.query_string()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(query) => query,
+  };
+  match store.archived_shows(&query) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(found) => json_response(StatusCode::OK, &found),
+  }
+}
+
+#[get("/archive/events/{id}/screenings")]
+async fn screenings(id: PathId<EventId>, store: web::Data<Store>) -> impl Responder {
+  let PathId(id) = id;
+  match store.screenings(id) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(None) => json_response(StatusCode::NOT_FOUND, &not_found("archived event", id)),
+    Ok(Some(found)) => json_response(StatusCode::OK, &found),
+  }
+}
+
+/// Snapshots today's Schedule of every area and the Events feed into the
+/// archive. Feeds that fail to load are skipped until the next round.
+pub async fn poll(store: web::Data<Store>) {
+  let mut interval = time::interval(Duration::from_secs(archive::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    if let Ok(areas) = get_areas().await {
+      for area in areas {
+        if let Ok(shows) = get_shows(&archive::filter(area.id, today())).await {
+          let _ = store.archive_shows(area.id, &shows, now());
+        }
+      }
+    }
+    if let Ok(events) = get_events(&EventQuery::default()).await {
+      let _ = store.archive_events(&events, now());
+    }
+  }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+  cfg.service(archived_shows).service(screenings);
+}
@@ -0,0 +1,54 @@
+use actix_web::{get, web, Responder};
+
+use libfinnkino_actix::extract::PathId;
+use libfinnkino_actix::{get_areas, get_event, get_shows};
+use libfinnkino_core::finnkino::{ErrorBuilder, ErrorKind};
+use libfinnkino_core::ical::Calendar;
+use libfinnkino_core::id::{AreaId, EventId};
+use libfinnkino_core::subscription::{Subscription, Tracker};
+use libfinnkino_core::time::{now, today};
+
+use crate::response::{calendar_response, error_response};
+
+#[get("/area/{id}.ics")]
+async fn area_calendar(id: PathId<AreaId>, tracker: web::Data<Tracker>) -> impl Responder {
+  let PathId(id) = id;
+  let subscription = Subscription::Area(id);
+  let filter = subscription.filter(today());
+  match futures::try_join!(get_areas(), get_shows(&filter)) {
+    Err(error) => error_response(error),
+    Ok((areas, matching)) => match areas.into_iter().find(|area| area.id == id) {
+      None => error_response(
+        ErrorBuilder::default()
+          .kind(ErrorKind::Status)
+          .status("404")
+          .title("Not Found")
+          .detail(format!("No area with id {}", id))
+          .build()
+          .unwrap(),
+      ),
+      Some(area) => calendar_response(&Calendar {
+        name: Some(area.name),
+        ..tracker.update(subscription, matching, now())
+      }),
+    },
+  }
+}
+
+#[get("/event/{id}.ics")]
+async fn event_calendar(id: PathId<EventId>, tracker: web::Data<Tracker>) -> impl Responder {
+  let PathId(id) = id;
+  let subscription = Subscription::Event(id);
+  let filter = subscription.filter(today());
+  match futures::try_join!(get_event(id), get_shows(&filter)) {
+    Err(error) => error_response(error),
+    Ok((details, matching)) => calendar_response(&Calendar {
+      name: Some(details.title),
+      ..tracker.update(subscription, matching, now())
+    }),
+  }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+  cfg.service(area_calendar).service(event_calendar);
+}
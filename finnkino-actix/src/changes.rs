@@ -0,0 +1,51 @@
+use actix_web::http::StatusCode;
+use actix_web::rt::time;
+use actix_web::{get, web, HttpRequest, Responder};
+use std::time::Duration;
+
+use libfinnkino_actix::{get_areas, get_shows};
+use libfinnkino_core::api::v1;
+use libfinnkino_core::changes::{self, ChangesQuery};
+use libfinnkino_core::storage::{database_error, not_found, Store};
+use libfinnkino_core::time::{now, today};
+
+use crate::response::json_response;
+
+#[get("/shows/changes")]
+async fn show_changes(req: HttpRequest, store: web::Data<Store>) -> impl Responder {
+  let query = match ChangesQuery::from_query(req.query_string()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(query) => query,
+  };
+  match store.schedule_changes(&query) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(None) => json_response(
+      StatusCode::NOT_FOUND,
+      &not_found("schedule snapshot of area", query.area),
+    ),
+    Ok(Some(found)) => json_response(StatusCode::OK, &found),
+  }
+}
+
+/// Snapshots the coming week of every area. Areas whose Schedule fails to
+/// load are skipped until the next round.
+pub async fn poll(store: web::Data<Store>) {
+  let mut interval = time::interval(Duration::from_secs(changes::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    let areas = match get_areas().await {
+      Err(_) => continue,
+      Ok(areas) => areas,
+    };
+    for area in areas {
+      if let Ok(shows) = get_shows(&changes::filter(area.id, today())).await {
+        let shows: Vec<v1::Show> = shows.into_iter().map(v1::Show::from).collect();
+        let _ = store.snapshot_schedule(area.id, &shows, now());
+      }
+    }
+  }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+  cfg.service(show_changes);
+}
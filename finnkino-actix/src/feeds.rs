@@ -0,0 +1,59 @@
+use actix_web::{get, web, HttpResponse, Responder};
+
+use libfinnkino_actix::{get_coming_soon, get_news};
+use libfinnkino_core::feeds::{Feed, Revisions, ATOM_CONTENT_TYPE, RSS_CONTENT_TYPE};
+use libfinnkino_core::time::now;
+
+use crate::response::error_response;
+
+fn atom_response(feed: &Feed) -> HttpResponse {
+  HttpResponse::Ok()
+    .content_type(ATOM_CONTENT_TYPE)
+    .body(feed.to_atom())
+}
+
+fn rss_response(feed: &Feed) -> HttpResponse {
+  HttpResponse::Ok()
+    .content_type(RSS_CONTENT_TYPE)
+    .body(feed.to_rss())
+}
+
+#[get("/coming-soon.atom")]
+async fn coming_soon_atom(revisions: web::Data<Revisions>) -> impl Responder {
+  match get_coming_soon().await {
+    Err(error) => error_response(error),
+    Ok(events) => atom_response(&Feed::coming_soon(events, &revisions, now())),
+  }
+}
+
+#[get("/coming-soon.rss")]
+async fn coming_soon_rss(revisions: web::Data<Revisions>) -> impl Responder {
+  match get_coming_soon().await {
+    Err(error) => error_response(error),
+    Ok(events) => rss_response(&Feed::coming_soon(events, &revisions, now())),
+  }
+}
+
+#[get("/news.atom")]
+async fn news_atom() -> impl Responder {
+  match get_news().await {
+    Err(error) => error_response(error),
+    Ok(articles) => atom_response(&Feed::news(articles, now())),
+  }
+}
+
+#[get("/news.rss")]
+async fn news_rss() -> impl Responder {
+  match get_news().await {
+    Err(error) => error_response(error),
+    Ok(articles) => rss_response(&Feed::news(articles, now())),
+  }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(coming_soon_atom)
+    .service(coming_soon_rss)
+    .service(news_atom)
+    .service(news_rss);
+}
@@ -0,0 +1,36 @@
+use actix_web::http::header::ContentType;
+use actix_web::http::StatusCode;
+use actix_web::{get, post, web, HttpResponse, Responder};
+use std::sync::Arc;
+
+use libfinnkino_actix::graphql::Upstream;
+use libfinnkino_core::graphql::{self, GraphiQLSource, Schema};
+
+use crate::response::json_response;
+
+pub fn schema() -> Schema {
+  graphql::schema(Arc::new(Upstream), |future| {
+    actix_web::rt::spawn(future);
+  })
+}
+
+#[get("/graphql")]
+async fn graphiql() -> impl Responder {
+  HttpResponse::Ok()
+    .content_type(ContentType::html())
+    .body(GraphiQLSource::build().endpoint(graphql::PATH).finish())
+}
+
+#[post("/graphql")]
+async fn execute(schema: web::Data<Schema>, body: web::Bytes) -> impl Responder {
+  let request = match graphql::parse_request(&body) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(request) => request,
+  };
+  let response = schema.execute(request).await;
+  json_response(StatusCode::OK, &response)
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+  cfg.service(graphiql).service(execute);
+}
@@ -0,0 +1,20 @@
+use actix_web::rt::time;
+use actix_web::web;
+use std::time::Duration;
+
+use libfinnkino_actix::{get_shows, uncached};
+use libfinnkino_core::live::{self, Hub};
+
+/// Polls the schedules of the areas that have subscribers. Failed polls are
+/// skipped until the next round.
+pub async fn poll(hub: web::Data<Hub>) {
+  let mut interval = time::interval(Duration::from_secs(live::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    for area in hub.watched() {
+      if let Ok(current) = uncached(get_shows(&live::filter(area))).await {
+        hub.update(area, current);
+      }
+    }
+  }
+}
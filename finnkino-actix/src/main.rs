@@ -1,39 +1,99 @@
-use actix_web::{get, http::header::ContentType, App, HttpResponse, HttpServer, Responder};
+use actix_web::dev::Service;
+use actix_web::http::header::{HeaderName, HeaderValue, LINK};
+use actix_web::{web, App, HttpServer};
+use futures::FutureExt;
 
-use libfinnkino_core::json::Errors as JsonErrors;
-use libfinnkino_actix::get_areas;
+use libfinnkino_core::api::{successor_link, DEPRECATION, SUNSET, UNVERSIONED_PREFIX, V1_PREFIX};
+use libfinnkino_core::config::{self, Config};
+use libfinnkino_core::feeds::Revisions;
+use libfinnkino_core::fixtures::Fixtures;
+use libfinnkino_core::live::Hub;
+use libfinnkino_core::storage::Store;
+use libfinnkino_core::subscription::Tracker;
+use libfinnkino_core::topics::Broker;
+use libfinnkino_core::webhooks::Webhooks;
 
-#[get("/")]
-async fn index() -> impl Responder {
-  HttpResponse::Ok().body("Hello world!")
-}
-
-#[get("/api/areas")]
-async fn areas() -> impl Responder {
-  match get_areas().await {
-    Err(error) => {
-      let errors = JsonErrors::from(error);
-      match serde_json::to_string(&errors) {
-        Err(error) => HttpResponse::InternalServerError().body(error.to_string()),
-        Ok(json) => HttpResponse::Ok()
-          .content_type(ContentType::json())
-          .body(json),
-      }
-    }
-    Ok(areas) => match serde_json::to_string(&areas) {
-      Err(error) => HttpResponse::InternalServerError().body(error.to_string()),
-      Ok(json) => HttpResponse::Ok()
-        .content_type(ContentType::json())
-        .body(json),
-    },
-  }
-}
+mod api;
+mod archive;
+mod calendars;
+mod changes;
+mod feeds;
+mod graphql;
+mod live;
+mod pages;
+mod response;
+mod watchlists;
+mod webhooks;
+mod websocket;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-  HttpServer::new(|| App::new().service(index).service(areas))
-    .workers(4)
-    .bind(("127.0.0.1", 8080))?
-    .run()
-    .await
+  let config = Config::load().map_err(std::io::Error::other)?;
+  let port = config::port().map_err(std::io::Error::other)?;
+  if let Some(dir) = config.fixtures() {
+    libfinnkino_actix::use_fixtures(Fixtures::new(dir));
+  }
+  if let Some(recording) = &config.recording {
+    libfinnkino_actix::use_recorder(recording.recorder());
+  }
+  if let Some(upstream) = config.upstream() {
+    libfinnkino_actix::use_upstream(upstream);
+  }
+  let tracker = web::Data::new(Tracker::default());
+  let revisions = web::Data::new(Revisions::default());
+  let schema = web::Data::new(graphql::schema());
+  let hub = web::Data::new(Hub::default());
+  actix_web::rt::spawn(live::poll(hub.clone()));
+  let broker = web::Data::new(Broker::default());
+  actix_web::rt::spawn(websocket::poll(broker.clone()));
+  let hooks = web::Data::new(Webhooks::default());
+  let store = web::Data::new(Store::open(config.database()).map_err(std::io::Error::other)?);
+  actix_web::rt::spawn(webhooks::poll(hooks.clone()));
+  actix_web::rt::spawn(archive::poll(store.clone()));
+  actix_web::rt::spawn(changes::poll(store.clone()));
+  HttpServer::new(move || {
+    App::new()
+      .app_data(tracker.clone())
+      .app_data(revisions.clone())
+      .app_data(schema.clone())
+      .app_data(hub.clone())
+      .app_data(broker.clone())
+      .app_data(hooks.clone())
+      .app_data(store.clone())
+      .configure(pages::configure)
+      .configure(graphql::configure)
+      .configure(websocket::configure)
+      .service(web::scope("/calendars").configure(calendars::configure))
+      .service(web::scope("/feeds").configure(feeds::configure))
+      .service(web::scope(V1_PREFIX).configure(api::v1))
+      .service(
+        web::scope(UNVERSIONED_PREFIX)
+          .wrap_fn(|req, srv| {
+            let link = successor_link(req.path());
+            srv.call(req).map(|result| {
+              result.map(|mut res| {
+                let headers = res.headers_mut();
+                headers.insert(
+                  HeaderName::from_static("deprecation"),
+                  HeaderValue::from_static(DEPRECATION),
+                );
+                headers.insert(
+                  HeaderName::from_static("sunset"),
+                  HeaderValue::from_static(SUNSET),
+                );
+                if let Some(value) = link.and_then(|link| HeaderValue::from_str(&link).ok()) {
+                  headers.insert(LINK, value);
+                }
+                res
+              })
+            })
+          })
+          .configure(api::v1),
+      )
+      .default_service(web::to(response::not_found))
+  })
+  .workers(4)
+  .bind(("127.0.0.1", port.unwrap_or(8080)))?
+  .run()
+  .await
 }
@@ -0,0 +1,105 @@
+use actix_web::http::StatusCode;
+use actix_web::{get, web, HttpRequest, Responder};
+
+use libfinnkino_actix::extract::{ApiError, PathId};
+use libfinnkino_actix::{get_areas, get_event, get_shows, get_upcoming_shows};
+use libfinnkino_core::finnkino::Error;
+use libfinnkino_core::html::{FilmPage, IndexPage, SchedulePage, WatchlistPage};
+use libfinnkino_core::id::{EventId, WatchlistId};
+use libfinnkino_core::json::{self, Errors as JsonErrors};
+use libfinnkino_core::schedule::ShowFilter;
+use libfinnkino_core::storage::{database_error, not_found, Store, Watchlist, WEEK_DAYS};
+use libfinnkino_core::time::today;
+
+use crate::response::{error_status, html_error_response, html_response};
+
+fn upstream_error_page(error: Error) -> actix_web::HttpResponse {
+  html_error_response(error_status(&error), &JsonErrors::from(error))
+}
+
+#[get("/")]
+async fn index() -> impl Responder {
+  match get_areas().await {
+    Err(error) => upstream_error_page(error),
+    Ok(areas) => html_response(
+      StatusCode::OK,
+      &IndexPage {
+        areas,
+        today: today(),
+      },
+    ),
+  }
+}
+
+#[get("/schedule")]
+async fn schedule(req: HttpRequest) -> impl Responder {
+  let mut filter = match ShowFilter::from_query(req.query_string()) {
+    Err(errors) => return html_error_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(filter) => filter,
+  };
+  let area_id = match filter.area {
+    None => {
+      let errors = JsonErrors {
+        errors: vec![json::Error::invalid_parameter("area", "Area is required")],
+      };
+      return html_error_response(StatusCode::BAD_REQUEST, &errors);
+    }
+    Some(area_id) => area_id,
+  };
+  let date = *filter.date.get_or_insert_with(today);
+  match futures::try_join!(get_areas(), get_shows(&filter)) {
+    Err(error) => upstream_error_page(error),
+    Ok((areas, shows)) => match areas.iter().find(|area| area.id == area_id) {
+      None => {
+        let errors = JsonErrors {
+          errors: vec![json::Error::invalid_parameter("area", "Unknown area")],
+        };
+        html_error_response(StatusCode::NOT_FOUND, &errors)
+      }
+      Some(area) => html_response(StatusCode::OK, &SchedulePage::new(area, date, shows)),
+    },
+  }
+}
+
+#[get("/films/{id}")]
+async fn film(id: Result<PathId<EventId>, ApiError>) -> impl Responder {
+  let id = match id {
+    Err(error) => return html_error_response(error.status, &error.errors),
+    Ok(PathId(id)) => id,
+  };
+  match futures::try_join!(get_event(id), get_upcoming_shows(id)) {
+    Err(error) => upstream_error_page(error),
+    Ok((event, upcoming)) => html_response(StatusCode::OK, &FilmPage::new(event, upcoming)),
+  }
+}
+
+#[get("/watchlists/{id}")]
+async fn watchlist(
+  id: Result<PathId<WatchlistId>, ApiError>,
+  store: web::Data<Store>,
+) -> impl Responder {
+  let id = match id {
+    Err(error) => return html_error_response(error.status, &error.errors),
+    Ok(PathId(id)) => id,
+  };
+  let found = match store.watchlist(id) {
+    Err(error) => {
+      return html_error_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error))
+    }
+    Ok(None) => return html_error_response(StatusCode::NOT_FOUND, &not_found("watchlist", id)),
+    Ok(Some(found)) => found,
+  };
+  let date = today();
+  match get_shows(&Watchlist::filter(date, WEEK_DAYS)).await {
+    Err(error) => upstream_error_page(error),
+    Ok(shows) => html_response(StatusCode::OK, &WatchlistPage::new(&found, date, shows)),
+  }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(index)
+    .service(schedule)
+    .service(film)
+    .service(watchlist);
+}
@@ -0,0 +1,53 @@
+use actix_web::http::header::ContentType;
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use serde::Serialize;
+
+use libfinnkino_core::finnkino::{self, Error};
+use libfinnkino_core::html::{ErrorPage, Template};
+use libfinnkino_core::ical::{self, Calendar};
+use libfinnkino_core::json::{self, Errors as JsonErrors};
+
+pub fn json_response<T: Serialize>(status: StatusCode, value: &T) -> HttpResponse {
+  match serde_json::to_string(value) {
+    Err(error) => HttpResponse::InternalServerError().body(error.to_string()),
+    Ok(json) => HttpResponse::build(status)
+      .content_type(ContentType::json())
+      .body(json),
+  }
+}
+
+/// The answer to requests no route matches.
+pub async fn not_found() -> HttpResponse {
+  let errors = JsonErrors {
+    errors: vec![json::Error::from_status(404, "Not Found")],
+  };
+  json_response(StatusCode::NOT_FOUND, &errors)
+}
+
+pub fn error_status(error: &Error) -> StatusCode {
+  StatusCode::from_u16(finnkino::error_status(error)).unwrap()
+}
+
+pub fn error_response(error: Error) -> HttpResponse {
+  json_response(error_status(&error), &JsonErrors::upstream(error))
+}
+
+pub fn html_response<T: Template>(status: StatusCode, page: &T) -> HttpResponse {
+  match page.render() {
+    Err(error) => HttpResponse::InternalServerError().body(error.to_string()),
+    Ok(html) => HttpResponse::build(status)
+      .content_type(ContentType::html())
+      .body(html),
+  }
+}
+
+pub fn html_error_response(status: StatusCode, errors: &JsonErrors) -> HttpResponse {
+  html_response(status, &ErrorPage::from(errors))
+}
+
+pub fn calendar_response(calendar: &Calendar) -> HttpResponse {
+  HttpResponse::Ok()
+    .content_type(ical::CONTENT_TYPE)
+    .body(calendar.to_string())
+}
@@ -0,0 +1,151 @@
+use actix_web::http::StatusCode;
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+
+use libfinnkino_actix::extract::PathId;
+use libfinnkino_actix::get_shows;
+use libfinnkino_core::api::v1;
+use libfinnkino_core::finnkino::Show;
+use libfinnkino_core::id::{SearchId, WatchlistId};
+use libfinnkino_core::schedule::UPCOMING_DAYS;
+use libfinnkino_core::storage::{
+  database_error, not_found, NewSearch, NewWatchlist, OwnerQuery, Store, Watchlist,
+};
+use libfinnkino_core::time::{now, today};
+
+use crate::response::{error_response, json_response};
+
+fn shows_response(shows: Vec<Show>) -> HttpResponse {
+  let shows: Vec<v1::Show> = shows.into_iter().map(v1::Show::from).collect();
+  json_response(StatusCode::OK, &shows)
+}
+
+#[post("/watchlists")]
+async fn create_watchlist(body: web::Bytes, store: web::Data<Store>) -> impl Responder {
+  let new = match NewWatchlist::from_json(&body) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(new) => new,
+  };
+  match store.create_watchlist(new, now()) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(created) => json_response(StatusCode::CREATED, &created),
+  }
+}
+
+#[get("/watchlists")]
+async fn list_watchlists(req: HttpRequest, store: web::Data<Store>) -> impl Responder {
+  let query = match OwnerQuery::from_query(req.query_string()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(query) => query,
+  };
+  match store.watchlists(query.owner.as_deref()) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(found) => json_response(StatusCode::OK, &found),
+  }
+}
+
+#[get("/watchlists/{id}")]
+async fn watchlist(id: PathId<WatchlistId>, store: web::Data<Store>) -> impl Responder {
+  let PathId(id) = id;
+  match store.watchlist(id) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(None) => json_response(StatusCode::NOT_FOUND, &not_found("watchlist", id)),
+    Ok(Some(found)) => json_response(StatusCode::OK, &found),
+  }
+}
+
+#[delete("/watchlists/{id}")]
+async fn delete_watchlist(id: PathId<WatchlistId>, store: web::Data<Store>) -> impl Responder {
+  let PathId(id) = id;
+  match store.delete_watchlist(id) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(false) => json_response(StatusCode::NOT_FOUND, &not_found("watchlist", id)),
+    Ok(true) => HttpResponse::NoContent().finish(),
+  }
+}
+
+#[get("/watchlists/{id}/shows")]
+async fn watchlist_shows(id: PathId<WatchlistId>, store: web::Data<Store>) -> impl Responder {
+  let PathId(id) = id;
+  let found = match store.watchlist(id) {
+    Err(error) => return json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(None) => return json_response(StatusCode::NOT_FOUND, &not_found("watchlist", id)),
+    Ok(Some(found)) => found,
+  };
+  let filter = Watchlist::filter(today(), UPCOMING_DAYS);
+  match get_shows(&filter).await {
+    Err(error) => error_response(error),
+    Ok(upcoming) => shows_response(found.shows(upcoming)),
+  }
+}
+
+#[post("/searches")]
+async fn create_search(body: web::Bytes, store: web::Data<Store>) -> impl Responder {
+  let new = match NewSearch::from_json(&body) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(new) => new,
+  };
+  match store.create_search(new, now()) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(created) => json_response(StatusCode::CREATED, &created),
+  }
+}
+
+#[get("/searches")]
+async fn list_searches(req: HttpRequest, store: web::Data<Store>) -> impl Responder {
+  let query = match OwnerQuery::from_query(req.query_string()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(query) => query,
+  };
+  match store.searches(query.owner.as_deref()) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(found) => json_response(StatusCode::OK, &found),
+  }
+}
+
+#[get("/searches/{id}")]
+async fn search(id: PathId<SearchId>, store: web::Data<Store>) -> impl Responder {
+  let PathId(id) = id;
+  match store.search(id) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(None) => json_response(StatusCode::NOT_FOUND, &not_found("search", id)),
+    Ok(Some(found)) => json_response(StatusCode::OK, &found),
+  }
+}
+
+#[delete("/searches/{id}")]
+async fn delete_search(id: PathId<SearchId>, store: web::Data<Store>) -> impl Responder {
+  let PathId(id) = id;
+  match store.delete_search(id) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(false) => json_response(StatusCode::NOT_FOUND, &not_found("search", id)),
+    Ok(true) => HttpResponse::NoContent().finish(),
+  }
+}
+
+#[get("/searches/{id}/shows")]
+async fn search_shows(id: PathId<SearchId>, store: web::Data<Store>) -> impl Responder {
+  let PathId(id) = id;
+  let found = match store.search(id) {
+    Err(error) => return json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(None) => return json_response(StatusCode::NOT_FOUND, &not_found("search", id)),
+    Ok(Some(found)) => found,
+  };
+  match get_shows(&found.filter(today())).await {
+    Err(error) => error_response(error),
+    Ok(matching) => shows_response(matching),
+  }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(create_watchlist)
+    .service(list_watchlists)
+    .service(watchlist)
+    .service(delete_watchlist)
+    .service(watchlist_shows)
+    .service(create_search)
+    .service(list_searches)
+    .service(search)
+    .service(delete_search)
+    .service(search_shows);
+}
@@ -0,0 +1,89 @@
+use actix_web::http::StatusCode;
+use actix_web::rt::time;
+use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use std::time::Duration;
+
+use libfinnkino_actix::extract::PathId;
+use libfinnkino_actix::webhooks::deliver;
+use libfinnkino_actix::{get_coming_soon, get_shows, uncached};
+use libfinnkino_core::id::{EventId, WebhookId};
+use libfinnkino_core::time::{now, today};
+use libfinnkino_core::webhooks::{self, RegistrationRequest, Webhooks};
+
+use crate::response::json_response;
+
+#[post("/webhooks")]
+async fn create_webhook(body: web::Bytes, hooks: web::Data<Webhooks>) -> impl Responder {
+  match RegistrationRequest::from_json(&body) {
+    Err(errors) => json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(request) => json_response(StatusCode::CREATED, &hooks.register(request, now())),
+  }
+}
+
+#[get("/webhooks")]
+async fn list_webhooks(hooks: web::Data<Webhooks>) -> impl Responder {
+  json_response(StatusCode::OK, &hooks.registrations())
+}
+
+#[get("/webhooks/{id}")]
+async fn webhook(id: PathId<WebhookId>, hooks: web::Data<Webhooks>) -> impl Responder {
+  let PathId(id) = id;
+  match hooks.registration(id) {
+    None => json_response(StatusCode::NOT_FOUND, &webhooks::not_found(id)),
+    Some(registration) => json_response(StatusCode::OK, &registration),
+  }
+}
+
+#[delete("/webhooks/{id}")]
+async fn delete_webhook(id: PathId<WebhookId>, hooks: web::Data<Webhooks>) -> impl Responder {
+  let PathId(id) = id;
+  if hooks.unregister(id) {
+    HttpResponse::NoContent().finish()
+  } else {
+    json_response(StatusCode::NOT_FOUND, &webhooks::not_found(id))
+  }
+}
+
+#[get("/webhooks/{id}/deliveries")]
+async fn webhook_deliveries(id: PathId<WebhookId>, hooks: web::Data<Webhooks>) -> impl Responder {
+  let PathId(id) = id;
+  match hooks.deliveries(id) {
+    None => json_response(StatusCode::NOT_FOUND, &webhooks::not_found(id)),
+    Some(log) => json_response(StatusCode::OK, &log),
+  }
+}
+
+/// Polls the Schedules of the areas with webhooks and delivers the payloads
+/// in the background. Areas whose Schedule fails to load are skipped until
+/// the next round.
+pub async fn poll(hooks: web::Data<Webhooks>) {
+  let mut interval = time::interval(Duration::from_secs(webhooks::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    let areas = hooks.areas();
+    if areas.is_empty() {
+      continue;
+    }
+    let coming_soon: Vec<EventId> = match uncached(get_coming_soon()).await {
+      Err(_) => continue,
+      Ok(events) => events.into_iter().map(|event| event.id).collect(),
+    };
+    for area in areas {
+      if let Ok(current) = uncached(get_shows(&webhooks::filter(area, today()))).await {
+        for outgoing in hooks.update(area, current, &coming_soon, now()) {
+          let hooks = hooks.clone();
+          actix_web::rt::spawn(async move { deliver(&hooks, outgoing).await });
+        }
+      }
+    }
+  }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+  cfg
+    .service(create_webhook)
+    .service(list_webhooks)
+    .service(webhook)
+    .service(delete_webhook)
+    .service(webhook_deliveries);
+}
@@ -0,0 +1,136 @@
+use actix::{Actor, ActorContext, AsyncContext, StreamHandler};
+use actix_web::http::header::{HeaderValue, SEC_WEBSOCKET_VERSION};
+use actix_web::http::StatusCode;
+use actix_web::rt::time;
+use actix_web::{get, web, Error, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use futures::channel::mpsc::Receiver;
+use std::time::Duration;
+
+use libfinnkino_actix::{get_coming_soon, get_shows, get_upcoming_shows, uncached};
+use libfinnkino_core::live;
+use libfinnkino_core::topics::{self, Broker, Client, Resource, Topic};
+
+use crate::response::json_response;
+
+struct Session {
+  broker: web::Data<Broker>,
+  client: Client,
+  receiver: Option<Receiver<String>>,
+}
+
+impl Actor for Session {
+  type Context = ws::WebsocketContext<Self>;
+
+  fn started(&mut self, ctx: &mut Self::Context) {
+    if let Some(receiver) = self.receiver.take() {
+      ctx.add_stream(receiver);
+    }
+  }
+
+  fn stopped(&mut self, _: &mut Self::Context) {
+    self.broker.disconnect(&self.client);
+  }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for Session {
+  fn handle(&mut self, item: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+    match item {
+      Ok(ws::Message::Text(text)) => self.broker.receive(&self.client, &text),
+      Ok(ws::Message::Ping(bytes)) => ctx.pong(&bytes),
+      Ok(ws::Message::Close(reason)) => {
+        ctx.close(reason);
+        ctx.stop();
+      }
+      Ok(_) => {}
+      Err(_) => ctx.stop(),
+    }
+  }
+}
+
+impl StreamHandler<String> for Session {
+  fn handle(&mut self, message: String, ctx: &mut Self::Context) {
+    ctx.text(message);
+  }
+
+  // The broker ends the receiver of a client that falls behind.
+  fn finished(&mut self, ctx: &mut Self::Context) {
+    ctx.stop();
+  }
+}
+
+#[get("/ws")]
+async fn subscribe(
+  req: HttpRequest,
+  stream: web::Payload,
+  broker: web::Data<Broker>,
+) -> Result<HttpResponse, Error> {
+  let version = req.headers().get(SEC_WEBSOCKET_VERSION);
+  match ws::handshake(&req) {
+    // actix also takes the drafts 7 and 8 that RFC 6455 replaced.
+    Ok(_) if version.is_some_and(|version| version == topics::WEBSOCKET_VERSION) => {}
+    Ok(_) | Err(ws::HandshakeError::NoVersionHeader | ws::HandshakeError::UnsupportedVersion) => {
+      let mut response = json_response(StatusCode::UPGRADE_REQUIRED, &topics::version_error());
+      let version = HeaderValue::from_static(topics::WEBSOCKET_VERSION);
+      response
+        .headers_mut()
+        .insert(SEC_WEBSOCKET_VERSION, version);
+      return Ok(response);
+    }
+    Err(_) => {
+      return Ok(json_response(
+        StatusCode::BAD_REQUEST,
+        &topics::handshake_error(),
+      ))
+    }
+  }
+  let (client, receiver) = broker.connect();
+  let session = Session {
+    broker,
+    client,
+    receiver: Some(receiver),
+  };
+  ws::start(session, &req, stream)
+}
+
+async fn fetch(topic: Topic) -> Option<Vec<Resource>> {
+  let resources = match topic {
+    Topic::Area(area) => get_shows(&live::filter(area))
+      .await
+      .ok()?
+      .into_iter()
+      .map(Resource::from)
+      .collect(),
+    Topic::Event(id) => get_upcoming_shows(id)
+      .await
+      .ok()?
+      .into_iter()
+      .map(Resource::from)
+      .collect(),
+    Topic::ComingSoon => get_coming_soon()
+      .await
+      .ok()?
+      .into_iter()
+      .map(Resource::from)
+      .collect(),
+  };
+  Some(resources)
+}
+
+/// Polls the feeds of the topics that have subscribers. Failed polls are
+/// skipped until the next round.
+pub async fn poll(broker: web::Data<Broker>) {
+  let mut interval = time::interval(Duration::from_secs(live::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    for topic in broker.watched() {
+      if let Some(resources) = uncached(fetch(topic)).await {
+        broker.update(topic, resources);
+      }
+    }
+  }
+}
+
+pub fn configure(cfg: &mut web::ServiceConfig) {
+  cfg.service(subscribe);
+}
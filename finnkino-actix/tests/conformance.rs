@@ -0,0 +1,4 @@
+#[actix_rt::test]
+async fn test_conformance() {
+  libfinnkino_conformance::check(env!("CARGO_BIN_EXE_finnkino-actix")).await;
+}
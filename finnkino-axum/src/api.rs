@@ -0,0 +1,116 @@
+use axum::extract::{RawQuery, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use futures::{stream, StreamExt};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use libfinnkino_axum::extract::{CalendarId, LastEventId, PathId};
+use libfinnkino_axum::{get_areas, get_event, get_show, get_shows, get_upcoming_shows};
+use libfinnkino_core::api::v1;
+use libfinnkino_core::ical::Calendar;
+use libfinnkino_core::id::{EventId, ShowId};
+use libfinnkino_core::live::{self, Hub};
+use libfinnkino_core::schedule::ShowFilter;
+
+use crate::response::{calendar_response, error_response, json_response};
+use crate::AppState;
+
+async fn areas() -> Response {
+  match get_areas().await {
+    Err(error) => error_response(error),
+    Ok(areas) => {
+      let areas: Vec<v1::Area> = areas.into_iter().map(v1::Area::from).collect();
+      json_response(StatusCode::OK, &areas)
+    }
+  }
+}
+
+async fn shows(RawQuery(query): RawQuery) -> Response {
+  let filter = match ShowFilter::from_query(query.as_deref().unwrap_or_default()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(filter) => filter,
+  };
+  match get_shows(&filter).await {
+    Err(error) => error_response(error),
+    Ok(shows) => {
+      let shows: Vec<v1::Show> = shows.into_iter().map(v1::Show::from).collect();
+      json_response(StatusCode::OK, &shows)
+    }
+  }
+}
+
+async fn shows_stream(
+  RawQuery(query): RawQuery,
+  LastEventId(last_event_id): LastEventId,
+  State(hub): State<Arc<Hub>>,
+) -> Response {
+  let area = match live::parse_query(query.as_deref().unwrap_or_default()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(area) => area,
+  };
+  let (backlog, receiver) = hub.subscribe(area, last_event_id);
+  if !hub.has_schedule(area) {
+    match get_shows(&live::filter(area)).await {
+      Err(error) => return error_response(error),
+      Ok(current) => hub.update(area, current),
+    }
+  }
+  let events = stream::iter(backlog).chain(receiver).map(|change| {
+    Ok::<_, Infallible>(
+      Event::default()
+        .data(change.data())
+        .event(change.kind.as_str())
+        .id(change.id.to_string()),
+    )
+  });
+  let heartbeat = KeepAlive::new()
+    .interval(Duration::from_secs(live::HEARTBEAT_SECS))
+    .text("heartbeat");
+  Sse::new(events).keep_alive(heartbeat).into_response()
+}
+
+async fn shows_calendar(RawQuery(query): RawQuery) -> Response {
+  let filter = match ShowFilter::from_query(query.as_deref().unwrap_or_default()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(filter) => filter,
+  };
+  match get_shows(&filter).await {
+    Err(error) => error_response(error),
+    Ok(matching) => calendar_response(&Calendar::new(matching)),
+  }
+}
+
+async fn show_calendar(CalendarId(id): CalendarId<ShowId>) -> Response {
+  match get_show(id).await {
+    Err(error) => error_response(error),
+    Ok(show) => calendar_response(&Calendar::new(vec![show])),
+  }
+}
+
+async fn event(PathId(id): PathId<EventId>) -> Response {
+  match futures::try_join!(get_event(id), get_upcoming_shows(id)) {
+    Err(error) => error_response(error),
+    Ok((details, upcoming)) => {
+      json_response(StatusCode::OK, &v1::EventDetail::new(details, upcoming))
+    }
+  }
+}
+
+pub fn v1() -> Router<AppState> {
+  Router::new()
+    .route("/areas", get(areas))
+    .route("/shows", get(shows))
+    .route("/shows/stream", get(shows_stream))
+    .route("/shows.ics", get(shows_calendar))
+    .route("/shows/{id}", get(show_calendar))
+    .route("/events/{id}", get(event))
+    .merge(crate::webhooks::routes())
+    .merge(crate::watchlists::routes())
+    .merge(crate::archive::routes())
+    .merge(crate::changes::routes())
+}
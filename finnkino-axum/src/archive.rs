@@ -0,0 +1,63 @@
+use axum::extract::{RawQuery, State};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+use libfinnkino_axum::extract::PathId;
+use libfinnkino_axum::{get_areas, get_events, get_shows};
+use libfinnkino_core::archive::{self, ArchiveQuery};
+use libfinnkino_core::events::EventQuery;
+use libfinnkino_core::id::EventId;
+use libfinnkino_core::storage::{database_error, not_found, Store};
+use libfinnkino_core::time::{now, today};
+
+use crate::response::json_response;
+use crate::AppState;
+
+async fn archived_shows(RawQuery(query): RawQuery, State(store): State<Arc<Store>>) -> Response {
+  let query = match ArchiveQuery::from_query(query.as_deref().unwrap_or_default()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(query) => query,
+  };
+  match store.archived_shows(&query) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(found) => json_response(StatusCode::OK, &found),
+  }
+}
+
+async fn screenings(PathId(id): PathId<EventId>, State(store): State<Arc<Store>>) -> Response {
+  match store.screenings(id) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(None) => json_response(StatusCode::NOT_FOUND, &not_found("archived event", id)),
+    Ok(Some(found)) => json_response(StatusCode::OK, &found),
+  }
+}
+
+/// Snapshots today's Schedule of every area and the Events feed into the
+/// archive. Feeds that fail to load are skipped until the next round.
+pub async fn poll(store: Arc<Store>) {
+  let mut interval = time::interval(Duration::from_secs(archive::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    if let Ok(areas) = get_areas().await {
+      for area in areas {
+        if let Ok(shows) = get_shows(&archive::filter(area.id, today())).await {
+          let _ = store.archive_shows(area.id, &shows, now());
+        }
+      }
+    }
+    if let Ok(events) = get_events(&EventQuery::default()).await {
+      let _ = store.archive_events(&events, now());
+    }
+  }
+}
+
+pub fn routes() -> Router<AppState> {
+  Router::new()
+    .route("/archive/shows", get(archived_shows))
+    .route("/archive/events/{id}/screenings", get(screenings))
+}
@@ -0,0 +1,63 @@
+use axum::extract::State;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use std::sync::Arc;
+
+use libfinnkino_axum::extract::CalendarId;
+use libfinnkino_axum::{get_areas, get_event, get_shows};
+use libfinnkino_core::finnkino::{ErrorBuilder, ErrorKind};
+use libfinnkino_core::ical::Calendar;
+use libfinnkino_core::id::{AreaId, EventId};
+use libfinnkino_core::subscription::{Subscription, Tracker};
+use libfinnkino_core::time::{now, today};
+
+use crate::response::{calendar_response, error_response};
+use crate::AppState;
+
+async fn area_calendar(
+  CalendarId(id): CalendarId<AreaId>,
+  State(tracker): State<Arc<Tracker>>,
+) -> Response {
+  let subscription = Subscription::Area(id);
+  let filter = subscription.filter(today());
+  match futures::try_join!(get_areas(), get_shows(&filter)) {
+    Err(error) => error_response(error),
+    Ok((areas, shows)) => match areas.into_iter().find(|area| area.id == id) {
+      None => error_response(
+        ErrorBuilder::default()
+          .kind(ErrorKind::Status)
+          .status("404")
+          .title("Not Found")
+          .detail(format!("No area with id {}", id))
+          .build()
+          .unwrap(),
+      ),
+      Some(area) => calendar_response(&Calendar {
+        name: Some(area.name),
+        ..tracker.update(subscription, shows, now())
+      }),
+    },
+  }
+}
+
+async fn event_calendar(
+  CalendarId(id): CalendarId<EventId>,
+  State(tracker): State<Arc<Tracker>>,
+) -> Response {
+  let subscription = Subscription::Event(id);
+  let filter = subscription.filter(today());
+  match futures::try_join!(get_event(id), get_shows(&filter)) {
+    Err(error) => error_response(error),
+    Ok((event, shows)) => calendar_response(&Calendar {
+      name: Some(event.title),
+      ..tracker.update(subscription, shows, now())
+    }),
+  }
+}
+
+pub fn routes() -> Router<AppState> {
+  Router::new()
+    .route("/area/{id}", get(area_calendar))
+    .route("/event/{id}", get(event_calendar))
+}
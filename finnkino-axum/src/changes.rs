@@ -0,0 +1,55 @@
+use axum::extract::{RawQuery, State};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+use libfinnkino_axum::{get_areas, get_shows};
+use libfinnkino_core::api::v1;
+use libfinnkino_core::changes::{self, ChangesQuery};
+use libfinnkino_core::storage::{database_error, not_found, Store};
+use libfinnkino_core::time::{now, today};
+
+use crate::response::json_response;
+use crate::AppState;
+
+async fn show_changes(RawQuery(query): RawQuery, State(store): State<Arc<Store>>) -> Response {
+  let query = match ChangesQuery::from_query(query.as_deref().unwrap_or_default()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(query) => query,
+  };
+  match store.schedule_changes(&query) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(None) => json_response(
+      StatusCode::NOT_FOUND,
+      &not_found("schedule snapshot of area", query.area),
+    ),
+    Ok(Some(found)) => json_response(StatusCode::OK, &found),
+  }
+}
+
+/// Snapshots the coming week of every area. Areas whose Schedule fails to
+/// load are skipped until the next round.
+pub async fn poll(store: Arc<Store>) {
+  let mut interval = time::interval(Duration::from_secs(changes::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    let areas = match get_areas().await {
+      Err(_) => continue,
+      Ok(areas) => areas,
+    };
+    for area in areas {
+      if let Ok(shows) = get_shows(&changes::filter(area.id, today())).await {
+        let shows: Vec<v1::Show> = shows.into_iter().map(v1::Show::from).collect();
+        let _ = store.snapshot_schedule(area.id, &shows, now());
+      }
+    }
+  }
+}
+
+pub fn routes() -> Router<AppState> {
+  Router::new().route("/shows/changes", get(show_changes))
+}
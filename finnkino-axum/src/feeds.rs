@@ -0,0 +1,68 @@
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use std::sync::Arc;
+
+use libfinnkino_axum::{get_coming_soon, get_news};
+use libfinnkino_core::feeds::{Feed, Revisions, ATOM_CONTENT_TYPE, RSS_CONTENT_TYPE};
+use libfinnkino_core::time::now;
+
+use crate::response::error_response;
+use crate::AppState;
+
+fn atom_response(feed: &Feed) -> Response {
+  (
+    StatusCode::OK,
+    [(CONTENT_TYPE, ATOM_CONTENT_TYPE)],
+    feed.to_atom(),
+  )
+    .into_response()
+}
+
+fn rss_response(feed: &Feed) -> Response {
+  (
+    StatusCode::OK,
+    [(CONTENT_TYPE, RSS_CONTENT_TYPE)],
+    feed.to_rss(),
+  )
+    .into_response()
+}
+
+async fn coming_soon_atom(State(revisions): State<Arc<Revisions>>) -> Response {
+  match get_coming_soon().await {
+    Err(error) => error_response(error),
+    Ok(events) => atom_response(&Feed::coming_soon(events, &revisions, now())),
+  }
+}
+
+async fn coming_soon_rss(State(revisions): State<Arc<Revisions>>) -> Response {
+  match get_coming_soon().await {
+    Err(error) => error_response(error),
+    Ok(events) => rss_response(&Feed::coming_soon(events, &revisions, now())),
+  }
+}
+
+async fn news_atom() -> Response {
+  match get_news().await {
+    Err(error) => error_response(error),
+    Ok(articles) => atom_response(&Feed::news(articles, now())),
+  }
+}
+
+async fn news_rss() -> Response {
+  match get_news().await {
+    Err(error) => error_response(error),
+    Ok(articles) => rss_response(&Feed::news(articles, now())),
+  }
+}
+
+pub fn routes() -> Router<AppState> {
+  Router::new()
+    .route("/coming-soon.atom", get(coming_soon_atom))
+    .route("/coming-soon.rss", get(coming_soon_rss))
+    .route("/news.atom", get(news_atom))
+    .route("/news.rss", get(news_rss))
+}
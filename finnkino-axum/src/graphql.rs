@@ -0,0 +1,34 @@
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use std::sync::Arc;
+
+use libfinnkino_axum::graphql::Upstream;
+use libfinnkino_core::graphql::{self, GraphiQLSource, Schema};
+
+use crate::response::json_response;
+use crate::AppState;
+
+pub fn schema() -> Schema {
+  graphql::schema(Arc::new(Upstream::default()), tokio::spawn)
+}
+
+async fn graphiql() -> Response {
+  Html(GraphiQLSource::build().endpoint(graphql::PATH).finish()).into_response()
+}
+
+async fn execute(State(schema): State<Schema>, body: Bytes) -> Response {
+  let request = match graphql::parse_request(&body) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(request) => request,
+  };
+  let response = schema.execute(request).await;
+  json_response(StatusCode::OK, &response)
+}
+
+pub fn routes() -> Router<AppState> {
+  Router::new().route(graphql::PATH, get(graphiql).post(execute))
+}
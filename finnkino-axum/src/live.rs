@@ -0,0 +1,20 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+use libfinnkino_axum::{get_shows, uncached};
+use libfinnkino_core::live::{self, Hub};
+
+/// Polls the schedules of the areas that have subscribers. Failed polls are
+/// skipped until the next round.
+pub async fn poll(hub: Arc<Hub>) {
+  let mut interval = time::interval(Duration::from_secs(live::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    for area in hub.watched() {
+      if let Ok(current) = uncached(get_shows(&live::filter(area))).await {
+        hub.update(area, current);
+      }
+    }
+  }
+}
@@ -0,0 +1,137 @@
+use axum::extract::{FromRef, Request};
+use axum::http::header::{HeaderName, HeaderValue, LINK};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::Router;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+use libfinnkino_core::api::{
+  is_deprecated_path, successor_link, DEPRECATION, SUNSET, UNVERSIONED_PREFIX, V1_PREFIX,
+};
+use libfinnkino_core::config::{self, Config};
+use libfinnkino_core::feeds::Revisions;
+use libfinnkino_core::fixtures::Fixtures;
+use libfinnkino_core::graphql::Schema;
+use libfinnkino_core::live::Hub;
+use libfinnkino_core::storage::Store;
+use libfinnkino_core::subscription::Tracker;
+use libfinnkino_core::topics::Broker;
+use libfinnkino_core::webhooks::Webhooks;
+
+mod api;
+mod archive;
+mod calendars;
+mod changes;
+mod feeds;
+mod graphql;
+mod live;
+mod pages;
+mod response;
+mod watchlists;
+mod webhooks;
+mod websocket;
+
+const PORT: u16 = 3000;
+
+/// What the handlers share, each part taken with `State<T>`.
+#[derive(Clone, FromRef)]
+struct AppState {
+  tracker: Arc<Tracker>,
+  revisions: Arc<Revisions>,
+  schema: Schema,
+  hub: Arc<Hub>,
+  broker: Arc<Broker>,
+  hooks: Arc<Webhooks>,
+  store: Arc<Store>,
+}
+
+async fn deprecation(req: Request, next: Next) -> Response {
+  let path = req.uri().path().to_string();
+  let mut res = next.run(req).await;
+  if is_deprecated_path(&path) {
+    let headers = res.headers_mut();
+    headers.insert(
+      HeaderName::from_static("deprecation"),
+      HeaderValue::from_static(DEPRECATION),
+    );
+    headers.insert(
+      HeaderName::from_static("sunset"),
+      HeaderValue::from_static(SUNSET),
+    );
+    if let Some(value) = successor_link(&path).and_then(|link| HeaderValue::from_str(&link).ok()) {
+      headers.insert(LINK, value);
+    }
+  }
+  res
+}
+
+#[tokio::main]
+async fn main() {
+  let config = match Config::load() {
+    Err(error) => {
+      println!("Loading the configuration failed! Error: {}", error);
+      return;
+    }
+    Ok(config) => config,
+  };
+  let port = match config::port() {
+    Err(error) => {
+      println!("Loading the configuration failed! Error: {}", error);
+      return;
+    }
+    Ok(port) => port.unwrap_or(PORT),
+  };
+  if let Some(dir) = config.fixtures() {
+    libfinnkino_axum::use_fixtures(Fixtures::new(dir));
+  }
+  if let Some(recording) = &config.recording {
+    libfinnkino_axum::use_recorder(recording.recorder());
+  }
+  if let Some(upstream) = config.upstream() {
+    libfinnkino_axum::use_upstream(upstream);
+  }
+  let store = match Store::open(config.database()) {
+    Err(error) => {
+      println!("Opening the database failed! Error: {}", error);
+      return;
+    }
+    Ok(store) => store,
+  };
+  let state = AppState {
+    tracker: Arc::new(Tracker::default()),
+    revisions: Arc::new(Revisions::default()),
+    schema: graphql::schema(),
+    hub: Arc::new(Hub::default()),
+    broker: Arc::new(Broker::default()),
+    hooks: Arc::new(Webhooks::default()),
+    store: Arc::new(store),
+  };
+  tokio::spawn(live::poll(state.hub.clone()));
+  tokio::spawn(websocket::poll(state.broker.clone()));
+  tokio::spawn(webhooks::poll(state.hooks.clone()));
+  tokio::spawn(archive::poll(state.store.clone()));
+  tokio::spawn(changes::poll(state.store.clone()));
+  let app = Router::new()
+    .merge(pages::routes())
+    .merge(graphql::routes())
+    .merge(websocket::routes())
+    .nest("/calendars", calendars::routes())
+    .nest("/feeds", feeds::routes())
+    .nest(V1_PREFIX, api::v1())
+    .nest(UNVERSIONED_PREFIX, api::v1())
+    .fallback(response::not_found)
+    .method_not_allowed_fallback(response::not_found)
+    .layer(middleware::from_fn(deprecation))
+    .with_state(state);
+  let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+    Err(error) => {
+      println!("Launch failed! Error: {}", error);
+      return;
+    }
+    Ok(listener) => listener,
+  };
+  if let Err(error) = axum::serve(listener, app).await {
+    println!("Serving failed! Error: {}", error);
+  }
+}
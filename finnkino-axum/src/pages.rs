@@ -0,0 +1,106 @@
+use axum::extract::{RawQuery, State};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use std::sync::Arc;
+
+use libfinnkino_axum::extract::{ApiError, PathId};
+use libfinnkino_axum::{get_areas, get_event, get_shows, get_upcoming_shows};
+use libfinnkino_core::finnkino::Error;
+use libfinnkino_core::html::{FilmPage, IndexPage, SchedulePage, WatchlistPage};
+use libfinnkino_core::id::{EventId, WatchlistId};
+use libfinnkino_core::json::{self, Errors as JsonErrors};
+use libfinnkino_core::schedule::ShowFilter;
+use libfinnkino_core::storage::{database_error, not_found, Store, Watchlist, WEEK_DAYS};
+use libfinnkino_core::time::today;
+
+use crate::response::{error_status, html_error_response, html_response};
+use crate::AppState;
+
+fn upstream_error_page(error: Error) -> Response {
+  html_error_response(error_status(&error), &JsonErrors::from(error))
+}
+
+async fn index() -> Response {
+  match get_areas().await {
+    Err(error) => upstream_error_page(error),
+    Ok(areas) => html_response(
+      StatusCode::OK,
+      &IndexPage {
+        areas,
+        today: today(),
+      },
+    ),
+  }
+}
+
+async fn schedule(RawQuery(query): RawQuery) -> Response {
+  let mut filter = match ShowFilter::from_query(query.as_deref().unwrap_or_default()) {
+    Err(errors) => return html_error_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(filter) => filter,
+  };
+  let area_id = match filter.area {
+    None => {
+      let errors = JsonErrors {
+        errors: vec![json::Error::invalid_parameter("area", "Area is required")],
+      };
+      return html_error_response(StatusCode::BAD_REQUEST, &errors);
+    }
+    Some(area_id) => area_id,
+  };
+  let date = *filter.date.get_or_insert_with(today);
+  match futures::try_join!(get_areas(), get_shows(&filter)) {
+    Err(error) => upstream_error_page(error),
+    Ok((areas, shows)) => match areas.iter().find(|area| area.id == area_id) {
+      None => {
+        let errors = JsonErrors {
+          errors: vec![json::Error::invalid_parameter("area", "Unknown area")],
+        };
+        html_error_response(StatusCode::NOT_FOUND, &errors)
+      }
+      Some(area) => html_response(StatusCode::OK, &SchedulePage::new(area, date, shows)),
+    },
+  }
+}
+
+async fn film(id: Result<PathId<EventId>, ApiError>) -> Response {
+  let id = match id {
+    Err(error) => return html_error_response(error.status, &error.errors),
+    Ok(PathId(id)) => id,
+  };
+  match futures::try_join!(get_event(id), get_upcoming_shows(id)) {
+    Err(error) => upstream_error_page(error),
+    Ok((event, upcoming)) => html_response(StatusCode::OK, &FilmPage::new(event, upcoming)),
+  }
+}
+
+async fn watchlist(
+  id: Result<PathId<WatchlistId>, ApiError>,
+  State(store): State<Arc<Store>>,
+) -> Response {
+  let id = match id {
+    Err(error) => return html_error_response(error.status, &error.errors),
+    Ok(PathId(id)) => id,
+  };
+  let found = match store.watchlist(id) {
+    Err(error) => {
+      return html_error_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error))
+    }
+    Ok(None) => return html_error_response(StatusCode::NOT_FOUND, &not_found("watchlist", id)),
+    Ok(Some(found)) => found,
+  };
+  let date = today();
+  match get_shows(&Watchlist::filter(date, WEEK_DAYS)).await {
+    Err(error) => upstream_error_page(error),
+    Ok(shows) => html_response(StatusCode::OK, &WatchlistPage::new(&found, date, shows)),
+  }
+}
+
+pub fn routes() -> Router<AppState> {
+  Router::new()
+    .route("/", get(index))
+    .route("/schedule", get(schedule))
+    .route("/films/{id}", get(film))
+    .route("/watchlists/{id}", get(watchlist))
+}
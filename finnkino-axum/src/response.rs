@@ -0,0 +1,56 @@
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
+use serde::Serialize;
+
+use libfinnkino_core::finnkino::{self, Error};
+use libfinnkino_core::html::{ErrorPage, Template};
+use libfinnkino_core::ical::{self, Calendar};
+use libfinnkino_core::json::{self, Errors as JsonErrors};
+
+pub fn json_response<T: Serialize>(status: StatusCode, value: &T) -> Response {
+  match serde_json::to_string(value) {
+    Err(error) => (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response(),
+    Ok(json) => (status, [(CONTENT_TYPE, "application/json")], json).into_response(),
+  }
+}
+
+pub fn no_content() -> Response {
+  StatusCode::NO_CONTENT.into_response()
+}
+
+/// The answer to requests no route or no method of a route matches.
+pub async fn not_found() -> Response {
+  let errors = JsonErrors {
+    errors: vec![json::Error::from_status(404, "Not Found")],
+  };
+  json_response(StatusCode::NOT_FOUND, &errors)
+}
+
+pub fn error_status(error: &Error) -> StatusCode {
+  StatusCode::from_u16(finnkino::error_status(error)).unwrap()
+}
+
+pub fn error_response(error: Error) -> Response {
+  json_response(error_status(&error), &JsonErrors::upstream(error))
+}
+
+pub fn html_response<T: Template>(status: StatusCode, page: &T) -> Response {
+  match page.render() {
+    Err(error) => (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response(),
+    Ok(html) => (status, Html(html)).into_response(),
+  }
+}
+
+pub fn html_error_response(status: StatusCode, errors: &JsonErrors) -> Response {
+  html_response(status, &ErrorPage::from(errors))
+}
+
+pub fn calendar_response(calendar: &Calendar) -> Response {
+  (
+    StatusCode::OK,
+    [(CONTENT_TYPE, ical::CONTENT_TYPE)],
+    calendar.to_string(),
+  )
+    .into_response()
+}
@@ -0,0 +1,142 @@
+use axum::body::Bytes;
+use axum::extract::{RawQuery, State};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use std::sync::Arc;
+
+use libfinnkino_axum::extract::PathId;
+use libfinnkino_axum::get_shows;
+use libfinnkino_core::api::v1;
+use libfinnkino_core::finnkino::Show;
+use libfinnkino_core::id::{SearchId, WatchlistId};
+use libfinnkino_core::schedule::UPCOMING_DAYS;
+use libfinnkino_core::storage::{
+  database_error, not_found, NewSearch, NewWatchlist, OwnerQuery, Store, Watchlist,
+};
+use libfinnkino_core::time::{now, today};
+
+use crate::response::{error_response, json_response, no_content};
+use crate::AppState;
+
+fn shows_response(shows: Vec<Show>) -> Response {
+  let shows: Vec<v1::Show> = shows.into_iter().map(v1::Show::from).collect();
+  json_response(StatusCode::OK, &shows)
+}
+
+async fn create_watchlist(State(store): State<Arc<Store>>, body: Bytes) -> Response {
+  let new = match NewWatchlist::from_json(&body) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(new) => new,
+  };
+  match store.create_watchlist(new, now()) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(created) => json_response(StatusCode::CREATED, &created),
+  }
+}
+
+async fn list_watchlists(RawQuery(query): RawQuery, State(store): State<Arc<Store>>) -> Response {
+  let query = match OwnerQuery::from_query(query.as_deref().unwrap_or_default()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(query) => query,
+  };
+  match store.watchlists(query.owner.as_deref()) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(found) => json_response(StatusCode::OK, &found),
+  }
+}
+
+async fn watchlist(PathId(id): PathId<WatchlistId>, State(store): State<Arc<Store>>) -> Response {
+  match store.watchlist(id) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(None) => json_response(StatusCode::NOT_FOUND, &not_found("watchlist", id)),
+    Ok(Some(found)) => json_response(StatusCode::OK, &found),
+  }
+}
+
+async fn delete_watchlist(
+  PathId(id): PathId<WatchlistId>,
+  State(store): State<Arc<Store>>,
+) -> Response {
+  match store.delete_watchlist(id) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(false) => json_response(StatusCode::NOT_FOUND, &not_found("watchlist", id)),
+    Ok(true) => no_content(),
+  }
+}
+
+async fn watchlist_shows(
+  PathId(id): PathId<WatchlistId>,
+  State(store): State<Arc<Store>>,
+) -> Response {
+  let found = match store.watchlist(id) {
+    Err(error) => return json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(None) => return json_response(StatusCode::NOT_FOUND, &not_found("watchlist", id)),
+    Ok(Some(found)) => found,
+  };
+  match get_shows(&Watchlist::filter(today(), UPCOMING_DAYS)).await {
+    Err(error) => error_response(error),
+    Ok(upcoming) => shows_response(found.shows(upcoming)),
+  }
+}
+
+async fn create_search(State(store): State<Arc<Store>>, body: Bytes) -> Response {
+  let new = match NewSearch::from_json(&body) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(new) => new,
+  };
+  match store.create_search(new, now()) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(created) => json_response(StatusCode::CREATED, &created),
+  }
+}
+
+async fn list_searches(RawQuery(query): RawQuery, State(store): State<Arc<Store>>) -> Response {
+  let query = match OwnerQuery::from_query(query.as_deref().unwrap_or_default()) {
+    Err(errors) => return json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(query) => query,
+  };
+  match store.searches(query.owner.as_deref()) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(found) => json_response(StatusCode::OK, &found),
+  }
+}
+
+async fn search(PathId(id): PathId<SearchId>, State(store): State<Arc<Store>>) -> Response {
+  match store.search(id) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(None) => json_response(StatusCode::NOT_FOUND, &not_found("search", id)),
+    Ok(Some(found)) => json_response(StatusCode::OK, &found),
+  }
+}
+
+async fn delete_search(PathId(id): PathId<SearchId>, State(store): State<Arc<Store>>) -> Response {
+  match store.delete_search(id) {
+    Err(error) => json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(false) => json_response(StatusCode::NOT_FOUND, &not_found("search", id)),
+    Ok(true) => no_content(),
+  }
+}
+
+async fn search_shows(PathId(id): PathId<SearchId>, State(store): State<Arc<Store>>) -> Response {
+  let found = match store.search(id) {
+    Err(error) => return json_response(StatusCode::INTERNAL_SERVER_ERROR, &database_error(error)),
+    Ok(None) => return json_response(StatusCode::NOT_FOUND, &not_found("search", id)),
+    Ok(Some(found)) => found,
+  };
+  match get_shows(&found.filter(today())).await {
+    Err(error) => error_response(error),
+    Ok(matching) => shows_response(matching),
+  }
+}
+
+pub fn routes() -> Router<AppState> {
+  Router::new()
+    .route("/watchlists", get(list_watchlists).post(create_watchlist))
+    .route("/watchlists/{id}", get(watchlist).delete(delete_watchlist))
+    .route("/watchlists/{id}/shows", get(watchlist_shows))
+    .route("/searches", get(list_searches).post(create_search))
+    .route("/searches/{id}", get(search).delete(delete_search))
+    .route("/searches/{id}/shows", get(search_shows))
+}
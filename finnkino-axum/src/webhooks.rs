@@ -0,0 +1,91 @@
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+use libfinnkino_axum::extract::PathId;
+use libfinnkino_axum::webhooks::deliver;
+use libfinnkino_axum::{get_coming_soon, get_shows, uncached};
+use libfinnkino_core::id::{EventId, WebhookId};
+use libfinnkino_core::time::{now, today};
+use libfinnkino_core::webhooks::{self, RegistrationRequest, Webhooks};
+
+use crate::response::{json_response, no_content};
+use crate::AppState;
+
+async fn create_webhook(State(hooks): State<Arc<Webhooks>>, body: Bytes) -> Response {
+  match RegistrationRequest::from_json(&body) {
+    Err(errors) => json_response(StatusCode::BAD_REQUEST, &errors),
+    Ok(request) => json_response(StatusCode::CREATED, &hooks.register(request, now())),
+  }
+}
+
+async fn list_webhooks(State(hooks): State<Arc<Webhooks>>) -> Response {
+  json_response(StatusCode::OK, &hooks.registrations())
+}
+
+async fn webhook(PathId(id): PathId<WebhookId>, State(hooks): State<Arc<Webhooks>>) -> Response {
+  match hooks.registration(id) {
+    None => json_response(StatusCode::NOT_FOUND, &webhooks::not_found(id)),
+    Some(registration) => json_response(StatusCode::OK, &registration),
+  }
+}
+
+async fn delete_webhook(
+  PathId(id): PathId<WebhookId>,
+  State(hooks): State<Arc<Webhooks>>,
+) -> Response {
+  if hooks.unregister(id) {
+    no_content()
+  } else {
+    json_response(StatusCode::NOT_FOUND, &webhooks::not_found(id))
+  }
+}
+
+async fn webhook_deliveries(
+  PathId(id): PathId<WebhookId>,
+  State(hooks): State<Arc<Webhooks>>,
+) -> Response {
+  match hooks.deliveries(id) {
+    None => json_response(StatusCode::NOT_FOUND, &webhooks::not_found(id)),
+    Some(log) => json_response(StatusCode::OK, &log),
+  }
+}
+
+/// Polls the Schedules of the areas with webhooks and delivers the payloads
+/// in the background. Areas whose Schedule fails to load are skipped until
+/// the next round.
+pub async fn poll(hooks: Arc<Webhooks>) {
+  let mut interval = time::interval(Duration::from_secs(webhooks::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    let areas = hooks.areas();
+    if areas.is_empty() {
+      continue;
+    }
+    let coming_soon: Vec<EventId> = match uncached(get_coming_soon()).await {
+      Err(_) => continue,
+      Ok(events) => events.into_iter().map(|event| event.id).collect(),
+    };
+    for area in areas {
+      if let Ok(current) = uncached(get_shows(&webhooks::filter(area, today()))).await {
+        for outgoing in hooks.update(area, current, &coming_soon, now()) {
+          let hooks = hooks.clone();
+          tokio::spawn(async move { deliver(&hooks, outgoing).await });
+        }
+      }
+    }
+  }
+}
+
+pub fn routes() -> Router<AppState> {
+  Router::new()
+    .route("/webhooks", get(list_webhooks).post(create_webhook))
+    .route("/webhooks/{id}", get(webhook).delete(delete_webhook))
+    .route("/webhooks/{id}/deliveries", get(webhook_deliveries))
+}
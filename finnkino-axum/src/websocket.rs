@@ -0,0 +1,102 @@
+use axum::extract::ws::rejection::WebSocketUpgradeRejection;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::header::SEC_WEBSOCKET_VERSION;
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::Response;
+use axum::routing::get;
+use axum::Router;
+use futures::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::{select, time};
+
+use libfinnkino_axum::{get_coming_soon, get_shows, get_upcoming_shows, uncached};
+use libfinnkino_core::live;
+use libfinnkino_core::topics::{self, Broker, Resource, Topic};
+
+use crate::response::json_response;
+use crate::AppState;
+
+async fn session(mut socket: WebSocket, broker: Arc<Broker>) {
+  let (client, mut receiver) = broker.connect();
+  loop {
+    select! {
+      message = socket.next() => match message {
+        Some(Ok(Message::Text(text))) => broker.receive(&client, text.as_str()),
+        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+        Some(Ok(_)) => {}
+      },
+      // The broker ends the receiver of a client that falls behind.
+      text = receiver.next() => match text {
+        Some(text) => {
+          if socket.send(Message::Text(text.into())).await.is_err() {
+            break;
+          }
+        }
+        None => break,
+      },
+    }
+  }
+  broker.disconnect(&client);
+}
+
+async fn subscribe(
+  upgrade: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+  State(broker): State<Arc<Broker>>,
+) -> Response {
+  match upgrade {
+    Err(WebSocketUpgradeRejection::InvalidWebSocketVersionHeader(_)) => {
+      let mut response = json_response(StatusCode::UPGRADE_REQUIRED, &topics::version_error());
+      let version = HeaderValue::from_static(topics::WEBSOCKET_VERSION);
+      response
+        .headers_mut()
+        .insert(SEC_WEBSOCKET_VERSION, version);
+      response
+    }
+    Err(_) => json_response(StatusCode::BAD_REQUEST, &topics::handshake_error()),
+    Ok(upgrade) => upgrade.on_upgrade(move |socket| session(socket, broker)),
+  }
+}
+
+async fn fetch(topic: Topic) -> Option<Vec<Resource>> {
+  let resources = match topic {
+    Topic::Area(area) => get_shows(&live::filter(area))
+      .await
+      .ok()?
+      .into_iter()
+      .map(Resource::from)
+      .collect(),
+    Topic::Event(id) => get_upcoming_shows(id)
+      .await
+      .ok()?
+      .into_iter()
+      .map(Resource::from)
+      .collect(),
+    Topic::ComingSoon => get_coming_soon()
+      .await
+      .ok()?
+      .into_iter()
+      .map(Resource::from)
+      .collect(),
+  };
+  Some(resources)
+}
+
+/// Polls the feeds of the topics that have subscribers. Failed polls are
+/// skipped until the next round.
+pub async fn poll(broker: Arc<Broker>) {
+  let mut interval = time::interval(Duration::from_secs(live::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    for topic in broker.watched() {
+      if let Some(resources) = uncached(fetch(topic)).await {
+        broker.update(topic, resources);
+      }
+    }
+  }
+}
+
+pub fn routes() -> Router<AppState> {
+  Router::new().route("/ws", get(subscribe))
+}
@@ -0,0 +1,4 @@
+#[tokio::test]
+async fn test_conformance() {
+  libfinnkino_conformance::check(env!("CARGO_BIN_EXE_finnkino-axum")).await;
+}
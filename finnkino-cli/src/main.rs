@@ -0,0 +1,162 @@
+use chrono::{NaiveDate, NaiveTime};
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use libfinnkino_core::api::v1;
+use libfinnkino_core::config::Config;
+use libfinnkino_core::events::EventQuery;
+use libfinnkino_core::finnkino::{Error, ErrorBuilder};
+use libfinnkino_core::fixtures::Fixtures;
+use libfinnkino_core::id::{AreaId, EventId};
+use libfinnkino_core::schedule::{ShowFilter, TIME_FORMAT};
+use libfinnkino_core::table::{self, Table};
+use libfinnkino_core::time;
+
+mod tui;
+
+/// Finnkino's areas, shows and films from the terminal.
+#[derive(Debug, Parser)]
+#[command(name = "finnkino", version)]
+struct Cli {
+  /// How to print the results.
+  #[arg(long, short, value_enum, default_value_t = Format::Table, global = true)]
+  format: Format,
+  /// Read the feeds from a fixture directory instead of Finnkino. Defaults
+  /// to `FINNKINO_FIXTURES` or `fixtures` of the configuration file.
+  #[arg(long, global = true)]
+  fixtures: Option<PathBuf>,
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+enum Format {
+  Table,
+  Json,
+  Csv,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// List the theatre areas.
+  Areas,
+  /// List the shows of a day.
+  Shows {
+    /// Only the shows of this area.
+    #[arg(long)]
+    area: Option<AreaId>,
+    /// The day as YYYY-MM-DD, today by default.
+    #[arg(long, value_parser = parse_date)]
+    date: Option<NaiveDate>,
+    /// Only the shows starting at or after HH:MM.
+    #[arg(long, value_parser = parse_time)]
+    after: Option<NaiveTime>,
+  },
+  /// List the films in theatres.
+  Events {
+    /// List the films coming soon instead.
+    #[arg(long)]
+    coming_soon: bool,
+  },
+  /// Show the details of a film.
+  Event { id: EventId },
+  /// Browse the shows interactively.
+  Tui,
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+  time::parse_api_date(value).ok_or_else(|| "must be a date formatted as YYYY-MM-DD".to_string())
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+  NaiveTime::parse_from_str(value, TIME_FORMAT)
+    .map_err(|_| "must be a time formatted as HH:MM".to_string())
+}
+
+/// Writes the results to stdout, ignoring a closed pipe like `| head`.
+fn print<T: Serialize>(format: Format, value: &T, table: Table) {
+  let output = match format {
+    Format::Table => table.to_text(),
+    Format::Json => format!("{}\n", serde_json::to_string_pretty(value).unwrap()),
+    Format::Csv => table.to_csv(),
+  };
+  let _ = io::stdout().lock().write_all(output.as_bytes());
+}
+
+async fn run(cli: Cli) -> Result<(), Error> {
+  match cli.command {
+    Command::Areas => {
+      let areas: Vec<v1::Area> = libfinnkino_client::get_areas()
+        .await?
+        .into_iter()
+        .map(v1::Area::from)
+        .collect();
+      print(cli.format, &areas, table::areas(&areas));
+    }
+    Command::Shows { area, date, after } => {
+      let filter = ShowFilter {
+        area,
+        date: date.or_else(|| Some(time::today())),
+        after,
+        ..ShowFilter::default()
+      };
+      let shows: Vec<v1::Show> = libfinnkino_client::get_shows(&filter)
+        .await?
+        .into_iter()
+        .map(v1::Show::from)
+        .collect();
+      print(cli.format, &shows, table::shows(&shows));
+    }
+    Command::Events { coming_soon } => {
+      let events = match coming_soon {
+        true => libfinnkino_client::get_coming_soon().await?,
+        false => libfinnkino_client::get_events(&EventQuery::default()).await?,
+      };
+      let events: Vec<v1::Event> = events.into_iter().map(v1::Event::from).collect();
+      print(cli.format, &events, table::events(&events));
+    }
+    Command::Event { id } => {
+      let event = v1::Event::from(libfinnkino_client::get_event(id).await?);
+      print(cli.format, &event, table::event(&event));
+    }
+    Command::Tui => tui::run().await.map_err(|error| {
+      ErrorBuilder::default()
+        .title("Terminal error")
+        .detail(error.to_string())
+        .build()
+        .unwrap()
+    })?,
+  }
+  Ok(())
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+  let cli = Cli::parse();
+  let config = match Config::load() {
+    Err(error) => {
+      eprintln!("finnkino: {}", error);
+      return ExitCode::FAILURE;
+    }
+    Ok(config) => config,
+  };
+  if let Some(dir) = cli.fixtures.clone().or_else(|| config.fixtures()) {
+    libfinnkino_client::use_fixtures(Fixtures::new(dir));
+  }
+  if let Some(recording) = &config.recording {
+    libfinnkino_client::use_recorder(recording.recorder());
+  }
+  if let Some(upstream) = config.upstream() {
+    libfinnkino_client::use_upstream(upstream);
+  }
+  match run(cli).await {
+    Err(error) => {
+      eprintln!("finnkino: {}", error);
+      ExitCode::FAILURE
+    }
+    Ok(()) => ExitCode::SUCCESS,
+  }
+}
@@ -0,0 +1,55 @@
+use ratatui::crossterm::event::{self, Event as TerminalEvent, KeyEventKind};
+use ratatui::DefaultTerminal;
+use std::io;
+
+use libfinnkino_core::finnkino::{Error, Event, Show};
+use libfinnkino_core::id::{AreaId, EventId};
+use libfinnkino_core::schedule::ShowFilter;
+use libfinnkino_core::time::LocalDateTime;
+use libfinnkino_tui::{draw, load, App, Loader};
+
+/// Loads the app's requests with the reqwest client.
+struct Client;
+
+impl Loader for Client {
+  async fn schedule_dates(&self, area: AreaId) -> Result<Vec<LocalDateTime>, Error> {
+    libfinnkino_client::get_schedule_dates(Some(area)).await
+  }
+
+  async fn shows(&self, filter: &ShowFilter) -> Result<Vec<Show>, Error> {
+    libfinnkino_client::get_shows(filter).await
+  }
+
+  async fn event(&self, id: EventId) -> Result<Event, Error> {
+    libfinnkino_client::get_event(id).await
+  }
+}
+
+async fn run_app(terminal: &mut DefaultTerminal) -> io::Result<()> {
+  let mut app = App::default();
+  match libfinnkino_client::get_areas().await {
+    Ok(areas) => {
+      let request = app.set_areas(areas);
+      load(&mut app, &Client, request).await;
+    }
+    Err(error) => app.set_error(error),
+  }
+  while !app.quit {
+    terminal.draw(|frame| draw(frame, &mut app))?;
+    if let TerminalEvent::Key(key) = event::read()? {
+      if key.kind == KeyEventKind::Press {
+        let request = app.on_key(key);
+        load(&mut app, &Client, request).await;
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Runs the terminal UI until it is quit, restoring the terminal after.
+pub async fn run() -> io::Result<()> {
+  let mut terminal = ratatui::init();
+  let result = run_app(&mut terminal).await;
+  ratatui::restore();
+  result
+}
@@ -0,0 +1,31 @@
+use std::net::{Ipv4Addr, SocketAddr};
+
+use libfinnkino_core::config::Config;
+use libfinnkino_core::fixtures::Fixtures;
+use libfinnkino_grpc::{Service, DEFAULT_PORT};
+use tonic::transport::Server;
+
+#[tokio::main]
+async fn main() -> Result<(), tonic::transport::Error> {
+  let config = match Config::load() {
+    Err(error) => {
+      println!("Loading the configuration failed! Error: {}", error);
+      return Ok(());
+    }
+    Ok(config) => config,
+  };
+  if let Some(dir) = config.fixtures() {
+    libfinnkino_client::use_fixtures(Fixtures::new(dir));
+  }
+  if let Some(recording) = &config.recording {
+    libfinnkino_client::use_recorder(recording.recorder());
+  }
+  if let Some(upstream) = config.upstream() {
+    libfinnkino_client::use_upstream(upstream);
+  }
+  let address = SocketAddr::from((Ipv4Addr::LOCALHOST, DEFAULT_PORT));
+  Server::builder()
+    .add_service(Service::default().into_server())
+    .serve(address)
+    .await
+}
@@ -0,0 +1,105 @@
+use actix_web::body::SizedStream;
+use actix_web::http::StatusCode;
+use actix_web::web::Bytes;
+use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+use chrono::NaiveDate;
+use clap::Parser;
+use futures::{future, stream, StreamExt};
+use std::io;
+use std::time::Duration;
+
+use libfinnkino_core::time;
+use libfinnkino_mock::{Mock, Options, Scenario};
+
+/// Serves Finnkino's XML feeds with synthetic data, e.g. for the servers'
+/// `upstream`.
+#[derive(Debug, Parser)]
+#[command(name = "finnkino-mock", version)]
+struct Cli {
+  #[arg(long, default_value = "127.0.0.1")]
+  host: String,
+  #[arg(long, default_value_t = 8090)]
+  port: u16,
+  /// The same seed and start day always give the same data.
+  #[arg(long, default_value_t = 0)]
+  seed: u64,
+  /// The first day with shows as YYYY-MM-DD, today by default.
+  #[arg(long, value_parser = parse_date)]
+  start: Option<NaiveDate>,
+  /// How many days have shows.
+  #[arg(long, default_value_t = 7)]
+  days: u32,
+  /// Milliseconds every response is delayed.
+  #[arg(long, default_value_t = 0)]
+  latency: u64,
+  /// Up to this many milliseconds more are added at random.
+  #[arg(long, default_value_t = 0)]
+  jitter: u64,
+  /// The share of requests, from 0 to 1, that fail.
+  #[arg(long, default_value_t = 0.0)]
+  error_rate: f64,
+  /// The status of the failed requests.
+  #[arg(long, default_value_t = 503)]
+  error_status: u16,
+  /// Faults to inject per feed, e.g. "Schedule: delay 6000ms; *: truncate".
+  #[arg(long, default_value = "")]
+  scenario: Scenario,
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+  time::parse_api_date(value).ok_or_else(|| "must be a date formatted as YYYY-MM-DD".to_string())
+}
+
+async fn feed(req: HttpRequest, mock: web::Data<Mock>) -> HttpResponse {
+  let reply = mock.respond(req.path(), req.query_string());
+  if !reply.delay.is_zero() {
+    actix_web::rt::time::sleep(reply.delay).await;
+  }
+  let status = StatusCode::from_u16(reply.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+  let mut response = HttpResponse::build(status);
+  let mut length = None;
+  for (name, value) in reply.headers {
+    if name.eq_ignore_ascii_case("Content-Length") {
+      length = value.parse().ok();
+    } else {
+      response.insert_header((name, value));
+    }
+  }
+  match length {
+    // A length actix doesn't compute itself, so that a truncated body ends
+    // short of it. The connection is dropped only after what there is was
+    // flushed.
+    Some(length) => {
+      let body = stream::once(future::ok(Bytes::from(reply.body))).chain(stream::once(async {
+        actix_web::rt::time::sleep(Duration::from_millis(50)).await;
+        Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Truncated"))
+      }));
+      response.body(SizedStream::new(length, body))
+    }
+    None => response.body(reply.body),
+  }
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+  let cli = Cli::parse();
+  let mock = web::Data::new(Mock::new(Options {
+    seed: cli.seed,
+    start: cli.start.unwrap_or_else(time::today),
+    days: cli.days,
+    latency: Duration::from_millis(cli.latency),
+    jitter: Duration::from_millis(cli.jitter),
+    error_rate: cli.error_rate,
+    error_status: cli.error_status,
+    scenario: cli.scenario,
+  }));
+  eprintln!("Serving the feeds at http://{}:{}/xml", cli.host, cli.port);
+  HttpServer::new(move || {
+    App::new()
+      .app_data(mock.clone())
+      .default_service(web::to(feed))
+  })
+  .bind((cli.host.as_str(), cli.port))?
+  .run()
+  .await
+}
@@ -0,0 +1,125 @@
+use rocket::futures::{stream, StreamExt};
+use rocket::http::uri::Origin;
+use rocket::http::Status;
+use rocket::response::stream::{Event, EventStream};
+use rocket::{Route, State};
+use std::sync::Arc;
+use std::time::Duration;
+
+use libfinnkino_core::api::v1;
+use libfinnkino_core::ical::Calendar;
+use libfinnkino_core::id::{EventId, ShowId};
+use libfinnkino_core::json::Errors as JsonErrors;
+use libfinnkino_core::live::{self, Hub};
+use libfinnkino_core::schedule::ShowFilter;
+use libfinnkino_rocket::extract::{ApiError, CalendarId, LastEventId, PathId};
+use libfinnkino_rocket::{get_areas, get_event, get_show, get_shows, get_upcoming_shows};
+
+use crate::response::{calendar_response, error_response, json_response, Response};
+
+#[get("/areas")]
+async fn areas() -> Response {
+  match get_areas().await {
+    Err(error) => error_response(error),
+    Ok(areas) => {
+      let areas: Vec<v1::Area> = areas.into_iter().map(v1::Area::from).collect();
+      json_response(Status::Ok, &areas)
+    }
+  }
+}
+
+#[get("/shows")]
+async fn shows(uri: &Origin<'_>) -> Response {
+  let query = uri.query().map(|query| query.as_str()).unwrap_or_default();
+  let filter = match ShowFilter::from_query(query) {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(filter) => filter,
+  };
+  match get_shows(&filter).await {
+    Err(error) => error_response(error),
+    Ok(shows) => {
+      let shows: Vec<v1::Show> = shows.into_iter().map(v1::Show::from).collect();
+      json_response(Status::Ok, &shows)
+    }
+  }
+}
+
+#[get("/shows/stream")]
+async fn shows_stream(
+  uri: &Origin<'_>,
+  last_event_id: LastEventId,
+  hub: &State<Arc<Hub>>,
+) -> Result<EventStream![], Response> {
+  let query = uri.query().map(|query| query.as_str()).unwrap_or_default();
+  let area = match live::parse_query(query) {
+    Err(errors) => return Err(json_response(Status::BadRequest, &errors)),
+    Ok(area) => area,
+  };
+  let LastEventId(last_event_id) = last_event_id;
+  let (backlog, receiver) = hub.subscribe(area, last_event_id);
+  if !hub.has_schedule(area) {
+    match get_shows(&live::filter(area)).await {
+      Err(error) => return Err(error_response(error)),
+      Ok(current) => hub.update(area, current),
+    }
+  }
+  let events = stream::iter(backlog).chain(receiver).map(|change| {
+    Event::data(change.data())
+      .event(change.kind.as_str())
+      .id(change.id.to_string())
+  });
+  Ok(EventStream::from(events).heartbeat(Duration::from_secs(live::HEARTBEAT_SECS)))
+}
+
+#[get("/shows.ics")]
+async fn shows_calendar(uri: &Origin<'_>) -> Response {
+  let query = uri.query().map(|query| query.as_str()).unwrap_or_default();
+  let filter = match ShowFilter::from_query(query) {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(filter) => filter,
+  };
+  match get_shows(&filter).await {
+    Err(error) => error_response(error),
+    Ok(matching) => calendar_response(&Calendar::new(matching)),
+  }
+}
+
+#[get("/shows/<id>")]
+async fn show_calendar(id: Result<CalendarId<ShowId>, ApiError>) -> Response {
+  let id = match id {
+    Err(error) => return json_response(error.status, &error.errors),
+    Ok(CalendarId(id)) => id,
+  };
+  match get_show(id).await {
+    Err(error) => error_response(error),
+    Ok(show) => calendar_response(&Calendar::new(vec![show])),
+  }
+}
+
+#[get("/events/<id>")]
+async fn event(id: Result<PathId<EventId>, JsonErrors>) -> Response {
+  let id = match id {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  match rocket::futures::try_join!(get_event(id), get_upcoming_shows(id)) {
+    Err(error) => error_response(error),
+    Ok((details, upcoming)) => json_response(Status::Ok, &v1::EventDetail::new(details, upcoming)),
+  }
+}
+
+pub fn v1() -> Vec<Route> {
+  let mut routes = routes![
+    areas,
+    shows,
+    shows_stream,
+    shows_calendar,
+    show_calendar,
+    event
+  ];
+  routes.extend(crate::webhooks::routes());
+  routes.extend(crate::watchlists::routes());
+  routes.extend(crate::archive::routes());
+  routes.extend(crate::changes::routes());
+  routes
+}
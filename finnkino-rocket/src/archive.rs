@@ -0,0 +1,66 @@
+use rocket::http::uri::Origin;
+use rocket::http::Status;
+use rocket::tokio::time;
+use rocket::{Route, State};
+use std::sync::Arc;
+use std::time::Duration;
+
+use libfinnkino_core::archive::{self, ArchiveQuery};
+use libfinnkino_core::events::EventQuery;
+use libfinnkino_core::id::EventId;
+use libfinnkino_core::json::Errors as JsonErrors;
+use libfinnkino_core::storage::{database_error, not_found, Store};
+use libfinnkino_core::time::{now, today};
+use libfinnkino_rocket::extract::PathId;
+use libfinnkino_rocket::{get_areas, get_events, get_shows};
+
+use crate::response::{json_response, Response};
+
+#[get("/archive/shows")]
+fn archived_shows(uri: &Origin<'_>, store: &State<Arc<Store>>) -> Response {
+  let query = uri.query().map(|query| query.as_str()).unwrap_or_default();
+  let query = match ArchiveQuery::from_query(query) {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(query) => query,
+  };
+  match store.archived_shows(&query) {
+    Err(error) => json_response(Status::InternalServerError, &database_error(error)),
+    Ok(found) => json_response(Status::Ok, &found),
+  }
+}
+
+#[get("/archive/events/<id>/screenings")]
+fn screenings(id: Result<PathId<EventId>, JsonErrors>, store: &State<Arc<Store>>) -> Response {
+  let id = match id {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  match store.screenings(id) {
+    Err(error) => json_response(Status::InternalServerError, &database_error(error)),
+    Ok(None) => json_response(Status::NotFound, &not_found("archived event", id)),
+    Ok(Some(found)) => json_response(Status::Ok, &found),
+  }
+}
+
+/// Snapshots today's Schedule of every area and the Events feed into the
+/// archive. Feeds that fail to load are skipped until the next round.
+pub async fn poll(store: Arc<Store>) {
+  let mut interval = time::interval(Duration::from_secs(archive::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    if let Ok(areas) = get_areas().await {
+      for area in areas {
+        if let Ok(shows) = get_shows(&archive::filter(area.id, today())).await {
+          let _ = store.archive_shows(area.id, &shows, now());
+        }
+      }
+    }
+    if let Ok(events) = get_events(&EventQuery::default()).await {
+      let _ = store.archive_events(&events, now());
+    }
+  }
+}
+
+pub fn routes() -> Vec<Route> {
+  routes![archived_shows, screenings]
+}
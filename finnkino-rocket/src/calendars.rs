@@ -0,0 +1,66 @@
+use rocket::{Route, State};
+
+use libfinnkino_core::finnkino::{ErrorBuilder, ErrorKind};
+use libfinnkino_core::ical::Calendar;
+use libfinnkino_core::id::{AreaId, EventId};
+use libfinnkino_core::subscription::{Subscription, Tracker};
+use libfinnkino_core::time::{now, today};
+use libfinnkino_rocket::extract::{ApiError, CalendarId};
+use libfinnkino_rocket::{get_areas, get_event, get_shows};
+
+use crate::response::{calendar_response, error_response, json_response, Response};
+
+#[get("/area/<id>")]
+async fn area_calendar(
+  id: Result<CalendarId<AreaId>, ApiError>,
+  tracker: &State<Tracker>,
+) -> Response {
+  let id = match id {
+    Err(error) => return json_response(error.status, &error.errors),
+    Ok(CalendarId(id)) => id,
+  };
+  let subscription = Subscription::Area(id);
+  let filter = subscription.filter(today());
+  match rocket::futures::try_join!(get_areas(), get_shows(&filter)) {
+    Err(error) => error_response(error),
+    Ok((areas, shows)) => match areas.into_iter().find(|area| area.id == id) {
+      None => error_response(
+        ErrorBuilder::default()
+          .kind(ErrorKind::Status)
+          .status("404")
+          .title("Not Found")
+          .detail(format!("No area with id {}", id))
+          .build()
+          .unwrap(),
+      ),
+      Some(area) => calendar_response(&Calendar {
+        name: Some(area.name),
+        ..tracker.update(subscription, shows, now())
+      }),
+    },
+  }
+}
+
+#[get("/event/<id>")]
+async fn event_calendar(
+  id: Result<CalendarId<EventId>, ApiError>,
+  tracker: &State<Tracker>,
+) -> Response {
+  let id = match id {
+    Err(error) => return json_response(error.status, &error.errors),
+    Ok(CalendarId(id)) => id,
+  };
+  let subscription = Subscription::Event(id);
+  let filter = subscription.filter(today());
+  match rocket::futures::try_join!(get_event(id), get_shows(&filter)) {
+    Err(error) => error_response(error),
+    Ok((event, shows)) => calendar_response(&Calendar {
+      name: Some(event.title),
+      ..tracker.update(subscription, shows, now())
+    }),
+  }
+}
+
+pub fn routes() -> Vec<Route> {
+  routes![area_calendar, event_calendar]
+}
@@ -0,0 +1,54 @@
+use rocket::http::uri::Origin;
+use rocket::http::Status;
+use rocket::tokio::time;
+use rocket::{Route, State};
+use std::sync::Arc;
+use std::time::Duration;
+
+use libfinnkino_core::api::v1;
+use libfinnkino_core::changes::{self, ChangesQuery};
+use libfinnkino_core::storage::{database_error, not_found, Store};
+use libfinnkino_core::time::{now, today};
+use libfinnkino_rocket::{get_areas, get_shows};
+
+use crate::response::{json_response, Response};
+
+#[get("/shows/changes")]
+fn show_changes(uri: &Origin<'_>, store: &State<Arc<Store>>) -> Response {
+  let query = uri.query().map(|query| query.as_str()).unwrap_or_default();
+  let query = match ChangesQuery::from_query(query) {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(query) => query,
+  };
+  match store.schedule_changes(&query) {
+    Err(error) => json_response(Status::InternalServerError, &database_error(error)),
+    Ok(None) => json_response(
+      Status::NotFound,
+      &not_found("schedule snapshot of area", query.area),
+    ),
+    Ok(Some(found)) => json_response(Status::Ok, &found),
+  }
+}
+
+/// Snapshots the coming week of every area. Areas whose Schedule fails to
+/// load are skipped until the next round.
+pub async fn poll(store: Arc<Store>) {
+  let mut interval = time::interval(Duration::from_secs(changes::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    let areas = match get_areas().await {
+      Err(_) => continue,
+      Ok(areas) => areas,
+    };
+    for area in areas {
+      if let Ok(shows) = get_shows(&changes::filter(area.id, today())).await {
+        let shows: Vec<v1::Show> = shows.into_iter().map(v1::Show::from).collect();
+        let _ = store.snapshot_schedule(area.id, &shows, now());
+      }
+    }
+  }
+}
+
+pub fn routes() -> Vec<Route> {
+  routes![show_changes]
+}
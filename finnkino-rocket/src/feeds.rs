@@ -0,0 +1,55 @@
+use rocket::http::{ContentType, Status};
+use rocket::{Route, State};
+use std::str::FromStr;
+
+use libfinnkino_core::feeds::{Feed, Revisions, ATOM_CONTENT_TYPE, RSS_CONTENT_TYPE};
+use libfinnkino_core::time::now;
+use libfinnkino_rocket::{get_coming_soon, get_news};
+
+use crate::response::{error_response, Response};
+
+fn atom_response(feed: &Feed) -> Response {
+  let content_type = ContentType::from_str(ATOM_CONTENT_TYPE).unwrap_or(ContentType::XML);
+  (Status::Ok, (content_type, feed.to_atom()))
+}
+
+fn rss_response(feed: &Feed) -> Response {
+  let content_type = ContentType::from_str(RSS_CONTENT_TYPE).unwrap_or(ContentType::XML);
+  (Status::Ok, (content_type, feed.to_rss()))
+}
+
+#[get("/coming-soon.atom")]
+async fn coming_soon_atom(revisions: &State<Revisions>) -> Response {
+  match get_coming_soon().await {
+    Err(error) => error_response(error),
+    Ok(events) => atom_response(&Feed::coming_soon(events, revisions, now())),
+  }
+}
+
+#[get("/coming-soon.rss")]
+async fn coming_soon_rss(revisions: &State<Revisions>) -> Response {
+  match get_coming_soon().await {
+    Err(error) => error_response(error),
+    Ok(events) => rss_response(&Feed::coming_soon(events, revisions, now())),
+  }
+}
+
+#[get("/news.atom")]
+async fn news_atom() -> Response {
+  match get_news().await {
+    Err(error) => error_response(error),
+    Ok(articles) => atom_response(&Feed::news(articles, now())),
+  }
+}
+
+#[get("/news.rss")]
+async fn news_rss() -> Response {
+  match get_news().await {
+    Err(error) => error_response(error),
+    Ok(articles) => rss_response(&Feed::news(articles, now())),
+  }
+}
+
+pub fn routes() -> Vec<Route> {
+  routes![coming_soon_atom, coming_soon_rss, news_atom, news_rss]
+}
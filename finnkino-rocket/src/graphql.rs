@@ -0,0 +1,37 @@
+use rocket::http::{ContentType, Status};
+use rocket::{Route, State};
+use std::sync::Arc;
+
+use libfinnkino_core::graphql::{self, GraphiQLSource, Schema};
+use libfinnkino_rocket::graphql::Upstream;
+
+use crate::response::{json_response, Response};
+
+pub fn schema() -> Schema {
+  graphql::schema(Arc::new(Upstream::default()), rocket::tokio::spawn)
+}
+
+#[get("/graphql")]
+fn graphiql() -> Response {
+  (
+    Status::Ok,
+    (
+      ContentType::HTML,
+      GraphiQLSource::build().endpoint(graphql::PATH).finish(),
+    ),
+  )
+}
+
+#[post("/graphql", data = "<body>")]
+async fn execute(schema: &State<Schema>, body: Vec<u8>) -> Response {
+  let request = match graphql::parse_request(&body) {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(request) => request,
+  };
+  let response = schema.execute(request).await;
+  json_response(Status::Ok, &response)
+}
+
+pub fn routes() -> Vec<Route> {
+  routes![graphiql, execute]
+}
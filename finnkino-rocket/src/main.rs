@@ -2,44 +2,121 @@
 extern crate rocket;
 extern crate libfinnkino_core;
 
-use rocket::http::{ContentType, Status};
+use rocket::fairing::AdHoc;
+use rocket::http::Header;
+use std::sync::Arc;
 
-use libfinnkino_core::json::Errors as JsonErrors;
-use libfinnkino_rocket::get_areas;
+use libfinnkino_core::api::{
+  is_deprecated_path, successor_link, DEPRECATION, SUNSET, UNVERSIONED_PREFIX, V1_PREFIX,
+};
+use libfinnkino_core::config::{self, Config};
+use libfinnkino_core::feeds::Revisions;
+use libfinnkino_core::fixtures::Fixtures;
+use libfinnkino_core::live::Hub;
+use libfinnkino_core::storage::Store;
+use libfinnkino_core::subscription::Tracker;
+use libfinnkino_core::topics::Broker;
+use libfinnkino_core::webhooks::Webhooks;
 
-#[get("/")]
-fn index() -> &'static str {
-  "Hello, world!"
+mod api;
+mod archive;
+mod calendars;
+mod changes;
+mod feeds;
+mod graphql;
+mod live;
+mod pages;
+mod response;
+mod watchlists;
+mod webhooks;
+mod websocket;
+
+fn deprecation() -> AdHoc {
+  AdHoc::on_response("Unversioned API deprecation", |req, res| {
+    Box::pin(async move {
+      let path = req.uri().path();
+      if is_deprecated_path(path.as_str()) {
+        res.set_header(Header::new("Deprecation", DEPRECATION));
+        res.set_header(Header::new("Sunset", SUNSET));
+        if let Some(link) = successor_link(path.as_str()) {
+          res.set_header(Header::new("Link", link));
+        }
+      }
+    })
+  })
 }
 
-#[get("/areas")]
-async fn areas() -> (Status, (ContentType, String)) {
-  match get_areas().await {
-    Err(error) => {
-      let errors = JsonErrors::from(error);
-      match serde_json::to_string(&errors) {
-        Err(error) => (
-          Status::InternalServerError,
-          (ContentType::Text, error.to_string()),
-        ),
-        Ok(json) => (Status::Ok, (ContentType::JSON, json)),
+fn poller() -> AdHoc {
+  AdHoc::on_liftoff("Pollers", |rocket| {
+    Box::pin(async move {
+      if let Some(hub) = rocket.state::<Arc<Hub>>() {
+        rocket::tokio::spawn(live::poll(hub.clone()));
       }
-    }
-    Ok(areas) => match serde_json::to_string(&areas) {
-      Err(error) => (
-        Status::InternalServerError,
-        (ContentType::Text, error.to_string()),
-      ),
-      Ok(json) => (Status::Ok, (ContentType::JSON, json)),
-    },
-  }
+      if let Some(broker) = rocket.state::<Arc<Broker>>() {
+        rocket::tokio::spawn(websocket::poll(broker.clone()));
+      }
+      if let Some(hooks) = rocket.state::<Arc<Webhooks>>() {
+        rocket::tokio::spawn(webhooks::poll(hooks.clone()));
+      }
+      if let Some(store) = rocket.state::<Arc<Store>>() {
+        rocket::tokio::spawn(archive::poll(store.clone()));
+        rocket::tokio::spawn(changes::poll(store.clone()));
+      }
+    })
+  })
 }
 
 #[rocket::main]
 async fn main() {
-  let result = rocket::build()
-    .mount("/", routes![index])
-    .mount("/api", routes![areas])
+  let config = match Config::load() {
+    Err(error) => {
+      println!("Loading the configuration failed! Error: {}", error);
+      return;
+    }
+    Ok(config) => config,
+  };
+  let figment = match config::port() {
+    Err(error) => {
+      println!("Loading the configuration failed! Error: {}", error);
+      return;
+    }
+    Ok(None) => rocket::Config::figment(),
+    Ok(Some(port)) => rocket::Config::figment().merge(("port", port)),
+  };
+  if let Some(dir) = config.fixtures() {
+    libfinnkino_rocket::use_fixtures(Fixtures::new(dir));
+  }
+  if let Some(recording) = &config.recording {
+    libfinnkino_rocket::use_recorder(recording.recorder());
+  }
+  if let Some(upstream) = config.upstream() {
+    libfinnkino_rocket::use_upstream(upstream);
+  }
+  let store = match Store::open(config.database()) {
+    Err(error) => {
+      println!("Opening the database failed! Error: {}", error);
+      return;
+    }
+    Ok(store) => store,
+  };
+  let result = rocket::custom(figment)
+    .manage(Tracker::default())
+    .manage(Revisions::default())
+    .manage(graphql::schema())
+    .manage(Arc::new(Hub::default()))
+    .manage(Arc::new(Broker::default()))
+    .manage(Arc::new(Webhooks::default()))
+    .manage(Arc::new(store))
+    .mount("/", pages::routes())
+    .mount("/", graphql::routes())
+    .mount("/", websocket::routes())
+    .mount("/calendars", calendars::routes())
+    .mount("/feeds", feeds::routes())
+    .mount(V1_PREFIX, api::v1())
+    .mount(UNVERSIONED_PREFIX, api::v1())
+    .register("/", catchers![response::catch_all])
+    .attach(deprecation())
+    .attach(poller())
     .launch()
     .await;
   if let Err(error) = result {
@@ -0,0 +1,102 @@
+use rocket::http::uri::Origin;
+use rocket::http::Status;
+use rocket::{Route, State};
+use std::sync::Arc;
+
+use libfinnkino_core::finnkino::Error;
+use libfinnkino_core::html::{FilmPage, IndexPage, SchedulePage, WatchlistPage};
+use libfinnkino_core::id::{EventId, WatchlistId};
+use libfinnkino_core::json::{self, Errors as JsonErrors};
+use libfinnkino_core::schedule::ShowFilter;
+use libfinnkino_core::storage::{database_error, not_found, Store, Watchlist, WEEK_DAYS};
+use libfinnkino_core::time::today;
+use libfinnkino_rocket::extract::PathId;
+use libfinnkino_rocket::{get_areas, get_event, get_shows, get_upcoming_shows};
+
+use crate::response::{error_status, html_error_response, html_response, Response};
+
+fn upstream_error_page(error: Error) -> Response {
+  html_error_response(error_status(&error), &JsonErrors::from(error))
+}
+
+#[get("/")]
+async fn index() -> Response {
+  match get_areas().await {
+    Err(error) => upstream_error_page(error),
+    Ok(areas) => html_response(
+      Status::Ok,
+      &IndexPage {
+        areas,
+        today: today(),
+      },
+    ),
+  }
+}
+
+#[get("/schedule")]
+async fn schedule(uri: &Origin<'_>) -> Response {
+  let query = uri.query().map(|query| query.as_str()).unwrap_or_default();
+  let mut filter = match ShowFilter::from_query(query) {
+    Err(errors) => return html_error_response(Status::BadRequest, &errors),
+    Ok(filter) => filter,
+  };
+  let area_id = match filter.area {
+    None => {
+      let errors = JsonErrors {
+        errors: vec![json::Error::invalid_parameter("area", "Area is required")],
+      };
+      return html_error_response(Status::BadRequest, &errors);
+    }
+    Some(area_id) => area_id,
+  };
+  let date = *filter.date.get_or_insert_with(today);
+  match rocket::futures::try_join!(get_areas(), get_shows(&filter)) {
+    Err(error) => upstream_error_page(error),
+    Ok((areas, shows)) => match areas.iter().find(|area| area.id == area_id) {
+      None => {
+        let errors = JsonErrors {
+          errors: vec![json::Error::invalid_parameter("area", "Unknown area")],
+        };
+        html_error_response(Status::NotFound, &errors)
+      }
+      Some(area) => html_response(Status::Ok, &SchedulePage::new(area, date, shows)),
+    },
+  }
+}
+
+#[get("/films/<id>")]
+async fn film(id: Result<PathId<EventId>, JsonErrors>) -> Response {
+  let id = match id {
+    Err(errors) => return html_error_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  match rocket::futures::try_join!(get_event(id), get_upcoming_shows(id)) {
+    Err(error) => upstream_error_page(error),
+    Ok((event, upcoming)) => html_response(Status::Ok, &FilmPage::new(event, upcoming)),
+  }
+}
+
+#[get("/watchlists/<id>")]
+async fn watchlist(
+  id: Result<PathId<WatchlistId>, JsonErrors>,
+  store: &State<Arc<Store>>,
+) -> Response {
+  let id = match id {
+    Err(errors) => return html_error_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  let found = match store.watchlist(id) {
+    Err(error) => return html_error_response(Status::InternalServerError, &database_error(error)),
+    Ok(None) => return html_error_response(Status::NotFound, &not_found("watchlist", id)),
+    Ok(Some(found)) => found,
+  };
+  let date = today();
+  match get_shows(&Watchlist::filter(date, WEEK_DAYS)).await {
+    Err(error) => upstream_error_page(error),
+    Ok(shows) => html_response(Status::Ok, &WatchlistPage::new(&found, date, shows)),
+  }
+}
+
+pub fn routes() -> Vec<Route> {
+  routes![index, schedule, film, watchlist]
+}
@@ -0,0 +1,65 @@
+use rocket::http::{ContentType, Status};
+use rocket::Request;
+use serde::Serialize;
+use std::str::FromStr;
+
+use libfinnkino_core::finnkino::{self, Error};
+use libfinnkino_core::html::{ErrorPage, Template};
+use libfinnkino_core::ical::{self, Calendar};
+use libfinnkino_core::json::{self, Errors as JsonErrors};
+
+pub type Response = (Status, (ContentType, String));
+
+pub fn json_response<T: Serialize>(status: Status, value: &T) -> Response {
+  match serde_json::to_string(value) {
+    Err(error) => (
+      Status::InternalServerError,
+      (ContentType::Text, error.to_string()),
+    ),
+    Ok(json) => (status, (ContentType::JSON, json)),
+  }
+}
+
+pub fn no_content() -> Response {
+  (Status::NoContent, (ContentType::JSON, String::new()))
+}
+
+/// Answers the requests no route matches, or whose guards failed, with JSON
+/// instead of Rocket's HTML pages.
+#[catch(default)]
+pub fn catch_all(status: Status, _: &Request<'_>) -> Response {
+  let errors = JsonErrors {
+    errors: vec![json::Error::from_status(
+      status.code,
+      status.reason().unwrap_or_default(),
+    )],
+  };
+  json_response(status, &errors)
+}
+
+pub fn error_status(error: &Error) -> Status {
+  Status::new(finnkino::error_status(error))
+}
+
+pub fn error_response(error: Error) -> Response {
+  json_response(error_status(&error), &JsonErrors::upstream(error))
+}
+
+pub fn html_response<T: Template>(status: Status, page: &T) -> Response {
+  match page.render() {
+    Err(error) => (
+      Status::InternalServerError,
+      (ContentType::Text, error.to_string()),
+    ),
+    Ok(html) => (status, (ContentType::HTML, html)),
+  }
+}
+
+pub fn html_error_response(status: Status, errors: &JsonErrors) -> Response {
+  html_response(status, &ErrorPage::from(errors))
+}
+
+pub fn calendar_response(calendar: &Calendar) -> Response {
+  let content_type = ContentType::from_str(ical::CONTENT_TYPE).unwrap_or(ContentType::Calendar);
+  (Status::Ok, (content_type, calendar.to_string()))
+}
@@ -0,0 +1,183 @@
+use rocket::http::uri::Origin;
+use rocket::http::Status;
+use rocket::{Route, State};
+use std::sync::Arc;
+
+use libfinnkino_core::api::v1;
+use libfinnkino_core::finnkino::Show;
+use libfinnkino_core::id::{SearchId, WatchlistId};
+use libfinnkino_core::json::Errors as JsonErrors;
+use libfinnkino_core::schedule::UPCOMING_DAYS;
+use libfinnkino_core::storage::{
+  database_error, not_found, NewSearch, NewWatchlist, OwnerQuery, Store, Watchlist,
+};
+use libfinnkino_core::time::{now, today};
+use libfinnkino_rocket::extract::PathId;
+use libfinnkino_rocket::get_shows;
+
+use crate::response::{error_response, json_response, no_content, Response};
+
+fn shows_response(shows: Vec<Show>) -> Response {
+  let shows: Vec<v1::Show> = shows.into_iter().map(v1::Show::from).collect();
+  json_response(Status::Ok, &shows)
+}
+
+#[post("/watchlists", data = "<body>")]
+fn create_watchlist(body: Vec<u8>, store: &State<Arc<Store>>) -> Response {
+  let new = match NewWatchlist::from_json(&body) {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(new) => new,
+  };
+  match store.create_watchlist(new, now()) {
+    Err(error) => json_response(Status::InternalServerError, &database_error(error)),
+    Ok(created) => json_response(Status::Created, &created),
+  }
+}
+
+#[get("/watchlists")]
+fn list_watchlists(uri: &Origin<'_>, store: &State<Arc<Store>>) -> Response {
+  let query = uri.query().map(|query| query.as_str()).unwrap_or_default();
+  let query = match OwnerQuery::from_query(query) {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(query) => query,
+  };
+  match store.watchlists(query.owner.as_deref()) {
+    Err(error) => json_response(Status::InternalServerError, &database_error(error)),
+    Ok(found) => json_response(Status::Ok, &found),
+  }
+}
+
+#[get("/watchlists/<id>")]
+fn watchlist(id: Result<PathId<WatchlistId>, JsonErrors>, store: &State<Arc<Store>>) -> Response {
+  let id = match id {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  match store.watchlist(id) {
+    Err(error) => json_response(Status::InternalServerError, &database_error(error)),
+    Ok(None) => json_response(Status::NotFound, &not_found("watchlist", id)),
+    Ok(Some(found)) => json_response(Status::Ok, &found),
+  }
+}
+
+#[delete("/watchlists/<id>")]
+fn delete_watchlist(
+  id: Result<PathId<WatchlistId>, JsonErrors>,
+  store: &State<Arc<Store>>,
+) -> Response {
+  let id = match id {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  match store.delete_watchlist(id) {
+    Err(error) => json_response(Status::InternalServerError, &database_error(error)),
+    Ok(false) => json_response(Status::NotFound, &not_found("watchlist", id)),
+    Ok(true) => no_content(),
+  }
+}
+
+#[get("/watchlists/<id>/shows")]
+async fn watchlist_shows(
+  id: Result<PathId<WatchlistId>, JsonErrors>,
+  store: &State<Arc<Store>>,
+) -> Response {
+  let id = match id {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  let found = match store.watchlist(id) {
+    Err(error) => return json_response(Status::InternalServerError, &database_error(error)),
+    Ok(None) => return json_response(Status::NotFound, &not_found("watchlist", id)),
+    Ok(Some(found)) => found,
+  };
+  match get_shows(&Watchlist::filter(today(), UPCOMING_DAYS)).await {
+    Err(error) => error_response(error),
+    Ok(upcoming) => shows_response(found.shows(upcoming)),
+  }
+}
+
+#[post("/searches", data = "<body>")]
+fn create_search(body: Vec<u8>, store: &State<Arc<Store>>) -> Response {
+  let new = match NewSearch::from_json(&body) {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(new) => new,
+  };
+  match store.create_search(new, now()) {
+    Err(error) => json_response(Status::InternalServerError, &database_error(error)),
+    Ok(created) => json_response(Status::Created, &created),
+  }
+}
+
+#[get("/searches")]
+fn list_searches(uri: &Origin<'_>, store: &State<Arc<Store>>) -> Response {
+  let query = uri.query().map(|query| query.as_str()).unwrap_or_default();
+  let query = match OwnerQuery::from_query(query) {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(query) => query,
+  };
+  match store.searches(query.owner.as_deref()) {
+    Err(error) => json_response(Status::InternalServerError, &database_error(error)),
+    Ok(found) => json_response(Status::Ok, &found),
+  }
+}
+
+#[get("/searches/<id>")]
+fn search(id: Result<PathId<SearchId>, JsonErrors>, store: &State<Arc<Store>>) -> Response {
+  let id = match id {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  match store.search(id) {
+    Err(error) => json_response(Status::InternalServerError, &database_error(error)),
+    Ok(None) => json_response(Status::NotFound, &not_found("search", id)),
+    Ok(Some(found)) => json_response(Status::Ok, &found),
+  }
+}
+
+#[delete("/searches/<id>")]
+fn delete_search(id: Result<PathId<SearchId>, JsonErrors>, store: &State<Arc<Store>>) -> Response {
+  let id = match id {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  match store.delete_search(id) {
+    Err(error) => json_response(Status::InternalServerError, &database_error(error)),
+    Ok(false) => json_response(Status::NotFound, &not_found("search", id)),
+    Ok(true) => no_content(),
+  }
+}
+
+#[get("/searches/<id>/shows")]
+async fn search_shows(
+  id: Result<PathId<SearchId>, JsonErrors>,
+  store: &State<Arc<Store>>,
+) -> Response {
+  let id = match id {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  let found = match store.search(id) {
+    Err(error) => return json_response(Status::InternalServerError, &database_error(error)),
+    Ok(None) => return json_response(Status::NotFound, &not_found("search", id)),
+    Ok(Some(found)) => found,
+  };
+  match get_shows(&found.filter(today())).await {
+    Err(error) => error_response(error),
+    Ok(matching) => shows_response(matching),
+  }
+}
+
+pub fn routes() -> Vec<Route> {
+  routes![
+    create_watchlist,
+    list_watchlists,
+    watchlist,
+    delete_watchlist,
+    watchlist_shows,
+    create_search,
+    list_searches,
+    search,
+    delete_search,
+    search_shows
+  ]
+}
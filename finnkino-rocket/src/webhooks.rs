@@ -0,0 +1,107 @@
+use rocket::http::Status;
+use rocket::tokio::time;
+use rocket::{Route, State};
+use std::sync::Arc;
+use std::time::Duration;
+
+use libfinnkino_core::id::{EventId, WebhookId};
+use libfinnkino_core::json::Errors as JsonErrors;
+use libfinnkino_core::time::{now, today};
+use libfinnkino_core::webhooks::{self, RegistrationRequest, Webhooks};
+use libfinnkino_rocket::extract::PathId;
+use libfinnkino_rocket::webhooks::deliver;
+use libfinnkino_rocket::{get_coming_soon, get_shows, uncached};
+
+use crate::response::{json_response, no_content, Response};
+
+#[post("/webhooks", data = "<body>")]
+fn create_webhook(body: Vec<u8>, hooks: &State<Arc<Webhooks>>) -> Response {
+  match RegistrationRequest::from_json(&body) {
+    Err(errors) => json_response(Status::BadRequest, &errors),
+    Ok(request) => json_response(Status::Created, &hooks.register(request, now())),
+  }
+}
+
+#[get("/webhooks")]
+fn list_webhooks(hooks: &State<Arc<Webhooks>>) -> Response {
+  json_response(Status::Ok, &hooks.registrations())
+}
+
+#[get("/webhooks/<id>")]
+fn webhook(id: Result<PathId<WebhookId>, JsonErrors>, hooks: &State<Arc<Webhooks>>) -> Response {
+  let id = match id {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  match hooks.registration(id) {
+    None => json_response(Status::NotFound, &webhooks::not_found(id)),
+    Some(registration) => json_response(Status::Ok, &registration),
+  }
+}
+
+#[delete("/webhooks/<id>")]
+fn delete_webhook(
+  id: Result<PathId<WebhookId>, JsonErrors>,
+  hooks: &State<Arc<Webhooks>>,
+) -> Response {
+  let id = match id {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  if hooks.unregister(id) {
+    no_content()
+  } else {
+    json_response(Status::NotFound, &webhooks::not_found(id))
+  }
+}
+
+#[get("/webhooks/<id>/deliveries")]
+fn webhook_deliveries(
+  id: Result<PathId<WebhookId>, JsonErrors>,
+  hooks: &State<Arc<Webhooks>>,
+) -> Response {
+  let id = match id {
+    Err(errors) => return json_response(Status::BadRequest, &errors),
+    Ok(PathId(id)) => id,
+  };
+  match hooks.deliveries(id) {
+    None => json_response(Status::NotFound, &webhooks::not_found(id)),
+    Some(log) => json_response(Status::Ok, &log),
+  }
+}
+
+/// Polls the Schedules of the areas with webhooks and delivers the payloads
+/// in the background. Areas whose Schedule fails to load are skipped until
+/// the next round.
+pub async fn poll(hooks: Arc<Webhooks>) {
+  let mut interval = time::interval(Duration::from_secs(webhooks::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    let areas = hooks.areas();
+    if areas.is_empty() {
+      continue;
+    }
+    let coming_soon: Vec<EventId> = match uncached(get_coming_soon()).await {
+      Err(_) => continue,
+      Ok(events) => events.into_iter().map(|event| event.id).collect(),
+    };
+    for area in areas {
+      if let Ok(current) = uncached(get_shows(&webhooks::filter(area, today()))).await {
+        for outgoing in hooks.update(area, current, &coming_soon, now()) {
+          let hooks = hooks.clone();
+          rocket::tokio::spawn(async move { deliver(&hooks, outgoing).await });
+        }
+      }
+    }
+  }
+}
+
+pub fn routes() -> Vec<Route> {
+  routes![
+    create_webhook,
+    list_webhooks,
+    webhook,
+    delete_webhook,
+    webhook_deliveries
+  ]
+}
@@ -0,0 +1,111 @@
+use rocket::futures::{SinkExt, StreamExt};
+use rocket::http::{Header, Status};
+use rocket::tokio::{select, time};
+use rocket::{Either, Responder, Route, State};
+use std::sync::Arc;
+use std::time::Duration;
+
+use libfinnkino_core::live;
+use libfinnkino_core::topics::{self, Broker, Resource, Topic};
+use libfinnkino_rocket::ws::{Channel, HandshakeError, Message, WebSocket};
+use libfinnkino_rocket::{get_coming_soon, get_shows, get_upcoming_shows, uncached};
+
+use crate::response::{json_response, Response};
+
+/// A failed handshake. Clients of another WebSocket version are told the one
+/// the server speaks.
+#[derive(Responder)]
+enum Rejection {
+  Invalid(Response),
+  Version(Response, Header<'static>),
+}
+
+#[get("/ws")]
+fn subscribe(
+  socket: Result<WebSocket, HandshakeError>,
+  broker: &State<Arc<Broker>>,
+) -> Either<Channel, Rejection> {
+  let socket = match socket {
+    Err(HandshakeError::Invalid) => {
+      let errors = topics::handshake_error();
+      return Either::Right(Rejection::Invalid(json_response(
+        Status::BadRequest,
+        &errors,
+      )));
+    }
+    Err(HandshakeError::Version) => {
+      let errors = topics::version_error();
+      let version = Header::new("Sec-WebSocket-Version", topics::WEBSOCKET_VERSION);
+      return Either::Right(Rejection::Version(
+        json_response(Status::UpgradeRequired, &errors),
+        version,
+      ));
+    }
+    Ok(socket) => socket,
+  };
+  let broker = broker.inner().clone();
+  Either::Left(socket.channel(move |mut stream| async move {
+    let (client, mut receiver) = broker.connect();
+    loop {
+      select! {
+        message = stream.next() => match message {
+          Some(Ok(Message::Text(text))) => broker.receive(&client, &text),
+          Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+          Some(Ok(_)) => {}
+        },
+        // The broker ends the receiver of a client that falls behind.
+        text = receiver.next() => match text {
+          Some(text) => {
+            if stream.send(Message::Text(text)).await.is_err() {
+              break;
+            }
+          }
+          None => break,
+        },
+      }
+    }
+    broker.disconnect(&client);
+  }))
+}
+
+async fn fetch(topic: Topic) -> Option<Vec<Resource>> {
+  let resources = match topic {
+    Topic::Area(area) => get_shows(&live::filter(area))
+      .await
+      .ok()?
+      .into_iter()
+      .map(Resource::from)
+      .collect(),
+    Topic::Event(id) => get_upcoming_shows(id)
+      .await
+      .ok()?
+      .into_iter()
+      .map(Resource::from)
+      .collect(),
+    Topic::ComingSoon => get_coming_soon()
+      .await
+      .ok()?
+      .into_iter()
+      .map(Resource::from)
+      .collect(),
+  };
+  Some(resources)
+}
+
+/// Polls the feeds of the topics that have subscribers. Failed polls are
+/// skipped until the next round.
+pub async fn poll(broker: Arc<Broker>) {
+  let mut interval = time::interval(Duration::from_secs(live::POLL_INTERVAL_SECS));
+  loop {
+    interval.tick().await;
+    for topic in broker.watched() {
+      if let Some(resources) = uncached(fetch(topic)).await {
+        broker.update(topic, resources);
+      }
+    }
+  }
+}
+
+pub fn routes() -> Vec<Route> {
+  routes![subscribe]
+}
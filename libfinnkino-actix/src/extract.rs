@@ -0,0 +1,124 @@
+use actix_web::dev::Payload;
+use actix_web::http::header::ContentType;
+use actix_web::http::StatusCode;
+use actix_web::{FromRequest, HttpRequest, HttpResponse, ResponseError};
+use futures::future::{ready, Ready};
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use libfinnkino_core::id::ParseIdError;
+use libfinnkino_core::json;
+use libfinnkino_core::live;
+
+#[derive(Debug)]
+pub struct ApiError {
+  pub status: StatusCode,
+  pub errors: json::Errors,
+}
+
+impl fmt::Display for ApiError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match serde_json::to_string(&self.errors) {
+      Err(error) => write!(f, "{}", error),
+      Ok(json) => write!(f, "{}", json),
+    }
+  }
+}
+
+impl ResponseError for ApiError {
+  fn status_code(&self) -> StatusCode {
+    self.status
+  }
+
+  fn error_response(&self) -> HttpResponse {
+    HttpResponse::build(self.status)
+      .content_type(ContentType::json())
+      .body(self.to_string())
+  }
+}
+
+/// Extracts the `{id}` segment of the matched path as one of the typed ids.
+#[derive(Debug, Eq, PartialEq)]
+pub struct PathId<T>(pub T);
+
+impl<T: FromStr<Err = ParseIdError>> FromRequest for PathId<T> {
+  type Error = ApiError;
+  type Future = Ready<Result<Self, Self::Error>>;
+
+  fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+    let value = req.match_info().get("id").unwrap_or_default();
+    ready(value.parse::<T>().map(PathId).map_err(|err| ApiError {
+      status: StatusCode::BAD_REQUEST,
+      errors: json::Errors {
+        errors: vec![json::Error::invalid_path_parameter("id", err.to_string())],
+      },
+    }))
+  }
+}
+
+/// The `Last-Event-ID` header of a reconnecting Server-Sent Events client.
+/// Missing and malformed ids are both `None`.
+#[derive(Debug, Eq, PartialEq)]
+pub struct LastEventId(pub Option<u64>);
+
+impl FromRequest for LastEventId {
+  type Error = Infallible;
+  type Future = Ready<Result<Self, Self::Error>>;
+
+  fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+    let id = req
+      .headers()
+      .get(live::LAST_EVENT_ID)
+      .and_then(|value| value.to_str().ok())
+      .and_then(live::parse_last_event_id);
+    ready(Ok(LastEventId(id)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use actix_web::test::TestRequest;
+  use libfinnkino_core::id::EventId;
+  use pretty_assertions::assert_eq;
+
+  #[actix_rt::test]
+  async fn test_extract_path_id() {
+    let req = TestRequest::default()
+      .param("id", "303000")
+      .to_http_request();
+    let id = PathId::<EventId>::extract(&req).await.unwrap();
+
+    assert_eq!(id, PathId(EventId(303000)));
+  }
+
+  #[actix_rt::test]
+  async fn test_extract_invalid_path_id() {
+    let req = TestRequest::default().param("id", "abc").to_http_request();
+    let error = PathId::<EventId>::extract(&req).await.unwrap_err();
+
+    assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+    assert_eq!(
+      error.errors.errors[0].source.as_ref().unwrap().parameter,
+      Some("id".to_string())
+    );
+  }
+
+  #[actix_rt::test]
+  async fn test_extract_last_event_id() {
+    let req = TestRequest::default()
+      .insert_header(("Last-Event-ID", "42"))
+      .to_http_request();
+    let missing = TestRequest::default().to_http_request();
+
+    assert_eq!(
+      LastEventId::extract(&req).await.unwrap(),
+      LastEventId(Some(42))
+    );
+    assert_eq!(
+      LastEventId::extract(&missing).await.unwrap(),
+      LastEventId(None)
+    );
+  }
+}
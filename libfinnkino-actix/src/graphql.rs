@@ -0,0 +1,50 @@
+use futures::channel::oneshot;
+use std::future::Future;
+
+use libfinnkino_core::events::EventQuery;
+use libfinnkino_core::finnkino::{Error, ErrorBuilder, Event, Show, TheatreArea};
+use libfinnkino_core::graphql::{Source, SourceFuture};
+use libfinnkino_core::schedule::ShowFilter;
+
+use crate::{get_areas, get_events, get_shows};
+
+/// Backs the GraphQL schema with the cached awc client. The awc futures are
+/// not `Send`, so they run on the local actix task set and hand their result
+/// back over a channel; the schema must therefore be executed, and its
+/// batches spawned, on an actix runtime.
+pub struct Upstream;
+
+fn local<T, F>(fetch: F) -> SourceFuture<T>
+where
+  T: Send + 'static,
+  F: Future<Output = Result<T, Error>> + 'static,
+{
+  let (sender, receiver) = oneshot::channel();
+  actix_web::rt::spawn(async move {
+    sender.send(fetch.await).ok();
+  });
+  Box::pin(async move {
+    receiver.await.unwrap_or_else(|_| {
+      Err(
+        ErrorBuilder::default()
+          .title("Request was cancelled")
+          .build()
+          .unwrap(),
+      )
+    })
+  })
+}
+
+impl Source for Upstream {
+  fn areas(&self) -> SourceFuture<Vec<TheatreArea>> {
+    local(get_areas())
+  }
+
+  fn shows(&self, filter: ShowFilter) -> SourceFuture<Vec<Show>> {
+    local(async move { get_shows(&filter).await })
+  }
+
+  fn events(&self, query: EventQuery) -> SourceFuture<Vec<Event>> {
+    local(async move { get_events(&query).await })
+  }
+}
@@ -1,34 +1,135 @@
 use actix_http::error::PayloadError;
 use awc::error::SendRequestError;
+use awc::http::header::{CONTENT_ENCODING, CONTENT_LENGTH};
 use futures::{future, TryFutureExt};
-use quick_xml::de::from_str;
+use std::net::SocketAddr;
+use std::sync::OnceLock;
 
-use libfinnkino_core::finnkino::{Error, ErrorBuilder, TheatreArea, TheatreAreas};
+use libfinnkino_core::events::EventQuery;
+use libfinnkino_core::finnkino::{
+  Error, ErrorBuilder, ErrorKind, Event, NewsArticle, Show, TheatreArea, BODY_LIMIT,
+};
+use libfinnkino_core::fixtures::Fixtures;
+use libfinnkino_core::id::{AreaId, EventId, ShowId};
+use libfinnkino_core::recording::{Exchange, Recorder};
+use libfinnkino_core::schedule::ShowFilter;
+use libfinnkino_core::time::LocalDateTime;
+use libfinnkino_core::transport::{FeedClient, Transport};
+use libfinnkino_core::webhooks::Outgoing;
 
-pub async fn get_areas() -> Result<std::vec::Vec<TheatreArea>, Error> {
-  let areas_xml = get_xml("https://www.finnkino.fi/xml/TheatreAreas").await;
-  match areas_xml {
-    Err(err) => Err(err),
-    Ok(xml) => match from_str::<TheatreAreas>(xml.as_str()) {
-      Err(err) => Err(
-        ErrorBuilder::default()
-          .title("Failed to serialize XML")
-          .detail(format!("{:?}", err))
-          .build()
-          .unwrap(),
-      ),
-      Ok(areas) => Ok(areas.theatre_areas),
-    },
+pub use libfinnkino_core::transport::uncached;
+
+pub mod extract;
+pub mod graphql;
+pub mod webhooks;
+
+struct Awc;
+
+impl Transport for Awc {
+  async fn send(&self, url: &str) -> Result<Exchange, Error> {
+    send(url).await
+  }
+
+  async fn post(&self, outgoing: &Outgoing, addr: SocketAddr) -> Result<u16, String> {
+    webhooks::post(outgoing, addr).await
   }
 }
 
-async fn get_xml(url: &str) -> Result<String, Error> {
+fn client() -> &'static FeedClient<Awc> {
+  static CLIENT: OnceLock<FeedClient<Awc>> = OnceLock::new();
+  CLIENT.get_or_init(|| FeedClient::new(Awc))
+}
+
+/// Serves the feeds from `fixtures` instead of Finnkino from now on. Only
+/// the first call has an effect.
+pub fn use_fixtures(fixtures: Fixtures) {
+  client().use_fixtures(fixtures);
+}
+
+/// Records or replays the requests to Finnkino with `recorder` from now on.
+/// Only the first call has an effect.
+pub fn use_recorder(recorder: Recorder) {
+  client().use_recorder(recorder);
+}
+
+/// Reads the feeds from `base_url` instead of Finnkino from now on. Only the
+/// first call has an effect.
+pub fn use_upstream(base_url: String) {
+  client().use_upstream(base_url);
+}
+
+pub async fn get_areas() -> Result<std::vec::Vec<TheatreArea>, Error> {
+  client().get_areas().await
+}
+
+pub async fn get_shows(filter: &ShowFilter) -> Result<std::vec::Vec<Show>, Error> {
+  client().get_shows(filter).await
+}
+
+pub async fn get_schedule_dates(
+  area: Option<AreaId>,
+) -> Result<std::vec::Vec<LocalDateTime>, Error> {
+  client().get_schedule_dates(area).await
+}
+
+pub async fn get_event(id: EventId) -> Result<Event, Error> {
+  client().get_event(id).await
+}
+
+pub async fn get_upcoming_shows(event_id: EventId) -> Result<std::vec::Vec<Show>, Error> {
+  client().get_upcoming_shows(event_id).await
+}
+
+pub async fn get_show(id: ShowId) -> Result<Show, Error> {
+  client().get_show(id).await
+}
+
+pub async fn get_events(query: &EventQuery) -> Result<std::vec::Vec<Event>, Error> {
+  client().get_events(query).await
+}
+
+pub async fn get_coming_soon() -> Result<std::vec::Vec<Event>, Error> {
+  client().get_coming_soon().await
+}
+
+pub async fn get_news() -> Result<std::vec::Vec<NewsArticle>, Error> {
+  client().get_news().await
+}
+
+/// The areas from `base_url` instead of the configured upstream.
+pub async fn fetch_areas(base_url: &str) -> Result<std::vec::Vec<TheatreArea>, Error> {
+  client().fetch_areas(base_url).await
+}
+
+pub async fn fetch_events(
+  base_url: &str,
+  query: &EventQuery,
+) -> Result<std::vec::Vec<Event>, Error> {
+  client().fetch_events(base_url, query).await
+}
+
+pub async fn fetch_shows(
+  base_url: &str,
+  filter: &ShowFilter,
+) -> Result<std::vec::Vec<Show>, Error> {
+  client().fetch_shows(base_url, filter).await
+}
+
+pub async fn fetch_schedule_dates(
+  base_url: &str,
+  area: Option<AreaId>,
+) -> Result<std::vec::Vec<LocalDateTime>, Error> {
+  client().fetch_schedule_dates(base_url, area).await
+}
+
+async fn send(url: &str) -> Result<Exchange, Error> {
   awc::Client::default()
     .get(url)
     .insert_header(("Accept", "text/xml, application/xml"))
     .send()
     .map_err(|err| {
       let mut error_builder = ErrorBuilder::default();
+      error_builder.kind(ErrorKind::Request);
       match err {
         SendRequestError::Url(url_error) => error_builder
           .title("Invalid URL")
@@ -49,6 +150,7 @@ async fn get_xml(url: &str) -> Result<String, Error> {
           .title("Http2 error")
           .detail(format!("{:?}", http2_error)),
         SendRequestError::Timeout => error_builder
+          .kind(ErrorKind::Timeout)
           .title("Response took too long")
           .detail(format!("{:?}", err)),
         SendRequestError::TunnelNotSupported => error_builder
@@ -58,73 +160,100 @@ async fn get_xml(url: &str) -> Result<String, Error> {
           .title("Error sending request body")
           .detail(format!("{:?}", body_error)),
         SendRequestError::Custom(custom_error, _debug) => error_builder
+          .kind(ErrorKind::Other)
           .title("Other error")
           .detail(custom_error.to_string()),
-        _ => todo!("{}", &err.to_string()),
+        _ => error_builder
+          .kind(ErrorKind::Other)
+          .title("Other error")
+          .detail(err.to_string()),
       };
       error_builder.build().unwrap()
     })
     .and_then(|mut resp| async move {
-      if resp.status().is_success() {
-        resp
-          .body()
-          .map_err(|err| {
-            let mut error_builder = ErrorBuilder::default();
-            match err {
-              PayloadError::Incomplete(incomplete_error) => {
-                if let Some(incomplete) = incomplete_error {
-                  error_builder
-                    .title("Incomplete")
-                    .code(format!("{:?}", incomplete.kind()))
-                    .detail(incomplete.to_string())
-                } else {
-                  error_builder.title("Incomplete")
-                }
-              }
-              PayloadError::EncodingCorrupted => error_builder
-                .title("Encoding corrupted")
-                .detail(format!("{:?}", err)),
-              PayloadError::Overflow => {
-                error_builder.title("Overflow").detail(format!("{:?}", err))
+      let status = resp.status().as_u16();
+      let headers = resp
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+          let value = String::from_utf8_lossy(value.as_bytes()).to_string();
+          (name.to_string(), value)
+        })
+        .collect();
+      // awc ends the body where the connection does, even short of its
+      // length. Compressed bodies are longer once decoded.
+      let length = match resp.headers().contains_key(CONTENT_ENCODING) {
+        true => None,
+        false => resp
+          .headers()
+          .get(CONTENT_LENGTH)
+          .and_then(|value| value.to_str().ok())
+          .and_then(|value| value.parse::<usize>().ok()),
+      };
+      resp
+        .body()
+        .limit(BODY_LIMIT)
+        .map_err(|err| {
+          let mut error_builder = ErrorBuilder::default();
+          error_builder.kind(ErrorKind::Body);
+          match err {
+            PayloadError::Incomplete(incomplete_error) => {
+              if let Some(incomplete) = incomplete_error {
+                error_builder
+                  .title("Incomplete")
+                  .code(format!("{:?}", incomplete.kind()))
+                  .detail(incomplete.to_string())
+              } else {
+                error_builder.title("Incomplete")
               }
-              PayloadError::UnknownLength => error_builder
-                .title("Unknown length")
-                .detail(format!("{:?}", err)),
-              PayloadError::Http2Payload(h2_payload) => error_builder
-                .title("Http2 payload error")
-                .detail(format!("{:?}", h2_payload)),
-              PayloadError::Io(io_error) => error_builder
-                .title("IO error")
-                .detail(format!("{:?}", io_error)),
-              _ => todo!("{}", &err.to_string()),
-            };
-            error_builder.build().unwrap()
-          })
-          .and_then(|content| match String::from_utf8(content.to_vec()) {
+            }
+            PayloadError::EncodingCorrupted => error_builder
+              .title("Encoding corrupted")
+              .detail(format!("{:?}", err)),
+            PayloadError::Overflow => error_builder.title("Overflow").detail(format!("{:?}", err)),
+            PayloadError::UnknownLength => error_builder
+              .title("Unknown length")
+              .detail(format!("{:?}", err)),
+            PayloadError::Http2Payload(h2_payload) => error_builder
+              .title("Http2 payload error")
+              .detail(format!("{:?}", h2_payload)),
+            PayloadError::Io(io_error) => error_builder
+              .title("IO error")
+              .detail(format!("{:?}", io_error)),
+            _ => error_builder
+              .kind(ErrorKind::Other)
+              .title("Other error")
+              .detail(err.to_string()),
+          };
+          error_builder.build().unwrap()
+        })
+        .and_then(|content| {
+          if let Some(length) = length.filter(|length| content.len() < *length) {
+            let error_builder = ErrorBuilder::default()
+              .kind(ErrorKind::Body)
+              .title("Incomplete")
+              .detail(format!("Received {} of {} bytes", content.len(), length))
+              .build();
+            return future::err(error_builder.unwrap());
+          }
+          match String::from_utf8(content.to_vec()) {
             Err(err) => {
               let error_builder = ErrorBuilder::default()
+                .kind(ErrorKind::Encoding)
                 .title("Failed to parse XML")
                 .detail(err.to_string())
                 .build();
               future::err(error_builder.unwrap())
             }
-            Ok(content_str) => future::ok(content_str),
-          })
-          .await
-      } else {
-        let error_builder = match resp.status().canonical_reason() {
-          None => ErrorBuilder::default()
-            .status(resp.status().as_str())
-            .title("Unknown response status")
-            .detail(format!("{:?}", resp.status()))
-            .build(),
-          Some(reason) => ErrorBuilder::default()
-            .status(resp.status().as_str())
-            .title(reason)
-            .build(),
-        };
-        Err(error_builder.unwrap())
-      }
+            Ok(body) => future::ok(Exchange {
+              url: url.to_string(),
+              status,
+              headers,
+              body,
+            }),
+          }
+        })
+        .await
     })
     .await
 }
@@ -159,13 +288,57 @@ mod tests {
       .mount(&mock_server)
       .await;
 
-    let xml_result = get_xml(format!("{}/xml/TheatreAreas", &mock_server.uri()).as_str())
+    let xml_result = client()
+      .get_xml(format!("{}/xml/TheatreAreas", &mock_server.uri()).as_str())
       .await
       .unwrap();
 
     assert_eq!(xml_result, body);
   }
 
+  #[actix_rt::test]
+  async fn test_get_xml_large_body() {
+    let mock_server = MockServer::start().await;
+    let area = "<TheatreArea><ID>1029</ID><Name>Valitse alue/teatteri</Name></TheatreArea>";
+    let body = format!("<TheatreAreas>{}</TheatreAreas>", area.repeat(40_000));
+    assert!(body.len() > 2 * 1024 * 1024);
+    Mock::given(method("GET"))
+      .and(path("/xml/TheatreAreas"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(body.clone(), "text/xml"))
+      .mount(&mock_server)
+      .await;
+
+    let xml_result = client()
+      .get_xml(format!("{}/xml/TheatreAreas", &mock_server.uri()).as_str())
+      .await
+      .unwrap();
+
+    assert_eq!(xml_result, body);
+  }
+
+  #[actix_rt::test]
+  async fn test_get_xml_too_large_body() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/xml/TheatreAreas"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(" ".repeat(BODY_LIMIT + 1), "text/xml"))
+      .mount(&mock_server)
+      .await;
+
+    let xml_result = client()
+      .get_xml(format!("{}/xml/TheatreAreas", &mock_server.uri()).as_str())
+      .await
+      .unwrap_err();
+    let error = ErrorBuilder::default()
+      .kind(ErrorKind::Body)
+      .title("Overflow")
+      .detail("Overflow")
+      .build()
+      .unwrap();
+
+    assert_eq!(xml_result, error);
+  }
+
   #[actix_rt::test]
   async fn test_get_xml_not_found() {
     // Start a background HTTP server on a random local port
@@ -180,10 +353,12 @@ mod tests {
       .mount(&mock_server)
       .await;
 
-    let xml_result = get_xml(format!("{}/xml/TheatreArea", &mock_server.uri()).as_str())
+    let xml_result = client()
+      .get_xml(format!("{}/xml/TheatreArea", &mock_server.uri()).as_str())
       .await
       .unwrap_err();
     let error = ErrorBuilder::default()
+      .kind(ErrorKind::Status)
       .status("404")
       .title("Not Found")
       .build()
@@ -207,7 +382,7 @@ mod tests {
       .mount(&mock_server)
       .await;
 
-    let xml_result = get_xml(
+    let xml_result = client().get_xml(
       format!(
         "htp://{}:{}/xml/TheatreArea",
         mock_url.host().unwrap(),
@@ -218,6 +393,7 @@ mod tests {
     .await
     .unwrap_err();
     let error = ErrorBuilder::default()
+      .kind(ErrorKind::Request)
       .title("Invalid URL")
       .detail("UnknownScheme")
       .build()
@@ -242,10 +418,12 @@ mod tests {
       .mount(&mock_server)
       .await;
 
-    let xml_result = get_xml(format!("{}/xml/TheatreAreas", &mock_server.uri()).as_str())
+    let xml_result = client()
+      .get_xml(format!("{}/xml/TheatreAreas", &mock_server.uri()).as_str())
       .await
       .unwrap_err();
     let error = ErrorBuilder::default()
+      .kind(ErrorKind::Timeout)
       .title("Response took too long")
       .detail("Timeout")
       .build()
@@ -275,10 +453,12 @@ mod tests {
       .mount(&mock_server)
       .await;
 
-    let xml_result = get_xml(format!("{}/xml/TheatreAreas", &mock_server.uri()).as_str())
+    let xml_result = client()
+      .get_xml(format!("{}/xml/TheatreAreas", &mock_server.uri()).as_str())
       .await
       .unwrap_err();
     let error = ErrorBuilder::default()
+      .kind(ErrorKind::Body)
       .title("Incomplete")
       .code("InvalidInput")
       .detail("invalid gzip header")
@@ -303,10 +483,12 @@ mod tests {
       .mount(&mock_server)
       .await;
 
-    let xml_result = get_xml(format!("{}/xml/TheatreAreas", &mock_server.uri()).as_str())
+    let xml_result = client()
+      .get_xml(format!("{}/xml/TheatreAreas", &mock_server.uri()).as_str())
       .await
       .unwrap_err();
     let error = ErrorBuilder::default()
+      .kind(ErrorKind::Encoding)
       .title("Failed to parse XML")
       .detail("invalid utf-8 sequence of 1 bytes from index 5")
       .build()
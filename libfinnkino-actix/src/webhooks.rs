@@ -0,0 +1,115 @@
+use std::net::SocketAddr;
+
+use libfinnkino_core::webhooks::{self, Outgoing, Webhooks};
+
+use crate::Awc;
+
+/// POSTs a payload until it is accepted with a 2xx, the attempts of the
+/// retry policy are used up or the webhook is deleted, logging every attempt.
+pub async fn deliver(webhooks: &Webhooks, outgoing: Outgoing) {
+  webhooks::deliver(&Awc, webhooks, outgoing).await
+}
+
+/// POSTs `outgoing` to `addr` instead of wherever its host resolves to by
+/// now, and answers a redirect with its own status.
+pub(crate) async fn post(outgoing: &Outgoing, addr: SocketAddr) -> Result<u16, String> {
+  let client = awc::Client::builder().disable_redirects().finish();
+  let mut request = client.post(outgoing.url.as_str()).address(addr);
+  for header in outgoing.headers() {
+    request = request.insert_header(header);
+  }
+  request
+    .send_body(outgoing.body.clone())
+    .await
+    .map(|response| response.status().as_u16())
+    .map_err(|error| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use libfinnkino_core::finnkino::Show;
+  use libfinnkino_core::id::{AreaId, EventId, ShowId};
+  use libfinnkino_core::time::LocalDateTime;
+  use libfinnkino_core::webhooks::{RegistrationRequest, SIGNATURE_HEADER, TRIGGER_HEADER};
+  use pretty_assertions::assert_eq;
+  use wiremock::matchers::{header, method, path};
+  use wiremock::{Mock, MockServer, ResponseTemplate};
+
+  const AREA: AreaId = AreaId(1014);
+  const EVENT: EventId = EventId(303000);
+
+  fn outgoing(webhooks: &Webhooks, url: String) -> Outgoing {
+    let request = RegistrationRequest {
+      url,
+      secret: "s3cret".to_string(),
+      area: AREA,
+      events: vec![EVENT],
+    };
+    webhooks.register(request, LocalDateTime::default());
+    let show = Show {
+      id: ShowId(1),
+      event_id: EVENT,
+      ..Show::default()
+    };
+    webhooks.update(AREA, vec![], &[], LocalDateTime::default());
+    webhooks
+      .update(AREA, vec![show], &[], LocalDateTime::default())
+      .remove(0)
+  }
+
+  #[actix_rt::test]
+  async fn test_post_signed_payload() {
+    let receiver = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(path("/hook"))
+      .and(header(TRIGGER_HEADER, "shows.added"))
+      .respond_with(ResponseTemplate::new(204))
+      .mount(&receiver)
+      .await;
+    let webhooks = Webhooks::default();
+    let outgoing = outgoing(&webhooks, format!("{}/hook", receiver.uri()));
+
+    assert_eq!(Ok(204), post(&outgoing, *receiver.address()).await);
+
+    let requests = receiver.received_requests().await.unwrap();
+    let signature = requests[0].headers.get(&SIGNATURE_HEADER.into()).unwrap();
+    let body = String::from_utf8(requests[0].body.clone()).unwrap();
+    assert!(webhooks::verify("s3cret", &body, signature.as_str()));
+  }
+
+  #[actix_rt::test]
+  async fn test_post_connects_to_the_resolved_address() {
+    let receiver = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(path("/hook"))
+      .respond_with(ResponseTemplate::new(204))
+      .mount(&receiver)
+      .await;
+    let webhooks = Webhooks::default();
+    let url = format!("http://webhooks.invalid:{}/hook", receiver.address().port());
+    let outgoing = outgoing(&webhooks, url);
+
+    assert_eq!(Ok(204), post(&outgoing, *receiver.address()).await);
+  }
+
+  #[actix_rt::test]
+  async fn test_post_does_not_follow_redirects() {
+    let receiver = MockServer::start().await;
+    Mock::given(method("POST"))
+      .and(path("/hook"))
+      .respond_with(ResponseTemplate::new(307).insert_header("Location", "/internal"))
+      .mount(&receiver)
+      .await;
+    Mock::given(method("POST"))
+      .and(path("/internal"))
+      .respond_with(ResponseTemplate::new(204))
+      .expect(0)
+      .mount(&receiver)
+      .await;
+    let webhooks = Webhooks::default();
+    let outgoing = outgoing(&webhooks, format!("{}/hook", receiver.uri()));
+
+    assert_eq!(Ok(307), post(&outgoing, *receiver.address()).await);
+  }
+}
@@ -0,0 +1,189 @@
+use axum::extract::{FromRequestParts, RawPathParams};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use std::convert::Infallible;
+use std::str::FromStr;
+
+use libfinnkino_core::ical;
+use libfinnkino_core::id::ParseIdError;
+use libfinnkino_core::json;
+use libfinnkino_core::live;
+
+/// A rejected request with the status to answer with.
+#[derive(Debug)]
+pub struct ApiError {
+  pub status: StatusCode,
+  pub errors: json::Errors,
+}
+
+impl IntoResponse for ApiError {
+  fn into_response(self) -> Response {
+    match serde_json::to_string(&self.errors) {
+      Err(error) => (StatusCode::INTERNAL_SERVER_ERROR, error.to_string()).into_response(),
+      Ok(json) => (self.status, [(CONTENT_TYPE, "application/json")], json).into_response(),
+    }
+  }
+}
+
+/// The `{id}` segment of the matched path, or `""` without one.
+async fn id_param<S: Send + Sync>(parts: &mut Parts, state: &S) -> String {
+  let params = RawPathParams::from_request_parts(parts, state).await;
+  params
+    .ok()
+    .and_then(|params| {
+      params
+        .iter()
+        .find(|(name, _)| *name == "id")
+        .map(|(_, value)| value.to_string())
+    })
+    .unwrap_or_default()
+}
+
+fn parse_id<T: FromStr<Err = ParseIdError>>(value: &str) -> Result<T, ApiError> {
+  value.parse::<T>().map_err(|err| ApiError {
+    status: StatusCode::BAD_REQUEST,
+    errors: json::Errors {
+      errors: vec![json::Error::invalid_path_parameter("id", err.to_string())],
+    },
+  })
+}
+
+/// Extracts the `{id}` segment of the matched path as one of the typed ids.
+#[derive(Debug, Eq, PartialEq)]
+pub struct PathId<T>(pub T);
+
+impl<S: Send + Sync, T: FromStr<Err = ParseIdError>> FromRequestParts<S> for PathId<T> {
+  type Rejection = ApiError;
+
+  async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+    parse_id(&id_param(parts, state).await).map(PathId)
+  }
+}
+
+/// A typed id taken from an `{id}` segment ending in `.ics`, as the router
+/// only matches whole segments. Without the extension the path is not found,
+/// as with a router that can match it.
+#[derive(Debug, Eq, PartialEq)]
+pub struct CalendarId<T>(pub T);
+
+impl<S: Send + Sync, T: FromStr<Err = ParseIdError>> FromRequestParts<S> for CalendarId<T> {
+  type Rejection = ApiError;
+
+  async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+    let segment = id_param(parts, state).await;
+    match segment.strip_suffix(ical::EXTENSION) {
+      None => Err(ApiError {
+        status: StatusCode::NOT_FOUND,
+        errors: json::Errors {
+          errors: vec![json::Error::from_status(404, "Not Found")],
+        },
+      }),
+      Some(id) => parse_id(id).map(CalendarId),
+    }
+  }
+}
+
+/// The `Last-Event-ID` header of a reconnecting Server-Sent Events client.
+/// Missing and malformed ids are both `None`.
+#[derive(Debug, Eq, PartialEq)]
+pub struct LastEventId(pub Option<u64>);
+
+impl<S: Send + Sync> FromRequestParts<S> for LastEventId {
+  type Rejection = Infallible;
+
+  async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+    let id = parts
+      .headers
+      .get(live::LAST_EVENT_ID)
+      .and_then(|value| value.to_str().ok())
+      .and_then(live::parse_last_event_id);
+    Ok(LastEventId(id))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use axum::body::Body;
+  use axum::http::Request;
+  use axum::routing::get;
+  use axum::Router;
+  use libfinnkino_core::id::{EventId, ShowId};
+  use pretty_assertions::assert_eq;
+  use tower::ServiceExt;
+
+  async fn event(PathId(id): PathId<EventId>) -> String {
+    id.to_string()
+  }
+
+  async fn calendar(CalendarId(id): CalendarId<ShowId>) -> String {
+    id.to_string()
+  }
+
+  async fn last_event_id(LastEventId(id): LastEventId) -> String {
+    format!("{:?}", id)
+  }
+
+  async fn call(request: Request<Body>) -> (StatusCode, String) {
+    let router = Router::new()
+      .route("/events/{id}", get(event))
+      .route("/shows/{id}", get(calendar))
+      .route("/stream", get(last_event_id));
+    let response = router.oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+  }
+
+  async fn get_path(path: &str) -> (StatusCode, String) {
+    call(Request::get(path).body(Body::empty()).unwrap()).await
+  }
+
+  #[tokio::test]
+  async fn test_extract_path_id() {
+    assert_eq!(
+      (StatusCode::OK, "303000".to_string()),
+      get_path("/events/303000").await
+    );
+  }
+
+  #[tokio::test]
+  async fn test_extract_invalid_path_id() {
+    let (status, body) = get_path("/events/abc").await;
+    let errors: serde_json::Value = serde_json::from_str(&body).unwrap();
+
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!("id", errors["errors"][0]["source"]["parameter"]);
+  }
+
+  #[tokio::test]
+  async fn test_extract_calendar_id() {
+    assert_eq!(
+      (StatusCode::OK, "1794851".to_string()),
+      get_path("/shows/1794851.ics").await
+    );
+    assert_eq!(StatusCode::NOT_FOUND, get_path("/shows/1794851").await.0);
+    assert_eq!(StatusCode::BAD_REQUEST, get_path("/shows/abc.ics").await.0);
+  }
+
+  #[tokio::test]
+  async fn test_extract_last_event_id() {
+    let request = Request::get("/stream")
+      .header("Last-Event-ID", "42")
+      .body(Body::empty())
+      .unwrap();
+
+    assert_eq!(
+      (StatusCode::OK, "Some(42)".to_string()),
+      call(request).await
+    );
+    assert_eq!(
+      (StatusCode::OK, "None".to_string()),
+      get_path("/stream").await
+    );
+  }
+}
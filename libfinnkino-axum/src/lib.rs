@@ -0,0 +1,10 @@
+//! Axum support for the servers. The feeds are fetched with the cached
+//! reqwest client of `libfinnkino-client`, which runs on any tokio runtime.
+
+pub use libfinnkino_client::{
+  fetch_areas, fetch_events, fetch_schedule_dates, fetch_shows, get_areas, get_coming_soon,
+  get_event, get_events, get_news, get_schedule_dates, get_show, get_shows, get_upcoming_shows,
+  graphql, uncached, use_fixtures, use_recorder, use_upstream, webhooks,
+};
+
+pub mod extract;
@@ -0,0 +1,43 @@
+use libfinnkino_core::events::EventQuery;
+use libfinnkino_core::finnkino::{Event, Show, TheatreArea};
+use libfinnkino_core::graphql::{Source, SourceFuture};
+use libfinnkino_core::schedule::ShowFilter;
+
+use crate::{base_url, fetch_areas, fetch_events, fetch_shows};
+
+/// Backs the GraphQL schema with the cached reqwest client.
+#[derive(Clone, Debug)]
+pub struct Upstream {
+  base_url: String,
+}
+
+impl Upstream {
+  pub fn new(base_url: &str) -> Self {
+    Upstream {
+      base_url: base_url.to_string(),
+    }
+  }
+}
+
+impl Default for Upstream {
+  fn default() -> Self {
+    Upstream::new(base_url())
+  }
+}
+
+impl Source for Upstream {
+  fn areas(&self) -> SourceFuture<Vec<TheatreArea>> {
+    let base_url = self.base_url.clone();
+    Box::pin(async move { fetch_areas(&base_url).await })
+  }
+
+  fn shows(&self, filter: ShowFilter) -> SourceFuture<Vec<Show>> {
+    let base_url = self.base_url.clone();
+    Box::pin(async move { fetch_shows(&base_url, &filter).await })
+  }
+
+  fn events(&self, query: EventQuery) -> SourceFuture<Vec<Event>> {
+    let base_url = self.base_url.clone();
+    Box::pin(async move { fetch_events(&base_url, &query).await })
+  }
+}
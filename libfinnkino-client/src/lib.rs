@@ -0,0 +1,362 @@
+//! The cached reqwest client for the Finnkino feeds. It runs on any tokio
+//! runtime, so the rocket, axum and gRPC servers and the CLI share it.
+
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use libfinnkino_core::events::EventQuery;
+use libfinnkino_core::finnkino::{
+  Error, ErrorBuilder, ErrorKind, Event, NewsArticle, Show, TheatreArea, BODY_LIMIT,
+};
+use libfinnkino_core::fixtures::Fixtures;
+use libfinnkino_core::id::{AreaId, EventId, ShowId};
+use libfinnkino_core::recording::{Exchange, Recorder};
+use libfinnkino_core::schedule::ShowFilter;
+use libfinnkino_core::time::LocalDateTime;
+use libfinnkino_core::transport::{FeedClient, Transport};
+use libfinnkino_core::webhooks::Outgoing;
+
+pub use libfinnkino_core::transport::uncached;
+
+pub mod graphql;
+pub mod webhooks;
+
+struct Reqwest;
+
+impl Transport for Reqwest {
+  async fn send(&self, url: &str) -> Result<Exchange, Error> {
+    send(url).await
+  }
+
+  async fn post(&self, outgoing: &Outgoing, addr: SocketAddr) -> Result<u16, String> {
+    webhooks::post(outgoing, addr).await
+  }
+}
+
+fn client() -> &'static FeedClient<Reqwest> {
+  static CLIENT: OnceLock<FeedClient<Reqwest>> = OnceLock::new();
+  CLIENT.get_or_init(|| FeedClient::new(Reqwest))
+}
+
+/// Serves the feeds from `fixtures` instead of Finnkino from now on. Only
+/// the first call has an effect.
+pub fn use_fixtures(fixtures: Fixtures) {
+  client().use_fixtures(fixtures);
+}
+
+/// Records or replays the requests to Finnkino with `recorder` from now on.
+/// Only the first call has an effect.
+pub fn use_recorder(recorder: Recorder) {
+  client().use_recorder(recorder);
+}
+
+/// Reads the feeds from `base_url` instead of Finnkino from now on. Only the
+/// first call has an effect.
+pub fn use_upstream(base_url: String) {
+  client().use_upstream(base_url);
+}
+
+pub(crate) fn base_url() -> &'static str {
+  client().base_url()
+}
+
+pub async fn get_areas() -> Result<std::vec::Vec<TheatreArea>, Error> {
+  client().get_areas().await
+}
+
+pub async fn get_shows(filter: &ShowFilter) -> Result<std::vec::Vec<Show>, Error> {
+  client().get_shows(filter).await
+}
+
+pub async fn get_schedule_dates(
+  area: Option<AreaId>,
+) -> Result<std::vec::Vec<LocalDateTime>, Error> {
+  client().get_schedule_dates(area).await
+}
+
+pub async fn get_event(id: EventId) -> Result<Event, Error> {
+  client().get_event(id).await
+}
+
+pub async fn get_upcoming_shows(event_id: EventId) -> Result<std::vec::Vec<Show>, Error> {
+  client().get_upcoming_shows(event_id).await
+}
+
+pub async fn get_show(id: ShowId) -> Result<Show, Error> {
+  client().get_show(id).await
+}
+
+pub async fn get_events(query: &EventQuery) -> Result<std::vec::Vec<Event>, Error> {
+  client().get_events(query).await
+}
+
+pub async fn get_coming_soon() -> Result<std::vec::Vec<Event>, Error> {
+  client().get_coming_soon().await
+}
+
+pub async fn get_news() -> Result<std::vec::Vec<NewsArticle>, Error> {
+  client().get_news().await
+}
+
+/// The areas from `base_url` instead of the configured upstream.
+pub async fn fetch_areas(base_url: &str) -> Result<std::vec::Vec<TheatreArea>, Error> {
+  client().fetch_areas(base_url).await
+}
+
+pub async fn fetch_events(
+  base_url: &str,
+  query: &EventQuery,
+) -> Result<std::vec::Vec<Event>, Error> {
+  client().fetch_events(base_url, query).await
+}
+
+pub async fn fetch_shows(
+  base_url: &str,
+  filter: &ShowFilter,
+) -> Result<std::vec::Vec<Show>, Error> {
+  client().fetch_shows(base_url, filter).await
+}
+
+pub async fn fetch_schedule_dates(
+  base_url: &str,
+  area: Option<AreaId>,
+) -> Result<std::vec::Vec<LocalDateTime>, Error> {
+  client().fetch_schedule_dates(base_url, area).await
+}
+
+async fn send(url: &str) -> Result<Exchange, Error> {
+  let response = reqwest::Client::new()
+    .get(url)
+    .timeout(Duration::from_secs(4))
+    .send()
+    .await;
+
+  match response {
+    Err(error) if error.is_timeout() => {
+      let error_builder = ErrorBuilder::default()
+        .kind(ErrorKind::Timeout)
+        .title("Response took too long")
+        .detail("Timeout")
+        .build();
+      Err(error_builder.unwrap())
+    }
+    Err(error) => {
+      let error_builder = ErrorBuilder::default()
+        .kind(ErrorKind::Request)
+        .title("Request error")
+        .detail(error.to_string())
+        .build();
+      Err(error_builder.unwrap())
+    }
+    Ok(resp) => {
+      let status = resp.status().as_u16();
+      let headers = resp
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+          let value = String::from_utf8_lossy(value.as_bytes()).to_string();
+          (name.to_string(), value)
+        })
+        .collect();
+      // Not `text()`, which would replace invalid UTF-8 instead of failing
+      // like the actix client.
+      match read_body(resp).await.map(String::from_utf8) {
+        Err(error) => Err(error),
+        Ok(Err(error)) => {
+          let error_builder = ErrorBuilder::default()
+            .kind(ErrorKind::Encoding)
+            .title("Failed to parse XML")
+            .detail(error.to_string())
+            .build();
+          Err(error_builder.unwrap())
+        }
+        Ok(Ok(body)) => Ok(Exchange {
+          url: url.to_string(),
+          status,
+          headers,
+          body,
+        }),
+      }
+    }
+  }
+}
+
+/// Reads the body of `resp`, failing once it grows past [`BODY_LIMIT`].
+async fn read_body(mut resp: reqwest::Response) -> Result<Vec<u8>, Error> {
+  let mut body = Vec::new();
+  loop {
+    match resp.chunk().await {
+      Ok(None) => return Ok(body),
+      Ok(Some(chunk)) if body.len() + chunk.len() <= BODY_LIMIT => body.extend_from_slice(&chunk),
+      Ok(Some(_)) => {
+        let error_builder = ErrorBuilder::default()
+          .kind(ErrorKind::Body)
+          .title("Overflow")
+          .detail(format!("The body is longer than {} bytes", BODY_LIMIT))
+          .build();
+        return Err(error_builder.unwrap());
+      }
+      Err(error) => {
+        let error_builder = ErrorBuilder::default()
+          .kind(ErrorKind::Body)
+          .title("Response error")
+          .detail(error.to_string())
+          .build();
+        return Err(error_builder.unwrap());
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+  use std::time::Duration;
+  use url::Url;
+  use wiremock::matchers::{method, path};
+  use wiremock::{Mock, MockServer, ResponseTemplate};
+
+  #[tokio::test]
+  async fn test_get_xml() {
+    // Start a background HTTP server on a random local port
+    let mock_server = MockServer::start().await;
+
+    // Arrange the behaviour of the MockServer adding a Mock:
+    // when it receives a GET request on '/hello' it will respond with a 200.
+    let body = r#"<?xml version="1.0"?>
+    <TheatreAreas xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+      <TheatreArea>
+        <ID>1029</ID>
+        <Name>Valitse alue/teatteri</Name>
+      </TheatreArea>
+    </TheatreAreas>"#;
+    Mock::given(method("GET"))
+      .and(path("/xml/TheatreAreas"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/xml"))
+      // Mounting the mock on the mock server - it's now effective!
+      .mount(&mock_server)
+      .await;
+
+    let xml_result = client()
+      .get_xml(format!("{}/xml/TheatreAreas", &mock_server.uri()).as_str())
+      .await
+      .unwrap();
+
+    assert_eq!(xml_result, body);
+  }
+
+  #[tokio::test]
+  async fn test_get_xml_too_large_body() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/xml/TheatreAreas"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(" ".repeat(BODY_LIMIT + 1), "text/xml"))
+      .mount(&mock_server)
+      .await;
+
+    let xml_result = client()
+      .get_xml(format!("{}/xml/TheatreAreas", &mock_server.uri()).as_str())
+      .await
+      .unwrap_err();
+    let error = ErrorBuilder::default()
+      .kind(ErrorKind::Body)
+      .title("Overflow")
+      .detail(format!("The body is longer than {} bytes", BODY_LIMIT))
+      .build()
+      .unwrap();
+
+    assert_eq!(xml_result, error);
+  }
+
+  #[tokio::test]
+  async fn test_get_xml_not_found() {
+    // Start a background HTTP server on a random local port
+    let mock_server = MockServer::start().await;
+
+    // Arrange the behaviour of the MockServer adding a Mock:
+    // when it receives a GET request on '/hello' it will respond with a 200.
+    Mock::given(method("GET"))
+      .and(path("/xml/TheatreAreas"))
+      .respond_with(ResponseTemplate::new(200))
+      // Mounting the mock on the mock server - it's now effective!
+      .mount(&mock_server)
+      .await;
+
+    let xml_result = client()
+      .get_xml(format!("{}/xml/TheatreArea", &mock_server.uri()).as_str())
+      .await
+      .unwrap_err();
+    let error = ErrorBuilder::default()
+      .kind(ErrorKind::Status)
+      .status("404")
+      .title("Not Found")
+      .build()
+      .unwrap();
+
+    assert_eq!(xml_result, error);
+  }
+
+  #[tokio::test]
+  async fn test_get_xml_incorrect_scheme() {
+    // Start a background HTTP server on a random local port
+    let mock_server = MockServer::start().await;
+    let mock_url = Url::parse(&mock_server.uri()).unwrap();
+
+    // Arrange the behaviour of the MockServer adding a Mock:
+    // when it receives a GET request on '/hello' it will respond with a 200.
+    Mock::given(method("GET"))
+      .and(path("/xml/TheatreAreas"))
+      .respond_with(ResponseTemplate::new(200))
+      // Mounting the mock on the mock server - it's now effective!
+      .mount(&mock_server)
+      .await;
+
+    let xml_url = format!(
+      "htp://{}:{}/xml/TheatreArea",
+      mock_url.host().unwrap(),
+      mock_url.port().unwrap()
+    );
+    let xml_result = client().get_xml(xml_url.as_str()).await.unwrap_err();
+    let error = ErrorBuilder::default()
+      .kind(ErrorKind::Request)
+      .title("Request error")
+      .detail(format!(
+        "builder error for url ({}): URL scheme is not allowed",
+        xml_url
+      ))
+      .build()
+      .unwrap();
+
+    assert_eq!(xml_result, error);
+  }
+
+  #[tokio::test]
+  async fn test_get_xml_timeout() {
+    // Start a background HTTP server on a random local port
+    let mock_server = MockServer::start().await;
+    // Default AWC timeout is 5 seconds
+    let delay = Duration::from_secs(6);
+
+    // Arrange the behaviour of the MockServer adding a Mock:
+    // when it receives a GET request on '/hello' it will respond with a 200.
+    Mock::given(method("GET"))
+      .and(path("/xml/TheatreAreas"))
+      .respond_with(ResponseTemplate::new(200).set_delay(delay))
+      // Mounting the mock on the mock server - it's now effective!
+      .mount(&mock_server)
+      .await;
+
+    let xml_url = format!("{}/xml/TheatreAreas", &mock_server.uri());
+    let xml_result = client().get_xml(xml_url.as_str()).await.unwrap_err();
+    let error = ErrorBuilder::default()
+      .kind(ErrorKind::Timeout)
+      .title("Response took too long")
+      .detail("Timeout")
+      .build()
+      .unwrap();
+
+    assert_eq!(xml_result, error);
+  }
+}
@@ -0,0 +1,213 @@
+use reqwest::{Client, Method};
+use serde_json::Value;
+use std::fmt;
+
+/// Matches any value in an expected JSON body, e.g. a timestamp.
+pub const ANY: &str = "<any>";
+
+const JSON: &str = "application/json";
+
+/// What the body of a response must be.
+#[derive(Clone, Debug)]
+pub enum Body {
+  /// JSON equal to this, but for the [`ANY`] values.
+  Json(Value),
+  /// Text starting with this.
+  Text(&'static str),
+  Empty,
+  /// A stream that never ends, so only its headers are checked.
+  Stream,
+}
+
+/// A request and the response every server must give to it.
+#[derive(Clone, Debug)]
+pub struct Case {
+  method: Method,
+  path: String,
+  body: Option<String>,
+  request_headers: Vec<(&'static str, &'static str)>,
+  status: u16,
+  headers: Vec<(&'static str, String)>,
+  expected: Body,
+}
+
+impl fmt::Display for Case {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} {}", self.method, self.path)
+  }
+}
+
+impl Case {
+  fn new(method: Method, path: impl Into<String>) -> Self {
+    Case {
+      method,
+      path: path.into(),
+      body: None,
+      request_headers: Vec::new(),
+      status: 200,
+      headers: Vec::new(),
+      expected: Body::Empty,
+    }
+  }
+
+  pub fn get(path: impl Into<String>) -> Self {
+    Case::new(Method::GET, path)
+  }
+
+  pub fn post(path: impl Into<String>, body: impl Into<String>) -> Self {
+    Case {
+      body: Some(body.into()),
+      ..Case::new(Method::POST, path)
+    }
+  }
+
+  pub fn delete(path: impl Into<String>) -> Self {
+    Case::new(Method::DELETE, path)
+  }
+
+  pub fn request_header(mut self, name: &'static str, value: &'static str) -> Self {
+    self.request_headers.push((name, value));
+    self
+  }
+
+  /// Expects a header of the response.
+  pub fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+    self.headers.push((name, value.into()));
+    self
+  }
+
+  pub fn json(mut self, status: u16, body: Value) -> Self {
+    self.status = status;
+    self.expected = Body::Json(body);
+    self.header("content-type", JSON)
+  }
+
+  pub fn text(mut self, status: u16, content_type: &str, prefix: &'static str) -> Self {
+    self.status = status;
+    self.expected = Body::Text(prefix);
+    self.header("content-type", content_type)
+  }
+
+  pub fn stream(mut self, status: u16, content_type: &str) -> Self {
+    self.status = status;
+    self.expected = Body::Stream;
+    self.header("content-type", content_type)
+  }
+
+  pub fn empty(mut self, status: u16) -> Self {
+    self.status = status;
+    self.expected = Body::Empty;
+    self
+  }
+
+  /// Sends the request to the server at `url` and lists how the response
+  /// differs from the expected one.
+  pub async fn run(&self, client: &Client, url: &str) -> Vec<String> {
+    let mut request = client.request(self.method.clone(), format!("{}{}", url, self.path));
+    for (name, value) in &self.request_headers {
+      request = request.header(*name, *value);
+    }
+    if let Some(body) = &self.body {
+      request = request.header("content-type", JSON).body(body.clone());
+    }
+    let response = match request.send().await {
+      Err(error) => return vec![format!("{}: {}", self, error)],
+      Ok(response) => response,
+    };
+
+    let mut differences = Vec::new();
+    if response.status().as_u16() != self.status {
+      differences.push(format!(
+        "{}: status {} instead of {}",
+        self,
+        response.status().as_u16(),
+        self.status
+      ));
+    }
+    for (name, expected) in &self.headers {
+      let actual = response
+        .headers()
+        .get(*name)
+        .and_then(|value| value.to_str().ok());
+      if actual != Some(expected.as_str()) {
+        differences.push(format!(
+          "{}: header {} is {:?} instead of {:?}",
+          self, name, actual, expected
+        ));
+      }
+    }
+    if let Body::Stream = self.expected {
+      return differences;
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    let matches = match &self.expected {
+      Body::Json(expected) => serde_json::from_str(&body)
+        .map(|actual| matches(expected, &actual))
+        .unwrap_or(false),
+      Body::Text(prefix) => body.starts_with(prefix),
+      Body::Empty => body.is_empty(),
+      Body::Stream => true,
+    };
+    if !matches {
+      let expected = match &self.expected {
+        Body::Json(expected) => expected.to_string(),
+        Body::Text(prefix) => format!("{}...", prefix),
+        Body::Empty | Body::Stream => String::new(),
+      };
+      differences.push(format!(
+        "{}: body\n  {}\ninstead of\n  {}",
+        self, body, expected
+      ));
+    }
+    differences
+  }
+}
+
+/// Whether `actual` is `expected`, where [`ANY`] stands for any value.
+fn matches(expected: &Value, actual: &Value) -> bool {
+  match (expected, actual) {
+    (Value::String(any), _) if any == ANY => true,
+    (Value::Array(expected), Value::Array(actual)) => {
+      expected.len() == actual.len()
+        && expected
+          .iter()
+          .zip(actual)
+          .all(|(expected, actual)| matches(expected, actual))
+    }
+    (Value::Object(expected), Value::Object(actual)) => {
+      expected.len() == actual.len()
+        && expected.iter().all(|(key, expected)| {
+          actual
+            .get(key)
+            .is_some_and(|actual| matches(expected, actual))
+        })
+    }
+    _ => expected == actual,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn test_matches() {
+    let expected = json!({"id": "1", "created": ANY, "tags": ["a", ANY]});
+
+    assert!(matches(
+      &expected,
+      &json!({"id": "1", "created": "2022-08-20T09:00:00Z", "tags": ["a", "b"]})
+    ));
+    assert!(!matches(
+      &expected,
+      &json!({"id": "2", "created": "2022-08-20T09:00:00Z", "tags": ["a", "b"]})
+    ));
+    assert!(!matches(&expected, &json!({"id": "1", "tags": ["a", "b"]})));
+    assert!(!matches(
+      &expected,
+      &json!({"id": "1", "created": null, "tags": ["a"]})
+    ));
+  }
+}
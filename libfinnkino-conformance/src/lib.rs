@@ -0,0 +1,408 @@
+//! A black-box conformance suite for the servers. A server binary is
+//! launched against a wiremock upstream serving `fixtures/`, and every route
+//! is checked for its status, headers and body. The servers all pass the
+//! same cases, so that they stay interchangeable.
+//!
+//! Each server runs the suite from its integration tests:
+//!
+//! ```ignore
+//! libfinnkino_conformance::check(env!("CARGO_BIN_EXE_finnkino-actix")).await;
+//! ```
+
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use libfinnkino_core::api::{DEPRECATION, SUNSET};
+use libfinnkino_core::feeds::{ATOM_CONTENT_TYPE, RSS_CONTENT_TYPE};
+use libfinnkino_core::{ical, live};
+
+pub mod case;
+pub mod server;
+pub mod upstream;
+
+pub use case::{Case, ANY};
+pub use server::Server;
+
+use upstream::{FAILING_EVENT, MALFORMED_EVENT, UNAVAILABLE_AREA};
+
+const HTML: &str = "text/html; charset=utf-8";
+const DOCTYPE: &str = "<!DOCTYPE html>";
+const VCALENDAR: &str = "BEGIN:VCALENDAR";
+
+/// A body from `expected/`.
+fn expected(json: &str) -> Value {
+  serde_json::from_str(json).unwrap()
+}
+
+fn not_found() -> Value {
+  json!({"errors": [{"status": "404", "title": "Not Found"}]})
+}
+
+fn no(what: &str) -> Value {
+  json!({"errors": [{"status": "404", "title": "Not Found", "detail": format!("No {}", what)}]})
+}
+
+fn invalid_id(id: &str, kind: &str) -> Value {
+  json!({"errors": [{
+    "status": "400",
+    "title": "Invalid path parameter",
+    "detail": format!("'{}' is not a valid {} id, it must be numeric", id, kind),
+    "source": {"parameter": "id"}
+  }]})
+}
+
+fn invalid_query(parameter: &str, detail: &str) -> Value {
+  json!({"errors": [{
+    "status": "400",
+    "title": "Invalid query parameter",
+    "detail": detail,
+    "source": {"parameter": parameter}
+  }]})
+}
+
+fn invalid_attribute(pointer: &str, detail: &str) -> Value {
+  json!({
+    "status": "400",
+    "title": "Invalid attribute",
+    "detail": detail,
+    "source": {"pointer": pointer}
+  })
+}
+
+fn upstream_status(status: u16, title: &str) -> Value {
+  json!({"errors": [{"status": status.to_string(), "title": title}]})
+}
+
+fn areas() -> Value {
+  json!([
+    {"id": "1029", "name": "Valitse alue/teatteri"},
+    {"id": "1014", "name": "Pääkaupunkiseutu"},
+    {"id": "1038", "name": "Helsinki: TENNISPALATSI"},
+    {"id": "1033", "name": "Helsinki: KINOPALATSI"}
+  ])
+}
+
+fn pages() -> Vec<Case> {
+  vec![
+    Case::get("/").text(200, HTML, DOCTYPE),
+    Case::get("/schedule?area=1014").text(200, HTML, DOCTYPE),
+    Case::get("/schedule").text(400, HTML, DOCTYPE),
+    Case::get("/schedule?area=1").text(404, HTML, DOCTYPE),
+    Case::get(format!("/schedule?area={}", UNAVAILABLE_AREA)).text(502, HTML, DOCTYPE),
+    Case::get("/films/303000").text(200, HTML, DOCTYPE),
+    Case::get("/films/abc").text(400, HTML, DOCTYPE),
+    Case::get("/films/1").text(404, HTML, DOCTYPE),
+    Case::get("/watchlists/1").text(404, HTML, DOCTYPE),
+    Case::get("/watchlists/abc").text(400, HTML, DOCTYPE),
+  ]
+}
+
+fn graphql() -> Vec<Case> {
+  vec![
+    Case::get("/graphql").text(200, HTML, DOCTYPE),
+    Case::post("/graphql", r#"{"query": "{ areas { id } }"}"#).json(
+      200,
+      json!({"data": {"areas": [{"id": "1029"}, {"id": "1014"}, {"id": "1038"}, {"id": "1033"}]}}),
+    ),
+    Case::post("/graphql", "{").json(
+      400,
+      json!({"errors": [invalid_attribute("", "EOF while parsing an object at line 1 column 1")]}),
+    ),
+  ]
+}
+
+fn websocket() -> Vec<Case> {
+  let invalid = json!({"errors": [{
+    "status": "400",
+    "title": "Invalid WebSocket handshake",
+    "detail": "Expected a GET with Upgrade: websocket, Connection: Upgrade and a Sec-WebSocket-Key"
+  }]});
+  vec![
+    Case::get("/ws")
+      .request_header("Connection", "Upgrade")
+      .request_header("Upgrade", "websocket")
+      .request_header("Sec-WebSocket-Version", "13")
+      .request_header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==")
+      .header("sec-websocket-accept", "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=")
+      .empty(101),
+    Case::get("/ws").json(400, invalid.clone()),
+    Case::get("/ws")
+      .request_header("Upgrade", "websocket")
+      .request_header("Sec-WebSocket-Version", "13")
+      .request_header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==")
+      .json(400, invalid),
+    Case::get("/ws")
+      .request_header("Connection", "keep-alive, Upgrade")
+      .request_header("Upgrade", "websocket")
+      .request_header("Sec-WebSocket-Version", "8")
+      .request_header("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ==")
+      .header("sec-websocket-version", "13")
+      .json(
+        426,
+        json!({"errors": [{
+          "status": "426",
+          "title": "Unsupported WebSocket version",
+          "detail": "Expected Sec-WebSocket-Version: 13"
+        }]}),
+      ),
+  ]
+}
+
+fn calendars() -> Vec<Case> {
+  vec![
+    Case::get("/calendars/area/1014.ics").text(200, ical::CONTENT_TYPE, VCALENDAR),
+    Case::get("/calendars/area/1.ics").json(404, no("area with id 1")),
+    Case::get("/calendars/area/abc.ics").json(400, invalid_id("abc", "area")),
+    Case::get("/calendars/area/1014").json(404, not_found()),
+    Case::get("/calendars/event/303000.ics").text(200, ical::CONTENT_TYPE, VCALENDAR),
+    Case::get("/calendars/event/1.ics").json(404, no("event with id 1")),
+  ]
+}
+
+fn feeds() -> Vec<Case> {
+  vec![
+    Case::get("/feeds/coming-soon.atom").text(200, ATOM_CONTENT_TYPE, "<?xml"),
+    Case::get("/feeds/coming-soon.rss").text(200, RSS_CONTENT_TYPE, "<?xml"),
+    Case::get("/feeds/news.atom").text(200, ATOM_CONTENT_TYPE, "<?xml"),
+    Case::get("/feeds/news.rss").text(200, RSS_CONTENT_TYPE, "<?xml"),
+    Case::get("/feeds/news.json").json(404, not_found()),
+  ]
+}
+
+fn api() -> Vec<Case> {
+  vec![
+    Case::get("/api/v1/areas").json(200, areas()),
+    Case::get("/api/v1/shows?area=1038")
+      .json(200, expected(include_str!("../expected/shows.json"))),
+    Case::get("/api/v1/shows?date=tomorrow").json(
+      400,
+      invalid_query("date", "Must be a date formatted as YYYY-MM-DD"),
+    ),
+    Case::get(format!("/api/v1/shows?area={}", UNAVAILABLE_AREA))
+      .json(502, upstream_status(503, "Service Unavailable")),
+    Case::get("/api/v1/shows/stream?area=1014")
+      .header("cache-control", "no-cache")
+      .stream(200, live::CONTENT_TYPE),
+    Case::get("/api/v1/shows/stream").json(400, invalid_query("area", "Is required")),
+    Case::get(format!("/api/v1/shows/stream?area={}", UNAVAILABLE_AREA))
+      .json(502, upstream_status(503, "Service Unavailable")),
+    Case::get("/api/v1/shows.ics?area=1038").text(200, ical::CONTENT_TYPE, VCALENDAR),
+    Case::get("/api/v1/shows/1794851.ics").text(200, ical::CONTENT_TYPE, VCALENDAR),
+    Case::get("/api/v1/shows/1.ics").json(404, no("upcoming show with id 1")),
+    Case::get("/api/v1/shows/abc.ics").json(400, invalid_id("abc", "show")),
+    Case::get("/api/v1/shows/1794851").json(404, not_found()),
+    Case::get("/api/v1/events/303000").json(200, expected(include_str!("../expected/event.json"))),
+    Case::get("/api/v1/events/1").json(404, no("event with id 1")),
+    Case::get("/api/v1/events/abc").json(400, invalid_id("abc", "event")),
+    Case::get(format!("/api/v1/events/{}", FAILING_EVENT))
+      .json(502, upstream_status(500, "Internal Server Error")),
+    Case::get(format!("/api/v1/events/{}", MALFORMED_EVENT)).json(
+      502,
+      json!({"errors": [{"status": "502", "title": "Failed to serialize XML", "detail": "Eof"}]}),
+    ),
+  ]
+}
+
+/// The unversioned paths answer like the v1 ones, pointing to them.
+fn deprecated_api() -> Vec<Case> {
+  vec![
+    Case::get("/api/areas")
+      .header("deprecation", DEPRECATION)
+      .header("sunset", SUNSET)
+      .header("link", "</api/v1/areas>; rel=\"successor-version\"")
+      .json(200, areas()),
+    Case::get("/api/events/abc")
+      .header("deprecation", DEPRECATION)
+      .header("sunset", SUNSET)
+      .header("link", "</api/v1/events/abc>; rel=\"successor-version\"")
+      .json(400, invalid_id("abc", "event")),
+  ]
+}
+
+/// Creates, reads and deletes a watchlist and a saved search, in this order
+/// against a fresh database.
+fn watchlists() -> Vec<Case> {
+  let watchlist = json!({
+    "id": "1",
+    "owner": "alice",
+    "name": "Weekend",
+    "events": ["303000"],
+    "created_at": ANY
+  });
+  let search = json!({
+    "id": "1",
+    "owner": "alice",
+    "name": "Evenings",
+    "query": "area=1038&after=18:00",
+    "created_at": ANY
+  });
+  vec![
+    Case::post(
+      "/api/v1/watchlists",
+      r#"{"owner": "alice", "name": "Weekend", "events": ["303000"]}"#,
+    )
+    .json(201, watchlist.clone()),
+    Case::post("/api/v1/watchlists", r#"{"owner": "", "name": "Weekend"}"#).json(
+      400,
+      json!({"errors": [invalid_attribute("/owner", "Must not be empty")]}),
+    ),
+    Case::post("/api/v1/watchlists", "{").json(
+      400,
+      json!({"errors": [invalid_attribute("", "EOF while parsing an object at line 1 column 1")]}),
+    ),
+    Case::get("/api/v1/watchlists?owner=alice").json(200, json!([watchlist])),
+    Case::get("/api/v1/watchlists?owner=bob").json(200, json!([])),
+    Case::get("/api/v1/watchlists/1").json(200, watchlist),
+    Case::get("/api/v1/watchlists/1/shows").json(
+      200,
+      expected(include_str!("../expected/watchlist-shows.json")),
+    ),
+    Case::get("/watchlists/1").text(200, HTML, DOCTYPE),
+    Case::get("/api/v1/watchlists/2").json(404, no("watchlist with id 2")),
+    Case::get("/api/v1/watchlists/2/shows").json(404, no("watchlist with id 2")),
+    Case::get("/api/v1/watchlists/abc").json(400, invalid_id("abc", "watchlist")),
+    Case::delete("/api/v1/watchlists/1").empty(204),
+    Case::delete("/api/v1/watchlists/1").json(404, no("watchlist with id 1")),
+    Case::post(
+      "/api/v1/searches",
+      r#"{"owner": "alice", "name": "Evenings", "query": "area=1038&after=18:00"}"#,
+    )
+    .json(201, search.clone()),
+    Case::post(
+      "/api/v1/searches",
+      r#"{"owner": "alice", "name": "Evenings", "query": "after=6pm"}"#,
+    )
+    .json(
+      400,
+      json!({"errors": [invalid_attribute("/query", "Must be a time formatted as HH:MM")]}),
+    ),
+    Case::get("/api/v1/searches").json(200, json!([search])),
+    Case::get("/api/v1/searches/1").json(200, search),
+    Case::get("/api/v1/searches/1/shows")
+      .json(200, expected(include_str!("../expected/search-shows.json"))),
+    Case::get("/api/v1/searches/2").json(404, no("search with id 2")),
+    Case::get("/api/v1/searches/abc/shows").json(400, invalid_id("abc", "search")),
+    Case::delete("/api/v1/searches/1").empty(204),
+    Case::delete("/api/v1/searches/1").json(404, no("search with id 1")),
+  ]
+}
+
+fn webhooks() -> Vec<Case> {
+  let webhook = json!({
+    "id": "1",
+    "url": "https://example.com/hook",
+    "area": "1014",
+    "events": [],
+    "created_at": ANY
+  });
+  vec![
+    Case::post(
+      "/api/v1/webhooks",
+      r#"{"url": "https://example.com/hook", "secret": "s3cret", "area": "1014"}"#,
+    )
+    .json(201, webhook.clone()),
+    Case::post(
+      "/api/v1/webhooks",
+      r#"{"url": "http://127.0.0.1:9/hook", "secret": "s3cret", "area": "1014"}"#,
+    )
+    .json(
+      400,
+      json!({"errors": [invalid_attribute(
+        "/url",
+        "'http://127.0.0.1:9/hook' is not a public address"
+      )]}),
+    ),
+    Case::post(
+      "/api/v1/webhooks",
+      r#"{"url": "nope", "secret": "", "area": "1014"}"#,
+    )
+    .json(
+      400,
+      json!({"errors": [
+        invalid_attribute("/url", "'nope' is not an http or https URL"),
+        invalid_attribute("/secret", "Must not be empty")
+      ]}),
+    ),
+    Case::get("/api/v1/webhooks").json(200, json!([webhook])),
+    Case::get("/api/v1/webhooks/1").json(200, webhook),
+    Case::get("/api/v1/webhooks/1/deliveries").json(200, json!([])),
+    Case::get("/api/v1/webhooks/2").json(404, no("webhook with id 2")),
+    Case::get("/api/v1/webhooks/abc").json(400, invalid_id("abc", "webhook")),
+    Case::delete("/api/v1/webhooks/1").empty(204),
+    Case::delete("/api/v1/webhooks/1").json(404, no("webhook with id 1")),
+    Case::get("/api/v1/webhooks/1/deliveries").json(404, no("webhook with id 1")),
+  ]
+}
+
+/// The archive and the snapshots are filled in the background, so only
+/// what does not depend on it is checked.
+fn history() -> Vec<Case> {
+  vec![
+    Case::get("/api/v1/archive/shows?date=2022-08-20").json(200, json!(ANY)),
+    Case::get("/api/v1/archive/shows").json(400, invalid_query("date", "Is required")),
+    Case::get("/api/v1/archive/events/1/screenings").json(404, no("archived event with id 1")),
+    Case::get("/api/v1/archive/events/abc/screenings").json(400, invalid_id("abc", "event")),
+    Case::get("/api/v1/shows/changes?area=1&since=2022-08-20")
+      .json(404, no("schedule snapshot of area with id 1")),
+    Case::get("/api/v1/shows/changes?area=1014").json(400, invalid_query("since", "Is required")),
+  ]
+}
+
+/// Paths and methods no route matches.
+fn fallbacks() -> Vec<Case> {
+  vec![
+    Case::get("/nope").json(404, not_found()),
+    Case::get("/api/v2/areas").json(404, not_found()),
+    Case::post("/api/v1/areas", "{}").json(404, not_found()),
+    Case::delete("/api/v1/watchlists").json(404, not_found()),
+  ]
+}
+
+/// Run against an upstream whose every feed fails.
+fn unavailable() -> Vec<Case> {
+  vec![Case::get("/api/v1/areas").json(502, upstream_status(503, "Service Unavailable"))]
+}
+
+/// Launches `program` and runs every case against it, failing with all the
+/// differences found.
+pub async fn check(program: &str) {
+  let upstream = upstream::start().await;
+  let server = Server::launch(program, &format!("{}/xml", upstream.uri()));
+  let client = Client::builder()
+    .no_proxy()
+    .timeout(Duration::from_secs(10))
+    .build()
+    .unwrap();
+
+  let cases = [
+    pages(),
+    graphql(),
+    websocket(),
+    calendars(),
+    feeds(),
+    api(),
+    deprecated_api(),
+    watchlists(),
+    webhooks(),
+    history(),
+    fallbacks(),
+  ];
+  let mut differences = Vec::new();
+  for case in cases.iter().flatten() {
+    differences.extend(case.run(&client, &server.url).await);
+  }
+  drop(server);
+
+  let upstream = upstream::start_unavailable().await;
+  let server = Server::launch(program, &format!("{}/xml", upstream.uri()));
+  for case in unavailable() {
+    differences.extend(case.run(&client, &server.url).await);
+  }
+  assert!(
+    differences.is_empty(),
+    "{} differs:\n\n{}",
+    program,
+    differences.join("\n\n")
+  );
+}
@@ -0,0 +1,77 @@
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use libfinnkino_core::config::{CONFIG_ENV, PORT_ENV, UPSTREAM_ENV};
+use libfinnkino_core::fixtures::FIXTURES_ENV;
+use libfinnkino_core::storage::DATABASE_ENV;
+
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A server binary running in the background with a database of its own,
+/// stopped and cleaned up when dropped.
+pub struct Server {
+  process: Child,
+  database: PathBuf,
+  pub url: String,
+}
+
+impl Server {
+  /// Starts `program` on a free port with the feeds from `upstream`, and
+  /// waits until it accepts connections.
+  pub fn launch(program: &str, upstream: &str) -> Server {
+    let port = TcpListener::bind("127.0.0.1:0")
+      .and_then(|listener| listener.local_addr())
+      .map(|address| address.port())
+      .expect("No free port");
+    let name = Path::new(program)
+      .file_stem()
+      .and_then(|name| name.to_str())
+      .unwrap_or("server");
+    let database = std::env::temp_dir().join(format!("finnkino-conformance-{}-{}.db", name, port));
+    let _ = std::fs::remove_file(&database);
+    // Away from any finnkino.toml or Rocket.toml of the working directory.
+    let process = Command::new(program)
+      .current_dir(std::env::temp_dir())
+      .env_remove(CONFIG_ENV)
+      .env_remove(FIXTURES_ENV)
+      .env(UPSTREAM_ENV, upstream)
+      .env(DATABASE_ENV, &database)
+      .env(PORT_ENV, port.to_string())
+      .stdin(Stdio::null())
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .spawn()
+      .unwrap_or_else(|error| panic!("Starting {} failed: {}", program, error));
+    let mut server = Server {
+      process,
+      database,
+      url: format!("http://127.0.0.1:{}", port),
+    };
+    server.wait_until_listening(port);
+    server
+  }
+
+  fn wait_until_listening(&mut self, port: u16) {
+    let started = Instant::now();
+    while TcpStream::connect(("127.0.0.1", port)).is_err() {
+      if let Ok(Some(status)) = self.process.try_wait() {
+        panic!("The server exited on startup with {}", status);
+      }
+      if started.elapsed() > STARTUP_TIMEOUT {
+        panic!("The server did not listen on {} in time", port);
+      }
+      thread::sleep(Duration::from_millis(50));
+    }
+  }
+}
+
+impl Drop for Server {
+  fn drop(&mut self) {
+    let _ = self.process.kill();
+    let _ = self.process.wait();
+    let _ = std::fs::remove_file(&self.database);
+  }
+}
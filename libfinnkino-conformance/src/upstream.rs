@@ -0,0 +1,64 @@
+use wiremock::matchers::{method, path_regex, query_param};
+use wiremock::{Mock, MockBuilder, MockServer, ResponseTemplate};
+
+/// The area whose Schedule fails with a 503.
+pub const UNAVAILABLE_AREA: &str = "9999";
+/// The event whose lookup fails with a 500.
+pub const FAILING_EVENT: &str = "500";
+/// The event whose lookup answers with malformed XML.
+pub const MALFORMED_EVENT: &str = "666";
+
+const THEATRE_AREAS: &str = include_str!("../../fixtures/TheatreAreas.xml");
+const SCHEDULE: &str = include_str!("../../fixtures/Schedule.xml");
+const SCHEDULE_DATES: &str = include_str!("../../fixtures/ScheduleDates.xml");
+const EVENTS: &str = include_str!("../../fixtures/Events.xml");
+const COMING_SOON: &str = include_str!("../../fixtures/Events/listType=ComingSoon.xml");
+const NEWS: &str = include_str!("../../fixtures/News.xml");
+
+fn feed(name: &str) -> MockBuilder {
+  Mock::given(method("GET")).and(path_regex(format!("(?i)^/xml/{}/?$", name)))
+}
+
+fn xml(body: &str) -> ResponseTemplate {
+  ResponseTemplate::new(200).set_body_raw(body, "text/xml; charset=utf-8")
+}
+
+/// Serves the feeds in `fixtures/` whatever the query, except for the
+/// failures above. The base URL of the feeds is `uri()` + `/xml`.
+pub async fn start() -> MockServer {
+  let server = MockServer::start().await;
+  let mocks = [
+    feed("Schedule")
+      .and(query_param("area", UNAVAILABLE_AREA))
+      .respond_with(ResponseTemplate::new(503)),
+    feed("Events")
+      .and(query_param("eventID", FAILING_EVENT))
+      .respond_with(ResponseTemplate::new(500)),
+    feed("Events")
+      .and(query_param("eventID", MALFORMED_EVENT))
+      .respond_with(xml("<Events><Event>")),
+    feed("Events")
+      .and(query_param("listType", "ComingSoon"))
+      .respond_with(xml(COMING_SOON)),
+    feed("TheatreAreas").respond_with(xml(THEATRE_AREAS)),
+    feed("Schedule").respond_with(xml(SCHEDULE)),
+    feed("ScheduleDates").respond_with(xml(SCHEDULE_DATES)),
+    feed("Events").respond_with(xml(EVENTS)),
+    feed("News").respond_with(xml(NEWS)),
+  ];
+  for mock in mocks {
+    mock.mount(&server).await;
+  }
+  server
+}
+
+/// Answers every feed with a 503, for what fails only when Finnkino is down
+/// as a whole.
+pub async fn start_unavailable() -> MockServer {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .respond_with(ResponseTemplate::new(503))
+    .mount(&server)
+    .await;
+  server
+}
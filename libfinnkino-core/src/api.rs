@@ -0,0 +1,80 @@
+//! Versioning of the public JSON API.
+//!
+//! Every route lives under a versioned prefix (`/api/v1`). The unversioned
+//! `/api` prefix is kept as an alias of v1 for existing clients, but every
+//! response served through it carries `Deprecation`, `Sunset` and a `Link` to
+//! the versioned successor.
+//!
+//! Response bodies of a version are built only from the types in its module
+//! (see [`v1`]), never by serializing the upstream models directly. Fields of
+//! a released version are never removed, renamed or retyped; breaking changes
+//! go into a new version module and prefix while the old one keeps its shape.
+
+pub mod v1;
+
+pub const UNVERSIONED_PREFIX: &str = "/api";
+pub const V1_PREFIX: &str = "/api/v1";
+
+/// Value of the `Deprecation` header (RFC 9745) for unversioned routes.
+pub const DEPRECATION: &str = "@1792281600";
+/// Value of the `Sunset` header (RFC 8594) for unversioned routes.
+pub const SUNSET: &str = "Thu, 01 Apr 2027 00:00:00 GMT";
+
+pub fn is_deprecated_path(path: &str) -> bool {
+  match path.strip_prefix(UNVERSIONED_PREFIX) {
+    None => false,
+    Some(rest) => {
+      (rest.is_empty() || rest.starts_with('/')) && !is_versioned(rest.trim_start_matches('/'))
+    }
+  }
+}
+
+pub fn successor_path(path: &str) -> Option<String> {
+  if is_deprecated_path(path) {
+    let rest = &path[UNVERSIONED_PREFIX.len()..];
+    Some(format!("{}{}", V1_PREFIX, rest))
+  } else {
+    None
+  }
+}
+
+pub fn successor_link(path: &str) -> Option<String> {
+  successor_path(path).map(|successor| format!("<{}>; rel=\"successor-version\"", successor))
+}
+
+fn is_versioned(rest: &str) -> bool {
+  let segment = rest.split('/').next().unwrap_or_default();
+  match segment.strip_prefix('v') {
+    None => false,
+    Some(version) => !version.is_empty() && version.chars().all(|c| c.is_ascii_digit()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_unversioned_path_is_deprecated() {
+    assert!(is_deprecated_path("/api/areas"));
+    assert!(is_deprecated_path("/api"));
+  }
+
+  #[test]
+  fn test_versioned_path_is_not_deprecated() {
+    assert!(!is_deprecated_path("/api/v1/areas"));
+    assert!(!is_deprecated_path("/api/v2"));
+    assert!(!is_deprecated_path("/"));
+    assert!(!is_deprecated_path("/apiary"));
+  }
+
+  #[test]
+  fn test_successor_link() {
+    assert_eq!(
+      successor_link("/api/areas"),
+      Some("</api/v1/areas>; rel=\"successor-version\"".to_string())
+    );
+    assert_eq!(successor_link("/api/v1/areas"), None);
+  }
+}
@@ -0,0 +1,373 @@
+//! Response shapes of `/api/v1`. These are frozen, see [`crate::api`].
+
+use serde::{Deserialize, Serialize};
+
+use crate::finnkino;
+use crate::id::{AreaId, EventId, ShowId};
+use crate::schedule;
+use crate::time::LocalDateTime;
+
+#[derive(Clone, Eq, Debug, PartialEq, Serialize)]
+pub struct Area {
+  pub id: AreaId,
+  pub name: String,
+}
+
+impl From<finnkino::TheatreArea> for Area {
+  fn from(area: finnkino::TheatreArea) -> Self {
+    Area {
+      id: area.id,
+      name: area.name,
+    }
+  }
+}
+
+#[derive(Clone, Eq, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Language {
+  pub code: String,
+  pub name: String,
+}
+
+impl From<&finnkino::Language> for Language {
+  fn from(language: &finnkino::Language) -> Self {
+    Language {
+      code: language.iso_two_letter_code.clone(),
+      name: language.name.clone(),
+    }
+  }
+}
+
+#[derive(Clone, Eq, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Show {
+  pub id: ShowId,
+  pub event_id: EventId,
+  pub title: String,
+  pub original_title: String,
+  pub start: LocalDateTime,
+  pub end: LocalDateTime,
+  pub length_in_minutes: String,
+  pub rating: String,
+  pub genres: Vec<String>,
+  pub theatre_id: AreaId,
+  pub theatre: String,
+  pub auditorium: String,
+  pub presentation_method: String,
+  pub spoken_language: Option<Language>,
+  pub subtitle_languages: Vec<Language>,
+  pub show_url: String,
+  pub event_url: String,
+}
+
+impl From<finnkino::Show> for Show {
+  fn from(show: finnkino::Show) -> Self {
+    Show {
+      genres: show.genres().into_iter().map(String::from).collect(),
+      spoken_language: show.spoken_language.as_ref().map(Language::from),
+      subtitle_languages: show
+        .subtitle_languages()
+        .into_iter()
+        .map(Language::from)
+        .collect(),
+      id: show.id,
+      event_id: show.event_id,
+      title: show.title,
+      original_title: show.original_title,
+      start: show.show_start,
+      end: show.show_end,
+      length_in_minutes: show.length_in_minutes,
+      rating: show.rating_label,
+      theatre_id: show.theatre_id,
+      theatre: show.theatre,
+      auditorium: show.auditorium,
+      presentation_method: show.presentation_method,
+      show_url: show.show_url,
+      event_url: show.event_url,
+    }
+  }
+}
+
+#[derive(Clone, Eq, Debug, PartialEq, Serialize)]
+pub struct Images {
+  pub small_portrait: Option<String>,
+  pub medium_portrait: Option<String>,
+  pub large_portrait: Option<String>,
+  pub small_landscape: Option<String>,
+  pub medium_landscape: Option<String>,
+  pub large_landscape: Option<String>,
+}
+
+impl From<finnkino::Images> for Images {
+  fn from(images: finnkino::Images) -> Self {
+    Images {
+      small_portrait: images.small_portrait,
+      medium_portrait: images.medium_portrait,
+      large_portrait: images.large_portrait,
+      small_landscape: images.small_landscape,
+      medium_landscape: images.medium_landscape,
+      large_landscape: images.large_landscape,
+    }
+  }
+}
+
+#[derive(Clone, Eq, Debug, PartialEq, Serialize)]
+pub struct Event {
+  pub id: EventId,
+  pub title: String,
+  pub original_title: String,
+  pub production_year: String,
+  pub length_in_minutes: String,
+  pub local_release: Option<LocalDateTime>,
+  pub rating: String,
+  pub genres: Vec<String>,
+  pub short_synopsis: String,
+  pub synopsis: String,
+  pub cast: Vec<String>,
+  pub directors: Vec<String>,
+  pub images: Images,
+  pub event_url: String,
+}
+
+impl From<finnkino::Event> for Event {
+  fn from(event: finnkino::Event) -> Self {
+    Event {
+      genres: event.genres().into_iter().map(String::from).collect(),
+      cast: event.cast.actors.iter().map(|a| a.full_name()).collect(),
+      directors: event
+        .directors
+        .directors
+        .iter()
+        .map(|d| d.full_name())
+        .collect(),
+      id: event.id,
+      title: event.title,
+      original_title: event.original_title,
+      production_year: event.production_year,
+      length_in_minutes: event.length_in_minutes,
+      local_release: event.local_release,
+      rating: event.rating_label,
+      short_synopsis: event.short_synopsis,
+      synopsis: event.synopsis,
+      images: Images::from(event.images),
+      event_url: event.event_url,
+    }
+  }
+}
+
+#[derive(Clone, Eq, Debug, PartialEq, Serialize)]
+pub struct TheatreShows {
+  pub theatre_id: AreaId,
+  pub theatre: String,
+  pub shows: Vec<Show>,
+}
+
+#[derive(Clone, Eq, Debug, PartialEq, Serialize)]
+pub struct EventDetail {
+  pub event: Event,
+  pub theatres: Vec<TheatreShows>,
+}
+
+impl From<schedule::TheatreSchedule> for TheatreShows {
+  fn from(theatre: schedule::TheatreSchedule) -> Self {
+    TheatreShows {
+      theatre_id: theatre.theatre_id,
+      theatre: theatre.theatre,
+      shows: theatre.shows.into_iter().map(Show::from).collect(),
+    }
+  }
+}
+
+impl EventDetail {
+  pub fn new(event: finnkino::Event, shows: Vec<finnkino::Show>) -> Self {
+    EventDetail {
+      event: Event::from(event),
+      theatres: schedule::group_by_theatre(shows)
+        .into_iter()
+        .map(TheatreShows::from)
+        .collect(),
+    }
+  }
+}
+
+pub const SHOW_TYPE: &str = "shows";
+pub const EVENT_TYPE: &str = "events";
+
+/// A JSON:API resource object of a show. Removed shows are sent as bare
+/// resource identifiers without attributes.
+#[derive(Clone, Eq, Debug, PartialEq, Serialize)]
+pub struct ShowResource {
+  #[serde(rename = "type")]
+  pub kind: &'static str,
+  pub id: ShowId,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub attributes: Option<Show>,
+}
+
+impl ShowResource {
+  pub fn new(show: finnkino::Show) -> Self {
+    ShowResource {
+      kind: SHOW_TYPE,
+      id: show.id,
+      attributes: Some(Show::from(show)),
+    }
+  }
+
+  pub fn identifier(id: ShowId) -> Self {
+    ShowResource {
+      kind: SHOW_TYPE,
+      id,
+      attributes: None,
+    }
+  }
+}
+
+#[derive(Clone, Eq, Debug, PartialEq, Serialize)]
+pub struct ShowDocument {
+  pub data: ShowResource,
+}
+
+impl ShowDocument {
+  pub fn new(show: finnkino::Show) -> Self {
+    ShowDocument {
+      data: ShowResource::new(show),
+    }
+  }
+
+  pub fn identifier(id: ShowId) -> Self {
+    ShowDocument {
+      data: ShowResource::identifier(id),
+    }
+  }
+}
+
+/// A JSON:API resource object of an event, like [`ShowResource`].
+#[derive(Clone, Eq, Debug, PartialEq, Serialize)]
+pub struct EventResource {
+  #[serde(rename = "type")]
+  pub kind: &'static str,
+  pub id: EventId,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub attributes: Option<Event>,
+}
+
+impl EventResource {
+  pub fn new(event: finnkino::Event) -> Self {
+    EventResource {
+      kind: EVENT_TYPE,
+      id: event.id,
+      attributes: Some(Event::from(event)),
+    }
+  }
+
+  pub fn identifier(id: EventId) -> Self {
+    EventResource {
+      kind: EVENT_TYPE,
+      id,
+      attributes: None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::time;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_area_shape() {
+    let area = Area::from(finnkino::TheatreArea {
+      id: AreaId(1014),
+      name: "Pääkaupunkiseutu".to_string(),
+    });
+
+    assert_eq!(
+      serde_json::to_string(&area).unwrap(),
+      r#"{"id":"1014","name":"Pääkaupunkiseutu"}"#
+    );
+  }
+
+  #[test]
+  fn test_show_shape() {
+    let show = Show::from(finnkino::Show {
+      id: ShowId(1794851),
+      event_id: EventId(303000),
+      title: "Minions".to_string(),
+      original_title: "Minions".to_string(),
+      show_start: time::parse_finnkino_datetime("2022-08-20T18:00:00").unwrap(),
+      show_end: time::parse_finnkino_datetime("2022-08-20T19:40:00").unwrap(),
+      length_in_minutes: "87".to_string(),
+      rating_label: "7".to_string(),
+      genres: "Animaatio, Komedia".to_string(),
+      theatre_id: AreaId(1038),
+      theatre: "Itis".to_string(),
+      auditorium: "sali 1".to_string(),
+      presentation_method: "2D".to_string(),
+      spoken_language: Some(finnkino::Language {
+        name: "suomi".to_string(),
+        name_in_language: "suomi".to_string(),
+        iso_two_letter_code: "FI".to_string(),
+      }),
+      show_url: "http://www.finnkino.fi/Websales/Show/1794851/".to_string(),
+      event_url: "http://www.finnkino.fi/Event/303000/".to_string(),
+      ..finnkino::Show::default()
+    });
+
+    assert_eq!(
+      serde_json::to_string(&show).unwrap(),
+      concat!(
+        r#"{"id":"1794851","event_id":"303000","title":"Minions","original_title":"Minions","#,
+        r#""start":"2022-08-20T18:00:00+03:00","end":"2022-08-20T19:40:00+03:00","#,
+        r#""length_in_minutes":"87","#,
+        r#""rating":"7","genres":["Animaatio","Komedia"],"theatre_id":"1038","theatre":"Itis","#,
+        r#""auditorium":"sali 1","presentation_method":"2D","#,
+        r#""spoken_language":{"code":"FI","name":"suomi"},"subtitle_languages":[],"#,
+        r#""show_url":"http://www.finnkino.fi/Websales/Show/1794851/","#,
+        r#""event_url":"http://www.finnkino.fi/Event/303000/"}"#
+      )
+    );
+  }
+
+  #[test]
+  fn test_event_detail_groups_shows_by_theatre() {
+    let show = |id: u64, theatre_id: u64, theatre: &str, start: &str| finnkino::Show {
+      id: ShowId(id),
+      theatre_id: AreaId(theatre_id),
+      theatre: theatre.to_string(),
+      show_start: time::parse_finnkino_datetime(start).unwrap(),
+      ..finnkino::Show::default()
+    };
+    let event = finnkino::Event {
+      id: EventId(303000),
+      ..finnkino::Event::default()
+    };
+    let shows = vec![
+      show(3, 1038, "Itis", "2022-08-20T20:00:00"),
+      show(1, 1012, "Tennispalatsi", "2022-08-20T18:00:00"),
+      show(2, 1038, "Itis", "2022-08-20T16:00:00"),
+    ];
+    let detail = EventDetail::new(event, shows);
+    let grouped: Vec<(&str, Vec<u64>)> = detail
+      .theatres
+      .iter()
+      .map(|theatre| {
+        let ids = theatre.shows.iter().map(|show| show.id.0).collect();
+        (theatre.theatre.as_str(), ids)
+      })
+      .collect();
+
+    assert_eq!(
+      vec![("Itis", vec![2, 3]), ("Tennispalatsi", vec![1])],
+      grouped
+    );
+  }
+
+  #[test]
+  fn test_show_document_shape() {
+    let document = ShowDocument::identifier(ShowId(1794851));
+
+    assert_eq!(
+      serde_json::to_string(&document).unwrap(),
+      r#"{"data":{"type":"shows","id":"1794851"}}"#
+    );
+  }
+}
@@ -0,0 +1,396 @@
+//! Archive of the shows and films seen in Finnkino's feeds, which only list
+//! the present. The archiver polls today's Schedule of every area and the
+//! Events feed, and the [`Store`] keeps each show and film once by id along
+//! with when it was first and last seen, and each show's areas: a region
+//! such as 1014 lists the shows of all its theatres.
+
+use chrono::NaiveDate;
+use rusqlite::params;
+use serde::Serialize;
+use std::vec::Vec;
+
+use crate::api::v1;
+use crate::finnkino::{Event, Show};
+use crate::id::{AreaId, EventId};
+use crate::json;
+use crate::schedule::ShowFilter;
+use crate::storage::{datetime, Store};
+use crate::time::{from_timestamp, LocalDateTime, API_DATE_FORMAT};
+
+pub const POLL_INTERVAL_SECS: u64 = 3600;
+
+/// The shows of an area archived on each poll.
+pub fn filter(area: AreaId, today: NaiveDate) -> ShowFilter {
+  ShowFilter {
+    area: Some(area),
+    date: Some(today),
+    ..ShowFilter::default()
+  }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ArchiveQuery {
+  pub date: NaiveDate,
+  pub area: Option<AreaId>,
+}
+
+impl ArchiveQuery {
+  /// Takes the required `date` and the optional `area` from the query
+  /// string. The other filters of the shows endpoint are not supported.
+  pub fn from_query(query: &str) -> Result<Self, json::Errors> {
+    let filter = ShowFilter::from_query(query)?;
+    let supported = ShowFilter {
+      area: filter.area,
+      date: filter.date,
+      ..ShowFilter::default()
+    };
+    if filter != supported {
+      return Err(json::Errors {
+        errors: vec![json::Error::invalid_parameter(
+          "",
+          "Only the date and area parameters are supported",
+        )],
+      });
+    }
+    match filter.date {
+      None => Err(json::Errors {
+        errors: vec![json::Error::invalid_parameter("date", "Is required")],
+      }),
+      Some(date) => Ok(ArchiveQuery {
+        date,
+        area: filter.area,
+      }),
+    }
+  }
+}
+
+/// A show as it was last served by `/api/v1/shows`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ArchivedShow {
+  #[serde(flatten)]
+  pub show: serde_json::Value,
+  pub first_seen: LocalDateTime,
+  pub last_seen: LocalDateTime,
+}
+
+/// The first and last archived screening of a film, and the areas whose
+/// Schedule listed it.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Screenings {
+  pub event_id: EventId,
+  pub title: String,
+  pub first_screening: LocalDateTime,
+  pub last_screening: LocalDateTime,
+  pub shows: u64,
+  pub areas: Vec<AreaId>,
+}
+
+impl Store {
+  /// Archives a snapshot of the shows of `area`, returning how many of them
+  /// had not been seen before. Shows seen before are updated to the snapshot.
+  pub fn archive_shows(
+    &self,
+    area: AreaId,
+    shows: &[Show],
+    now: LocalDateTime,
+  ) -> rusqlite::Result<usize> {
+    let mut connection = self.connection.lock().unwrap();
+    let transaction = connection.transaction()?;
+    let mut added = 0;
+    for show in shows {
+      let seen: bool = transaction.query_row(
+        "SELECT EXISTS (SELECT 1 FROM archived_shows WHERE id = ?1)",
+        params![show.id.0],
+        |row| row.get(0),
+      )?;
+      let data = serde_json::to_string(&v1::Show::from(show.clone())).unwrap();
+      transaction.execute(
+        "INSERT INTO archived_shows
+           (id, event_id, theatre_id, title, date, starts_at, data, first_seen, last_seen)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8)
+         ON CONFLICT (id) DO UPDATE SET
+           event_id = excluded.event_id,
+           theatre_id = excluded.theatre_id,
+           title = excluded.title,
+           date = excluded.date,
+           starts_at = excluded.starts_at,
+           data = excluded.data,
+           last_seen = excluded.last_seen",
+        params![
+          show.id.0,
+          show.event_id.0,
+          show.theatre_id.0,
+          show.title,
+          show
+            .show_start
+            .date_naive()
+            .format(API_DATE_FORMAT)
+            .to_string(),
+          show.show_start.timestamp(),
+          data,
+          now.to_rfc3339()
+        ],
+      )?;
+      transaction.execute(
+        "INSERT OR IGNORE INTO archived_show_areas (show_id, area_id) VALUES (?1, ?2)",
+        params![show.id.0, area.0],
+      )?;
+      if !seen {
+        added += 1;
+      }
+    }
+    transaction.commit()?;
+    Ok(added)
+  }
+
+  /// Archives a snapshot of the Events feed, returning how many of the films
+  /// had not been seen before.
+  pub fn archive_events(&self, events: &[Event], now: LocalDateTime) -> rusqlite::Result<usize> {
+    let mut connection = self.connection.lock().unwrap();
+    let transaction = connection.transaction()?;
+    let mut added = 0;
+    for event in events {
+      let seen: bool = transaction.query_row(
+        "SELECT EXISTS (SELECT 1 FROM archived_events WHERE id = ?1)",
+        params![event.id.0],
+        |row| row.get(0),
+      )?;
+      let data = serde_json::to_string(&v1::Event::from(event.clone())).unwrap();
+      transaction.execute(
+        "INSERT INTO archived_events (id, title, data, first_seen, last_seen)
+         VALUES (?1, ?2, ?3, ?4, ?4)
+         ON CONFLICT (id) DO UPDATE SET
+           title = excluded.title,
+           data = excluded.data,
+           last_seen = excluded.last_seen",
+        params![event.id.0, event.title, data, now.to_rfc3339()],
+      )?;
+      if !seen {
+        added += 1;
+      }
+    }
+    transaction.commit()?;
+    Ok(added)
+  }
+
+  pub fn archived_shows(&self, query: &ArchiveQuery) -> rusqlite::Result<Vec<ArchivedShow>> {
+    let connection = self.connection.lock().unwrap();
+    let mut statement = connection.prepare(
+      "SELECT data, first_seen, last_seen FROM archived_shows
+       WHERE date = ?1 AND (?2 IS NULL OR id IN
+         (SELECT show_id FROM archived_show_areas WHERE area_id = ?2))
+       ORDER BY starts_at, id",
+    )?;
+    let date = query.date.format(API_DATE_FORMAT).to_string();
+    let rows = statement.query_map(params![date, query.area.map(|area| area.0)], |row| {
+      let data: String = row.get(0)?;
+      Ok(ArchivedShow {
+        show: serde_json::from_str(&data).unwrap_or_default(),
+        first_seen: datetime(row, 1)?,
+        last_seen: datetime(row, 2)?,
+      })
+    })?;
+    rows.collect()
+  }
+
+  /// The first and last screening of a film among the archived shows, `None`
+  /// when none of its shows have been archived. The title is the one of the
+  /// Events feed if the film has been seen there.
+  pub fn screenings(&self, event: EventId) -> rusqlite::Result<Option<Screenings>> {
+    let connection = self.connection.lock().unwrap();
+    let (first, last, shows, title) = connection.query_row(
+      "SELECT MIN(starts_at), MAX(starts_at), COUNT(*), COALESCE(
+         (SELECT title FROM archived_events WHERE id = ?1),
+         (SELECT title FROM archived_shows WHERE event_id = ?1 ORDER BY starts_at LIMIT 1))
+       FROM archived_shows WHERE event_id = ?1",
+      params![event.0],
+      |row| {
+        Ok((
+          row.get::<_, Option<i64>>(0)?,
+          row.get::<_, Option<i64>>(1)?,
+          row.get::<_, u64>(2)?,
+          row.get::<_, Option<String>>(3)?,
+        ))
+      },
+    )?;
+    let (first, last) = match (first, last) {
+      (Some(first), Some(last)) => (first, last),
+      _ => return Ok(None),
+    };
+    let mut statement = connection.prepare(
+      "SELECT DISTINCT area_id FROM archived_show_areas
+       JOIN archived_shows ON archived_shows.id = show_id
+       WHERE event_id = ?1 ORDER BY area_id",
+    )?;
+    let areas = statement
+      .query_map(params![event.0], |row| row.get(0).map(AreaId))?
+      .collect::<rusqlite::Result<Vec<AreaId>>>()?;
+    Ok(Some(Screenings {
+      event_id: event,
+      title: title.unwrap_or_default(),
+      first_screening: from_timestamp(first),
+      last_screening: from_timestamp(last),
+      shows,
+      areas,
+    }))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::id::ShowId;
+  use crate::time::parse_finnkino_datetime;
+  use pretty_assertions::assert_eq;
+
+  const REGION: AreaId = AreaId(1014);
+  const TENNISPALATSI: AreaId = AreaId(1038);
+  const KINOPALATSI: AreaId = AreaId(1033);
+
+  fn show(id: u64, theatre: AreaId, start: &str) -> Show {
+    Show {
+      id: ShowId(id),
+      event_id: EventId(303000),
+      title: "Minions".to_string(),
+      theatre_id: theatre,
+      show_start: parse_finnkino_datetime(start).unwrap(),
+      ..Show::default()
+    }
+  }
+
+  fn at(value: &str) -> LocalDateTime {
+    parse_finnkino_datetime(value).unwrap()
+  }
+
+  #[test]
+  fn test_parse_query() {
+    let date = NaiveDate::from_ymd_opt(2026, 9, 1).unwrap();
+
+    assert_eq!(
+      Ok(ArchiveQuery {
+        date,
+        area: Some(AreaId(1014))
+      }),
+      ArchiveQuery::from_query("date=2026-09-01&area=1014")
+    );
+    assert_eq!(
+      Ok(ArchiveQuery { date, area: None }),
+      ArchiveQuery::from_query("date=2026-09-01")
+    );
+    let errors = ArchiveQuery::from_query("area=1014").unwrap_err();
+    assert_eq!(
+      Some("date".to_string()),
+      errors.errors[0].source.as_ref().unwrap().parameter
+    );
+    assert!(ArchiveQuery::from_query("date=2026-09-01&genre=Draama").is_err());
+  }
+
+  #[test]
+  fn test_shows_are_archived_once() {
+    let store = Store::open_in_memory().unwrap();
+    let first = vec![
+      show(1, KINOPALATSI, "2026-09-01T18:00:00"),
+      show(2, TENNISPALATSI, "2026-09-01T12:00:00"),
+    ];
+    let mut moved = show(1, KINOPALATSI, "2026-09-01T19:00:00");
+    moved.auditorium = "sali 2".to_string();
+
+    assert_eq!(
+      2,
+      store
+        .archive_shows(REGION, &first, at("2026-09-01T09:00:00"))
+        .unwrap()
+    );
+    assert_eq!(
+      0,
+      store
+        .archive_shows(TENNISPALATSI, &first[1..], at("2026-09-01T09:00:00"))
+        .unwrap()
+    );
+    assert_eq!(
+      1,
+      store
+        .archive_shows(
+          REGION,
+          &[moved, show(3, KINOPALATSI, "2026-09-02T18:00:00")],
+          at("2026-09-01T10:00:00")
+        )
+        .unwrap()
+    );
+
+    let date = NaiveDate::from_ymd_opt(2026, 9, 1).unwrap();
+    let archived = store
+      .archived_shows(&ArchiveQuery { date, area: None })
+      .unwrap();
+    assert_eq!(
+      vec!["2", "1"],
+      archived
+        .iter()
+        .map(|show| show.show["id"].as_str().unwrap())
+        .collect::<Vec<_>>()
+    );
+    assert_eq!("sali 2", archived[1].show["auditorium"]);
+    assert_eq!(at("2026-09-01T09:00:00"), archived[1].first_seen);
+    assert_eq!(at("2026-09-01T10:00:00"), archived[1].last_seen);
+    let in_area = |area| {
+      store
+        .archived_shows(&ArchiveQuery {
+          date,
+          area: Some(area),
+        })
+        .unwrap()
+    };
+    assert_eq!(2, in_area(REGION).len());
+    let archived = in_area(TENNISPALATSI);
+    assert_eq!(1, archived.len());
+    assert_eq!("2", archived[0].show["id"]);
+    assert_eq!(at("2026-09-01T09:00:00"), archived[0].last_seen);
+    assert_eq!(0, in_area(KINOPALATSI).len());
+  }
+
+  #[test]
+  fn test_screenings() {
+    let store = Store::open_in_memory().unwrap();
+    let now = at("2026-09-01T09:00:00");
+    let shows = [
+      show(1, KINOPALATSI, "2026-09-03T18:00:00"),
+      show(2, TENNISPALATSI, "2026-08-20T12:00:00"),
+      show(3, KINOPALATSI, "2026-09-10T21:00:00"),
+    ];
+    store.archive_shows(REGION, &shows, now).unwrap();
+    store
+      .archive_shows(TENNISPALATSI, &shows[1..2], now)
+      .unwrap();
+
+    let screenings = store.screenings(EventId(303000)).unwrap().unwrap();
+    assert_eq!(
+      Screenings {
+        event_id: EventId(303000),
+        title: "Minions".to_string(),
+        first_screening: at("2026-08-20T12:00:00"),
+        last_screening: at("2026-09-10T21:00:00"),
+        shows: 3,
+        areas: vec![REGION, TENNISPALATSI],
+      },
+      screenings
+    );
+    assert_eq!(None, store.screenings(EventId(302000)).unwrap());
+
+    let event = Event {
+      id: EventId(303000),
+      title: "Minions: The Rise of Gru".to_string(),
+      ..Event::default()
+    };
+    assert_eq!(
+      1,
+      store
+        .archive_events(std::slice::from_ref(&event), now)
+        .unwrap()
+    );
+    assert_eq!(0, store.archive_events(&[event], now).unwrap());
+    assert_eq!(
+      "Minions: The Rise of Gru",
+      store.screenings(EventId(303000)).unwrap().unwrap().title
+    );
+  }
+}
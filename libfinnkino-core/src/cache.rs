@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+pub struct Cache<V> {
+  ttl: Duration,
+  entries: Mutex<HashMap<String, (Instant, V)>>,
+}
+
+impl<V: Clone> Cache<V> {
+  pub fn new(ttl: Duration) -> Self {
+    Cache {
+      ttl,
+      entries: Mutex::new(HashMap::new()),
+    }
+  }
+
+  pub fn get(&self, key: &str) -> Option<V> {
+    let mut entries = self.entries.lock().unwrap();
+    match entries.get(key) {
+      Some((inserted, value)) if inserted.elapsed() < self.ttl => Some(value.clone()),
+      Some(_) => {
+        entries.remove(key);
+        None
+      }
+      None => None,
+    }
+  }
+
+  pub fn insert(&self, key: &str, value: V) {
+    let mut entries = self.entries.lock().unwrap();
+    entries.retain(|_, (inserted, _)| inserted.elapsed() < self.ttl);
+    entries.insert(key.to_string(), (Instant::now(), value));
+  }
+}
+
+impl<V: Clone> Default for Cache<V> {
+  fn default() -> Self {
+    Cache::new(DEFAULT_TTL)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_get_inserted_value() {
+    let cache = Cache::default();
+    cache.insert("key", "value".to_string());
+
+    assert_eq!(Some("value".to_string()), cache.get("key"));
+    assert_eq!(None, cache.get("other"));
+  }
+
+  #[test]
+  fn test_expired_value_is_not_returned() {
+    let cache = Cache::new(Duration::ZERO);
+    cache.insert("key", "value".to_string());
+
+    assert_eq!(None, cache.get("key"));
+  }
+}
@@ -0,0 +1,428 @@
+//! Changes to an area's program between two snapshots of its Schedule. The
+//! servers snapshot the next [`SNAPSHOT_DAYS`] days of every area once an
+//! hour, and the [`Store`] keeps a new snapshot only when the program has
+//! changed since the previous one.
+
+use chrono::{Duration, NaiveDate, NaiveTime};
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::vec::Vec;
+
+use crate::api::v1;
+use crate::id::{AreaId, EventId, ShowId};
+use crate::json;
+use crate::schedule::{self, ShowFilter};
+use crate::storage::Store;
+use crate::time::{self, from_timestamp, LocalDateTime};
+
+pub const POLL_INTERVAL_SECS: u64 = 3600;
+pub const SNAPSHOT_DAYS: i64 = 7;
+
+/// The shows snapshotted of an area.
+pub fn filter(area: AreaId, today: NaiveDate) -> ShowFilter {
+  ShowFilter {
+    area: Some(area),
+    date: Some(today),
+    until: Some(today + Duration::days(SNAPSHOT_DAYS - 1)),
+    ..ShowFilter::default()
+  }
+}
+
+/// One attribute of a show that changed between two snapshots.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Change<T> {
+  pub id: ShowId,
+  pub event_id: EventId,
+  pub title: String,
+  pub from: T,
+  pub to: T,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct ScheduleChanges {
+  pub added: Vec<v1::Show>,
+  pub removed: Vec<v1::Show>,
+  pub moved: Vec<Change<LocalDateTime>>,
+  pub auditorium_changed: Vec<Change<String>>,
+  pub presentation_changed: Vec<Change<String>>,
+}
+
+impl ScheduleChanges {
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty()
+      && self.removed.is_empty()
+      && self.moved.is_empty()
+      && self.auditorium_changed.is_empty()
+      && self.presentation_changed.is_empty()
+  }
+}
+
+fn change<T: Clone + PartialEq>(
+  changes: &mut Vec<Change<T>>,
+  before: &v1::Show,
+  after: &v1::Show,
+  value: impl Fn(&v1::Show) -> &T,
+) {
+  if value(before) != value(after) {
+    changes.push(Change {
+      id: after.id,
+      event_id: after.event_id,
+      title: after.title.clone(),
+      from: value(before).clone(),
+      to: value(after).clone(),
+    });
+  }
+}
+
+/// Compares two snapshots of a schedule with [`schedule::diff`], breaking
+/// the changed shows down by attribute. A show that both moved and changed
+/// auditorium is listed under both.
+pub fn compare(previous: &[v1::Show], current: &[v1::Show]) -> ScheduleChanges {
+  let diff = schedule::diff(previous, current);
+  let published: HashMap<_, &v1::Show> = previous.iter().map(|show| (show.id, show)).collect();
+  let mut changes = ScheduleChanges {
+    added: diff.added,
+    removed: diff.removed,
+    ..ScheduleChanges::default()
+  };
+  for show in &diff.changed {
+    let before = published[&show.id];
+    change(&mut changes.moved, before, show, |show| &show.start);
+    change(&mut changes.auditorium_changed, before, show, |show| {
+      &show.auditorium
+    });
+    change(&mut changes.presentation_changed, before, show, |show| {
+      &show.presentation_method
+    });
+  }
+  changes
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ChangesQuery {
+  pub area: AreaId,
+  pub since: LocalDateTime,
+}
+
+impl ChangesQuery {
+  /// Takes the required `area` and `since` from the query string. `since`
+  /// is a local date and time like `2026-10-17T12:00:00`, or a date for its
+  /// midnight.
+  pub fn from_query(query: &str) -> Result<Self, json::Errors> {
+    let params =
+      serde_urlencoded::from_str::<Vec<(String, String)>>(query).map_err(|err| json::Errors {
+        errors: vec![json::Error::invalid_parameter("", err.to_string())],
+      })?;
+    let mut area = None;
+    let mut since = None;
+    let mut errors = Vec::new();
+    for (name, value) in params {
+      let result = match name.as_str() {
+        "area" => value
+          .parse()
+          .map(|id| area = Some(id))
+          .map_err(|_| "Must be numeric".to_string()),
+        "since" => parse_since(&value).map(|datetime| since = Some(datetime)),
+        _ => Err("Unknown parameter".to_string()),
+      };
+      if let Err(detail) = result {
+        errors.push(json::Error::invalid_parameter(&name, detail));
+      }
+    }
+    match (area, since) {
+      (Some(area), Some(since)) if errors.is_empty() => Ok(ChangesQuery { area, since }),
+      _ => {
+        if errors.is_empty() && area.is_none() {
+          errors.push(json::Error::invalid_parameter("area", "Is required"));
+        }
+        if errors.is_empty() && since.is_none() {
+          errors.push(json::Error::invalid_parameter("since", "Is required"));
+        }
+        Err(json::Errors { errors })
+      }
+    }
+  }
+}
+
+fn parse_since(value: &str) -> Result<LocalDateTime, String> {
+  time::parse_finnkino_datetime(value)
+    .or_else(|| {
+      time::parse_api_date(value).map(|date| time::from_local(&date.and_time(NaiveTime::MIN)))
+    })
+    .ok_or_else(|| "Must be a date and time formatted as YYYY-MM-DDTHH:MM:SS".to_string())
+}
+
+/// The changes to an area's program between the snapshot current at `from`
+/// and the latest one, last confirmed at `to`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct AreaChanges {
+  pub area: AreaId,
+  pub from: LocalDateTime,
+  pub to: LocalDateTime,
+  #[serde(flatten)]
+  pub changes: ScheduleChanges,
+}
+
+/// The snapshot current at a time.
+const CURRENT_AT: &str = "SELECT taken_at, last_seen, shows FROM schedule_snapshots
+  WHERE area_id = ?1 AND taken_at <= ?2 ORDER BY taken_at DESC, id DESC LIMIT 1";
+const FIRST_AFTER: &str = "SELECT taken_at, last_seen, shows FROM schedule_snapshots
+  WHERE area_id = ?1 AND taken_at > ?2 ORDER BY taken_at, id LIMIT 1";
+
+struct Snapshot {
+  taken_at: i64,
+  last_seen: i64,
+  shows: Vec<v1::Show>,
+}
+
+fn snapshot(
+  connection: &rusqlite::Connection,
+  sql: &str,
+  area: AreaId,
+  at: i64,
+) -> rusqlite::Result<Option<Snapshot>> {
+  connection
+    .query_row(sql, params![area.0, at], |row| {
+      let shows: String = row.get(2)?;
+      Ok(Snapshot {
+        taken_at: row.get(0)?,
+        last_seen: row.get(1)?,
+        shows: serde_json::from_str(&shows).unwrap_or_default(),
+      })
+    })
+    .optional()
+}
+
+impl Store {
+  /// Records a snapshot of an area's program. It is stored only if it
+  /// differs from the latest one, returning whether it did.
+  pub fn snapshot_schedule(
+    &self,
+    area: AreaId,
+    shows: &[v1::Show],
+    now: LocalDateTime,
+  ) -> rusqlite::Result<bool> {
+    let connection = self.connection.lock().unwrap();
+    let shows = serde_json::to_string(shows).unwrap();
+    let latest = connection
+      .query_row(
+        "SELECT id, shows FROM schedule_snapshots WHERE area_id = ?1
+         ORDER BY taken_at DESC, id DESC LIMIT 1",
+        params![area.0],
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+      )
+      .optional()?;
+    match latest {
+      Some((id, latest)) if latest == shows => {
+        connection.execute(
+          "UPDATE schedule_snapshots SET last_seen = ?2 WHERE id = ?1",
+          params![id, now.timestamp()],
+        )?;
+        Ok(false)
+      }
+      _ => {
+        connection.execute(
+          "INSERT INTO schedule_snapshots (area_id, taken_at, last_seen, shows)
+           VALUES (?1, ?2, ?2, ?3)",
+          params![area.0, now.timestamp(), shows],
+        )?;
+        Ok(true)
+      }
+    }
+  }
+
+  /// The changes to an area's program since `since`, compared to the
+  /// snapshot current at the time or the first one after it. Only the days
+  /// both snapshots cover are compared, so the shows of days that have gone
+  /// by are not reported as removed, nor the shows of days that have come
+  /// into the window as added. `None` when the area has no snapshots.
+  pub fn schedule_changes(&self, query: &ChangesQuery) -> rusqlite::Result<Option<AreaChanges>> {
+    let connection = self.connection.lock().unwrap();
+    let since = query.since.timestamp();
+    let base = match snapshot(&connection, CURRENT_AT, query.area, since)? {
+      Some(base) => Some(base),
+      None => snapshot(&connection, FIRST_AFTER, query.area, since)?,
+    };
+    let latest = snapshot(&connection, CURRENT_AT, query.area, i64::MAX)?;
+    let (base, latest) = match (base, latest) {
+      (Some(base), Some(latest)) => (base, latest),
+      _ => return Ok(None),
+    };
+    let first_day = from_timestamp(latest.taken_at).date_naive();
+    let last_day = from_timestamp(base.taken_at).date_naive() + Duration::days(SNAPSHOT_DAYS - 1);
+    let covered = |shows: Vec<v1::Show>| -> Vec<v1::Show> {
+      shows
+        .into_iter()
+        .filter(|show| (first_day..=last_day).contains(&show.start.date_naive()))
+        .collect()
+    };
+    Ok(Some(AreaChanges {
+      area: query.area,
+      from: from_timestamp(base.taken_at),
+      to: from_timestamp(latest.last_seen),
+      changes: compare(&covered(base.shows), &covered(latest.shows)),
+    }))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::time::parse_finnkino_datetime;
+  use pretty_assertions::assert_eq;
+
+  const AREA: AreaId = AreaId(1014);
+
+  fn at(value: &str) -> LocalDateTime {
+    parse_finnkino_datetime(value).unwrap()
+  }
+
+  fn show(id: u64, start: &str, auditorium: &str) -> v1::Show {
+    v1::Show {
+      id: ShowId(id),
+      event_id: EventId(303000),
+      title: "Minions".to_string(),
+      original_title: String::new(),
+      start: at(start),
+      end: at(start),
+      length_in_minutes: String::new(),
+      rating: String::new(),
+      genres: vec![],
+      theatre_id: AreaId(1033),
+      theatre: "Tennispalatsi".to_string(),
+      auditorium: auditorium.to_string(),
+      presentation_method: "2D".to_string(),
+      spoken_language: None,
+      subtitle_languages: vec![],
+      show_url: String::new(),
+      event_url: String::new(),
+    }
+  }
+
+  #[test]
+  fn test_compare() {
+    let previous = vec![
+      show(1, "2026-10-17T18:00:00", "sali 1"),
+      show(2, "2026-10-17T20:00:00", "sali 1"),
+      show(3, "2026-10-17T12:00:00", "sali 3"),
+    ];
+    let mut imax = show(2, "2026-10-17T20:00:00", "sali 1");
+    imax.presentation_method = "IMAX".to_string();
+    let current = vec![
+      show(1, "2026-10-17T18:30:00", "sali 2"),
+      imax,
+      show(4, "2026-10-18T18:00:00", "sali 1"),
+    ];
+
+    let changes = compare(&previous, &current);
+    assert_eq!(vec![ShowId(4)], ids(&changes.added));
+    assert_eq!(vec![ShowId(3)], ids(&changes.removed));
+    assert_eq!(
+      vec![Change {
+        id: ShowId(1),
+        event_id: EventId(303000),
+        title: "Minions".to_string(),
+        from: at("2026-10-17T18:00:00"),
+        to: at("2026-10-17T18:30:00"),
+      }],
+      changes.moved
+    );
+    assert_eq!("sali 2", changes.auditorium_changed[0].to);
+    assert_eq!(ShowId(1), changes.auditorium_changed[0].id);
+    assert_eq!("IMAX", changes.presentation_changed[0].to);
+    assert!(compare(&current, &current).is_empty());
+  }
+
+  fn ids(shows: &[v1::Show]) -> Vec<ShowId> {
+    shows.iter().map(|show| show.id).collect()
+  }
+
+  #[test]
+  fn test_parse_query() {
+    assert_eq!(
+      Ok(ChangesQuery {
+        area: AREA,
+        since: at("2026-10-17T12:00:00"),
+      }),
+      ChangesQuery::from_query("area=1014&since=2026-10-17T12:00:00")
+    );
+    assert_eq!(
+      at("2026-10-17T00:00:00"),
+      ChangesQuery::from_query("area=1014&since=2026-10-17")
+        .unwrap()
+        .since
+    );
+    let errors = ChangesQuery::from_query("area=1014").unwrap_err();
+    assert_eq!(
+      Some("since".to_string()),
+      errors.errors[0].source.as_ref().unwrap().parameter
+    );
+    assert!(ChangesQuery::from_query("area=abc&since=2026-10-17").is_err());
+    assert!(ChangesQuery::from_query("area=1014&since=yesterday").is_err());
+  }
+
+  #[test]
+  fn test_changes_since_stored_snapshots() {
+    let store = Store::open_in_memory().unwrap();
+    let morning = vec![
+      show(1, "2026-10-16T21:00:00", "sali 1"),
+      show(2, "2026-10-17T18:00:00", "sali 1"),
+    ];
+    let noon = vec![
+      show(1, "2026-10-16T21:00:00", "sali 1"),
+      show(2, "2026-10-17T18:00:00", "sali 4"),
+    ];
+    // Taken a day later, the evening's window reaches a day further.
+    let evening = vec![
+      show(2, "2026-10-17T19:00:00", "sali 4"),
+      show(5, "2026-10-22T18:00:00", "sali 1"),
+      show(6, "2026-10-23T18:00:00", "sali 1"),
+    ];
+    let query = |since: &str| ChangesQuery {
+      area: AREA,
+      since: at(since),
+    };
+
+    assert_eq!(
+      None,
+      store
+        .schedule_changes(&query("2026-10-16T09:00:00"))
+        .unwrap()
+    );
+    assert!(store
+      .snapshot_schedule(AREA, &morning, at("2026-10-16T09:00:00"))
+      .unwrap());
+    assert!(!store
+      .snapshot_schedule(AREA, &morning, at("2026-10-16T10:00:00"))
+      .unwrap());
+    assert!(store
+      .snapshot_schedule(AREA, &noon, at("2026-10-16T12:00:00"))
+      .unwrap());
+    assert!(store
+      .snapshot_schedule(AREA, &evening, at("2026-10-17T09:00:00"))
+      .unwrap());
+
+    let changes = store
+      .schedule_changes(&query("2026-10-16T11:00:00"))
+      .unwrap()
+      .unwrap();
+    assert_eq!(at("2026-10-16T09:00:00"), changes.from);
+    assert_eq!(at("2026-10-17T09:00:00"), changes.to);
+    assert!(changes.changes.removed.is_empty());
+    assert_eq!(vec![ShowId(5)], ids(&changes.changes.added));
+    assert_eq!(ShowId(2), changes.changes.moved[0].id);
+    assert_eq!("sali 1", changes.changes.auditorium_changed[0].from);
+
+    let changes = store
+      .schedule_changes(&query("2026-10-15T00:00:00"))
+      .unwrap()
+      .unwrap();
+    assert_eq!(at("2026-10-16T09:00:00"), changes.from);
+    let changes = store
+      .schedule_changes(&query("2026-10-17T10:00:00"))
+      .unwrap()
+      .unwrap();
+    assert!(changes.changes.is_empty());
+  }
+}
@@ -0,0 +1,141 @@
+//! The `finnkino.toml` configuration shared by the servers and the
+//! command-line client. The file named by `FINNKINO_CONFIG` is read if set,
+//! otherwise `finnkino.toml` in the working directory if it exists.
+//!
+//! ```toml
+//! database = "/var/lib/finnkino/finnkino.db"
+//! fixtures = "fixtures"
+//! upstream = "http://127.0.0.1:8090/xml"
+//!
+//! [recording]
+//! dir = "recordings"
+//! mode = "replay"
+//! ```
+
+use serde::Deserialize;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use crate::fixtures::FIXTURES_ENV;
+use crate::recording::RecordingConfig;
+use crate::storage::{DATABASE_ENV, DEFAULT_DATABASE};
+
+pub const CONFIG_ENV: &str = "FINNKINO_CONFIG";
+pub const DEFAULT_CONFIG: &str = "finnkino.toml";
+/// Overrides the `upstream` of the configuration.
+pub const UPSTREAM_ENV: &str = "FINNKINO_UPSTREAM";
+/// The port the servers listen on instead of their default.
+pub const PORT_ENV: &str = "FINNKINO_PORT";
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  /// The SQLite database of the servers.
+  pub database: Option<PathBuf>,
+  /// A directory of feed fixtures to read instead of Finnkino, see
+  /// [`crate::fixtures`].
+  pub fixtures: Option<PathBuf>,
+  /// Record or replay the requests to Finnkino, see [`crate::recording`].
+  pub recording: Option<RecordingConfig>,
+  /// The base URL of the XML feeds, e.g. of the `finnkino-mock` server,
+  /// instead of Finnkino's.
+  pub upstream: Option<String>,
+}
+
+impl Config {
+  pub fn from_toml(text: &str) -> Result<Self, String> {
+    toml::from_str(text).map_err(|error| error.to_string())
+  }
+
+  pub fn load() -> Result<Self, String> {
+    let (path, required) = match std::env::var(CONFIG_ENV) {
+      Ok(path) => (path, true),
+      Err(_) => (DEFAULT_CONFIG.to_string(), false),
+    };
+    match fs::read_to_string(&path) {
+      Err(error) if error.kind() == ErrorKind::NotFound && !required => Ok(Config::default()),
+      Err(error) => Err(format!("{}: {}", path, error)),
+      Ok(text) => Config::from_toml(&text).map_err(|error| format!("{}: {}", path, error)),
+    }
+  }
+
+  /// The database named by `FINNKINO_DATABASE`, the configured one or
+  /// `finnkino.db`.
+  pub fn database(&self) -> PathBuf {
+    match std::env::var(DATABASE_ENV) {
+      Ok(path) => PathBuf::from(path),
+      Err(_) => self
+        .database
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_DATABASE)),
+    }
+  }
+
+  /// The fixture directory named by `FINNKINO_FIXTURES` or the configured
+  /// one. Without either the feeds are read from Finnkino.
+  pub fn fixtures(&self) -> Option<PathBuf> {
+    match std::env::var(FIXTURES_ENV) {
+      Ok(path) => Some(PathBuf::from(path)),
+      Err(_) => self.fixtures.clone(),
+    }
+  }
+
+  /// The base URL named by `FINNKINO_UPSTREAM` or the configured one.
+  /// Without either the feeds are read from Finnkino.
+  pub fn upstream(&self) -> Option<String> {
+    match std::env::var(UPSTREAM_ENV) {
+      Ok(url) => Some(url),
+      Err(_) => self.upstream.clone(),
+    }
+  }
+}
+
+/// The port named by `FINNKINO_PORT`, if set.
+pub fn port() -> Result<Option<u16>, String> {
+  match std::env::var(PORT_ENV) {
+    Err(_) => Ok(None),
+    Ok(port) => port
+      .parse()
+      .map(Some)
+      .map_err(|_| format!("{}: '{}' is not a port", PORT_ENV, port)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::recording::Mode;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_from_toml() {
+    assert_eq!(
+      Ok(Config {
+        database: Some(PathBuf::from("/tmp/finnkino.db")),
+        fixtures: Some(PathBuf::from("fixtures")),
+        recording: None,
+        upstream: None,
+      }),
+      Config::from_toml("database = \"/tmp/finnkino.db\"\nfixtures = \"fixtures\"\n")
+    );
+    assert_eq!(
+      Some(RecordingConfig {
+        dir: PathBuf::from("recordings"),
+        mode: Mode::Strict,
+      }),
+      Config::from_toml("[recording]\ndir = \"recordings\"\nmode = \"strict\"\n")
+        .unwrap()
+        .recording
+    );
+    assert!(Config::from_toml("[recording]\ndir = \"recordings\"\nmode = \"once\"\n").is_err());
+    assert_eq!(
+      Some("http://127.0.0.1:8090/xml".to_string()),
+      Config::from_toml("upstream = \"http://127.0.0.1:8090/xml\"\n")
+        .unwrap()
+        .upstream
+    );
+    assert_eq!(Ok(Config::default()), Config::from_toml(""));
+    assert!(Config::from_toml("port = 8080").is_err());
+  }
+}
@@ -0,0 +1,75 @@
+use std::vec::Vec;
+
+use crate::id::{AreaId, EventId};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ListType {
+  NowInTheatres,
+  ComingSoon,
+}
+
+impl ListType {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ListType::NowInTheatres => "NowInTheatres",
+      ListType::ComingSoon => "ComingSoon",
+    }
+  }
+}
+
+#[derive(Builder, Clone, Debug, Default, Eq, PartialEq)]
+#[builder(setter(into, strip_option), default)]
+pub struct EventQuery {
+  pub event: Option<EventId>,
+  pub area: Option<AreaId>,
+  pub list_type: Option<ListType>,
+}
+
+impl EventQuery {
+  pub fn upstream_query(&self) -> Vec<(&'static str, String)> {
+    let mut query = Vec::new();
+    if let Some(event) = &self.event {
+      query.push(("eventID", event.to_string()));
+    }
+    if let Some(area) = &self.area {
+      query.push(("area", area.to_string()));
+    }
+    if let Some(list_type) = &self.list_type {
+      query.push(("listType", list_type.as_str().to_string()));
+    }
+    query
+  }
+
+  pub fn events_url(&self, base_url: &str) -> String {
+    let query = serde_urlencoded::to_string(self.upstream_query()).unwrap_or_default();
+    if query.is_empty() {
+      format!("{}/Events/", base_url)
+    } else {
+      format!("{}/Events/?{}", base_url, query)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_events_url() {
+    let query = EventQueryBuilder::default()
+      .event(EventId(303000))
+      .list_type(ListType::ComingSoon)
+      .build()
+      .unwrap();
+
+    assert_eq!(
+      "https://www.finnkino.fi/xml/Events/?eventID=303000&listType=ComingSoon",
+      query.events_url("https://www.finnkino.fi/xml")
+    );
+    assert_eq!(
+      "https://www.finnkino.fi/xml/Events/",
+      EventQuery::default().events_url("https://www.finnkino.fi/xml")
+    );
+  }
+}
@@ -0,0 +1,425 @@
+//! Atom and RSS 2.0 feeds of the Coming Soon films and Finnkino news.
+
+use atom_syndication as atom;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::vec::Vec;
+
+use crate::finnkino::{Event, NewsArticle};
+use crate::id::EventId;
+use crate::time::{self, LocalDateTime};
+
+pub const ATOM_CONTENT_TYPE: &str = "application/atom+xml; charset=utf-8";
+pub const RSS_CONTENT_TYPE: &str = "application/rss+xml; charset=utf-8";
+pub const FINNKINO_URL: &str = "https://www.finnkino.fi/";
+pub const GENERATOR: &str = "finnkino-rs";
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct FeedItem {
+  pub id: String,
+  pub title: String,
+  pub link: String,
+  /// HTML
+  pub summary: String,
+  /// HTML
+  pub content: Option<String>,
+  pub categories: Vec<String>,
+  pub published: Option<LocalDateTime>,
+  pub updated: LocalDateTime,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Feed {
+  pub id: String,
+  pub title: String,
+  pub link: String,
+  pub description: String,
+  pub updated: LocalDateTime,
+  pub items: Vec<FeedItem>,
+}
+
+impl Feed {
+  pub fn coming_soon(events: Vec<Event>, revisions: &Revisions, now: LocalDateTime) -> Feed {
+    let items = events
+      .into_iter()
+      .map(|event| {
+        let revision = revisions.revise(&event, now);
+        event_item(event, revision)
+      })
+      .collect();
+    Feed::new(
+      "urn:finnkino:feeds:coming-soon",
+      "Finnkino: Coming soon",
+      "Films coming soon to Finnkino theatres",
+      items,
+      now,
+    )
+  }
+
+  pub fn news(articles: Vec<NewsArticle>, now: LocalDateTime) -> Feed {
+    let items = articles
+      .into_iter()
+      .map(|article| news_item(article, now))
+      .collect();
+    Feed::new(
+      "urn:finnkino:feeds:news",
+      "Finnkino: News",
+      "News from Finnkino",
+      items,
+      now,
+    )
+  }
+
+  fn new(
+    id: &str,
+    title: &str,
+    description: &str,
+    mut items: Vec<FeedItem>,
+    now: LocalDateTime,
+  ) -> Feed {
+    items.sort_by(|a, b| (b.updated, &a.id).cmp(&(a.updated, &b.id)));
+    Feed {
+      id: id.to_string(),
+      title: title.to_string(),
+      link: FINNKINO_URL.to_string(),
+      description: description.to_string(),
+      updated: items.iter().map(|item| item.updated).max().unwrap_or(now),
+      items,
+    }
+  }
+
+  pub fn to_atom(&self) -> String {
+    let entries: Vec<atom::Entry> = self
+      .items
+      .iter()
+      .map(|item| {
+        atom::EntryBuilder::default()
+          .id(item.id.clone())
+          .title(item.title.as_str())
+          .updated(item.updated.fixed_offset())
+          .published(item.published.map(|published| published.fixed_offset()))
+          .links(alternate_link(&item.link))
+          .summary(Some(atom::Text::html(item.summary.clone())))
+          .content(item.content.as_ref().map(|content| {
+            atom::ContentBuilder::default()
+              .content_type(Some("html".to_string()))
+              .value(Some(content.clone()))
+              .build()
+          }))
+          .categories(
+            item
+              .categories
+              .iter()
+              .map(|category| {
+                atom::CategoryBuilder::default()
+                  .term(category.clone())
+                  .build()
+              })
+              .collect::<Vec<atom::Category>>(),
+          )
+          .build()
+      })
+      .collect();
+    atom::FeedBuilder::default()
+      .id(self.id.clone())
+      .title(self.title.as_str())
+      .subtitle(Some(atom::Text::plain(self.description.clone())))
+      .updated(self.updated.fixed_offset())
+      .authors(vec![atom::PersonBuilder::default()
+        .name("Finnkino")
+        .uri(Some(FINNKINO_URL.to_string()))
+        .build()])
+      .generator(Some(
+        atom::GeneratorBuilder::default().value(GENERATOR).build(),
+      ))
+      .links(alternate_link(&self.link))
+      .entries(entries)
+      .build()
+      .to_string()
+  }
+
+  pub fn to_rss(&self) -> String {
+    let items: Vec<rss::Item> = self
+      .items
+      .iter()
+      .map(|item| {
+        rss::ItemBuilder::default()
+          .title(Some(item.title.clone()))
+          .link(Some(item.link.clone()).filter(|link| !link.is_empty()))
+          .description(Some(item.summary.clone()))
+          .content(item.content.clone())
+          .guid(Some(
+            rss::GuidBuilder::default()
+              .value(item.id.clone())
+              .permalink(false)
+              .build(),
+          ))
+          .pub_date(Some(item.updated.to_rfc2822()))
+          .categories(
+            item
+              .categories
+              .iter()
+              .map(|category| {
+                rss::CategoryBuilder::default()
+                  .name(category.clone())
+                  .build()
+              })
+              .collect::<Vec<rss::Category>>(),
+          )
+          .build()
+      })
+      .collect();
+    rss::ChannelBuilder::default()
+      .title(self.title.clone())
+      .link(self.link.clone())
+      .description(self.description.clone())
+      .generator(Some(GENERATOR.to_string()))
+      .last_build_date(Some(self.updated.to_rfc2822()))
+      .items(items)
+      .build()
+      .to_string()
+  }
+}
+
+/// Remembers what each Coming Soon film looked like, so its entry is only
+/// marked updated when the local release date or synopsis changes. Films seen
+/// for the first time after a restart count as new.
+#[derive(Debug, Default)]
+pub struct Revisions {
+  revisions: Mutex<HashMap<EventId, Revision>>,
+}
+
+#[derive(Clone, Debug)]
+struct Revision {
+  local_release: Option<LocalDateTime>,
+  synopsis: String,
+  published: LocalDateTime,
+  updated: LocalDateTime,
+}
+
+impl Revisions {
+  fn revise(&self, event: &Event, now: LocalDateTime) -> Revision {
+    let mut revisions = self.revisions.lock().unwrap();
+    let revision = revisions.entry(event.id).or_insert_with(|| Revision {
+      local_release: event.local_release,
+      synopsis: event.synopsis.clone(),
+      published: now,
+      updated: now,
+    });
+    if revision.local_release != event.local_release || revision.synopsis != event.synopsis {
+      revision.local_release = event.local_release;
+      revision.synopsis = event.synopsis.clone();
+      revision.updated = now;
+    }
+    revision.clone()
+  }
+}
+
+fn event_item(event: Event, revision: Revision) -> FeedItem {
+  let mut summary = Vec::new();
+  if let Some(local_release) = event.local_release {
+    summary.push(format!(
+      "Premiere {}.",
+      time::to_finnkino_date(&local_release.date_naive())
+    ));
+  }
+  if !event.short_synopsis.is_empty() {
+    summary.push(escape_html(&event.short_synopsis));
+  }
+  FeedItem {
+    id: format!("urn:finnkino:event:{}", event.id),
+    title: event.title.clone(),
+    link: event.event_url.clone(),
+    summary: summary.join(" "),
+    content: Some(event.synopsis.clone()).filter(|synopsis| !synopsis.is_empty()),
+    categories: event
+      .genres()
+      .iter()
+      .map(|genre| genre.to_string())
+      .collect(),
+    published: Some(revision.published),
+    updated: revision.updated,
+  }
+}
+
+fn news_item(article: NewsArticle, now: LocalDateTime) -> FeedItem {
+  let id = if article.article_url.is_empty() {
+    format!("urn:finnkino:news:{}", article.title)
+  } else {
+    article.article_url.clone()
+  };
+  FeedItem {
+    id,
+    title: article.title,
+    link: article.article_url,
+    summary: article.html_lead,
+    content: None,
+    categories: article
+      .categories
+      .categories
+      .into_iter()
+      .map(|category| category.name)
+      .collect(),
+    published: article.publish_date,
+    updated: article.publish_date.unwrap_or(now),
+  }
+}
+
+fn alternate_link(href: &str) -> Vec<atom::Link> {
+  if href.is_empty() {
+    return Vec::new();
+  }
+  vec![atom::LinkBuilder::default()
+    .href(href)
+    .rel("alternate")
+    .mime_type(Some("text/html".to_string()))
+    .build()]
+}
+
+fn escape_html(value: &str) -> String {
+  value
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::finnkino::{NewsCategories, NewsCategory};
+  use pretty_assertions::assert_eq;
+  use rss::validation::Validate;
+
+  fn at(datetime: &str) -> LocalDateTime {
+    time::parse_finnkino_datetime(datetime).unwrap()
+  }
+
+  fn event() -> Event {
+    Event {
+      id: EventId(303000),
+      title: "Minions & Friends".to_string(),
+      local_release: Some(at("2022-09-01T00:00:00")),
+      genres: "Animaatio, Komedia".to_string(),
+      short_synopsis: "Gru <3".to_string(),
+      synopsis: "<p>Gru grows up.</p>".to_string(),
+      event_url: "http://www.finnkino.fi/Event/303000/".to_string(),
+      ..Event::default()
+    }
+  }
+
+  fn article() -> NewsArticle {
+    NewsArticle {
+      title: "Syksyn ensi-illat".to_string(),
+      publish_date: Some(at("2022-08-19T09:00:00")),
+      html_lead: "<p>Katso syksyn elokuvat</p>".to_string(),
+      article_url: "http://www.finnkino.fi/news/syksy/".to_string(),
+      categories: NewsCategories {
+        categories: vec![NewsCategory {
+          id: "1073".to_string(),
+          name: "Elokuvauutiset".to_string(),
+        }],
+      },
+      ..NewsArticle::default()
+    }
+  }
+
+  #[test]
+  fn test_coming_soon_atom() {
+    let feed = Feed::coming_soon(
+      vec![event()],
+      &Revisions::default(),
+      at("2022-08-20T12:00:00"),
+    );
+
+    insta::assert_snapshot!(feed.to_atom());
+  }
+
+  #[test]
+  fn test_atom_feed_is_valid() {
+    let feed = Feed::coming_soon(
+      vec![event()],
+      &Revisions::default(),
+      at("2022-08-20T12:00:00"),
+    );
+    let parsed = atom::Feed::read_from(feed.to_atom().as_bytes()).unwrap();
+    let entry = &parsed.entries()[0];
+
+    assert_eq!("urn:finnkino:feeds:coming-soon", parsed.id());
+    assert_eq!("urn:finnkino:event:303000", entry.id());
+    assert_eq!("Minions & Friends", entry.title().as_str());
+    assert_eq!(
+      "Premiere 01.09.2022. Gru &lt;3",
+      entry.summary().unwrap().as_str()
+    );
+    assert_eq!(
+      "http://www.finnkino.fi/Event/303000/",
+      entry.links()[0].href()
+    );
+    assert_eq!(2, entry.categories().len());
+  }
+
+  #[test]
+  fn test_rss_feed_is_valid() {
+    let feed = Feed::news(vec![article()], at("2022-08-20T12:00:00"));
+    let channel = rss::Channel::read_from(feed.to_rss().as_bytes()).unwrap();
+
+    channel.validate().unwrap();
+    assert_eq!(1, channel.items().len());
+    assert_eq!(
+      Some("http://www.finnkino.fi/news/syksy/"),
+      channel.items()[0].guid().map(|guid| guid.value())
+    );
+    assert_eq!(
+      Some("Fri, 19 Aug 2022 09:00:00 +0300"),
+      channel.items()[0].pub_date()
+    );
+  }
+
+  #[test]
+  fn test_empty_feeds_are_valid() {
+    let now = at("2022-08-20T12:00:00");
+    let news = Feed::news(vec![], now);
+    let coming_soon = Feed::coming_soon(vec![], &Revisions::default(), now);
+
+    rss::Channel::read_from(news.to_rss().as_bytes())
+      .unwrap()
+      .validate()
+      .unwrap();
+    rss::Channel::read_from(coming_soon.to_rss().as_bytes())
+      .unwrap()
+      .validate()
+      .unwrap();
+    atom::Feed::read_from(news.to_atom().as_bytes()).unwrap();
+    assert_eq!(now, coming_soon.updated);
+  }
+
+  #[test]
+  fn test_entry_updated_only_on_release_or_synopsis_change() {
+    let revisions = Revisions::default();
+    let first = at("2022-08-20T12:00:00");
+    Feed::coming_soon(vec![event()], &revisions, first);
+
+    let unchanged = Feed::coming_soon(
+      vec![Event {
+        title: "Minions".to_string(),
+        ..event()
+      }],
+      &revisions,
+      at("2022-08-21T12:00:00"),
+    );
+    assert_eq!(first, unchanged.items[0].updated);
+
+    let later = at("2022-08-22T12:00:00");
+    let changed = Feed::coming_soon(
+      vec![Event {
+        local_release: Some(at("2022-09-08T00:00:00")),
+        ..event()
+      }],
+      &revisions,
+      later,
+    );
+    assert_eq!(later, changed.items[0].updated);
+    assert_eq!(Some(first), changed.items[0].published);
+    assert_eq!(later, changed.updated);
+  }
+}
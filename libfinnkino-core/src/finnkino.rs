@@ -1,6 +1,29 @@
+use quick_xml::de::from_str;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::vec::Vec;
 
+use crate::id::{AreaId, EventId, ShowId};
+use crate::time::{self, LocalDateTime};
+
+pub const BASE_URL: &str = "https://www.finnkino.fi/xml";
+
+/// The longest feed body the clients read, in bytes, so that an upstream
+/// cannot make them buffer without end.
+pub const BODY_LIMIT: usize = 8 * 1024 * 1024;
+
+pub fn from_xml<T: DeserializeOwned>(xml: &str) -> Result<T, Error> {
+  from_str::<T>(xml).map_err(|err| {
+    ErrorBuilder::default()
+      .kind(ErrorKind::Xml)
+      .title("Failed to serialize XML")
+      .detail(format!("{:?}", err))
+      .build()
+      .unwrap()
+  })
+}
+
 #[derive(Deserialize, Debug)]
 pub struct TheatreAreas {
   #[serde(rename(deserialize = "TheatreArea"))]
@@ -9,12 +32,275 @@ pub struct TheatreAreas {
 
 #[derive(Eq, Debug, Deserialize, PartialEq, Serialize)]
 pub struct TheatreArea {
+  #[serde(rename(deserialize = "ID"))]
+  pub id: AreaId,
+  #[serde(rename(deserialize = "Name"))]
+  pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Schedule {
+  #[serde(rename(deserialize = "PubDate"), default)]
+  pub pub_date: LocalDateTime,
+  #[serde(rename(deserialize = "Shows"))]
+  pub shows: Shows,
+}
+
+/// The days that have shows, each at midnight.
+#[derive(Deserialize, Debug)]
+pub struct ScheduleDates {
+  #[serde(rename(deserialize = "dateTime"), default)]
+  pub dates: Vec<LocalDateTime>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Shows {
+  #[serde(rename(deserialize = "Show"), default)]
+  pub shows: Vec<Show>,
+}
+
+#[derive(Clone, Eq, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Language {
+  #[serde(rename(deserialize = "Name"))]
+  pub name: String,
+  #[serde(rename(deserialize = "NameInLanguage"))]
+  pub name_in_language: String,
+  #[serde(rename(deserialize = "ISOTwoLetterCode"))]
+  pub iso_two_letter_code: String,
+}
+
+impl Language {
+  pub fn matches(&self, language: &str) -> bool {
+    self.iso_two_letter_code.eq_ignore_ascii_case(language)
+      || self.name.to_lowercase() == language.to_lowercase()
+      || self.name_in_language.to_lowercase() == language.to_lowercase()
+  }
+}
+
+#[derive(Clone, Eq, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Show {
+  #[serde(rename(deserialize = "ID"))]
+  pub id: ShowId,
+  #[serde(rename(deserialize = "dttmShowStart"))]
+  pub show_start: LocalDateTime,
+  #[serde(rename(deserialize = "dttmShowEnd"))]
+  pub show_end: LocalDateTime,
+  #[serde(rename(deserialize = "EventID"))]
+  pub event_id: EventId,
+  #[serde(rename(deserialize = "Title"))]
+  pub title: String,
+  #[serde(rename(deserialize = "OriginalTitle"))]
+  pub original_title: String,
+  #[serde(rename(deserialize = "ProductionYear"))]
+  pub production_year: String,
+  #[serde(rename(deserialize = "LengthInMinutes"))]
+  pub length_in_minutes: String,
+  #[serde(
+    rename(deserialize = "dtLocalRelease"),
+    deserialize_with = "time::deserialize_optional"
+  )]
+  pub local_release: Option<LocalDateTime>,
+  #[serde(rename(deserialize = "Rating"))]
+  pub rating: String,
+  #[serde(rename(deserialize = "RatingLabel"))]
+  pub rating_label: String,
+  #[serde(rename(deserialize = "EventType"))]
+  pub event_type: String,
+  #[serde(rename(deserialize = "Genres"))]
+  pub genres: String,
+  #[serde(rename(deserialize = "TheatreID"))]
+  pub theatre_id: AreaId,
+  #[serde(rename(deserialize = "TheatreAuditriumID"))]
+  pub auditorium_id: String,
+  #[serde(rename(deserialize = "Theatre"))]
+  pub theatre: String,
+  #[serde(rename(deserialize = "TheatreAuditorium"))]
+  pub auditorium: String,
+  #[serde(rename(deserialize = "PresentationMethod"))]
+  pub presentation_method: String,
+  #[serde(rename(deserialize = "ShowURL"))]
+  pub show_url: String,
+  #[serde(rename(deserialize = "EventURL"))]
+  pub event_url: String,
+  #[serde(rename(deserialize = "SpokenLanguage"))]
+  pub spoken_language: Option<Language>,
+  #[serde(rename(deserialize = "SubtitleLanguage1"))]
+  pub subtitle_language_1: Option<Language>,
+  #[serde(rename(deserialize = "SubtitleLanguage2"))]
+  pub subtitle_language_2: Option<Language>,
+}
+
+impl Show {
+  pub fn genres(&self) -> Vec<&str> {
+    split_genres(&self.genres)
+  }
+
+  pub fn subtitle_languages(&self) -> Vec<&Language> {
+    self
+      .subtitle_language_1
+      .iter()
+      .chain(self.subtitle_language_2.iter())
+      .filter(|language| !language.iso_two_letter_code.is_empty())
+      .collect()
+  }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Events {
+  #[serde(rename(deserialize = "Event"), default)]
+  pub events: Vec<Event>,
+}
+
+#[derive(Clone, Eq, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Images {
+  #[serde(rename(deserialize = "EventSmallImagePortrait"))]
+  pub small_portrait: Option<String>,
+  #[serde(rename(deserialize = "EventMediumImagePortrait"))]
+  pub medium_portrait: Option<String>,
+  #[serde(rename(deserialize = "EventLargeImagePortrait"))]
+  pub large_portrait: Option<String>,
+  #[serde(rename(deserialize = "EventSmallImageLandscape"))]
+  pub small_landscape: Option<String>,
+  #[serde(rename(deserialize = "EventMediumImageLandscape"))]
+  pub medium_landscape: Option<String>,
+  #[serde(rename(deserialize = "EventLargeImageLandscape"))]
+  pub large_landscape: Option<String>,
+}
+
+#[derive(Clone, Eq, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Person {
+  #[serde(rename(deserialize = "FirstName"))]
+  pub first_name: String,
+  #[serde(rename(deserialize = "LastName"))]
+  pub last_name: String,
+}
+
+impl Person {
+  pub fn full_name(&self) -> String {
+    format!("{} {}", self.first_name, self.last_name)
+      .trim()
+      .to_string()
+  }
+}
+
+#[derive(Clone, Eq, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Cast {
+  #[serde(rename(deserialize = "Actor"))]
+  pub actors: Vec<Person>,
+}
+
+#[derive(Clone, Eq, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Directors {
+  #[serde(rename(deserialize = "Director"))]
+  pub directors: Vec<Person>,
+}
+
+#[derive(Clone, Eq, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Event {
+  #[serde(rename(deserialize = "ID"))]
+  pub id: EventId,
+  #[serde(rename(deserialize = "Title"))]
+  pub title: String,
+  #[serde(rename(deserialize = "OriginalTitle"))]
+  pub original_title: String,
+  #[serde(rename(deserialize = "ProductionYear"))]
+  pub production_year: String,
+  #[serde(rename(deserialize = "LengthInMinutes"))]
+  pub length_in_minutes: String,
+  #[serde(
+    rename(deserialize = "dtLocalRelease"),
+    deserialize_with = "time::deserialize_optional"
+  )]
+  pub local_release: Option<LocalDateTime>,
+  #[serde(rename(deserialize = "Rating"))]
+  pub rating: String,
+  #[serde(rename(deserialize = "RatingLabel"))]
+  pub rating_label: String,
+  #[serde(rename(deserialize = "LocalDistributorName"))]
+  pub local_distributor_name: String,
+  #[serde(rename(deserialize = "EventType"))]
+  pub event_type: String,
+  #[serde(rename(deserialize = "Genres"))]
+  pub genres: String,
+  #[serde(rename(deserialize = "ShortSynopsis"))]
+  pub short_synopsis: String,
+  #[serde(rename(deserialize = "Synopsis"))]
+  pub synopsis: String,
+  #[serde(rename(deserialize = "EventURL"))]
+  pub event_url: String,
+  #[serde(rename(deserialize = "Images"))]
+  pub images: Images,
+  #[serde(rename(deserialize = "Cast"))]
+  pub cast: Cast,
+  #[serde(rename(deserialize = "Directors"))]
+  pub directors: Directors,
+}
+
+impl Event {
+  pub fn genres(&self) -> Vec<&str> {
+    split_genres(&self.genres)
+  }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct News {
+  #[serde(rename(deserialize = "NewsArticle"), default)]
+  pub articles: Vec<NewsArticle>,
+}
+
+#[derive(Clone, Eq, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct NewsCategory {
   #[serde(rename(deserialize = "ID"))]
   pub id: String,
   #[serde(rename(deserialize = "Name"))]
   pub name: String,
 }
 
+#[derive(Clone, Eq, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct NewsCategories {
+  #[serde(rename(deserialize = "NewsArticleCategory"))]
+  pub categories: Vec<NewsCategory>,
+}
+
+#[derive(Clone, Eq, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct NewsArticle {
+  #[serde(rename(deserialize = "Title"))]
+  pub title: String,
+  #[serde(
+    rename(deserialize = "PublishDate"),
+    deserialize_with = "time::deserialize_optional"
+  )]
+  pub publish_date: Option<LocalDateTime>,
+  #[serde(rename(deserialize = "HTMLLead"))]
+  pub html_lead: String,
+  #[serde(rename(deserialize = "ArticleURL"))]
+  pub article_url: String,
+  #[serde(rename(deserialize = "NewsArticleCategories"))]
+  pub categories: NewsCategories,
+  #[serde(rename(deserialize = "ImageURL"))]
+  pub image_url: String,
+  #[serde(rename(deserialize = "ThumbnailURL"))]
+  pub thumbnail_url: String,
+}
+
+fn split_genres(genres: &str) -> Vec<&str> {
+  genres
+    .split(',')
+    .map(str::trim)
+    .filter(|genre| !genre.is_empty())
+    .collect()
+}
+
 #[derive(Builder, Clone, Eq, Debug, PartialEq, Serialize)]
 #[builder(setter(into))]
 pub struct Error {
@@ -30,4 +316,288 @@ pub struct Error {
   #[builder(setter(strip_option), default)]
   #[serde(skip_serializing_if = "Option::is_none")]
   pub detail: Option<String>,
+  #[builder(default)]
+  #[serde(skip)]
+  pub kind: ErrorKind,
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let title = self.title.as_deref().unwrap_or("Error");
+    match &self.detail {
+      Some(detail) => write!(f, "{}: {}", title, detail),
+      None => f.write_str(title),
+    }
+  }
+}
+
+/// The HTTP status the servers answer `error` with: 404 when Finnkino has no
+/// such thing, and 502 for any other failure upstream.
+pub fn error_status(error: &Error) -> u16 {
+  match error.status.as_deref() {
+    Some("404") => 404,
+    _ => 502,
+  }
+}
+
+/// The broad cause of an [`Error`], the same whichever client fetched the
+/// feed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ErrorKind {
+  /// The request could not be sent, e.g. the host is unreachable.
+  Request,
+  /// Finnkino did not answer in time.
+  Timeout,
+  /// Finnkino or we answered with an error status.
+  Status,
+  /// The body could not be read, e.g. it was cut short or its compression
+  /// is corrupt.
+  Body,
+  /// The body is not UTF-8.
+  Encoding,
+  /// The body is not the expected XML.
+  Xml,
+  #[default]
+  Other,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  const SCHEDULE_XML: &str = r#"<?xml version="1.0"?>
+  <Schedule xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+    <PubDate>2022-08-20T12:00:00</PubDate>
+    <Shows>
+      <Show>
+        <ID>1794851</ID>
+        <dtAccounting>2022-08-20T00:00:00</dtAccounting>
+        <dttmShowStart>2022-08-20T18:00:00</dttmShowStart>
+        <dttmShowEnd>2022-08-20T19:40:00</dttmShowEnd>
+        <EventID>303000</EventID>
+        <Title>Minions: The Rise of Gru</Title>
+        <OriginalTitle>Minions: The Rise of Gru</OriginalTitle>
+        <ProductionYear>2022</ProductionYear>
+        <LengthInMinutes>87</LengthInMinutes>
+        <dtLocalRelease>2022-07-01T00:00:00</dtLocalRelease>
+        <Rating>7</Rating>
+        <RatingLabel>7</RatingLabel>
+        <EventType>Movie</EventType>
+        <Genres>Animaatio, Komedia</Genres>
+        <TheatreID>1038</TheatreID>
+        <TheatreAuditriumID>1234</TheatreAuditriumID>
+        <TheatreAndAuditorium>Itis, sali 1</TheatreAndAuditorium>
+        <Theatre>Itis</Theatre>
+        <TheatreAuditorium>sali 1</TheatreAuditorium>
+        <PresentationMethodAndLanguage>2D, FI</PresentationMethodAndLanguage>
+        <PresentationMethod>2D</PresentationMethod>
+        <EventSeries />
+        <ShowURL>http://www.finnkino.fi/Websales/Show/1794851/</ShowURL>
+        <EventURL>http://www.finnkino.fi/Event/303000/</EventURL>
+        <SpokenLanguage>
+          <Name>suomi</Name>
+          <NameInLanguage>suomi</NameInLanguage>
+          <ISOTwoLetterCode>FI</ISOTwoLetterCode>
+        </SpokenLanguage>
+        <SubtitleLanguage1>
+          <Name>ruotsi</Name>
+          <NameInLanguage>svenska</NameInLanguage>
+          <ISOTwoLetterCode>SV</ISOTwoLetterCode>
+        </SubtitleLanguage1>
+      </Show>
+    </Shows>
+  </Schedule>"#;
+
+  #[test]
+  fn test_can_parse_areas() {
+    let xml = r#"<?xml version="1.0"?>
+    <TheatreAreas xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+      <TheatreArea>
+        <ID>1029</ID>
+        <Name>Valitse alue/teatteri</Name>
+      </TheatreArea>
+    </TheatreAreas>"#;
+    let areas = from_xml::<TheatreAreas>(xml).unwrap();
+    let expected_areas = vec![TheatreArea {
+      id: AreaId(1029),
+      name: "Valitse alue/teatteri".to_string(),
+    }];
+
+    assert_eq!(expected_areas, areas.theatre_areas);
+  }
+
+  #[test]
+  fn test_can_parse_schedule() {
+    let schedule = from_xml::<Schedule>(SCHEDULE_XML).unwrap();
+    let show = &schedule.shows.shows[0];
+
+    assert_eq!(1, schedule.shows.shows.len());
+    assert_eq!(ShowId(1794851), show.id);
+    assert_eq!("2022-08-20T18:00:00+03:00", show.show_start.to_rfc3339());
+    assert_eq!(
+      Some("2022-07-01T00:00:00+03:00".to_string()),
+      show.local_release.map(|release| release.to_rfc3339())
+    );
+    assert_eq!(EventId(303000), show.event_id);
+    assert_eq!("2D", show.presentation_method);
+    assert_eq!(vec!["Animaatio", "Komedia"], show.genres());
+    assert_eq!(
+      "FI",
+      show.spoken_language.as_ref().unwrap().iso_two_letter_code
+    );
+    assert_eq!(1, show.subtitle_languages().len());
+    assert_eq!(None, show.subtitle_language_2);
+  }
+
+  #[test]
+  fn test_can_parse_empty_schedule() {
+    let xml = r#"<?xml version="1.0"?>
+    <Schedule>
+      <PubDate>2022-08-20T12:00:00</PubDate>
+      <Shows />
+    </Schedule>"#;
+    let schedule = from_xml::<Schedule>(xml).unwrap();
+
+    assert!(schedule.shows.shows.is_empty());
+  }
+
+  #[test]
+  fn test_can_parse_schedule_dates() {
+    let xml = r#"<?xml version="1.0"?>
+    <Dates xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+      <dateTime>2022-08-20T00:00:00</dateTime>
+      <dateTime>2022-08-21T00:00:00</dateTime>
+    </Dates>"#;
+    let dates = from_xml::<ScheduleDates>(xml).unwrap();
+
+    assert_eq!(
+      vec!["2022-08-20T00:00:00+03:00", "2022-08-21T00:00:00+03:00"],
+      dates
+        .dates
+        .iter()
+        .map(|date| date.to_rfc3339())
+        .collect::<Vec<_>>()
+    );
+    assert!(from_xml::<ScheduleDates>("<Dates />")
+      .unwrap()
+      .dates
+      .is_empty());
+  }
+
+  #[test]
+  fn test_can_parse_events() {
+    let xml = r#"<?xml version="1.0"?>
+    <Events xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+      <Event>
+        <ID>303000</ID>
+        <Title>Minions: The Rise of Gru</Title>
+        <OriginalTitle>Minions: The Rise of Gru</OriginalTitle>
+        <ProductionYear>2022</ProductionYear>
+        <LengthInMinutes>87</LengthInMinutes>
+        <dtLocalRelease>2022-07-01T00:00:00</dtLocalRelease>
+        <Rating>7</Rating>
+        <RatingLabel>7</RatingLabel>
+        <EventType>Movie</EventType>
+        <Genres>Animaatio, Komedia</Genres>
+        <ShortSynopsis>Short</ShortSynopsis>
+        <Synopsis>Long</Synopsis>
+        <EventURL>http://www.finnkino.fi/Event/303000/</EventURL>
+        <Images>
+          <EventSmallImagePortrait>http://media.finnkino.fi/small.jpg</EventSmallImagePortrait>
+          <EventLargeImagePortrait>http://media.finnkino.fi/large.jpg</EventLargeImagePortrait>
+        </Images>
+        <Cast>
+          <Actor>
+            <FirstName>Steve</FirstName>
+            <LastName>Carell</LastName>
+          </Actor>
+        </Cast>
+        <Directors>
+          <Director>
+            <FirstName>Kyle</FirstName>
+            <LastName>Balda</LastName>
+          </Director>
+        </Directors>
+      </Event>
+    </Events>"#;
+    let events = from_xml::<Events>(xml).unwrap();
+    let event = &events.events[0];
+
+    assert_eq!(EventId(303000), event.id);
+    assert_eq!(vec!["Animaatio", "Komedia"], event.genres());
+    assert!(event.local_release.is_some());
+    assert_eq!(
+      Some("http://media.finnkino.fi/large.jpg".to_string()),
+      event.images.large_portrait
+    );
+    assert_eq!(None, event.images.medium_portrait);
+    assert_eq!("Steve Carell", event.cast.actors[0].full_name());
+    assert_eq!("Kyle Balda", event.directors.directors[0].full_name());
+  }
+
+  #[test]
+  fn test_empty_local_release_is_none() {
+    let xml = r#"<Events><Event><ID>1</ID><dtLocalRelease /></Event></Events>"#;
+    let events = from_xml::<Events>(xml).unwrap();
+
+    assert_eq!(None, events.events[0].local_release);
+  }
+
+  #[test]
+  fn test_can_parse_news() {
+    let xml = r#"<?xml version="1.0"?>
+    <News xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+      <NewsArticle>
+        <Title>Syksyn ensi-illat</Title>
+        <PublishDate>2022-08-19T09:00:00</PublishDate>
+        <HTMLLead>&lt;p&gt;Katso syksyn elokuvat&lt;/p&gt;</HTMLLead>
+        <ArticleURL>http://www.finnkino.fi/news/syksy/</ArticleURL>
+        <NewsArticleCategories>
+          <NewsArticleCategory>
+            <ID>1073</ID>
+            <Name>Elokuvauutiset</Name>
+          </NewsArticleCategory>
+        </NewsArticleCategories>
+        <ImageURL>http://media.finnkino.fi/news/syksy.jpg</ImageURL>
+        <ThumbnailURL />
+      </NewsArticle>
+    </News>"#;
+    let news = from_xml::<News>(xml).unwrap();
+    let article = &news.articles[0];
+
+    assert_eq!("Syksyn ensi-illat", article.title);
+    assert_eq!(
+      "2022-08-19T09:00:00+03:00",
+      article.publish_date.unwrap().to_rfc3339()
+    );
+    assert_eq!("<p>Katso syksyn elokuvat</p>", article.html_lead);
+    assert_eq!("Elokuvauutiset", article.categories.categories[0].name);
+    assert_eq!("", article.thumbnail_url);
+  }
+
+  #[test]
+  fn test_parse_non_numeric_id_fails() {
+    let xml =
+      r#"<TheatreAreas><TheatreArea><ID>abc</ID><Name>x</Name></TheatreArea></TheatreAreas>"#;
+
+    assert!(from_xml::<TheatreAreas>(xml).is_err());
+  }
+
+  #[test]
+  fn test_parse_malformed_xml_fails() {
+    let error = from_xml::<Schedule>("<Schedule>").unwrap_err();
+
+    assert_eq!(Some("Failed to serialize XML".to_string()), error.title);
+    assert_eq!(ErrorKind::Xml, error.kind);
+  }
+
+  #[test]
+  fn test_error_status() {
+    let error = |status: &str| ErrorBuilder::default().status(status).build().unwrap();
+
+    assert_eq!(404, error_status(&error("404")));
+    assert_eq!(502, error_status(&error("503")));
+    assert_eq!(502, error_status(&from_xml::<Schedule>("<Schedule>").unwrap_err()));
+  }
 }
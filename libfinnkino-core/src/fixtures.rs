@@ -0,0 +1,306 @@
+//! Finnkino's feeds read from a directory instead of the network, for
+//! working offline. A request is looked up by feed and query: the file
+//! `Schedule/area=1014&dt=20.08.2022.xml` serves exactly that request, with
+//! the query parameters sorted, and `Schedule.xml` any request of the feed
+//! without a file of its own. The feeds are `TheatreAreas`, `ScheduleDates`,
+//! `Schedule`, `Events` and `News`, each as served by Finnkino.
+//!
+//! The file of a request stands in for Finnkino's response to it, so its
+//! shows only go through the filters the clients apply to every Schedule,
+//! such as genre or language. A feed-wide file is also filtered here the way
+//! Finnkino filters the feed, except that an area matches only its own
+//! theatre, that without a date every show is listed and that the
+//! ScheduleDates are the same for every area.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::vec::Vec;
+
+use crate::events::{EventQuery, EventQueryBuilder};
+use crate::finnkino::{
+  from_xml, Error, ErrorBuilder, ErrorKind, Event, Events, News, NewsArticle, Schedule,
+  ScheduleDates, Show, TheatreArea, TheatreAreas, BASE_URL,
+};
+use crate::id::{AreaId, EventId};
+use crate::schedule::{schedule_dates_url, ShowFilter};
+use crate::time::LocalDateTime;
+
+/// Overrides the `fixtures` directory of the configuration.
+pub const FIXTURES_ENV: &str = "FINNKINO_FIXTURES";
+
+/// The feed and the sorted query of a feed URL, e.g. `("Schedule",
+/// "area=1014&dt=20.08.2022")`.
+pub fn feed_key(url: &str) -> (String, String) {
+  let (path, query) = url.split_once('?').unwrap_or((url, ""));
+  let feed = path
+    .trim_end_matches('/')
+    .rsplit('/')
+    .next()
+    .unwrap_or_default();
+  let mut params: Vec<&str> = query.split('&').filter(|param| !param.is_empty()).collect();
+  params.sort_unstable();
+  (feed.to_string(), params.join("&"))
+}
+
+#[derive(Clone, Debug)]
+pub struct Fixtures {
+  dir: PathBuf,
+}
+
+/// The XML of a request, and whether it came from the request's own file.
+struct Fixture {
+  xml: String,
+  exact: bool,
+}
+
+fn not_found(detail: String) -> Error {
+  ErrorBuilder::default()
+    .kind(ErrorKind::Status)
+    .status("404")
+    .title("Not Found")
+    .detail(detail)
+    .build()
+    .unwrap()
+}
+
+impl Fixtures {
+  pub fn new(dir: impl AsRef<Path>) -> Self {
+    Fixtures {
+      dir: dir.as_ref().to_path_buf(),
+    }
+  }
+
+  fn read(&self, path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|error| {
+      ErrorBuilder::default()
+        .title("Fixture error")
+        .detail(format!("{}: {}", path.display(), error))
+        .build()
+        .unwrap()
+    })
+  }
+
+  fn lookup(&self, url: &str) -> Result<Fixture, Error> {
+    let (feed, query) = feed_key(url);
+    let path = self.dir.join(&feed).join(format!("{}.xml", query));
+    if !query.is_empty() && path.is_file() {
+      let xml = self.read(&path)?;
+      return Ok(Fixture { xml, exact: true });
+    }
+    let xml = self.read(&self.dir.join(format!("{}.xml", feed)))?;
+    Ok(Fixture { xml, exact: false })
+  }
+
+  /// The XML served for a feed URL.
+  pub fn xml(&self, url: &str) -> Result<String, Error> {
+    self.lookup(url).map(|fixture| fixture.xml)
+  }
+
+  pub fn areas(&self) -> Result<Vec<TheatreArea>, Error> {
+    let xml = self.xml(&format!("{}/TheatreAreas", BASE_URL))?;
+    from_xml::<TheatreAreas>(&xml).map(|areas| areas.theatre_areas)
+  }
+
+  pub fn schedule_dates(&self, area: Option<AreaId>) -> Result<Vec<LocalDateTime>, Error> {
+    let xml = self.xml(&schedule_dates_url(BASE_URL, area))?;
+    from_xml::<ScheduleDates>(&xml).map(|dates| dates.dates)
+  }
+
+  pub fn shows(&self, filter: &ShowFilter) -> Result<Vec<Show>, Error> {
+    let fixture = self.lookup(&filter.schedule_url(BASE_URL))?;
+    let shows = from_xml::<Schedule>(&fixture.xml)?.shows.shows;
+    if fixture.exact {
+      return Ok(filter.apply(shows));
+    }
+    let until = filter.until.or(filter.date);
+    Ok(
+      filter.apply(
+        shows
+          .into_iter()
+          .filter(|show| filter.area.is_none_or(|area| show.theatre_id == area))
+          .filter(|show| filter.event.is_none_or(|event| show.event_id == event))
+          .filter(|show| {
+            let date = show.show_start.date_naive();
+            filter.date.is_none_or(|from| date >= from) && until.is_none_or(|until| date <= until)
+          })
+          .collect(),
+      ),
+    )
+  }
+
+  pub fn events(&self, query: &EventQuery) -> Result<Vec<Event>, Error> {
+    let fixture = self.lookup(&query.events_url(BASE_URL))?;
+    let events = from_xml::<Events>(&fixture.xml)?.events;
+    if fixture.exact {
+      return Ok(events);
+    }
+    Ok(
+      events
+        .into_iter()
+        .filter(|event| query.event.is_none_or(|id| event.id == id))
+        .collect(),
+    )
+  }
+
+  pub fn event(&self, id: EventId) -> Result<Event, Error> {
+    let query = EventQueryBuilder::default().event(id).build().unwrap();
+    let events = self.events(&query)?;
+    events
+      .into_iter()
+      .find(|event| event.id == id)
+      .ok_or_else(|| not_found(format!("No event with id {}", id)))
+  }
+
+  pub fn news(&self) -> Result<Vec<NewsArticle>, Error> {
+    let xml = self.xml(&format!("{}/News/", BASE_URL))?;
+    from_xml::<News>(&xml).map(|news| news.articles)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::events::ListType;
+  use crate::id::ShowId;
+  use chrono::NaiveDate;
+  use pretty_assertions::assert_eq;
+
+  const SCHEDULE_XML: &str = r#"<?xml version="1.0"?>
+  <Schedule>
+    <Shows>
+      <Show><ID>1</ID><EventID>303000</EventID><TheatreID>1038</TheatreID>
+        <dttmShowStart>2022-08-20T18:30:00</dttmShowStart><Genres>Draama</Genres></Show>
+      <Show><ID>2</ID><EventID>302000</EventID><TheatreID>1038</TheatreID>
+        <dttmShowStart>2022-08-21T12:00:00</dttmShowStart><Genres>Komedia</Genres></Show>
+      <Show><ID>3</ID><EventID>303000</EventID><TheatreID>1033</TheatreID>
+        <dttmShowStart>2022-08-20T21:00:00</dttmShowStart><Genres>Draama</Genres></Show>
+    </Shows>
+  </Schedule>"#;
+
+  const EVENTS_XML: &str = r#"<?xml version="1.0"?>
+  <Events>
+    <Event><ID>303000</ID><Title>Minions</Title></Event>
+    <Event><ID>302000</ID><Title>Elvis</Title></Event>
+  </Events>"#;
+
+  fn fixtures(name: &str) -> Fixtures {
+    let dir =
+      std::env::temp_dir().join(format!("finnkino-fixtures-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("Schedule.xml"), SCHEDULE_XML).unwrap();
+    fs::write(dir.join("Events.xml"), EVENTS_XML).unwrap();
+    fs::write(
+      dir.join("ScheduleDates.xml"),
+      "<Dates><dateTime>2022-08-20T00:00:00</dateTime></Dates>",
+    )
+    .unwrap();
+    Fixtures::new(dir)
+  }
+
+  fn ids(shows: Vec<Show>) -> Vec<ShowId> {
+    shows.into_iter().map(|show| show.id).collect()
+  }
+
+  #[test]
+  fn test_shows_are_filtered() {
+    let fixtures = fixtures("shows");
+    let date = NaiveDate::from_ymd_opt(2022, 8, 20).unwrap();
+
+    assert_eq!(3, fixtures.shows(&ShowFilter::default()).unwrap().len());
+    let filter = ShowFilter {
+      area: Some(AreaId(1038)),
+      ..ShowFilter::default()
+    };
+    assert_eq!(
+      vec![ShowId(1), ShowId(2)],
+      ids(fixtures.shows(&filter).unwrap())
+    );
+    let filter = ShowFilter {
+      date: Some(date),
+      genre: Some("draama".to_string()),
+      ..ShowFilter::default()
+    };
+    assert_eq!(
+      vec![ShowId(1), ShowId(3)],
+      ids(fixtures.shows(&filter).unwrap())
+    );
+    let filter = ShowFilter {
+      event: Some(EventId(302000)),
+      ..ShowFilter::default()
+    };
+    assert_eq!(vec![ShowId(2)], ids(fixtures.shows(&filter).unwrap()));
+  }
+
+  #[test]
+  fn test_schedule_dates() {
+    let fixtures = fixtures("dates");
+    let dates = fixtures.schedule_dates(Some(AreaId(1038))).unwrap();
+
+    assert_eq!(
+      vec![NaiveDate::from_ymd_opt(2022, 8, 20).unwrap()],
+      dates
+        .iter()
+        .map(|date| date.date_naive())
+        .collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn test_request_files_are_served_as_is() {
+    let fixtures = fixtures("requests");
+    let dir = fixtures.dir.join("Schedule");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("area=1014&dt=20.08.2022.xml"), SCHEDULE_XML).unwrap();
+    let filter = ShowFilter::from_query("date=2022-08-20&area=1014&genre=draama").unwrap();
+
+    assert_eq!(
+      vec![ShowId(1), ShowId(3)],
+      ids(fixtures.shows(&filter).unwrap())
+    );
+    let filter = ShowFilter::from_query("date=2022-08-20&area=1033").unwrap();
+    assert_eq!(vec![ShowId(3)], ids(fixtures.shows(&filter).unwrap()));
+  }
+
+  #[test]
+  fn test_feed_key() {
+    assert_eq!(
+      (
+        "Schedule".to_string(),
+        "area=1014&dt=20.08.2022".to_string()
+      ),
+      feed_key("https://www.finnkino.fi/xml/Schedule/?dt=20.08.2022&area=1014")
+    );
+    assert_eq!(
+      ("TheatreAreas".to_string(), String::new()),
+      feed_key("https://www.finnkino.fi/xml/TheatreAreas")
+    );
+  }
+
+  #[test]
+  fn test_events() {
+    let fixtures = fixtures("events");
+    let dir = fixtures.dir.join("Events");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+      dir.join("listType=ComingSoon.xml"),
+      "<Events><Event><ID>304000</ID><Title>Bullet Train</Title></Event></Events>",
+    )
+    .unwrap();
+
+    assert_eq!(2, fixtures.events(&EventQuery::default()).unwrap().len());
+    assert_eq!("Elvis", fixtures.event(EventId(302000)).unwrap().title);
+    assert_eq!(
+      Some("404".to_string()),
+      fixtures.event(EventId(1)).unwrap_err().status
+    );
+    let query = EventQuery {
+      list_type: Some(ListType::ComingSoon),
+      ..EventQuery::default()
+    };
+    assert_eq!("Bullet Train", fixtures.events(&query).unwrap()[0].title);
+    assert_eq!(
+      Some("Fixture error".to_string()),
+      fixtures.news().unwrap_err().title
+    );
+  }
+}
@@ -0,0 +1,666 @@
+//! GraphQL schema over areas, theatres, events and shows. The upstream is
+//! reached through a `Source`, so both client libraries can back it with
+//! their own HTTP client and cache.
+
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::{
+  Context, EmptyMutation, EmptySubscription, Enum, ErrorExtensions, InputObject, Object,
+  SimpleObject, ID,
+};
+use chrono::Duration;
+use futures::future::{self, BoxFuture};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::vec::Vec;
+
+use crate::events::{EventQuery, ListType as EventListType};
+use crate::finnkino::{self, Error};
+use crate::id::{AreaId, EventId, ParseIdError};
+use crate::json;
+use crate::schedule::{ShowFilter, UPCOMING_DAYS};
+use crate::time;
+
+pub use async_graphql::http::GraphiQLSource;
+pub use async_graphql::{Request, Response};
+
+pub const PATH: &str = "/graphql";
+
+pub type SourceFuture<T> = BoxFuture<'static, Result<T, Error>>;
+
+pub trait Source: Send + Sync + 'static {
+  fn areas(&self) -> SourceFuture<Vec<finnkino::TheatreArea>>;
+  fn shows(&self, filter: ShowFilter) -> SourceFuture<Vec<finnkino::Show>>;
+  fn events(&self, query: EventQuery) -> SourceFuture<Vec<finnkino::Event>>;
+}
+
+pub type Schema = async_graphql::Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// Builds the schema. Batched event lookups run on tasks started with
+/// `spawner`, which must be able to drive the futures of `source`.
+pub fn schema<S, R>(source: Arc<dyn Source>, spawner: S) -> Schema
+where
+  S: Fn(BoxFuture<'static, ()>) -> R + Send + Sync + 'static,
+{
+  Schema::build(Query, EmptyMutation, EmptySubscription)
+    .data(DataLoader::new(
+      EventLoader {
+        source: source.clone(),
+      },
+      spawner,
+    ))
+    .data(source)
+    .finish()
+}
+
+/// Parses a POSTed request, with the same errors as the other JSON bodies.
+pub fn parse_request(body: &[u8]) -> Result<Request, json::Errors> {
+  serde_json::from_slice(body).map_err(|error| json::Errors {
+    errors: vec![json::Error::invalid_attribute("", error.to_string())],
+  })
+}
+
+fn source<'a>(ctx: &Context<'a>) -> &'a Arc<dyn Source> {
+  ctx.data_unchecked::<Arc<dyn Source>>()
+}
+
+fn upstream_error(error: Error) -> async_graphql::Error {
+  let status = finnkino::error_status(&error).to_string();
+  async_graphql::Error::new(error.to_string())
+    .extend_with(|_, extensions| extensions.set("status", status))
+}
+
+fn invalid_arguments(errors: json::Errors) -> async_graphql::Error {
+  async_graphql::Error::new(errors.invalid_arguments())
+    .extend_with(|_, extensions| extensions.set("status", "400"))
+}
+
+fn parse_id<T: FromStr<Err = ParseIdError>>(id: &ID) -> async_graphql::Result<T> {
+  id.parse::<T>()
+    .map_err(|err| async_graphql::Error::new(err.to_string()))
+}
+
+#[derive(Clone, Debug, Default, InputObject, Serialize)]
+pub struct ShowFilterInput {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub area: Option<ID>,
+  /// YYYY-MM-DD
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub date: Option<String>,
+  /// YYYY-MM-DD
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub until: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub event: Option<ID>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub genre: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub presentation: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub language: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub subtitles: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub rating: Option<String>,
+  /// HH:MM
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub after: Option<String>,
+  /// HH:MM
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub before: Option<String>,
+}
+
+impl ShowFilterInput {
+  /// Validates the input the same way as the query parameters of the
+  /// shows endpoint.
+  fn to_filter(&self) -> async_graphql::Result<ShowFilter> {
+    let query = serde_urlencoded::to_string(self)
+      .map_err(|err| async_graphql::Error::new(err.to_string()))?;
+    ShowFilter::from_query(&query).map_err(invalid_arguments)
+  }
+}
+
+async fn fetch_shows(ctx: &Context<'_>, filter: ShowFilter) -> async_graphql::Result<Vec<Show>> {
+  let shows = source(ctx).shows(filter).await.map_err(upstream_error)?;
+  Ok(shows.into_iter().map(Show).collect())
+}
+
+#[derive(Clone, Copy, Debug, Enum, Eq, PartialEq)]
+pub enum ListType {
+  NowInTheatres,
+  ComingSoon,
+}
+
+impl From<ListType> for EventListType {
+  fn from(list_type: ListType) -> Self {
+    match list_type {
+      ListType::NowInTheatres => EventListType::NowInTheatres,
+      ListType::ComingSoon => EventListType::ComingSoon,
+    }
+  }
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+  async fn areas(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<Area>> {
+    let areas = source(ctx).areas().await.map_err(upstream_error)?;
+    Ok(areas.into_iter().map(Area).collect())
+  }
+
+  async fn area(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<Option<Area>> {
+    let id = parse_id::<AreaId>(&id)?;
+    let areas = source(ctx).areas().await.map_err(upstream_error)?;
+    Ok(areas.into_iter().find(|area| area.id == id).map(Area))
+  }
+
+  async fn shows(
+    &self,
+    ctx: &Context<'_>,
+    filter: Option<ShowFilterInput>,
+  ) -> async_graphql::Result<Vec<Show>> {
+    let filter = filter.unwrap_or_default().to_filter()?;
+    fetch_shows(ctx, filter).await
+  }
+
+  async fn events(
+    &self,
+    ctx: &Context<'_>,
+    list_type: Option<ListType>,
+    area: Option<ID>,
+  ) -> async_graphql::Result<Vec<Event>> {
+    let query = EventQuery {
+      area: area.as_ref().map(parse_id::<AreaId>).transpose()?,
+      list_type: list_type.map(EventListType::from),
+      ..EventQuery::default()
+    };
+    let events = source(ctx).events(query).await.map_err(upstream_error)?;
+    Ok(events.into_iter().map(Event).collect())
+  }
+
+  async fn event(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<Option<Event>> {
+    load_event(ctx, parse_id::<EventId>(&id)?).await
+  }
+}
+
+async fn load_event(ctx: &Context<'_>, id: EventId) -> async_graphql::Result<Option<Event>> {
+  let loader = ctx.data_unchecked::<DataLoader<EventLoader>>();
+  let event = loader.load_one(id).await.map_err(upstream_error)?;
+  Ok(event.map(Event))
+}
+
+/// A theatre area, which is either a region or a single theatre.
+pub struct Area(pub finnkino::TheatreArea);
+
+#[Object]
+impl Area {
+  async fn id(&self) -> ID {
+    ID(self.0.id.to_string())
+  }
+
+  async fn name(&self) -> &str {
+    &self.0.name
+  }
+
+  /// Theatres with shows in the area on the given day, today by default.
+  async fn theatres(
+    &self,
+    ctx: &Context<'_>,
+    date: Option<String>,
+  ) -> async_graphql::Result<Vec<Theatre>> {
+    let filter = ShowFilterInput {
+      area: Some(ID(self.0.id.to_string())),
+      date,
+      ..ShowFilterInput::default()
+    }
+    .to_filter()?;
+    let shows = source(ctx).shows(filter).await.map_err(upstream_error)?;
+    let mut seen = HashSet::new();
+    let mut theatres: Vec<Theatre> = shows
+      .into_iter()
+      .filter(|show| seen.insert(show.theatre_id))
+      .map(|show| Theatre {
+        id: show.theatre_id,
+        name: show.theatre,
+      })
+      .collect();
+    theatres.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(theatres)
+  }
+
+  async fn shows(
+    &self,
+    ctx: &Context<'_>,
+    filter: Option<ShowFilterInput>,
+  ) -> async_graphql::Result<Vec<Show>> {
+    let filter = ShowFilterInput {
+      area: Some(ID(self.0.id.to_string())),
+      ..filter.unwrap_or_default()
+    }
+    .to_filter()?;
+    fetch_shows(ctx, filter).await
+  }
+}
+
+pub struct Theatre {
+  pub id: AreaId,
+  pub name: String,
+}
+
+#[Object]
+impl Theatre {
+  async fn id(&self) -> ID {
+    ID(self.id.to_string())
+  }
+
+  async fn name(&self) -> &str {
+    &self.name
+  }
+
+  async fn shows(
+    &self,
+    ctx: &Context<'_>,
+    filter: Option<ShowFilterInput>,
+  ) -> async_graphql::Result<Vec<Show>> {
+    let filter = ShowFilterInput {
+      area: Some(ID(self.id.to_string())),
+      ..filter.unwrap_or_default()
+    }
+    .to_filter()?;
+    fetch_shows(ctx, filter).await
+  }
+}
+
+#[derive(SimpleObject)]
+pub struct Language {
+  pub code: String,
+  pub name: String,
+}
+
+impl From<&finnkino::Language> for Language {
+  fn from(language: &finnkino::Language) -> Self {
+    Language {
+      code: language.iso_two_letter_code.clone(),
+      name: language.name.clone(),
+    }
+  }
+}
+
+#[derive(SimpleObject)]
+pub struct Images {
+  pub small_portrait: Option<String>,
+  pub medium_portrait: Option<String>,
+  pub large_portrait: Option<String>,
+  pub small_landscape: Option<String>,
+  pub medium_landscape: Option<String>,
+  pub large_landscape: Option<String>,
+}
+
+pub struct Show(pub finnkino::Show);
+
+#[Object]
+impl Show {
+  async fn id(&self) -> ID {
+    ID(self.0.id.to_string())
+  }
+
+  /// RFC 3339
+  async fn start(&self) -> String {
+    self.0.show_start.to_rfc3339()
+  }
+
+  /// RFC 3339
+  async fn end(&self) -> String {
+    self.0.show_end.to_rfc3339()
+  }
+
+  async fn title(&self) -> &str {
+    &self.0.title
+  }
+
+  async fn original_title(&self) -> &str {
+    &self.0.original_title
+  }
+
+  async fn genres(&self) -> Vec<&str> {
+    self.0.genres()
+  }
+
+  async fn rating(&self) -> &str {
+    &self.0.rating
+  }
+
+  async fn theatre(&self) -> Theatre {
+    Theatre {
+      id: self.0.theatre_id,
+      name: self.0.theatre.clone(),
+    }
+  }
+
+  async fn auditorium(&self) -> &str {
+    &self.0.auditorium
+  }
+
+  async fn presentation_method(&self) -> &str {
+    &self.0.presentation_method
+  }
+
+  async fn spoken_language(&self) -> Option<Language> {
+    self.0.spoken_language.as_ref().map(Language::from)
+  }
+
+  async fn subtitle_languages(&self) -> Vec<Language> {
+    self
+      .0
+      .subtitle_languages()
+      .into_iter()
+      .map(Language::from)
+      .collect()
+  }
+
+  async fn show_url(&self) -> &str {
+    &self.0.show_url
+  }
+
+  async fn event(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Event>> {
+    load_event(ctx, self.0.event_id).await
+  }
+}
+
+pub struct Event(pub finnkino::Event);
+
+#[Object]
+impl Event {
+  async fn id(&self) -> ID {
+    ID(self.0.id.to_string())
+  }
+
+  async fn title(&self) -> &str {
+    &self.0.title
+  }
+
+  async fn original_title(&self) -> &str {
+    &self.0.original_title
+  }
+
+  async fn production_year(&self) -> &str {
+    &self.0.production_year
+  }
+
+  async fn length_in_minutes(&self) -> &str {
+    &self.0.length_in_minutes
+  }
+
+  /// RFC 3339
+  async fn local_release(&self) -> Option<String> {
+    self
+      .0
+      .local_release
+      .map(|local_release| local_release.to_rfc3339())
+  }
+
+  async fn rating(&self) -> &str {
+    &self.0.rating
+  }
+
+  async fn genres(&self) -> Vec<&str> {
+    self.0.genres()
+  }
+
+  async fn short_synopsis(&self) -> &str {
+    &self.0.short_synopsis
+  }
+
+  async fn synopsis(&self) -> &str {
+    &self.0.synopsis
+  }
+
+  async fn images(&self) -> Images {
+    let images = self.0.images.clone();
+    Images {
+      small_portrait: images.small_portrait,
+      medium_portrait: images.medium_portrait,
+      large_portrait: images.large_portrait,
+      small_landscape: images.small_landscape,
+      medium_landscape: images.medium_landscape,
+      large_landscape: images.large_landscape,
+    }
+  }
+
+  async fn cast(&self) -> Vec<String> {
+    self
+      .0
+      .cast
+      .actors
+      .iter()
+      .map(|actor| actor.full_name())
+      .collect()
+  }
+
+  async fn directors(&self) -> Vec<String> {
+    self
+      .0
+      .directors
+      .directors
+      .iter()
+      .map(|director| director.full_name())
+      .collect()
+  }
+
+  async fn event_url(&self) -> &str {
+    &self.0.event_url
+  }
+
+  /// Shows of the event, by default in the upcoming two weeks.
+  async fn shows(
+    &self,
+    ctx: &Context<'_>,
+    filter: Option<ShowFilterInput>,
+  ) -> async_graphql::Result<Vec<Show>> {
+    let mut filter = ShowFilterInput {
+      event: Some(ID(self.0.id.to_string())),
+      ..filter.unwrap_or_default()
+    }
+    .to_filter()?;
+    if filter.date.is_none() {
+      let today = time::today();
+      filter.date = Some(today);
+      filter.until = Some(today + Duration::days(UPCOMING_DAYS - 1));
+    }
+    fetch_shows(ctx, filter).await
+  }
+}
+
+/// Loads the events of a batch with the listings of films in theatres and
+/// coming soon, and only looks up the rest one by one.
+pub struct EventLoader {
+  source: Arc<dyn Source>,
+}
+
+impl Loader<EventId> for EventLoader {
+  type Value = finnkino::Event;
+  type Error = Error;
+
+  async fn load(&self, keys: &[EventId]) -> Result<HashMap<EventId, Self::Value>, Self::Error> {
+    let listing = |list_type| EventQuery {
+      list_type: Some(list_type),
+      ..EventQuery::default()
+    };
+    let (now_in_theatres, coming_soon) = future::try_join(
+      self.source.events(listing(EventListType::NowInTheatres)),
+      self.source.events(listing(EventListType::ComingSoon)),
+    )
+    .await?;
+    let mut events: HashMap<EventId, Self::Value> = now_in_theatres
+      .into_iter()
+      .chain(coming_soon)
+      .filter(|event| keys.contains(&event.id))
+      .map(|event| (event.id, event))
+      .collect();
+    let missing = keys
+      .iter()
+      .filter(|id| !events.contains_key(*id))
+      .map(|id| {
+        self.source.events(EventQuery {
+          event: Some(*id),
+          ..EventQuery::default()
+        })
+      });
+    for event in future::try_join_all(missing).await?.into_iter().flatten() {
+      if keys.contains(&event.id) {
+        events.insert(event.id, event);
+      }
+    }
+    Ok(events)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::id::ShowId;
+  use futures::executor::block_on;
+  use pretty_assertions::assert_eq;
+  use std::sync::Mutex;
+
+  #[derive(Default)]
+  struct FakeSource {
+    event_queries: Mutex<Vec<EventQuery>>,
+  }
+
+  fn event(id: u64, title: &str) -> finnkino::Event {
+    finnkino::Event {
+      id: EventId(id),
+      title: title.to_string(),
+      ..finnkino::Event::default()
+    }
+  }
+
+  fn show(id: u64, event_id: u64) -> finnkino::Show {
+    finnkino::Show {
+      id: ShowId(id),
+      event_id: EventId(event_id),
+      theatre_id: AreaId(1038),
+      theatre: "Espoo: ISO OMENA".to_string(),
+      ..finnkino::Show::default()
+    }
+  }
+
+  impl Source for FakeSource {
+    fn areas(&self) -> SourceFuture<Vec<finnkino::TheatreArea>> {
+      Box::pin(future::ok(vec![finnkino::TheatreArea {
+        id: AreaId(1014),
+        name: "Pääkaupunkiseutu".to_string(),
+      }]))
+    }
+
+    fn shows(&self, filter: ShowFilter) -> SourceFuture<Vec<finnkino::Show>> {
+      let shows = vec![
+        show(1, 303000),
+        show(2, 303000),
+        show(3, 303001),
+        show(4, 1),
+      ];
+      Box::pin(future::ok(
+        shows
+          .into_iter()
+          .filter(|show| filter.event.is_none() || filter.event == Some(show.event_id))
+          .collect(),
+      ))
+    }
+
+    fn events(&self, query: EventQuery) -> SourceFuture<Vec<finnkino::Event>> {
+      self.event_queries.lock().unwrap().push(query.clone());
+      let events = match (query.event, query.list_type) {
+        (Some(EventId(1)), _) => vec![event(1, "Special screening")],
+        (Some(_), _) => vec![],
+        (None, Some(EventListType::ComingSoon)) => vec![event(303001, "Coming soon")],
+        (None, _) => vec![event(303000, "Minions")],
+      };
+      Box::pin(future::ok(events))
+    }
+  }
+
+  fn execute(source: Arc<FakeSource>, query: &str) -> Response {
+    let schema = schema(source, |future| {
+      std::thread::spawn(move || block_on(future));
+    });
+    block_on(schema.execute(query))
+  }
+
+  #[test]
+  fn test_nested_events_are_batched() {
+    let source = Arc::new(FakeSource::default());
+    let response = execute(source.clone(), "{ shows { id event { id title } } }");
+
+    assert_eq!(
+      serde_json::json!({
+        "shows": [
+          { "id": "1", "event": { "id": "303000", "title": "Minions" } },
+          { "id": "2", "event": { "id": "303000", "title": "Minions" } },
+          { "id": "3", "event": { "id": "303001", "title": "Coming soon" } },
+          { "id": "4", "event": { "id": "1", "title": "Special screening" } },
+        ]
+      }),
+      response.data.into_json().unwrap()
+    );
+    let queries = source.event_queries.lock().unwrap();
+    assert_eq!(3, queries.len());
+    assert_eq!(
+      1,
+      queries.iter().filter(|query| query.event.is_some()).count()
+    );
+  }
+
+  #[test]
+  fn test_event_shows() {
+    let source = Arc::new(FakeSource::default());
+    let response = execute(
+      source,
+      r#"{ event(id: "303001") { title shows { id theatre { name } } } }"#,
+    );
+
+    assert_eq!(
+      serde_json::json!({
+        "event": {
+          "title": "Coming soon",
+          "shows": [{ "id": "3", "theatre": { "name": "Espoo: ISO OMENA" } }]
+        }
+      }),
+      response.data.into_json().unwrap()
+    );
+  }
+
+  #[test]
+  fn test_invalid_filter_is_an_error() {
+    let source = Arc::new(FakeSource::default());
+    let response = execute(source, r#"{ shows(filter: { after: "6pm" }) { id } }"#);
+
+    assert_eq!(1, response.errors.len());
+    assert_eq!(
+      "Invalid arguments: after: Must be a time formatted as HH:MM",
+      response.errors[0].message
+    );
+  }
+
+  #[test]
+  fn test_parse_request() {
+    let request = parse_request(br#"{"query": "{ areas { id } }"}"#).unwrap();
+    assert_eq!("{ areas { id } }", request.query);
+
+    let errors = parse_request(b"{").unwrap_err();
+    assert_eq!(
+      Some("Invalid attribute".to_string()),
+      errors.errors[0].title
+    );
+  }
+
+  #[test]
+  fn test_schema() {
+    let schema = schema(Arc::new(FakeSource::default()), |_| ());
+
+    insta::assert_snapshot!(schema.sdl());
+  }
+}
@@ -0,0 +1,328 @@
+use chrono::{Duration, NaiveDate};
+use std::vec::Vec;
+
+use crate::finnkino::{Event, Person, Show, TheatreArea};
+use crate::id::{AreaId, EventId};
+use crate::json;
+use crate::schedule::{self, TheatreSchedule};
+use crate::storage::{Watchlist, WEEK_DAYS};
+
+pub use askama::{Error, Template};
+
+#[derive(Template)]
+#[template(path = "index.html")]
+pub struct IndexPage {
+  pub areas: Vec<TheatreArea>,
+  pub today: NaiveDate,
+}
+
+#[derive(Template)]
+#[template(path = "schedule.html")]
+pub struct SchedulePage {
+  pub area: AreaId,
+  pub area_name: String,
+  pub date: NaiveDate,
+  pub previous_date: NaiveDate,
+  pub next_date: NaiveDate,
+  pub theatres: Vec<TheatreSchedule>,
+}
+
+impl SchedulePage {
+  pub fn new(area: &TheatreArea, date: NaiveDate, shows: Vec<Show>) -> Self {
+    SchedulePage {
+      area: area.id,
+      area_name: area.name.clone(),
+      date,
+      previous_date: date - Duration::days(1),
+      next_date: date + Duration::days(1),
+      theatres: schedule::group_by_theatre(shows),
+    }
+  }
+}
+
+#[derive(Template)]
+#[template(path = "film.html")]
+pub struct FilmPage {
+  pub event: Event,
+  pub theatres: Vec<TheatreSchedule>,
+}
+
+impl FilmPage {
+  pub fn new(event: Event, shows: Vec<Show>) -> Self {
+    FilmPage {
+      event,
+      theatres: schedule::group_by_theatre(shows),
+    }
+  }
+
+  fn directors(&self) -> String {
+    names(&self.event.directors.directors)
+  }
+
+  fn cast(&self) -> String {
+    names(&self.event.cast.actors)
+  }
+}
+
+pub struct FilmShows {
+  pub event_id: EventId,
+  pub title: String,
+  pub shows: Vec<Show>,
+}
+
+#[derive(Template)]
+#[template(path = "watchlist.html")]
+pub struct WatchlistPage {
+  pub name: String,
+  pub date: NaiveDate,
+  pub until: NaiveDate,
+  pub films: Vec<FilmShows>,
+}
+
+impl WatchlistPage {
+  /// Groups the week's shows of the watched films by film, in title order.
+  /// Films of the same title, such as a dubbed and an original version, stay
+  /// apart.
+  pub fn new(watchlist: &Watchlist, date: NaiveDate, shows: Vec<Show>) -> Self {
+    let mut shows = watchlist.shows(shows);
+    shows.sort_by(|a, b| {
+      (&a.title, a.event_id, a.show_start).cmp(&(&b.title, b.event_id, b.show_start))
+    });
+    let mut films: Vec<FilmShows> = Vec::new();
+    for show in shows {
+      match films.last_mut() {
+        Some(film) if film.event_id == show.event_id => film.shows.push(show),
+        _ => films.push(FilmShows {
+          event_id: show.event_id,
+          title: show.title.clone(),
+          shows: vec![show],
+        }),
+      }
+    }
+    WatchlistPage {
+      name: watchlist.name.clone(),
+      date,
+      until: date + Duration::days(WEEK_DAYS - 1),
+      films,
+    }
+  }
+}
+
+#[derive(Template)]
+#[template(path = "error.html")]
+pub struct ErrorPage {
+  pub title: String,
+  pub details: Vec<String>,
+}
+
+impl From<&json::Errors> for ErrorPage {
+  fn from(errors: &json::Errors) -> Self {
+    let title = errors
+      .errors
+      .first()
+      .and_then(|error| error.title.clone())
+      .unwrap_or_else(|| "Error".to_string());
+    ErrorPage {
+      title,
+      details: errors
+        .errors
+        .iter()
+        .filter_map(|error| error.detail.clone())
+        .collect(),
+    }
+  }
+}
+
+fn names(people: &[Person]) -> String {
+  people
+    .iter()
+    .map(Person::full_name)
+    .collect::<Vec<String>>()
+    .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::finnkino::{Cast, Directors, Images, Language};
+  use crate::id::{ShowId, WatchlistId};
+  use crate::time;
+
+  fn areas() -> Vec<TheatreArea> {
+    vec![
+      TheatreArea {
+        id: AreaId(1014),
+        name: "Pääkaupunkiseutu".to_string(),
+      },
+      TheatreArea {
+        id: AreaId(1038),
+        name: "Espoo: ISO OMENA".to_string(),
+      },
+    ]
+  }
+
+  fn show(id: u64, theatre: &str, start: &str) -> Show {
+    Show {
+      id: ShowId(id),
+      event_id: EventId(303000),
+      title: "Minions & Friends".to_string(),
+      show_start: time::parse_finnkino_datetime(start).unwrap(),
+      theatre_id: AreaId(1038),
+      theatre: theatre.to_string(),
+      auditorium: "sali 1".to_string(),
+      presentation_method: "2D".to_string(),
+      spoken_language: Some(Language {
+        name: "suomi".to_string(),
+        name_in_language: "suomi".to_string(),
+        iso_two_letter_code: "FI".to_string(),
+      }),
+      show_url: format!("http://www.finnkino.fi/Websales/Show/{}/", id),
+      ..Show::default()
+    }
+  }
+
+  #[test]
+  fn test_render_index() {
+    let page = IndexPage {
+      areas: areas(),
+      today: NaiveDate::from_ymd_opt(2022, 8, 20).unwrap(),
+    };
+
+    insta::assert_snapshot!(page.render().unwrap());
+  }
+
+  #[test]
+  fn test_render_schedule() {
+    let shows = vec![
+      show(2, "Espoo: ISO OMENA", "2022-08-20T20:00:00"),
+      show(1, "Espoo: ISO OMENA", "2022-08-20T18:00:00"),
+    ];
+    let page = SchedulePage::new(
+      &areas()[1],
+      NaiveDate::from_ymd_opt(2022, 8, 20).unwrap(),
+      shows,
+    );
+
+    insta::assert_snapshot!(page.render().unwrap());
+  }
+
+  #[test]
+  fn test_render_empty_schedule() {
+    let page = SchedulePage::new(
+      &areas()[0],
+      NaiveDate::from_ymd_opt(2022, 8, 20).unwrap(),
+      vec![],
+    );
+
+    insta::assert_snapshot!(page.render().unwrap());
+  }
+
+  #[test]
+  fn test_render_film() {
+    let event = Event {
+      id: EventId(303000),
+      title: "Minions & Friends".to_string(),
+      original_title: "Minions: The Rise of Gru".to_string(),
+      production_year: "2022".to_string(),
+      length_in_minutes: "87".to_string(),
+      local_release: time::parse_finnkino_datetime("2022-07-01T00:00:00"),
+      rating_label: "7".to_string(),
+      genres: "Animaatio, Komedia".to_string(),
+      synopsis: "<b>Gru</b> grows up.".to_string(),
+      images: Images {
+        large_portrait: Some("http://media.finnkino.fi/large.jpg".to_string()),
+        ..Images::default()
+      },
+      cast: Cast {
+        actors: vec![Person {
+          first_name: "Steve".to_string(),
+          last_name: "Carell".to_string(),
+        }],
+      },
+      directors: Directors {
+        directors: vec![Person {
+          first_name: "Kyle".to_string(),
+          last_name: "Balda".to_string(),
+        }],
+      },
+      ..Event::default()
+    };
+    let page = FilmPage::new(
+      event,
+      vec![show(1, "Espoo: ISO OMENA", "2022-08-20T18:00:00")],
+    );
+
+    insta::assert_snapshot!(page.render().unwrap());
+  }
+
+  #[test]
+  fn test_render_watchlist() {
+    let watchlist = Watchlist {
+      id: WatchlistId(1),
+      owner: "anna".to_string(),
+      name: "Anna's films".to_string(),
+      events: vec![EventId(303000)],
+      created_at: time::LocalDateTime::default(),
+    };
+    let mut other = show(3, "Tennispalatsi", "2022-08-21T18:00:00");
+    other.event_id = EventId(302000);
+    let shows = vec![
+      show(2, "Tennispalatsi", "2022-08-22T20:00:00"),
+      other,
+      show(1, "Espoo: ISO OMENA", "2022-08-20T18:00:00"),
+    ];
+    let page = WatchlistPage::new(
+      &watchlist,
+      NaiveDate::from_ymd_opt(2022, 8, 20).unwrap(),
+      shows,
+    );
+
+    insta::assert_snapshot!(page.render().unwrap());
+  }
+
+  #[test]
+  fn test_watchlist_keeps_films_of_the_same_title_apart() {
+    let watchlist = Watchlist {
+      id: WatchlistId(1),
+      owner: "anna".to_string(),
+      name: "Anna's films".to_string(),
+      events: vec![EventId(303000), EventId(303001)],
+      created_at: time::LocalDateTime::default(),
+    };
+    let mut shows = vec![
+      show(1, "Tennispalatsi", "2022-08-20T18:00:00"),
+      show(2, "Tennispalatsi", "2022-08-20T19:00:00"),
+      show(3, "Tennispalatsi", "2022-08-20T20:00:00"),
+      show(4, "Tennispalatsi", "2022-08-20T21:00:00"),
+    ];
+    shows[1].event_id = EventId(303001);
+    shows[3].event_id = EventId(303001);
+    let page = WatchlistPage::new(
+      &watchlist,
+      NaiveDate::from_ymd_opt(2022, 8, 20).unwrap(),
+      shows,
+    );
+    let films: Vec<(EventId, Vec<ShowId>)> = page
+      .films
+      .iter()
+      .map(|film| (film.event_id, film.shows.iter().map(|show| show.id).collect()))
+      .collect();
+
+    assert_eq!(
+      vec![
+        (EventId(303000), vec![ShowId(1), ShowId(3)]),
+        (EventId(303001), vec![ShowId(2), ShowId(4)]),
+      ],
+      films
+    );
+  }
+
+  #[test]
+  fn test_render_error() {
+    let errors = json::Errors {
+      errors: vec![json::Error::invalid_parameter("date", "Must be a date")],
+    };
+
+    insta::assert_snapshot!(ErrorPage::from(&errors).render().unwrap());
+  }
+}
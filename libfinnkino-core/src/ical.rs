@@ -0,0 +1,281 @@
+//! iCalendar (RFC 5545) export of shows. Times are written as
+//! `Europe/Helsinki` wall-clock times with the matching VTIMEZONE.
+
+use chrono::Utc;
+use std::fmt;
+use std::vec::Vec;
+
+use crate::finnkino::Show;
+use crate::time::{self, LocalDateTime};
+
+pub const CONTENT_TYPE: &str = "text/calendar; charset=utf-8";
+pub const EXTENSION: &str = ".ics";
+pub const PRODID: &str = "-//finnkino-rs//Finnkino showtimes//EN";
+pub const UID_DOMAIN: &str = "finnkino.fi";
+
+const LOCAL_FORMAT: &str = "%Y%m%dT%H%M%S";
+const UTC_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const MAX_LINE_LENGTH: usize = 75;
+
+const VTIMEZONE: &[&str] = &[
+  "BEGIN:VTIMEZONE",
+  "TZID:Europe/Helsinki",
+  "BEGIN:DAYLIGHT",
+  "TZOFFSETFROM:+0200",
+  "TZOFFSETTO:+0300",
+  "TZNAME:EEST",
+  "DTSTART:19700329T030000",
+  "RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU",
+  "END:DAYLIGHT",
+  "BEGIN:STANDARD",
+  "TZOFFSETFROM:+0300",
+  "TZOFFSETTO:+0200",
+  "TZNAME:EET",
+  "DTSTART:19701025T040000",
+  "RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU",
+  "END:STANDARD",
+  "END:VTIMEZONE",
+];
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Calendar {
+  pub name: Option<String>,
+  pub stamp: LocalDateTime,
+  pub shows: Vec<Show>,
+  /// Shows that were published before but are not played anymore.
+  pub cancelled: Vec<Show>,
+}
+
+impl Calendar {
+  pub fn new(shows: Vec<Show>) -> Self {
+    Calendar {
+      name: None,
+      stamp: time::now(),
+      shows,
+      cancelled: Vec::new(),
+    }
+  }
+
+  fn lines(&self) -> Vec<String> {
+    let mut lines = vec![
+      "BEGIN:VCALENDAR".to_string(),
+      "VERSION:2.0".to_string(),
+      format!("PRODID:{}", PRODID),
+      "CALSCALE:GREGORIAN".to_string(),
+      "METHOD:PUBLISH".to_string(),
+    ];
+    if let Some(name) = &self.name {
+      lines.push(format!("X-WR-CALNAME:{}", escape(name)));
+    }
+    lines.push(format!("X-WR-TIMEZONE:{}", time::TIMEZONE.name()));
+    lines.extend(VTIMEZONE.iter().map(|line| line.to_string()));
+    for show in &self.shows {
+      lines.extend(event_lines(show, &self.stamp, false));
+    }
+    for show in &self.cancelled {
+      lines.extend(event_lines(show, &self.stamp, true));
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines
+  }
+}
+
+impl fmt::Display for Calendar {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for line in self.lines() {
+      write!(f, "{}\r\n", fold(&line))?;
+    }
+    Ok(())
+  }
+}
+
+/// The UID of a show stays the same across exports, so re-importing a
+/// calendar updates the events instead of duplicating them.
+pub fn show_uid(show: &Show) -> String {
+  format!("show-{}@{}", show.id, UID_DOMAIN)
+}
+
+fn event_lines(show: &Show, stamp: &LocalDateTime, cancelled: bool) -> Vec<String> {
+  let mut lines = vec![
+    "BEGIN:VEVENT".to_string(),
+    format!("UID:{}", show_uid(show)),
+    format!("DTSTAMP:{}", to_utc(stamp)),
+    format!("DTSTART;{}", to_local(&show.show_start)),
+  ];
+  if show.show_end > show.show_start {
+    lines.push(format!("DTEND;{}", to_local(&show.show_end)));
+  }
+  if cancelled {
+    lines.push("STATUS:CANCELLED".to_string());
+    lines.push("SEQUENCE:1".to_string());
+  }
+  lines.push(format!("SUMMARY:{}", escape(&show.title)));
+  let location = [show.theatre.as_str(), show.auditorium.as_str()]
+    .iter()
+    .filter(|part| !part.is_empty())
+    .copied()
+    .collect::<Vec<&str>>()
+    .join(", ");
+  if !location.is_empty() {
+    lines.push(format!("LOCATION:{}", escape(&location)));
+  }
+  let description = description(show);
+  if !description.is_empty() {
+    lines.push(format!("DESCRIPTION:{}", escape(&description)));
+  }
+  if !show.show_url.is_empty() {
+    lines.push(format!("URL:{}", show.show_url));
+  }
+  lines.push("END:VEVENT".to_string());
+  lines
+}
+
+fn description(show: &Show) -> String {
+  let mut description = Vec::new();
+  if !show.original_title.is_empty() && show.original_title != show.title {
+    description.push(show.original_title.clone());
+  }
+  if !show.presentation_method.is_empty() {
+    description.push(show.presentation_method.clone());
+  }
+  if let Some(language) = &show.spoken_language {
+    description.push(format!("Language: {}", language.name));
+  }
+  let subtitles: Vec<String> = show
+    .subtitle_languages()
+    .iter()
+    .map(|language| language.name.clone())
+    .collect();
+  if !subtitles.is_empty() {
+    description.push(format!("Subtitles: {}", subtitles.join(", ")));
+  }
+  description.join("\n")
+}
+
+fn to_local(datetime: &LocalDateTime) -> String {
+  format!(
+    "TZID={}:{}",
+    time::TIMEZONE.name(),
+    datetime.format(LOCAL_FORMAT)
+  )
+}
+
+fn to_utc(datetime: &LocalDateTime) -> String {
+  datetime.with_timezone(&Utc).format(UTC_FORMAT).to_string()
+}
+
+fn escape(value: &str) -> String {
+  let mut escaped = String::with_capacity(value.len());
+  for c in value.chars() {
+    match c {
+      '\\' => escaped.push_str("\\\\"),
+      ';' => escaped.push_str("\\;"),
+      ',' => escaped.push_str("\\,"),
+      '\n' => escaped.push_str("\\n"),
+      '\r' => {}
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+/// Folds a content line to at most 75 octets per line without splitting
+/// UTF-8 characters.
+fn fold(line: &str) -> String {
+  let mut folded = String::with_capacity(line.len());
+  let mut length = 0;
+  for c in line.chars() {
+    if length + c.len_utf8() > MAX_LINE_LENGTH {
+      folded.push_str("\r\n ");
+      length = 1;
+    }
+    folded.push(c);
+    length += c.len_utf8();
+  }
+  folded
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::finnkino::Language;
+  use crate::id::{AreaId, EventId, ShowId};
+  use pretty_assertions::assert_eq;
+
+  fn show() -> Show {
+    Show {
+      id: ShowId(1794851),
+      event_id: EventId(303000),
+      title: "Minions: Gru, pahis vailla vertaa".to_string(),
+      original_title: "Minions: The Rise of Gru".to_string(),
+      show_start: time::parse_finnkino_datetime("2022-08-20T18:00:00").unwrap(),
+      show_end: time::parse_finnkino_datetime("2022-08-20T19:47:00").unwrap(),
+      theatre_id: AreaId(1038),
+      theatre: "Espoo: ISO OMENA".to_string(),
+      auditorium: "sali 1".to_string(),
+      presentation_method: "2D".to_string(),
+      spoken_language: Some(Language {
+        name: "suomi".to_string(),
+        name_in_language: "suomi".to_string(),
+        iso_two_letter_code: "FI".to_string(),
+      }),
+      show_url: "http://www.finnkino.fi/Websales/Show/1794851/".to_string(),
+      ..Show::default()
+    }
+  }
+
+  #[test]
+  fn test_calendar() {
+    let calendar = Calendar {
+      name: Some("Espoo: ISO OMENA".to_string()),
+      stamp: time::parse_finnkino_datetime("2022-08-20T12:00:00").unwrap(),
+      shows: vec![show()],
+      ..Calendar::default()
+    };
+
+    insta::assert_snapshot!(calendar.to_string().replace("\r\n", "\n"));
+  }
+
+  #[test]
+  fn test_lines_end_with_crlf() {
+    let calendar = Calendar::new(vec![show()]);
+    let ics = calendar.to_string();
+
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    assert!(!ics.replace("\r\n", "").contains('\n'));
+  }
+
+  #[test]
+  fn test_cancelled_show() {
+    let calendar = Calendar {
+      cancelled: vec![show()],
+      ..Calendar::default()
+    };
+    let ics = calendar.to_string();
+
+    assert!(ics.contains("UID:show-1794851@finnkino.fi\r\n"));
+    assert!(ics.contains("STATUS:CANCELLED\r\nSEQUENCE:1\r\n"));
+  }
+
+  #[test]
+  fn test_escape() {
+    assert_eq!("a\\, b\\; c\\\\d\\ne", escape("a, b; c\\d\ne"));
+  }
+
+  #[test]
+  fn test_fold_long_lines() {
+    let line = format!("SUMMARY:{}", "ä".repeat(40));
+    let folded = fold(&line);
+
+    for part in folded.split("\r\n") {
+      assert!(part.len() <= MAX_LINE_LENGTH);
+    }
+    assert_eq!(line, folded.replace("\r\n ", ""));
+  }
+
+  #[test]
+  fn test_uid_is_stable() {
+    assert_eq!("show-1794851@finnkino.fi", show_uid(&show()));
+  }
+}
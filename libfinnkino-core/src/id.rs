@@ -0,0 +1,108 @@
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::error;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseIdError {
+  pub kind: &'static str,
+  pub value: String,
+}
+
+impl fmt::Display for ParseIdError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "'{}' is not a valid {} id, it must be numeric",
+      self.value, self.kind
+    )
+  }
+}
+
+impl error::Error for ParseIdError {}
+
+macro_rules! id_type {
+  ($name:ident, $kind:expr) => {
+    #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct $name(pub u64);
+
+    impl FromStr for $name {
+      type Err = ParseIdError;
+
+      fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let trimmed = value.trim();
+        if trimmed.is_empty() || !trimmed.chars().all(|c| c.is_ascii_digit()) {
+          return Err(ParseIdError {
+            kind: $kind,
+            value: value.to_string(),
+          });
+        }
+        trimmed.parse::<u64>().map($name).map_err(|_| ParseIdError {
+          kind: $kind,
+          value: value.to_string(),
+        })
+      }
+    }
+
+    impl fmt::Display for $name {
+      fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+      }
+    }
+
+    impl Serialize for $name {
+      fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+      }
+    }
+
+    impl<'de> Deserialize<'de> for $name {
+      fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(de::Error::custom)
+      }
+    }
+  };
+}
+
+id_type!(AreaId, "area");
+id_type!(EventId, "event");
+id_type!(ShowId, "show");
+id_type!(WebhookId, "webhook");
+id_type!(WatchlistId, "watchlist");
+id_type!(SearchId, "search");
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_parse_numeric_id() {
+    assert_eq!(Ok(AreaId(1014)), "1014".parse::<AreaId>());
+    assert_eq!(Ok(EventId(303000)), "303000".parse::<EventId>());
+  }
+
+  #[test]
+  fn test_parse_invalid_id() {
+    let error = "12a".parse::<ShowId>().unwrap_err();
+
+    assert_eq!(
+      "'12a' is not a valid show id, it must be numeric",
+      error.to_string()
+    );
+    assert!("".parse::<ShowId>().is_err());
+    assert!("-1".parse::<ShowId>().is_err());
+    assert!("99999999999999999999999".parse::<ShowId>().is_err());
+  }
+
+  #[test]
+  fn test_serializes_as_string() {
+    assert_eq!("\"1014\"", serde_json::to_string(&AreaId(1014)).unwrap());
+    assert_eq!(
+      EventId(303000),
+      serde_json::from_str::<EventId>("\"303000\"").unwrap()
+    );
+  }
+}
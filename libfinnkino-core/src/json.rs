@@ -52,11 +52,98 @@ pub struct Error {
   pub meta: Option<HashMap<String, String>>,
 }
 
-#[derive(Debug, PartialEq, Serialize)]
+impl Error {
+  pub fn invalid_parameter(parameter: &str, detail: impl Into<String>) -> Self {
+    ErrorBuilder::default()
+      .status("400")
+      .title("Invalid query parameter")
+      .detail(detail.into())
+      .source(
+        ErrorSourceBuilder::default()
+          .parameter(parameter)
+          .build()
+          .unwrap(),
+      )
+      .build()
+      .unwrap()
+  }
+
+  pub fn invalid_path_parameter(parameter: &str, detail: impl Into<String>) -> Self {
+    ErrorBuilder::default()
+      .status("400")
+      .title("Invalid path parameter")
+      .detail(detail.into())
+      .source(
+        ErrorSourceBuilder::default()
+          .parameter(parameter)
+          .build()
+          .unwrap(),
+      )
+      .build()
+      .unwrap()
+  }
+
+  /// An error of the request as a whole, e.g. of a path no route matches,
+  /// with the reason phrase of its status as the title.
+  pub fn from_status(status: u16, reason: &str) -> Self {
+    ErrorBuilder::default()
+      .status(status.to_string())
+      .title(reason)
+      .build()
+      .unwrap()
+  }
+
+  /// An invalid member of a JSON request body, pointed to with a JSON
+  /// Pointer like `/url`.
+  pub fn invalid_attribute(pointer: &str, detail: impl Into<String>) -> Self {
+    ErrorBuilder::default()
+      .status("400")
+      .title("Invalid attribute")
+      .detail(detail.into())
+      .source(
+        ErrorSourceBuilder::default()
+          .pointer(pointer)
+          .build()
+          .unwrap(),
+      )
+      .build()
+      .unwrap()
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub struct Errors {
   pub errors: Vec<Error>,
 }
 
+impl Errors {
+  /// The errors of an upstream `error`, with the status Finnkino answered
+  /// or else the one the servers answer with.
+  pub fn upstream(mut error: finnkino::Error) -> Self {
+    let status = finnkino::error_status(&error);
+    error.status.get_or_insert_with(|| status.to_string());
+    Errors::from(error)
+  }
+
+  /// The errors of invalid arguments as one message, each detail after the
+  /// parameter it is about.
+  pub fn invalid_arguments(&self) -> String {
+    let details: Vec<String> = self
+      .errors
+      .iter()
+      .map(|error| {
+        let parameter = error
+          .source
+          .as_ref()
+          .and_then(|source| source.parameter.clone())
+          .unwrap_or_default();
+        format!("{}: {}", parameter, error.detail.clone().unwrap_or_default())
+      })
+      .collect();
+    format!("Invalid arguments: {}", details.join(", "))
+  }
+}
+
 impl From<finnkino::Error> for Errors {
   fn from(finnkino_error: finnkino::Error) -> Self {
     let mut error_builder = ErrorBuilder::default();
@@ -83,6 +170,14 @@ mod tests {
   use super::*;
   use pretty_assertions::assert_eq;
 
+  #[test]
+  fn test_error_from_status() {
+    assert_eq!(
+      "{\"status\":\"404\",\"title\":\"Not Found\"}",
+      serde_json::to_string(&Error::from_status(404, "Not Found")).unwrap()
+    );
+  }
+
   #[test]
   fn test_can_convert_from_finnkino_error_with_status() {
     let finnkino_error = finnkino::ErrorBuilder::default().status("some-status").build().unwrap();
@@ -150,4 +245,46 @@ mod tests {
 
     assert_eq!(expected_errors, json_errors);
   }
+
+  #[test]
+  fn test_upstream_errors_keep_finnkinos_status() {
+    let finnkino_error = finnkino::ErrorBuilder::default()
+      .status("503")
+      .title("Service Unavailable")
+      .build()
+      .unwrap();
+
+    assert_eq!(
+      Some("503".to_string()),
+      Errors::upstream(finnkino_error).errors[0].status
+    );
+  }
+
+  #[test]
+  fn test_upstream_errors_without_status_are_bad_gateway() {
+    let finnkino_error = finnkino::ErrorBuilder::default()
+      .title("Failed to serialize XML")
+      .build()
+      .unwrap();
+
+    assert_eq!(
+      Some("502".to_string()),
+      Errors::upstream(finnkino_error).errors[0].status
+    );
+  }
+
+  #[test]
+  fn test_invalid_arguments() {
+    let errors = Errors {
+      errors: vec![
+        Error::invalid_parameter("date", "Must be a date"),
+        Error::invalid_parameter("after", "Must be a time"),
+      ],
+    };
+
+    assert_eq!(
+      "Invalid arguments: date: Must be a date, after: Must be a time",
+      errors.invalid_arguments()
+    );
+  }
 }
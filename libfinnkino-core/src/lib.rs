@@ -1,5 +1,28 @@
 #[macro_use]
 extern crate derive_builder;
 
+pub mod api;
+pub mod archive;
+pub mod cache;
+pub mod changes;
+pub mod config;
+pub mod events;
+pub mod feeds;
 pub mod finnkino;
+pub mod fixtures;
+pub mod graphql;
+pub mod html;
+pub mod ical;
+pub mod id;
 pub mod json;
+pub mod live;
+pub mod recording;
+pub mod schedule;
+pub mod storage;
+pub mod subscription;
+pub mod table;
+pub mod time;
+pub mod topics;
+pub mod transport;
+pub mod webhooks;
+pub mod xml;
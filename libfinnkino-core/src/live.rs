@@ -0,0 +1,338 @@
+//! Live schedule changes of an area. A poller feeds successive Schedule
+//! snapshots of the watched areas to a [`Hub`], which numbers the changes
+//! between them and passes them on to the subscribers as Server-Sent Events.
+//! The latest changes are kept, so a client reconnecting with `Last-Event-ID`
+//! gets what it missed instead of the whole schedule again.
+
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::vec::Vec;
+
+use crate::api::v1;
+use crate::finnkino::Show;
+use crate::id::AreaId;
+use crate::json;
+use crate::schedule::{self, ShowFilter};
+
+pub const CONTENT_TYPE: &str = "text/event-stream";
+pub const LAST_EVENT_ID: &str = "Last-Event-ID";
+pub const HEARTBEAT: &str = ": heartbeat\n\n";
+pub const HEARTBEAT_SECS: u64 = 15;
+pub const POLL_INTERVAL_SECS: u64 = 60;
+pub const HISTORY_SIZE: usize = 500;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChangeKind {
+  Added,
+  Changed,
+  Removed,
+}
+
+impl ChangeKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ChangeKind::Added => "added",
+      ChangeKind::Changed => "changed",
+      ChangeKind::Removed => "removed",
+    }
+  }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Change {
+  pub id: u64,
+  pub kind: ChangeKind,
+  pub show: Show,
+}
+
+impl Change {
+  /// The JSON:API document sent as the data of the event.
+  pub fn data(&self) -> String {
+    let document = match self.kind {
+      ChangeKind::Removed => v1::ShowDocument::identifier(self.show.id),
+      _ => v1::ShowDocument::new(self.show.clone()),
+    };
+    serde_json::to_string(&document).unwrap()
+  }
+
+  pub fn to_sse(&self) -> String {
+    format!(
+      "id: {}\nevent: {}\ndata: {}\n\n",
+      self.id,
+      self.kind.as_str(),
+      self.data()
+    )
+  }
+}
+
+/// The shows streamed for an area, the same as `/api/v1/shows?area=`.
+pub fn filter(area: AreaId) -> ShowFilter {
+  ShowFilter {
+    area: Some(area),
+    ..ShowFilter::default()
+  }
+}
+
+/// Takes the area of the stream from the query string. The area is required
+/// and the other filters of the shows endpoint are not supported.
+pub fn parse_query(query: &str) -> Result<AreaId, json::Errors> {
+  let filter = ShowFilter::from_query(query)?;
+  match filter.area {
+    Some(area) if filter == self::filter(area) => Ok(area),
+    Some(_) => Err(json::Errors {
+      errors: vec![json::Error::invalid_parameter(
+        "",
+        "Only the area parameter is supported",
+      )],
+    }),
+    None => Err(json::Errors {
+      errors: vec![json::Error::invalid_parameter("area", "Is required")],
+    }),
+  }
+}
+
+pub fn parse_last_event_id(value: &str) -> Option<u64> {
+  value.trim().parse().ok()
+}
+
+#[derive(Debug, Default)]
+struct Feed {
+  shows: Option<Vec<Show>>,
+  last_id: u64,
+  history: VecDeque<Change>,
+  subscribers: Vec<UnboundedSender<Change>>,
+}
+
+impl Feed {
+  /// The changes after `last_event_id`, or the whole schedule as added
+  /// shows if the client is new or has missed more than the history holds.
+  fn backlog(&self, last_event_id: Option<u64>) -> Vec<Change> {
+    if let Some(last_event_id) = last_event_id {
+      if last_event_id == self.last_id {
+        return Vec::new();
+      }
+      let first_id = self.history.front().map(|change| change.id);
+      if last_event_id < self.last_id && first_id.is_some_and(|id| id <= last_event_id + 1) {
+        return self
+          .history
+          .iter()
+          .filter(|change| change.id > last_event_id)
+          .cloned()
+          .collect();
+      }
+    }
+    self
+      .shows
+      .iter()
+      .flatten()
+      .map(|show| Change {
+        id: self.last_id,
+        kind: ChangeKind::Added,
+        show: show.clone(),
+      })
+      .collect()
+  }
+}
+
+#[derive(Debug)]
+pub struct Hub {
+  history_size: usize,
+  feeds: Mutex<HashMap<AreaId, Feed>>,
+}
+
+impl Hub {
+  pub fn new(history_size: usize) -> Self {
+    Hub {
+      history_size,
+      feeds: Mutex::new(HashMap::new()),
+    }
+  }
+
+  pub fn has_schedule(&self, area: AreaId) -> bool {
+    let feeds = self.feeds.lock().unwrap();
+    feeds.get(&area).is_some_and(|feed| feed.shows.is_some())
+  }
+
+  /// The areas to poll. Areas without subscribers are forgotten.
+  pub fn watched(&self) -> Vec<AreaId> {
+    let mut feeds = self.feeds.lock().unwrap();
+    for feed in feeds.values_mut() {
+      feed
+        .subscribers
+        .retain(|subscriber| !subscriber.is_closed());
+    }
+    feeds.retain(|_, feed| !feed.subscribers.is_empty());
+    let mut areas: Vec<AreaId> = feeds.keys().copied().collect();
+    areas.sort_by_key(|area| area.0);
+    areas
+  }
+
+  /// Records a new snapshot of the schedule of an area and sends the changes
+  /// since the previous one to the subscribers. Every show of the first
+  /// snapshot is added.
+  pub fn update(&self, area: AreaId, shows: Vec<Show>) {
+    let mut feeds = self.feeds.lock().unwrap();
+    let feed = feeds.entry(area).or_default();
+    let previous = feed.shows.replace(shows).unwrap_or_default();
+    let diff = schedule::diff(&previous, feed.shows.as_deref().unwrap_or_default());
+    let changes = [
+      (ChangeKind::Added, diff.added),
+      (ChangeKind::Changed, diff.changed),
+      (ChangeKind::Removed, diff.removed),
+    ];
+    for (kind, shows) in changes {
+      for show in shows {
+        feed.last_id += 1;
+        let change = Change {
+          id: feed.last_id,
+          kind,
+          show,
+        };
+        feed
+          .subscribers
+          .retain(|subscriber| subscriber.unbounded_send(change.clone()).is_ok());
+        feed.history.push_back(change);
+      }
+    }
+    while feed.history.len() > self.history_size {
+      feed.history.pop_front();
+    }
+  }
+
+  /// Returns the changes the client has missed and a receiver of the next
+  /// ones.
+  pub fn subscribe(
+    &self,
+    area: AreaId,
+    last_event_id: Option<u64>,
+  ) -> (Vec<Change>, UnboundedReceiver<Change>) {
+    let mut feeds = self.feeds.lock().unwrap();
+    let feed = feeds.entry(area).or_default();
+    let (sender, receiver) = mpsc::unbounded();
+    feed.subscribers.push(sender);
+    (feed.backlog(last_event_id), receiver)
+  }
+}
+
+impl Default for Hub {
+  fn default() -> Self {
+    Hub::new(HISTORY_SIZE)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::id::ShowId;
+  use futures::StreamExt;
+  use pretty_assertions::assert_eq;
+
+  const AREA: AreaId = AreaId(1014);
+
+  fn show(id: u64, auditorium: &str) -> Show {
+    Show {
+      id: ShowId(id),
+      auditorium: auditorium.to_string(),
+      ..Show::default()
+    }
+  }
+
+  fn summary(changes: &[Change]) -> Vec<(u64, &'static str, u64)> {
+    changes
+      .iter()
+      .map(|change| (change.id, change.kind.as_str(), change.show.id.0))
+      .collect()
+  }
+
+  #[test]
+  fn test_subscribers_get_schedule_and_changes() {
+    let hub = Hub::default();
+    hub.update(AREA, vec![show(1, "sali 1"), show(2, "sali 2")]);
+    let (backlog, receiver) = hub.subscribe(AREA, None);
+    hub.update(AREA, vec![show(1, "sali 3"), show(3, "sali 1")]);
+    drop(hub);
+    let changes: Vec<Change> = futures::executor::block_on(receiver.collect());
+
+    assert_eq!(vec![(2, "added", 1), (2, "added", 2)], summary(&backlog));
+    assert_eq!(
+      vec![(3, "added", 3), (4, "changed", 1), (5, "removed", 2)],
+      summary(&changes)
+    );
+  }
+
+  #[test]
+  fn test_resume_from_last_event_id() {
+    let hub = Hub::default();
+    hub.update(AREA, vec![show(1, "sali 1")]);
+    hub.update(AREA, vec![show(1, "sali 1"), show(2, "sali 1")]);
+    hub.update(AREA, vec![show(2, "sali 1")]);
+
+    let (backlog, _) = hub.subscribe(AREA, Some(2));
+    assert_eq!(vec![(3, "removed", 1)], summary(&backlog));
+    let (backlog, _) = hub.subscribe(AREA, Some(3));
+    assert!(backlog.is_empty());
+  }
+
+  #[test]
+  fn test_resume_after_history_sends_schedule() {
+    let hub = Hub::new(1);
+    hub.update(AREA, vec![]);
+    hub.update(AREA, vec![show(1, "sali 1")]);
+    hub.update(AREA, vec![show(1, "sali 1"), show(2, "sali 1")]);
+
+    let (backlog, _) = hub.subscribe(AREA, Some(0));
+    assert_eq!(vec![(2, "added", 1), (2, "added", 2)], summary(&backlog));
+    let (backlog, _) = hub.subscribe(AREA, Some(1));
+    assert_eq!(vec![(2, "added", 2)], summary(&backlog));
+    let (backlog, _) = hub.subscribe(AREA, Some(99));
+    assert_eq!(2, backlog.len());
+  }
+
+  #[test]
+  fn test_first_schedule_is_sent_to_waiting_subscribers() {
+    let hub = Hub::default();
+    let (backlog, receiver) = hub.subscribe(AREA, None);
+    assert!(!hub.has_schedule(AREA));
+    hub.update(AREA, vec![show(1, "sali 1")]);
+    drop(hub);
+    let changes: Vec<Change> = futures::executor::block_on(receiver.collect());
+
+    assert!(backlog.is_empty());
+    assert_eq!(vec![(1, "added", 1)], summary(&changes));
+  }
+
+  #[test]
+  fn test_areas_without_subscribers_are_not_watched() {
+    let hub = Hub::default();
+    let (_, receiver) = hub.subscribe(AREA, None);
+    let (_, other) = hub.subscribe(AreaId(1038), None);
+    drop(other);
+
+    assert_eq!(vec![AREA], hub.watched());
+    drop(receiver);
+    assert!(hub.watched().is_empty());
+  }
+
+  #[test]
+  fn test_sse_event() {
+    let change = Change {
+      id: 7,
+      kind: ChangeKind::Removed,
+      show: show(1794851, "sali 1"),
+    };
+
+    assert_eq!(
+      "id: 7\nevent: removed\ndata: {\"data\":{\"type\":\"shows\",\"id\":\"1794851\"}}\n\n",
+      change.to_sse()
+    );
+  }
+
+  #[test]
+  fn test_parse_query() {
+    assert_eq!(AREA, parse_query("area=1014").unwrap());
+    assert!(parse_query("").is_err());
+    assert!(parse_query("area=1014&date=2022-08-20").is_err());
+    assert!(parse_query("area=abc").is_err());
+  }
+}
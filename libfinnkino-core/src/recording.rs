@@ -0,0 +1,211 @@
+//! Recording of the requests to Finnkino, to build realistic fixtures and to
+//! replay them deterministically. Each exchange is saved as JSON in the
+//! recording directory, in a file named after a hash of its URL, so that a
+//! scrubbed URL is still replayed for the original request.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::vec::Vec;
+
+use crate::finnkino::{Error, ErrorBuilder};
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+  /// Every request goes to Finnkino and is recorded over any earlier one.
+  Record,
+  /// Recorded requests are replayed, the others go to Finnkino and are
+  /// recorded.
+  Replay,
+  /// Recorded requests are replayed and the others fail, for tests.
+  Strict,
+}
+
+/// A request to Finnkino and its response.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Exchange {
+  pub url: String,
+  pub status: u16,
+  pub headers: Vec<(String, String)>,
+  pub body: String,
+}
+
+/// The `[recording]` table of the configuration.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RecordingConfig {
+  pub dir: PathBuf,
+  pub mode: Mode,
+}
+
+impl RecordingConfig {
+  pub fn recorder(&self) -> Recorder {
+    Recorder::new(&self.dir, self.mode).scrub(scrub_cookies)
+  }
+}
+
+/// Drops the cookies Finnkino sets, which have no place in a recording.
+pub fn scrub_cookies(exchange: &mut Exchange) {
+  exchange
+    .headers
+    .retain(|(name, _)| !name.eq_ignore_ascii_case("set-cookie"));
+}
+
+type Scrubber = Arc<dyn Fn(&mut Exchange) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct Recorder {
+  dir: PathBuf,
+  mode: Mode,
+  scrubbers: Vec<Scrubber>,
+}
+
+fn recording_error(detail: String) -> Error {
+  ErrorBuilder::default()
+    .title("Recording error")
+    .detail(detail)
+    .build()
+    .unwrap()
+}
+
+impl Recorder {
+  pub fn new(dir: impl AsRef<Path>, mode: Mode) -> Self {
+    Recorder {
+      dir: dir.as_ref().to_path_buf(),
+      mode,
+      scrubbers: Vec::new(),
+    }
+  }
+
+  /// Adds a hook that edits every exchange before it is saved, e.g. to drop
+  /// a header or mask a token.
+  pub fn scrub(mut self, scrubber: impl Fn(&mut Exchange) + Send + Sync + 'static) -> Self {
+    self.scrubbers.push(Arc::new(scrubber));
+    self
+  }
+
+  pub fn mode(&self) -> Mode {
+    self.mode
+  }
+
+  pub fn path(&self, url: &str) -> PathBuf {
+    let hash = hex::encode(Sha256::digest(url.as_bytes()));
+    self.dir.join(format!("{}.json", &hash[..16]))
+  }
+
+  /// The recorded exchange of `url`, or `None` when the request should go to
+  /// Finnkino. Fails in strict mode when the request was not recorded.
+  pub fn replay(&self, url: &str) -> Result<Option<Exchange>, Error> {
+    if self.mode == Mode::Record {
+      return Ok(None);
+    }
+    let path = self.path(url);
+    match fs::read_to_string(&path) {
+      Ok(json) => serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|error| recording_error(format!("{}: {}", path.display(), error))),
+      Err(_) if self.mode == Mode::Replay => Ok(None),
+      Err(_) => Err(
+        ErrorBuilder::default()
+          .title("Unrecorded request")
+          .detail(format!("No recording of {} in {}", url, self.dir.display()))
+          .build()
+          .unwrap(),
+      ),
+    }
+  }
+
+  /// Saves an exchange fetched from Finnkino, scrubbed.
+  pub fn record(&self, exchange: &Exchange) -> Result<(), Error> {
+    if self.mode == Mode::Strict {
+      return Ok(());
+    }
+    let path = self.path(&exchange.url);
+    let mut scrubbed = exchange.clone();
+    for scrubber in &self.scrubbers {
+      scrubber(&mut scrubbed);
+    }
+    let json = serde_json::to_string_pretty(&scrubbed).unwrap();
+    fs::create_dir_all(&self.dir)
+      .and_then(|_| fs::write(&path, json))
+      .map_err(|error| recording_error(format!("{}: {}", path.display(), error)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  const URL: &str = "https://www.finnkino.fi/xml/TheatreAreas";
+
+  fn dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+      "finnkino-recording-{}-{}",
+      name,
+      std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+  }
+
+  fn exchange() -> Exchange {
+    Exchange {
+      url: URL.to_string(),
+      status: 200,
+      headers: vec![
+        ("content-type".to_string(), "text/xml".to_string()),
+        ("Set-Cookie".to_string(), "session=secret".to_string()),
+      ],
+      body: "<TheatreAreas />".to_string(),
+    }
+  }
+
+  #[test]
+  fn test_record_and_replay() {
+    let dir = dir("replay");
+    let recorder = Recorder::new(&dir, Mode::Record).scrub(scrub_cookies);
+
+    assert_eq!(Ok(None), recorder.replay(URL));
+    recorder.record(&exchange()).unwrap();
+
+    let replayed = Recorder::new(&dir, Mode::Replay).replay(URL).unwrap();
+    assert_eq!(
+      Some(vec![("content-type".to_string(), "text/xml".to_string())]),
+      replayed.map(|exchange| exchange.headers)
+    );
+    assert_eq!(
+      Ok(None),
+      Recorder::new(&dir, Mode::Replay).replay(&format!("{}/", URL))
+    );
+  }
+
+  #[test]
+  fn test_strict_mode_fails_on_unrecorded_requests() {
+    let dir = dir("strict");
+    let recorder = Recorder::new(&dir, Mode::Strict);
+
+    recorder.record(&exchange()).unwrap();
+    let error = recorder.replay(URL).unwrap_err();
+    assert_eq!(Some("Unrecorded request".to_string()), error.title);
+    assert!(!dir.exists());
+  }
+
+  #[test]
+  fn test_scrubbed_url_is_replayed_for_the_original() {
+    let dir = dir("scrub");
+    let recorder = Recorder::new(&dir, Mode::Replay).scrub(|exchange| {
+      exchange.url = exchange.url.replace("finnkino.fi", "example.com");
+    });
+    recorder.record(&exchange()).unwrap();
+
+    let replayed = Recorder::new(&dir, Mode::Strict).replay(URL).unwrap();
+    assert_eq!(
+      "https://www.example.com/xml/TheatreAreas",
+      replayed.unwrap().url
+    );
+  }
+}
@@ -0,0 +1,531 @@
+use chrono::{Duration, NaiveDate, NaiveTime};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::str::FromStr;
+use std::vec::Vec;
+
+use crate::api::v1;
+use crate::finnkino::Show;
+use crate::id::{AreaId, EventId, ShowId};
+use crate::json;
+use crate::time::{self, LocalDateTime};
+
+pub const TIME_FORMAT: &str = "%H:%M";
+pub const MAX_DAYS: i64 = 31;
+pub const UPCOMING_DAYS: i64 = 14;
+
+/// The ScheduleDates feed of the days with shows in `area`, or in any area.
+pub fn schedule_dates_url(base_url: &str, area: Option<AreaId>) -> String {
+  match area {
+    Some(area) => format!("{}/ScheduleDates/?area={}", base_url, area),
+    None => format!("{}/ScheduleDates/", base_url),
+  }
+}
+
+#[derive(Builder, Clone, Debug, Default, Eq, PartialEq)]
+#[builder(setter(into, strip_option), default)]
+pub struct ShowFilter {
+  pub area: Option<AreaId>,
+  pub date: Option<NaiveDate>,
+  pub until: Option<NaiveDate>,
+  pub event: Option<EventId>,
+  pub genre: Option<String>,
+  pub presentation: Option<String>,
+  pub language: Option<String>,
+  pub subtitles: Option<String>,
+  pub rating: Option<String>,
+  pub after: Option<NaiveTime>,
+  pub before: Option<NaiveTime>,
+}
+
+impl ShowFilter {
+  pub fn from_query(query: &str) -> Result<ShowFilter, json::Errors> {
+    let params =
+      serde_urlencoded::from_str::<Vec<(String, String)>>(query).map_err(|err| json::Errors {
+        errors: vec![json::Error::invalid_parameter("", err.to_string())],
+      })?;
+    let mut filter = ShowFilter::default();
+    let mut errors = Vec::new();
+    for (name, value) in params {
+      if value.is_empty() {
+        errors.push(json::Error::invalid_parameter(
+          &name,
+          "Value must not be empty",
+        ));
+        continue;
+      }
+      let result = match name.as_str() {
+        "area" => parse_id(&value).map(|id| filter.area = Some(id)),
+        "date" => parse_date(&value).map(|date| filter.date = Some(date)),
+        "until" => parse_date(&value).map(|date| filter.until = Some(date)),
+        "event" => parse_id(&value).map(|id| filter.event = Some(id)),
+        "genre" => {
+          filter.genre = Some(value);
+          Ok(())
+        }
+        "presentation" => {
+          filter.presentation = Some(value);
+          Ok(())
+        }
+        "language" => {
+          filter.language = Some(value);
+          Ok(())
+        }
+        "subtitles" => {
+          filter.subtitles = Some(value);
+          Ok(())
+        }
+        "rating" => {
+          filter.rating = Some(value);
+          Ok(())
+        }
+        "after" => parse_time(&value).map(|time| filter.after = Some(time)),
+        "before" => parse_time(&value).map(|time| filter.before = Some(time)),
+        _ => Err("Unknown parameter".to_string()),
+      };
+      if let Err(detail) = result {
+        errors.push(json::Error::invalid_parameter(&name, detail));
+      }
+    }
+    if errors.is_empty() {
+      if let Err(detail) = filter.validate_range() {
+        errors.push(json::Error::invalid_parameter("until", detail));
+      }
+    }
+    if errors.is_empty() {
+      Ok(filter)
+    } else {
+      Err(json::Errors { errors })
+    }
+  }
+
+  /// All shows of every area in the next `UPCOMING_DAYS` days.
+  pub fn from_today(today: NaiveDate) -> ShowFilter {
+    ShowFilter {
+      date: Some(today),
+      until: Some(today + Duration::days(UPCOMING_DAYS - 1)),
+      ..ShowFilter::default()
+    }
+  }
+
+  pub fn upcoming(event: EventId, today: NaiveDate) -> ShowFilter {
+    ShowFilter {
+      event: Some(event),
+      ..ShowFilter::from_today(today)
+    }
+  }
+
+  pub fn nr_of_days(&self) -> Option<i64> {
+    match (self.date, self.until) {
+      (Some(date), Some(until)) => Some((until - date).num_days() + 1),
+      _ => None,
+    }
+  }
+
+  pub fn upstream_query(&self) -> Vec<(&'static str, String)> {
+    let mut query = Vec::new();
+    if let Some(area) = &self.area {
+      query.push(("area", area.to_string()));
+    }
+    if let Some(date) = &self.date {
+      query.push(("dt", time::to_finnkino_date(date)));
+    }
+    if let Some(event) = &self.event {
+      query.push(("eventID", event.to_string()));
+    }
+    if let Some(nr_of_days) = self.nr_of_days() {
+      query.push(("nrOfDays", nr_of_days.to_string()));
+    }
+    query
+  }
+
+  pub fn schedule_url(&self, base_url: &str) -> String {
+    let query = serde_urlencoded::to_string(self.upstream_query()).unwrap_or_default();
+    if query.is_empty() {
+      format!("{}/Schedule/", base_url)
+    } else {
+      format!("{}/Schedule/?{}", base_url, query)
+    }
+  }
+
+  pub fn matches(&self, show: &Show) -> bool {
+    if let Some(genre) = &self.genre {
+      if !show
+        .genres()
+        .iter()
+        .any(|g| g.to_lowercase() == genre.to_lowercase())
+      {
+        return false;
+      }
+    }
+    if let Some(presentation) = &self.presentation {
+      let method = show.presentation_method.to_lowercase();
+      let presentation = presentation.to_lowercase();
+      if method != presentation && !method.split_whitespace().any(|token| token == presentation) {
+        return false;
+      }
+    }
+    if let Some(language) = &self.language {
+      match &show.spoken_language {
+        Some(spoken_language) if spoken_language.matches(language) => {}
+        _ => return false,
+      }
+    }
+    if let Some(subtitles) = &self.subtitles {
+      if !show
+        .subtitle_languages()
+        .iter()
+        .any(|l| l.matches(subtitles))
+      {
+        return false;
+      }
+    }
+    if let Some(rating) = &self.rating {
+      if !show.rating.eq_ignore_ascii_case(rating)
+        && !show.rating_label.eq_ignore_ascii_case(rating)
+      {
+        return false;
+      }
+    }
+    if self.after.is_some() || self.before.is_some() {
+      let start = show.show_start.time();
+      if self.after.is_some_and(|after| start < after) {
+        return false;
+      }
+      if self.before.is_some_and(|before| start > before) {
+        return false;
+      }
+    }
+    true
+  }
+
+  pub fn apply(&self, shows: Vec<Show>) -> Vec<Show> {
+    shows
+      .into_iter()
+      .filter(|show| self.matches(show))
+      .collect()
+  }
+
+  fn validate_range(&self) -> Result<(), String> {
+    match (self.date, self.until) {
+      (None, Some(_)) => Err("Requires the date parameter".to_string()),
+      (Some(date), Some(until)) if until < date => Err("Must not be before date".to_string()),
+      (Some(_), Some(_)) if self.nr_of_days().unwrap_or_default() > MAX_DAYS => {
+        Err(format!("Range must not exceed {} days", MAX_DAYS))
+      }
+      _ => Ok(()),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TheatreSchedule {
+  pub theatre_id: AreaId,
+  pub theatre: String,
+  pub shows: Vec<Show>,
+}
+
+/// Groups shows by theatre, theatres ordered by name and shows by start time.
+pub fn group_by_theatre(mut shows: Vec<Show>) -> Vec<TheatreSchedule> {
+  shows.sort_by(|a, b| (&a.theatre, &a.show_start, &a.id).cmp(&(&b.theatre, &b.show_start, &b.id)));
+  let mut theatres: Vec<TheatreSchedule> = Vec::new();
+  for show in shows {
+    match theatres.last_mut() {
+      Some(theatre) if theatre.theatre_id == show.theatre_id => theatre.shows.push(show),
+      _ => theatres.push(TheatreSchedule {
+        theatre_id: show.theatre_id,
+        theatre: show.theatre.clone(),
+        shows: vec![show],
+      }),
+    }
+  }
+  theatres
+}
+
+/// A show as compared by [`diff`], from the feed or as served by the API.
+pub trait Scheduled: Clone + PartialEq {
+  type Id: Copy + Eq + Hash + Ord;
+
+  fn id(&self) -> Self::Id;
+  fn start(&self) -> LocalDateTime;
+}
+
+impl Scheduled for Show {
+  type Id = ShowId;
+
+  fn id(&self) -> ShowId {
+    self.id
+  }
+
+  fn start(&self) -> LocalDateTime {
+    self.show_start
+  }
+}
+
+impl Scheduled for v1::Show {
+  type Id = ShowId;
+
+  fn id(&self) -> ShowId {
+    self.id
+  }
+
+  fn start(&self) -> LocalDateTime {
+    self.start
+  }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ScheduleDiff<T = Show> {
+  pub added: Vec<T>,
+  pub changed: Vec<T>,
+  pub removed: Vec<T>,
+}
+
+impl<T> ScheduleDiff<T> {
+  pub fn is_empty(&self) -> bool {
+    self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+  }
+}
+
+/// Compares two snapshots of a schedule by id. Added and changed shows
+/// keep their order in `current`, removed shows are ordered by start time.
+pub fn diff<T: Scheduled>(previous: &[T], current: &[T]) -> ScheduleDiff<T> {
+  let published: HashMap<T::Id, &T> = previous.iter().map(|show| (show.id(), show)).collect();
+  let ids: HashSet<T::Id> = current.iter().map(Scheduled::id).collect();
+  let mut diff = ScheduleDiff {
+    added: Vec::new(),
+    changed: Vec::new(),
+    removed: Vec::new(),
+  };
+  for show in current {
+    match published.get(&show.id()) {
+      None => diff.added.push(show.clone()),
+      Some(before) if *before != show => diff.changed.push(show.clone()),
+      Some(_) => {}
+    }
+  }
+  diff.removed = previous
+    .iter()
+    .filter(|show| !ids.contains(&show.id()))
+    .cloned()
+    .collect();
+  diff.removed.sort_by_key(|show| (show.start(), show.id()));
+  diff
+}
+
+fn parse_id<T: FromStr>(value: &str) -> Result<T, String> {
+  value
+    .parse::<T>()
+    .map_err(|_| "Must be numeric".to_string())
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+  time::parse_api_date(value).ok_or_else(|| "Must be a date formatted as YYYY-MM-DD".to_string())
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, String> {
+  NaiveTime::parse_from_str(value, TIME_FORMAT)
+    .map_err(|_| "Must be a time formatted as HH:MM".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::finnkino::Language;
+  use pretty_assertions::assert_eq;
+
+  fn show() -> Show {
+    Show {
+      id: ShowId(1794851),
+      show_start: time::parse_finnkino_datetime("2022-08-20T18:30:00").unwrap(),
+      genres: "Animaatio, Komedia".to_string(),
+      rating: "7".to_string(),
+      rating_label: "7".to_string(),
+      presentation_method: "IMAX 3D".to_string(),
+      spoken_language: Some(Language {
+        name: "englanti".to_string(),
+        name_in_language: "English".to_string(),
+        iso_two_letter_code: "EN".to_string(),
+      }),
+      subtitle_language_1: Some(Language {
+        name: "suomi".to_string(),
+        name_in_language: "suomi".to_string(),
+        iso_two_letter_code: "FI".to_string(),
+      }),
+      ..Show::default()
+    }
+  }
+
+  #[test]
+  fn test_can_parse_query() {
+    let filter = ShowFilter::from_query(
+      "area=1014&date=2022-08-20&until=2022-08-22&genre=komedia&after=18:00",
+    )
+    .unwrap();
+    let expected_filter = ShowFilterBuilder::default()
+      .area(AreaId(1014))
+      .date(NaiveDate::from_ymd_opt(2022, 8, 20).unwrap())
+      .until(NaiveDate::from_ymd_opt(2022, 8, 22).unwrap())
+      .genre("komedia")
+      .after(NaiveTime::from_hms_opt(18, 0, 0).unwrap())
+      .build()
+      .unwrap();
+
+    assert_eq!(expected_filter, filter);
+  }
+
+  #[test]
+  fn test_invalid_query_reports_parameters() {
+    let errors = ShowFilter::from_query("area=abc&after=6pm&foo=bar").unwrap_err();
+    let parameters: Vec<Option<String>> = errors
+      .errors
+      .iter()
+      .map(|error| {
+        error
+          .source
+          .as_ref()
+          .and_then(|source| source.parameter.clone())
+      })
+      .collect();
+
+    assert_eq!(
+      vec![
+        Some("area".to_string()),
+        Some("after".to_string()),
+        Some("foo".to_string())
+      ],
+      parameters
+    );
+    assert_eq!(Some("400".to_string()), errors.errors[0].status);
+  }
+
+  #[test]
+  fn test_until_requires_date() {
+    let errors = ShowFilter::from_query("until=2022-08-22").unwrap_err();
+
+    assert_eq!(
+      Some("until".to_string()),
+      errors.errors[0].source.as_ref().unwrap().parameter
+    );
+  }
+
+  #[test]
+  fn test_until_must_not_be_before_date() {
+    assert!(ShowFilter::from_query("date=2022-08-22&until=2022-08-20").is_err());
+    assert!(ShowFilter::from_query("date=2022-08-01&until=2022-09-01").is_err());
+  }
+
+  #[test]
+  fn test_schedule_url() {
+    let filter =
+      ShowFilter::from_query("area=1014&date=2022-08-20&until=2022-08-22&event=303000").unwrap();
+
+    assert_eq!(
+      "https://www.finnkino.fi/xml/Schedule/?area=1014&dt=20.08.2022&eventID=303000&nrOfDays=3",
+      filter.schedule_url("https://www.finnkino.fi/xml")
+    );
+    assert_eq!(
+      "https://www.finnkino.fi/xml/Schedule/",
+      ShowFilter::default().schedule_url("https://www.finnkino.fi/xml")
+    );
+  }
+
+  #[test]
+  fn test_schedule_dates_url() {
+    assert_eq!(
+      "https://www.finnkino.fi/xml/ScheduleDates/?area=1014",
+      schedule_dates_url("https://www.finnkino.fi/xml", Some(AreaId(1014)))
+    );
+    assert_eq!(
+      "https://www.finnkino.fi/xml/ScheduleDates/",
+      schedule_dates_url("https://www.finnkino.fi/xml", None)
+    );
+  }
+
+  #[test]
+  fn test_upcoming_schedule_url() {
+    let filter = ShowFilter::upcoming(
+      EventId(303000),
+      NaiveDate::from_ymd_opt(2022, 8, 20).unwrap(),
+    );
+
+    assert_eq!(
+      "https://www.finnkino.fi/xml/Schedule/?dt=20.08.2022&eventID=303000&nrOfDays=14",
+      filter.schedule_url("https://www.finnkino.fi/xml")
+    );
+  }
+
+  #[test]
+  fn test_matches() {
+    let show = show();
+    let matching = [
+      "genre=animaatio",
+      "presentation=imax",
+      "presentation=IMAX%203D",
+      "language=en",
+      "language=englanti",
+      "subtitles=fi",
+      "rating=7",
+      "after=18:00&before=19:00",
+    ];
+    let not_matching = [
+      "genre=draama",
+      "presentation=2d",
+      "language=fi",
+      "subtitles=sv",
+      "rating=12",
+      "after=19:00",
+      "before=18:00",
+    ];
+
+    for query in matching {
+      assert!(
+        ShowFilter::from_query(query).unwrap().matches(&show),
+        "{}",
+        query
+      );
+    }
+    for query in not_matching {
+      assert!(
+        !ShowFilter::from_query(query).unwrap().matches(&show),
+        "{}",
+        query
+      );
+    }
+  }
+
+  #[test]
+  fn test_diff() {
+    let changed = Show {
+      auditorium: "sali 2".to_string(),
+      ..show()
+    };
+    let removed = Show {
+      id: ShowId(1),
+      ..show()
+    };
+    let added = Show {
+      id: ShowId(2),
+      ..show()
+    };
+
+    let diff = diff(
+      &[show(), removed.clone()],
+      &[changed.clone(), added.clone()],
+    );
+
+    assert_eq!(
+      ScheduleDiff {
+        added: vec![added],
+        changed: vec![changed],
+        removed: vec![removed],
+      },
+      diff
+    );
+  }
+
+  #[test]
+  fn test_diff_of_same_schedule_is_empty() {
+    assert!(diff(&[show()], &[show()]).is_empty());
+  }
+}
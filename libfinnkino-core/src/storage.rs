@@ -0,0 +1,597 @@
+//! SQLite storage of watchlists, saved searches, the schedule archive of
+//! [`crate::archive`], which keeps every show with the areas that listed it,
+//! and the schedule snapshots of [`crate::changes`]. The schema is created
+//! and upgraded by [`MIGRATIONS`], the applied ones being counted in the
+//! database's `user_version`. Queries are quick enough to run on the request
+//! handlers behind a single connection.
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use std::vec::Vec;
+
+use crate::finnkino::Show;
+use crate::id::{EventId, SearchId, WatchlistId};
+use crate::json;
+use crate::schedule::{ShowFilter, UPCOMING_DAYS};
+use crate::time::{self, LocalDateTime};
+
+pub const DATABASE_ENV: &str = "FINNKINO_DATABASE";
+pub const DEFAULT_DATABASE: &str = "finnkino.db";
+pub const WEEK_DAYS: i64 = 7;
+
+/// The schema, one migration per release that changed it. Never edit a
+/// migration that has been released, add a new one.
+pub const MIGRATIONS: &[&str] = &[r#"
+  CREATE TABLE watchlists (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    owner TEXT NOT NULL,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL
+  );
+  CREATE TABLE watchlist_events (
+    watchlist_id INTEGER NOT NULL REFERENCES watchlists (id) ON DELETE CASCADE,
+    event_id INTEGER NOT NULL,
+    PRIMARY KEY (watchlist_id, event_id)
+  );
+  CREATE TABLE saved_searches (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    owner TEXT NOT NULL,
+    name TEXT NOT NULL,
+    query TEXT NOT NULL,
+    created_at TEXT NOT NULL
+  );
+  CREATE INDEX watchlists_owner ON watchlists (owner);
+  CREATE INDEX saved_searches_owner ON saved_searches (owner);
+"#, r#"
+  CREATE TABLE archived_shows (
+    id INTEGER PRIMARY KEY,
+    event_id INTEGER NOT NULL,
+    area_id INTEGER NOT NULL,
+    title TEXT NOT NULL,
+    date TEXT NOT NULL,
+    starts_at INTEGER NOT NULL,
+    data TEXT NOT NULL,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL
+  );
+  CREATE TABLE archived_events (
+    id INTEGER PRIMARY KEY,
+    title TEXT NOT NULL,
+    data TEXT NOT NULL,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL
+  );
+  CREATE INDEX archived_shows_date ON archived_shows (date, area_id);
+  CREATE INDEX archived_shows_event ON archived_shows (event_id);
+"#, r#"
+  CREATE TABLE schedule_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    area_id INTEGER NOT NULL,
+    taken_at INTEGER NOT NULL,
+    last_seen INTEGER NOT NULL,
+    shows TEXT NOT NULL
+  );
+  CREATE INDEX schedule_snapshots_area ON schedule_snapshots (area_id, taken_at);
+"#, r#"
+  ALTER TABLE archived_shows RENAME COLUMN area_id TO theatre_id;
+  DROP INDEX archived_shows_date;
+  CREATE INDEX archived_shows_date ON archived_shows (date);
+  CREATE TABLE archived_show_areas (
+    show_id INTEGER NOT NULL REFERENCES archived_shows (id) ON DELETE CASCADE,
+    area_id INTEGER NOT NULL,
+    PRIMARY KEY (show_id, area_id)
+  );
+  CREATE INDEX archived_show_areas_area ON archived_show_areas (area_id);
+  INSERT INTO archived_show_areas (show_id, area_id) SELECT id, theatre_id FROM archived_shows;
+"#];
+
+pub fn database_error(error: rusqlite::Error) -> json::Errors {
+  json::Errors {
+    errors: vec![json::ErrorBuilder::default()
+      .status("500")
+      .title("Database error")
+      .detail(error.to_string())
+      .build()
+      .unwrap()],
+  }
+}
+
+pub fn not_found(kind: &str, id: impl std::fmt::Display) -> json::Errors {
+  json::Errors {
+    errors: vec![json::ErrorBuilder::default()
+      .status("404")
+      .title("Not Found")
+      .detail(format!("No {} with id {}", kind, id))
+      .build()
+      .unwrap()],
+  }
+}
+
+fn parse_body<'a, T: Deserialize<'a>>(body: &'a [u8]) -> Result<T, json::Errors> {
+  serde_json::from_slice(body).map_err(|error| json::Errors {
+    errors: vec![json::Error::invalid_attribute("", error.to_string())],
+  })
+}
+
+fn check_not_empty(errors: &mut Vec<json::Error>, pointer: &str, value: &str) {
+  if value.trim().is_empty() {
+    errors.push(json::Error::invalid_attribute(pointer, "Must not be empty"));
+  }
+}
+
+/// The `owner` a list of watchlists or saved searches can be narrowed to.
+#[derive(Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct OwnerQuery {
+  pub owner: Option<String>,
+}
+
+impl OwnerQuery {
+  pub fn from_query(query: &str) -> Result<Self, json::Errors> {
+    serde_urlencoded::from_str(query).map_err(|error| json::Errors {
+      errors: vec![json::Error::invalid_parameter("owner", error.to_string())],
+    })
+  }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct NewWatchlist {
+  pub owner: String,
+  pub name: String,
+  #[serde(default)]
+  pub events: Vec<EventId>,
+}
+
+impl NewWatchlist {
+  pub fn from_json(body: &[u8]) -> Result<Self, json::Errors> {
+    let watchlist: NewWatchlist = parse_body(body)?;
+    let mut errors = Vec::new();
+    check_not_empty(&mut errors, "/owner", &watchlist.owner);
+    check_not_empty(&mut errors, "/name", &watchlist.name);
+    if errors.is_empty() {
+      Ok(watchlist)
+    } else {
+      Err(json::Errors { errors })
+    }
+  }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Watchlist {
+  pub id: WatchlistId,
+  pub owner: String,
+  pub name: String,
+  pub events: Vec<EventId>,
+  pub created_at: LocalDateTime,
+}
+
+impl Watchlist {
+  /// The shows to pick the watched films' shows from, `days` days from
+  /// `today` on.
+  pub fn filter(today: NaiveDate, days: i64) -> ShowFilter {
+    ShowFilter {
+      date: Some(today),
+      until: Some(today + Duration::days(days - 1)),
+      ..ShowFilter::default()
+    }
+  }
+
+  pub fn shows(&self, shows: Vec<Show>) -> Vec<Show> {
+    shows
+      .into_iter()
+      .filter(|show| self.events.contains(&show.event_id))
+      .collect()
+  }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct NewSearch {
+  pub owner: String,
+  pub name: String,
+  pub query: String,
+}
+
+impl NewSearch {
+  pub fn from_json(body: &[u8]) -> Result<Self, json::Errors> {
+    let search: NewSearch = parse_body(body)?;
+    let mut errors = Vec::new();
+    check_not_empty(&mut errors, "/owner", &search.owner);
+    check_not_empty(&mut errors, "/name", &search.name);
+    if let Err(invalid) = ShowFilter::from_query(&search.query) {
+      errors.extend(
+        invalid
+          .errors
+          .into_iter()
+          .map(|error| json::Error::invalid_attribute("/query", error.detail.unwrap_or_default())),
+      );
+    }
+    if errors.is_empty() {
+      Ok(search)
+    } else {
+      Err(json::Errors { errors })
+    }
+  }
+}
+
+/// A saved `/api/v1/shows` query string.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct SavedSearch {
+  pub id: SearchId,
+  pub owner: String,
+  pub name: String,
+  pub query: String,
+  pub created_at: LocalDateTime,
+}
+
+impl SavedSearch {
+  /// The filter of the query. Searches without a date match the upcoming
+  /// shows instead of only today's.
+  pub fn filter(&self, today: NaiveDate) -> ShowFilter {
+    let mut filter = ShowFilter::from_query(&self.query).unwrap_or_default();
+    if filter.date.is_none() {
+      filter.date = Some(today);
+      filter.until = filter
+        .until
+        .or(Some(today + Duration::days(UPCOMING_DAYS - 1)));
+    }
+    filter
+  }
+}
+
+pub(crate) fn datetime(row: &Row, index: usize) -> rusqlite::Result<LocalDateTime> {
+  let value: String = row.get(index)?;
+  Ok(time::parse_finnkino_datetime(&value).unwrap_or_default())
+}
+
+fn search_from_row(row: &Row) -> rusqlite::Result<SavedSearch> {
+  Ok(SavedSearch {
+    id: SearchId(row.get(0)?),
+    owner: row.get(1)?,
+    name: row.get(2)?,
+    query: row.get(3)?,
+    created_at: datetime(row, 4)?,
+  })
+}
+
+#[derive(Debug)]
+pub struct Store {
+  pub(crate) connection: Mutex<Connection>,
+}
+
+impl Store {
+  pub fn open(path: impl AsRef<Path>) -> rusqlite::Result<Self> {
+    Store::new(Connection::open(path)?)
+  }
+
+  pub fn open_in_memory() -> rusqlite::Result<Self> {
+    Store::new(Connection::open_in_memory()?)
+  }
+
+  fn new(mut connection: Connection) -> rusqlite::Result<Self> {
+    connection.pragma_update(None, "foreign_keys", true)?;
+    migrate(&mut connection)?;
+    Ok(Store {
+      connection: Mutex::new(connection),
+    })
+  }
+
+  pub fn schema_version(&self) -> rusqlite::Result<usize> {
+    let connection = self.connection.lock().unwrap();
+    connection.pragma_query_value(None, "user_version", |row| row.get(0))
+  }
+
+  pub fn create_watchlist(
+    &self,
+    watchlist: NewWatchlist,
+    now: LocalDateTime,
+  ) -> rusqlite::Result<Watchlist> {
+    let mut connection = self.connection.lock().unwrap();
+    let transaction = connection.transaction()?;
+    transaction.execute(
+      "INSERT INTO watchlists (owner, name, created_at) VALUES (?1, ?2, ?3)",
+      params![watchlist.owner, watchlist.name, now.to_rfc3339()],
+    )?;
+    let id = WatchlistId(transaction.last_insert_rowid() as u64);
+    let mut events = watchlist.events;
+    events.sort();
+    events.dedup();
+    for event in &events {
+      transaction.execute(
+        "INSERT INTO watchlist_events (watchlist_id, event_id) VALUES (?1, ?2)",
+        params![id.0, event.0],
+      )?;
+    }
+    transaction.commit()?;
+    Ok(Watchlist {
+      id,
+      owner: watchlist.owner,
+      name: watchlist.name,
+      events,
+      created_at: now,
+    })
+  }
+
+  pub fn watchlists(&self, owner: Option<&str>) -> rusqlite::Result<Vec<Watchlist>> {
+    let connection = self.connection.lock().unwrap();
+    let mut statement = connection.prepare(
+      "SELECT id, owner, name, created_at FROM watchlists
+       WHERE ?1 IS NULL OR owner = ?1 ORDER BY id",
+    )?;
+    let rows = statement.query_map(params![owner], |row| {
+      Ok(Watchlist {
+        id: WatchlistId(row.get(0)?),
+        owner: row.get(1)?,
+        name: row.get(2)?,
+        events: Vec::new(),
+        created_at: datetime(row, 3)?,
+      })
+    })?;
+    let mut watchlists = rows.collect::<rusqlite::Result<Vec<Watchlist>>>()?;
+    for watchlist in &mut watchlists {
+      watchlist.events = watchlist_events(&connection, watchlist.id)?;
+    }
+    Ok(watchlists)
+  }
+
+  pub fn watchlist(&self, id: WatchlistId) -> rusqlite::Result<Option<Watchlist>> {
+    let connection = self.connection.lock().unwrap();
+    let watchlist = connection
+      .query_row(
+        "SELECT owner, name, created_at FROM watchlists WHERE id = ?1",
+        params![id.0],
+        |row| {
+          Ok(Watchlist {
+            id,
+            owner: row.get(0)?,
+            name: row.get(1)?,
+            events: Vec::new(),
+            created_at: datetime(row, 2)?,
+          })
+        },
+      )
+      .optional()?;
+    match watchlist {
+      None => Ok(None),
+      Some(watchlist) => Ok(Some(Watchlist {
+        events: watchlist_events(&connection, id)?,
+        ..watchlist
+      })),
+    }
+  }
+
+  pub fn delete_watchlist(&self, id: WatchlistId) -> rusqlite::Result<bool> {
+    let connection = self.connection.lock().unwrap();
+    let deleted = connection.execute("DELETE FROM watchlists WHERE id = ?1", params![id.0])?;
+    Ok(deleted > 0)
+  }
+
+  pub fn create_search(
+    &self,
+    search: NewSearch,
+    now: LocalDateTime,
+  ) -> rusqlite::Result<SavedSearch> {
+    let connection = self.connection.lock().unwrap();
+    connection.execute(
+      "INSERT INTO saved_searches (owner, name, query, created_at) VALUES (?1, ?2, ?3, ?4)",
+      params![search.owner, search.name, search.query, now.to_rfc3339()],
+    )?;
+    Ok(SavedSearch {
+      id: SearchId(connection.last_insert_rowid() as u64),
+      owner: search.owner,
+      name: search.name,
+      query: search.query,
+      created_at: now,
+    })
+  }
+
+  pub fn searches(&self, owner: Option<&str>) -> rusqlite::Result<Vec<SavedSearch>> {
+    let connection = self.connection.lock().unwrap();
+    let mut statement = connection.prepare(
+      "SELECT id, owner, name, query, created_at FROM saved_searches
+       WHERE ?1 IS NULL OR owner = ?1 ORDER BY id",
+    )?;
+    let rows = statement.query_map(params![owner], search_from_row)?;
+    rows.collect()
+  }
+
+  pub fn search(&self, id: SearchId) -> rusqlite::Result<Option<SavedSearch>> {
+    let connection = self.connection.lock().unwrap();
+    connection
+      .query_row(
+        "SELECT id, owner, name, query, created_at FROM saved_searches WHERE id = ?1",
+        params![id.0],
+        search_from_row,
+      )
+      .optional()
+  }
+
+  pub fn delete_search(&self, id: SearchId) -> rusqlite::Result<bool> {
+    let connection = self.connection.lock().unwrap();
+    let deleted = connection.execute("DELETE FROM saved_searches WHERE id = ?1", params![id.0])?;
+    Ok(deleted > 0)
+  }
+}
+
+fn migrate(connection: &mut Connection) -> rusqlite::Result<()> {
+  let version: usize = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+  for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+    let transaction = connection.transaction()?;
+    transaction.execute_batch(migration)?;
+    transaction.pragma_update(None, "user_version", index + 1)?;
+    transaction.commit()?;
+  }
+  Ok(())
+}
+
+fn watchlist_events(connection: &Connection, id: WatchlistId) -> rusqlite::Result<Vec<EventId>> {
+  let mut statement = connection
+    .prepare("SELECT event_id FROM watchlist_events WHERE watchlist_id = ?1 ORDER BY event_id")?;
+  let rows = statement.query_map(params![id.0], |row| row.get(0).map(EventId))?;
+  rows.collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::id::{AreaId, ShowId};
+  use pretty_assertions::assert_eq;
+
+  fn watchlist(owner: &str, events: Vec<EventId>) -> NewWatchlist {
+    NewWatchlist {
+      owner: owner.to_string(),
+      name: "Films".to_string(),
+      events,
+    }
+  }
+
+  #[test]
+  fn test_migrations_are_applied_once() {
+    let path = std::env::temp_dir().join(format!("finnkino-{}.db", std::process::id()));
+    let store = Store::open(&path).unwrap();
+    store
+      .create_watchlist(watchlist("anna", vec![]), LocalDateTime::default())
+      .unwrap();
+    drop(store);
+    let store = Store::open(&path).unwrap();
+
+    assert_eq!(MIGRATIONS.len(), store.schema_version().unwrap());
+    assert_eq!(1, store.watchlists(None).unwrap().len());
+    drop(store);
+    std::fs::remove_file(path).unwrap();
+  }
+
+  #[test]
+  fn test_archived_shows_keep_their_theatre_as_area() {
+    let mut connection = Connection::open_in_memory().unwrap();
+    connection.execute_batch(&MIGRATIONS[..3].concat()).unwrap();
+    connection.pragma_update(None, "user_version", 3).unwrap();
+    connection
+      .execute(
+        "INSERT INTO archived_shows
+           (id, event_id, area_id, title, date, starts_at, data, first_seen, last_seen)
+         VALUES (1, 303000, 1038, 'Minions', '2026-09-01', 0, '{}', '', '')",
+        [],
+      )
+      .unwrap();
+    migrate(&mut connection).unwrap();
+
+    let area: u64 = connection
+      .query_row(
+        "SELECT area_id FROM archived_show_areas WHERE show_id = 1",
+        [],
+        |row| row.get(0),
+      )
+      .unwrap();
+    assert_eq!(1038, area);
+  }
+
+  #[test]
+  fn test_watchlists() {
+    let store = Store::open_in_memory().unwrap();
+    let created = store
+      .create_watchlist(
+        watchlist(
+          "anna",
+          vec![EventId(303000), EventId(302000), EventId(303000)],
+        ),
+        LocalDateTime::default(),
+      )
+      .unwrap();
+    store
+      .create_watchlist(watchlist("ben", vec![]), LocalDateTime::default())
+      .unwrap();
+
+    assert_eq!(vec![EventId(302000), EventId(303000)], created.events);
+    assert_eq!(Some(created.clone()), store.watchlist(created.id).unwrap());
+    assert_eq!(
+      vec![created.clone()],
+      store.watchlists(Some("anna")).unwrap()
+    );
+    assert_eq!(2, store.watchlists(None).unwrap().len());
+    assert!(store.delete_watchlist(created.id).unwrap());
+    assert!(!store.delete_watchlist(created.id).unwrap());
+    assert_eq!(None, store.watchlist(created.id).unwrap());
+  }
+
+  #[test]
+  fn test_saved_searches() {
+    let store = Store::open_in_memory().unwrap();
+    let search = NewSearch {
+      owner: "anna".to_string(),
+      name: "Tennispalatsi".to_string(),
+      query: "area=1038&language=fi".to_string(),
+    };
+    let created = store
+      .create_search(search, LocalDateTime::default())
+      .unwrap();
+
+    assert_eq!(Some(created.clone()), store.search(created.id).unwrap());
+    assert_eq!(vec![created.clone()], store.searches(Some("anna")).unwrap());
+    assert!(store.searches(Some("ben")).unwrap().is_empty());
+    assert!(store.delete_search(created.id).unwrap());
+    assert_eq!(None, store.search(created.id).unwrap());
+  }
+
+  #[test]
+  fn test_saved_search_filter() {
+    let today = NaiveDate::from_ymd_opt(2022, 8, 20).unwrap();
+    let search = |query: &str| SavedSearch {
+      id: SearchId(1),
+      owner: "anna".to_string(),
+      name: "Search".to_string(),
+      query: query.to_string(),
+      created_at: LocalDateTime::default(),
+    };
+
+    let filter = search("area=1038").filter(today);
+    assert_eq!(Some(AreaId(1038)), filter.area);
+    assert_eq!(Some(today), filter.date);
+    assert_eq!(
+      Some(today + Duration::days(UPCOMING_DAYS - 1)),
+      filter.until
+    );
+    assert_eq!(None, search("date=2022-08-22").filter(today).until);
+  }
+
+  #[test]
+  fn test_watchlist_shows() {
+    let watchlist = Watchlist {
+      id: WatchlistId(1),
+      owner: "anna".to_string(),
+      name: "Films".to_string(),
+      events: vec![EventId(303000)],
+      created_at: LocalDateTime::default(),
+    };
+    let show = |id: u64, event: u64| Show {
+      id: ShowId(id),
+      event_id: EventId(event),
+      ..Show::default()
+    };
+
+    let shows = watchlist.shows(vec![show(1, 303000), show(2, 302000)]);
+    assert_eq!(
+      vec![ShowId(1)],
+      shows.iter().map(|show| show.id).collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn test_invalid_bodies() {
+    let errors = NewWatchlist::from_json(br#"{"owner":"","name":"Films"}"#).unwrap_err();
+    assert_eq!(
+      Some("/owner".to_string()),
+      errors.errors[0].source.as_ref().unwrap().pointer
+    );
+    let errors =
+      NewSearch::from_json(br#"{"owner":"anna","name":"x","query":"area=abc"}"#).unwrap_err();
+    assert_eq!(
+      Some("/query".to_string()),
+      errors.errors[0].source.as_ref().unwrap().pointer
+    );
+    assert!(NewWatchlist::from_json(br#"{"owner":"anna","name":"x","events":[1]}"#).is_err());
+  }
+}
@@ -0,0 +1,228 @@
+//! Subscribable calendars remember the shows they have published, so shows
+//! that drop out of the Schedule feed before they start can be published as
+//! cancelled for a while instead of silently vanishing from calendars.
+
+use chrono::{Duration, NaiveDate};
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::vec::Vec;
+
+use crate::finnkino::Show;
+use crate::ical::Calendar;
+use crate::id::{AreaId, EventId, ShowId};
+use crate::schedule::ShowFilter;
+use crate::time::LocalDateTime;
+
+pub const GRACE_PERIOD_DAYS: i64 = 7;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Subscription {
+  Area(AreaId),
+  Event(EventId),
+}
+
+impl Subscription {
+  pub fn filter(&self, today: NaiveDate) -> ShowFilter {
+    match self {
+      Subscription::Area(area) => ShowFilter {
+        area: Some(*area),
+        ..ShowFilter::from_today(today)
+      },
+      Subscription::Event(event) => ShowFilter::upcoming(*event, today),
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+struct Published {
+  show: Show,
+  removed_at: Option<LocalDateTime>,
+}
+
+impl Published {
+  fn is_cancelled(&self) -> bool {
+    self
+      .removed_at
+      .is_some_and(|removed_at| self.show.show_start > removed_at)
+  }
+}
+
+#[derive(Debug)]
+pub struct Tracker {
+  grace_period: Duration,
+  published: Mutex<HashMap<Subscription, HashMap<ShowId, Published>>>,
+}
+
+impl Tracker {
+  pub fn new(grace_period: Duration) -> Self {
+    Tracker {
+      grace_period,
+      published: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Records the shows currently in the feed and returns the calendar to
+  /// publish. Shows removed before their start are listed as cancelled and
+  /// shows removed after it are kept as they were, both until the grace
+  /// period has passed.
+  pub fn update(
+    &self,
+    subscription: Subscription,
+    shows: Vec<Show>,
+    now: LocalDateTime,
+  ) -> Calendar {
+    let mut feeds = self.published.lock().unwrap();
+    let published = feeds.entry(subscription).or_default();
+    let current: HashSet<ShowId> = shows.iter().map(|show| show.id).collect();
+    for (id, entry) in published.iter_mut() {
+      if !current.contains(id) && entry.removed_at.is_none() {
+        entry.removed_at = Some(now);
+      }
+    }
+    let grace_period = self.grace_period;
+    published.retain(|_, entry| match entry.removed_at {
+      None => true,
+      Some(removed_at) => now.0 - removed_at.0 < grace_period,
+    });
+    for show in shows {
+      published.insert(
+        show.id,
+        Published {
+          show,
+          removed_at: None,
+        },
+      );
+    }
+
+    let (mut cancelled, mut shows): (Vec<&Published>, Vec<&Published>) =
+      published.values().partition(|entry| entry.is_cancelled());
+    cancelled.sort_by_key(|entry| (entry.show.show_start, entry.show.id));
+    shows.sort_by_key(|entry| (entry.show.show_start, entry.show.id));
+    Calendar {
+      name: None,
+      stamp: now,
+      shows: shows.into_iter().map(|entry| entry.show.clone()).collect(),
+      cancelled: cancelled
+        .into_iter()
+        .map(|entry| entry.show.clone())
+        .collect(),
+    }
+  }
+}
+
+impl Default for Tracker {
+  fn default() -> Self {
+    Tracker::new(Duration::days(GRACE_PERIOD_DAYS))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::time;
+  use pretty_assertions::assert_eq;
+
+  fn show(id: u64, start: &str) -> Show {
+    Show {
+      id: ShowId(id),
+      show_start: time::parse_finnkino_datetime(start).unwrap(),
+      ..Show::default()
+    }
+  }
+
+  fn at(datetime: &str) -> LocalDateTime {
+    time::parse_finnkino_datetime(datetime).unwrap()
+  }
+
+  fn ids(shows: &[Show]) -> Vec<u64> {
+    shows.iter().map(|show| show.id.0).collect()
+  }
+
+  const AREA: Subscription = Subscription::Area(AreaId(1014));
+
+  #[test]
+  fn test_removed_show_is_cancelled() {
+    let tracker = Tracker::default();
+    tracker.update(
+      AREA,
+      vec![
+        show(1, "2022-08-20T18:00:00"),
+        show(2, "2022-08-21T18:00:00"),
+      ],
+      at("2022-08-20T12:00:00"),
+    );
+    let calendar = tracker.update(
+      AREA,
+      vec![show(1, "2022-08-20T18:00:00")],
+      at("2022-08-20T13:00:00"),
+    );
+
+    assert_eq!(vec![1], ids(&calendar.shows));
+    assert_eq!(vec![2], ids(&calendar.cancelled));
+  }
+
+  #[test]
+  fn test_show_that_has_started_is_not_cancelled() {
+    let tracker = Tracker::default();
+    tracker.update(
+      AREA,
+      vec![show(1, "2022-08-20T18:00:00")],
+      at("2022-08-20T12:00:00"),
+    );
+    let calendar = tracker.update(AREA, vec![], at("2022-08-20T19:00:00"));
+
+    assert_eq!(vec![1], ids(&calendar.shows));
+    assert!(calendar.cancelled.is_empty());
+  }
+
+  #[test]
+  fn test_cancellation_expires_after_grace_period() {
+    let tracker = Tracker::new(Duration::days(1));
+    tracker.update(
+      AREA,
+      vec![show(2, "2022-08-25T18:00:00")],
+      at("2022-08-20T12:00:00"),
+    );
+    tracker.update(AREA, vec![], at("2022-08-20T13:00:00"));
+    let calendar = tracker.update(AREA, vec![], at("2022-08-21T13:00:00"));
+
+    assert!(calendar.shows.is_empty());
+    assert!(calendar.cancelled.is_empty());
+  }
+
+  #[test]
+  fn test_show_can_come_back() {
+    let tracker = Tracker::default();
+    tracker.update(
+      AREA,
+      vec![show(2, "2022-08-21T18:00:00")],
+      at("2022-08-20T12:00:00"),
+    );
+    tracker.update(AREA, vec![], at("2022-08-20T13:00:00"));
+    let calendar = tracker.update(
+      AREA,
+      vec![show(2, "2022-08-21T18:00:00")],
+      at("2022-08-20T14:00:00"),
+    );
+
+    assert_eq!(vec![2], ids(&calendar.shows));
+    assert!(calendar.cancelled.is_empty());
+  }
+
+  #[test]
+  fn test_subscriptions_are_tracked_separately() {
+    let tracker = Tracker::default();
+    tracker.update(
+      AREA,
+      vec![show(1, "2022-08-21T18:00:00")],
+      at("2022-08-20T12:00:00"),
+    );
+    let calendar = tracker.update(
+      Subscription::Event(EventId(303000)),
+      vec![],
+      at("2022-08-20T13:00:00"),
+    );
+
+    assert!(calendar.cancelled.is_empty());
+  }
+}
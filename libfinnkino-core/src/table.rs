@@ -0,0 +1,190 @@
+//! Plain-text tables of areas, shows and films for the command-line client,
+//! written either aligned for the terminal or as CSV.
+
+use std::vec::Vec;
+
+use crate::api::v1;
+
+pub const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Table {
+  pub headers: Vec<&'static str>,
+  pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+  /// The columns padded to their widest cell and separated by two spaces.
+  /// The last column is not padded.
+  pub fn to_text(&self) -> String {
+    let mut widths: Vec<usize> = self.headers.iter().map(|header| header.len()).collect();
+    for row in &self.rows {
+      for (width, cell) in widths.iter_mut().zip(row) {
+        *width = (*width).max(cell.chars().count());
+      }
+    }
+    let headers: Vec<String> = self
+      .headers
+      .iter()
+      .map(|header| header.to_string())
+      .collect();
+    let mut text = String::new();
+    for row in std::iter::once(&headers).chain(&self.rows) {
+      let mut line = String::new();
+      for (index, (cell, width)) in row.iter().zip(&widths).enumerate() {
+        if index > 0 {
+          line.push_str("  ");
+        }
+        line.push_str(cell);
+        if index + 1 < row.len() {
+          line.extend(std::iter::repeat_n(' ', width - cell.chars().count()));
+        }
+      }
+      text.push_str(line.trim_end());
+      text.push('\n');
+    }
+    text
+  }
+
+  pub fn to_csv(&self) -> String {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(&self.headers).unwrap();
+    for row in &self.rows {
+      writer.write_record(row).unwrap();
+    }
+    String::from_utf8(writer.into_inner().unwrap()).unwrap()
+  }
+}
+
+pub fn areas(areas: &[v1::Area]) -> Table {
+  Table {
+    headers: vec!["ID", "NAME"],
+    rows: areas
+      .iter()
+      .map(|area| vec![area.id.to_string(), area.name.clone()])
+      .collect(),
+  }
+}
+
+pub fn shows(shows: &[v1::Show]) -> Table {
+  Table {
+    headers: vec![
+      "ID",
+      "START",
+      "TITLE",
+      "THEATRE",
+      "AUDITORIUM",
+      "PRESENTATION",
+      "LANGUAGE",
+    ],
+    rows: shows
+      .iter()
+      .map(|show| {
+        vec![
+          show.id.to_string(),
+          show.start.format(DATETIME_FORMAT).to_string(),
+          show.title.clone(),
+          show.theatre.clone(),
+          show.auditorium.clone(),
+          show.presentation_method.clone(),
+          show
+            .spoken_language
+            .as_ref()
+            .map(|language| language.code.clone())
+            .unwrap_or_default(),
+        ]
+      })
+      .collect(),
+  }
+}
+
+fn release(event: &v1::Event) -> String {
+  event
+    .local_release
+    .map(|release| release.date_naive().to_string())
+    .unwrap_or_default()
+}
+
+pub fn events(events: &[v1::Event]) -> Table {
+  Table {
+    headers: vec!["ID", "TITLE", "RELEASE", "LENGTH", "RATING", "GENRES"],
+    rows: events
+      .iter()
+      .map(|event| {
+        vec![
+          event.id.to_string(),
+          event.title.clone(),
+          release(event),
+          event.length_in_minutes.clone(),
+          event.rating.clone(),
+          event.genres.join(", "),
+        ]
+      })
+      .collect(),
+  }
+}
+
+/// A film as one row per field.
+pub fn event(event: &v1::Event) -> Table {
+  let fields = [
+    ("ID", event.id.to_string()),
+    ("Title", event.title.clone()),
+    ("Original title", event.original_title.clone()),
+    ("Year", event.production_year.clone()),
+    ("Length", event.length_in_minutes.clone()),
+    ("Rating", event.rating.clone()),
+    ("Genres", event.genres.join(", ")),
+    ("Release", release(event)),
+    ("Directors", event.directors.join(", ")),
+    ("Cast", event.cast.join(", ")),
+    ("Synopsis", event.short_synopsis.clone()),
+  ];
+  Table {
+    headers: vec!["FIELD", "VALUE"],
+    rows: fields
+      .into_iter()
+      .map(|(field, value)| vec![field.to_string(), value])
+      .collect(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::id::AreaId;
+  use pretty_assertions::assert_eq;
+
+  fn areas_table() -> Table {
+    areas(&[
+      v1::Area {
+        id: AreaId(1014),
+        name: "Pääkaupunkiseutu".to_string(),
+      },
+      v1::Area {
+        id: AreaId(1015),
+        name: "Jyväskylä: FANTASIA, \"Sali\"".to_string(),
+      },
+    ])
+  }
+
+  #[test]
+  fn test_to_text() {
+    assert_eq!(
+      "ID    NAME\n1014  Pääkaupunkiseutu\n1015  Jyväskylä: FANTASIA, \"Sali\"\n",
+      areas_table().to_text()
+    );
+    let table = Table {
+      headers: vec!["A", "B"],
+      rows: vec![vec!["long value".to_string(), String::new()]],
+    };
+    assert_eq!("A           B\nlong value\n", table.to_text());
+  }
+
+  #[test]
+  fn test_to_csv() {
+    assert_eq!(
+      "ID,NAME\n1014,Pääkaupunkiseutu\n1015,\"Jyväskylä: FANTASIA, \"\"Sali\"\"\"\n",
+      areas_table().to_csv()
+    );
+  }
+}
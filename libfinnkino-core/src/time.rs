@@ -0,0 +1,178 @@
+//! Finnkino publishes wall-clock times of Finland without an offset. They are
+//! read as `Europe/Helsinki` times and written out as RFC 3339.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::ops::Deref;
+
+pub const TIMEZONE: Tz = chrono_tz::Europe::Helsinki;
+pub const FINNKINO_DATE_FORMAT: &str = "%d.%m.%Y";
+pub const FINNKINO_DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+pub const API_DATE_FORMAT: &str = "%Y-%m-%d";
+
+pub fn now() -> LocalDateTime {
+  LocalDateTime(Utc::now().with_timezone(&TIMEZONE))
+}
+
+pub fn today() -> NaiveDate {
+  now().date_naive()
+}
+
+/// Resolves a Finnish wall-clock time. Times repeated when DST ends resolve to
+/// the first occurrence, times skipped when DST starts are moved past the gap.
+pub fn from_local(naive: &NaiveDateTime) -> LocalDateTime {
+  match TIMEZONE.from_local_datetime(naive).earliest() {
+    Some(datetime) => LocalDateTime(datetime),
+    None => from_local(&(*naive + Duration::hours(1))),
+  }
+}
+
+/// The local time of a Unix timestamp, the epoch if it is out of range.
+pub fn from_timestamp(seconds: i64) -> LocalDateTime {
+  Utc
+    .timestamp_opt(seconds, 0)
+    .single()
+    .map(|datetime| LocalDateTime(datetime.with_timezone(&TIMEZONE)))
+    .unwrap_or_default()
+}
+
+pub fn parse_finnkino_datetime(value: &str) -> Option<LocalDateTime> {
+  let value = value.trim();
+  match NaiveDateTime::parse_from_str(value, FINNKINO_DATETIME_FORMAT) {
+    Ok(naive) => Some(from_local(&naive)),
+    Err(_) => DateTime::parse_from_rfc3339(value)
+      .ok()
+      .map(|datetime| LocalDateTime(datetime.with_timezone(&TIMEZONE))),
+  }
+}
+
+/// Parses a date given to our API, preferring ISO 8601 but still accepting
+/// Finnkino's own `dd.mm.yyyy`.
+pub fn parse_api_date(value: &str) -> Option<NaiveDate> {
+  NaiveDate::parse_from_str(value, API_DATE_FORMAT)
+    .or_else(|_| NaiveDate::parse_from_str(value, FINNKINO_DATE_FORMAT))
+    .ok()
+}
+
+pub fn to_finnkino_date(date: &NaiveDate) -> String {
+  date.format(FINNKINO_DATE_FORMAT).to_string()
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct LocalDateTime(pub DateTime<Tz>);
+
+impl LocalDateTime {
+  pub fn to_rfc3339(self) -> String {
+    self.0.to_rfc3339_opts(SecondsFormat::Secs, false)
+  }
+}
+
+impl Default for LocalDateTime {
+  fn default() -> Self {
+    LocalDateTime(DateTime::<Utc>::UNIX_EPOCH.with_timezone(&TIMEZONE))
+  }
+}
+
+impl Deref for LocalDateTime {
+  type Target = DateTime<Tz>;
+
+  fn deref(&self) -> &Self::Target {
+    &self.0
+  }
+}
+
+impl fmt::Display for LocalDateTime {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.to_rfc3339())
+  }
+}
+
+impl Serialize for LocalDateTime {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_rfc3339())
+  }
+}
+
+impl<'de> Deserialize<'de> for LocalDateTime {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    parse_finnkino_datetime(&value)
+      .ok_or_else(|| de::Error::custom(format!("invalid date and time: {}", value)))
+  }
+}
+
+pub fn deserialize_optional<'de, D: Deserializer<'de>>(
+  deserializer: D,
+) -> Result<Option<LocalDateTime>, D::Error> {
+  match Option::<String>::deserialize(deserializer)? {
+    None => Ok(None),
+    Some(value) if value.trim().is_empty() => Ok(None),
+    Some(value) => parse_finnkino_datetime(&value)
+      .map(Some)
+      .ok_or_else(|| de::Error::custom(format!("invalid date and time: {}", value))),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_summer_time() {
+    let datetime = parse_finnkino_datetime("2022-08-20T18:00:00").unwrap();
+
+    assert_eq!("2022-08-20T18:00:00+03:00", datetime.to_rfc3339());
+  }
+
+  #[test]
+  fn test_winter_time() {
+    let datetime = parse_finnkino_datetime("2022-12-20T18:00:00").unwrap();
+
+    assert_eq!("2022-12-20T18:00:00+02:00", datetime.to_rfc3339());
+  }
+
+  #[test]
+  fn test_ambiguous_time_resolves_to_first_occurrence() {
+    let datetime = parse_finnkino_datetime("2022-10-30T03:30:00").unwrap();
+
+    assert_eq!("2022-10-30T03:30:00+03:00", datetime.to_rfc3339());
+  }
+
+  #[test]
+  fn test_skipped_time_moves_past_the_gap() {
+    let datetime = parse_finnkino_datetime("2022-03-27T03:30:00").unwrap();
+
+    assert_eq!("2022-03-27T04:30:00+03:00", datetime.to_rfc3339());
+  }
+
+  #[test]
+  fn test_rfc3339_is_converted_to_helsinki() {
+    let datetime = parse_finnkino_datetime("2022-08-20T15:00:00Z").unwrap();
+
+    assert_eq!("2022-08-20T18:00:00+03:00", datetime.to_rfc3339());
+  }
+
+  #[test]
+  fn test_parse_api_date() {
+    let date = NaiveDate::from_ymd_opt(2022, 8, 20).unwrap();
+
+    assert_eq!(Some(date), parse_api_date("2022-08-20"));
+    assert_eq!(Some(date), parse_api_date("20.08.2022"));
+    assert_eq!(None, parse_api_date("08/20/2022"));
+    assert_eq!("20.08.2022", to_finnkino_date(&date));
+  }
+
+  #[test]
+  fn test_serialize_as_rfc3339() {
+    let datetime = parse_finnkino_datetime("2022-08-20T18:00:00").unwrap();
+
+    assert_eq!(
+      "\"2022-08-20T18:00:00+03:00\"",
+      serde_json::to_string(&datetime).unwrap()
+    );
+  }
+}
@@ -0,0 +1,456 @@
+//! Topic subscriptions for WebSocket clients. A client subscribes to topics
+//! with JSON text messages like `{"action":"subscribe","topic":"area:1014"}`
+//! and gets a message for every show or event added, changed or removed
+//! between two polls of the feed behind the topic.
+
+use futures::channel::mpsc::{self, Receiver, Sender};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::vec::Vec;
+
+use crate::api::v1;
+use crate::finnkino::{Event, Show};
+use crate::id::{AreaId, EventId};
+use crate::json;
+use crate::live::ChangeKind;
+use crate::schedule::{self, Scheduled};
+use crate::time::LocalDateTime;
+
+pub const COMING_SOON: &str = "coming-soon";
+/// The messages a client may fall behind before it is disconnected.
+pub const BUFFER: usize = 64;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Topic {
+  /// Today's shows of an area.
+  Area(AreaId),
+  /// The upcoming shows of an event.
+  Event(EventId),
+  /// The events coming soon.
+  ComingSoon,
+}
+
+impl FromStr for Topic {
+  type Err = String;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    let invalid = || {
+      format!(
+        "'{}' is not one of area:<id>, event:<id> or {}",
+        value, COMING_SOON
+      )
+    };
+    match value.split_once(':') {
+      None if value == COMING_SOON => Ok(Topic::ComingSoon),
+      Some(("area", id)) => id.parse().map(Topic::Area).map_err(|_| invalid()),
+      Some(("event", id)) => id.parse().map(Topic::Event).map_err(|_| invalid()),
+      _ => Err(invalid()),
+    }
+  }
+}
+
+impl fmt::Display for Topic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Topic::Area(id) => write!(f, "area:{}", id),
+      Topic::Event(id) => write!(f, "event:{}", id),
+      Topic::ComingSoon => write!(f, "{}", COMING_SOON),
+    }
+  }
+}
+
+impl Serialize for Topic {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Resource {
+  Show(v1::ShowResource),
+  Event(v1::EventResource),
+}
+
+impl Resource {
+  fn identifier(&self) -> Resource {
+    match self {
+      Resource::Show(show) => Resource::Show(v1::ShowResource::identifier(show.id)),
+      Resource::Event(event) => Resource::Event(v1::EventResource::identifier(event.id)),
+    }
+  }
+}
+
+impl Scheduled for Resource {
+  type Id = (&'static str, u64);
+
+  fn id(&self) -> Self::Id {
+    match self {
+      Resource::Show(show) => (show.kind, show.id.0),
+      Resource::Event(event) => (event.kind, event.id.0),
+    }
+  }
+
+  fn start(&self) -> LocalDateTime {
+    match self {
+      Resource::Show(show) => show.attributes.as_ref().map(|show| show.start),
+      Resource::Event(event) => event
+        .attributes
+        .as_ref()
+        .and_then(|event| event.local_release),
+    }
+    .unwrap_or_default()
+  }
+}
+
+impl From<Show> for Resource {
+  fn from(show: Show) -> Self {
+    Resource::Show(v1::ShowResource::new(show))
+  }
+}
+
+impl From<Event> for Resource {
+  fn from(event: Event) -> Self {
+    Resource::Event(v1::EventResource::new(event))
+  }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Action {
+  Subscribe,
+  Unsubscribe,
+}
+
+#[derive(Debug, Deserialize)]
+struct Command {
+  action: Action,
+  topic: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Message<'a> {
+  Subscribed { topic: Topic },
+  Unsubscribed { topic: Topic },
+  Added { topic: Topic, data: &'a Resource },
+  Changed { topic: Topic, data: &'a Resource },
+  Removed { topic: Topic, data: &'a Resource },
+  Error { errors: Vec<json::Error> },
+}
+
+impl Message<'_> {
+  fn change(kind: ChangeKind, topic: Topic, data: &Resource) -> Message<'_> {
+    match kind {
+      ChangeKind::Added => Message::Added { topic, data },
+      ChangeKind::Changed => Message::Changed { topic, data },
+      ChangeKind::Removed => Message::Removed { topic, data },
+    }
+  }
+
+  fn to_json(&self) -> String {
+    serde_json::to_string(self).unwrap()
+  }
+}
+
+fn invalid_message(detail: impl Into<String>) -> Message<'static> {
+  Message::Error {
+    errors: vec![json::ErrorBuilder::default()
+      .status("400")
+      .title("Invalid message")
+      .detail(detail.into())
+      .build()
+      .unwrap()],
+  }
+}
+
+/// The only WebSocket version the servers speak, sent back in
+/// `Sec-WebSocket-Version` to clients that ask for another.
+pub const WEBSOCKET_VERSION: &str = "13";
+
+/// The answer to a request for the WebSocket endpoint that is not a
+/// handshake.
+pub fn handshake_error() -> json::Errors {
+  json::Errors {
+    errors: vec![json::ErrorBuilder::default()
+      .status("400")
+      .title("Invalid WebSocket handshake")
+      .detail("Expected a GET with Upgrade: websocket, Connection: Upgrade and a Sec-WebSocket-Key")
+      .build()
+      .unwrap()],
+  }
+}
+
+/// The answer to a handshake without `Sec-WebSocket-Version: 13`, with a 426
+/// Upgrade Required as RFC 6455 asks.
+pub fn version_error() -> json::Errors {
+  json::Errors {
+    errors: vec![json::ErrorBuilder::default()
+      .status("426")
+      .title("Unsupported WebSocket version")
+      .detail(format!(
+        "Expected Sec-WebSocket-Version: {}",
+        WEBSOCKET_VERSION
+      ))
+      .build()
+      .unwrap()],
+  }
+}
+
+/// A connected client. Messages to it are sent through the receiver
+/// returned by [`Broker::connect`], which ends when the client falls more
+/// than [`BUFFER`] messages behind.
+#[derive(Debug)]
+pub struct Client {
+  id: u64,
+  sender: Sender<String>,
+}
+
+impl Client {
+  fn send(&self, message: &Message) {
+    send(&mut self.sender.clone(), message.to_json());
+  }
+}
+
+/// Sends a message without waiting, closing the channel of a client that
+/// has not kept up.
+fn send(sender: &mut Sender<String>, message: String) -> bool {
+  let sent = sender.try_send(message).is_ok();
+  if !sent {
+    sender.close_channel();
+  }
+  sent
+}
+
+#[derive(Debug, Default)]
+struct Feed {
+  resources: Option<Vec<Resource>>,
+  clients: HashMap<u64, Sender<String>>,
+}
+
+#[derive(Debug, Default)]
+pub struct Broker {
+  next_client: AtomicU64,
+  feeds: Mutex<HashMap<Topic, Feed>>,
+}
+
+impl Broker {
+  pub fn connect(&self) -> (Client, Receiver<String>) {
+    let (sender, receiver) = mpsc::channel(BUFFER);
+    let id = self.next_client.fetch_add(1, Ordering::Relaxed);
+    (Client { id, sender }, receiver)
+  }
+
+  /// Handles a text message from a client.
+  pub fn receive(&self, client: &Client, text: &str) {
+    let command = match serde_json::from_str::<Command>(text) {
+      Err(error) => return client.send(&invalid_message(error.to_string())),
+      Ok(command) => command,
+    };
+    let topic = match command.topic.parse::<Topic>() {
+      Err(detail) => return client.send(&invalid_message(detail)),
+      Ok(topic) => topic,
+    };
+    let mut feeds = self.feeds.lock().unwrap();
+    match command.action {
+      Action::Subscribe => {
+        let feed = feeds.entry(topic).or_default();
+        feed.clients.insert(client.id, client.sender.clone());
+        client.send(&Message::Subscribed { topic });
+      }
+      Action::Unsubscribe => {
+        if let Some(feed) = feeds.get_mut(&topic) {
+          feed.clients.remove(&client.id);
+        }
+        client.send(&Message::Unsubscribed { topic });
+      }
+    }
+  }
+
+  pub fn disconnect(&self, client: &Client) {
+    let mut feeds = self.feeds.lock().unwrap();
+    for feed in feeds.values_mut() {
+      feed.clients.remove(&client.id);
+    }
+  }
+
+  /// The topics to poll. Topics without subscribers are forgotten.
+  pub fn watched(&self) -> Vec<Topic> {
+    let mut feeds = self.feeds.lock().unwrap();
+    for feed in feeds.values_mut() {
+      feed.clients.retain(|_, sender| !sender.is_closed());
+    }
+    feeds.retain(|_, feed| !feed.clients.is_empty());
+    feeds.keys().copied().collect()
+  }
+
+  /// Records the current resources of a topic and notifies the subscribers
+  /// of the changes since the previous poll. The first poll of a topic only
+  /// records the resources.
+  pub fn update(&self, topic: Topic, resources: Vec<Resource>) {
+    let mut feeds = self.feeds.lock().unwrap();
+    let feed = match feeds.get_mut(&topic) {
+      None => return,
+      Some(feed) => feed,
+    };
+    let previous = match feed.resources.replace(resources) {
+      None => return,
+      Some(previous) => previous,
+    };
+    let diff = schedule::diff(&previous, feed.resources.as_deref().unwrap_or_default());
+    let removed: Vec<Resource> = diff.removed.iter().map(Resource::identifier).collect();
+    let changes = [
+      (ChangeKind::Added, diff.added),
+      (ChangeKind::Changed, diff.changed),
+      (ChangeKind::Removed, removed),
+    ];
+    for (kind, resources) in changes {
+      for resource in resources {
+        let message = Message::change(kind, topic, &resource).to_json();
+        feed
+          .clients
+          .retain(|_, sender| send(sender, message.clone()));
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::id::ShowId;
+  use futures::StreamExt;
+  use pretty_assertions::assert_eq;
+
+  const AREA: Topic = Topic::Area(AreaId(1014));
+
+  fn show(id: u64, auditorium: &str) -> Resource {
+    Resource::from(Show {
+      id: ShowId(id),
+      auditorium: auditorium.to_string(),
+      ..Show::default()
+    })
+  }
+
+  fn messages(receiver: Receiver<String>) -> Vec<serde_json::Value> {
+    futures::executor::block_on(receiver.collect::<Vec<String>>())
+      .iter()
+      .map(|message| serde_json::from_str(message).unwrap())
+      .collect()
+  }
+
+  fn summary(messages: &[serde_json::Value]) -> Vec<String> {
+    messages
+      .iter()
+      .map(|message| {
+        format!(
+          "{} {} {}",
+          message["type"].as_str().unwrap_or_default(),
+          message["topic"].as_str().unwrap_or_default(),
+          message["data"]["id"].as_str().unwrap_or_default()
+        )
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_parse_topic() {
+    assert_eq!(Ok(AREA), "area:1014".parse());
+    assert_eq!(Ok(Topic::Event(EventId(303000))), "event:303000".parse());
+    assert_eq!(Ok(Topic::ComingSoon), "coming-soon".parse::<Topic>());
+    assert!("area:abc".parse::<Topic>().is_err());
+    assert!("films".parse::<Topic>().is_err());
+    assert_eq!("event:303000", Topic::Event(EventId(303000)).to_string());
+  }
+
+  #[test]
+  fn test_subscriber_is_notified_of_changes() {
+    let broker = Broker::default();
+    let (client, receiver) = broker.connect();
+    broker.receive(&client, r#"{"action":"subscribe","topic":"area:1014"}"#);
+    broker.update(AREA, vec![show(1, "sali 1"), show(2, "sali 1")]);
+    broker.update(AREA, vec![show(1, "sali 2"), show(3, "sali 1")]);
+    drop(client);
+    drop(broker);
+    let received = messages(receiver);
+
+    assert_eq!(
+      vec![
+        "subscribed area:1014 ",
+        "added area:1014 3",
+        "changed area:1014 1",
+        "removed area:1014 2",
+      ],
+      summary(&received)
+    );
+    assert_eq!("sali 2", received[2]["data"]["attributes"]["auditorium"]);
+    assert_eq!(
+      serde_json::json!({"type": "shows", "id": "2"}),
+      received[3]["data"]
+    );
+  }
+
+  #[test]
+  fn test_unsubscribe() {
+    let broker = Broker::default();
+    let (client, receiver) = broker.connect();
+    broker.receive(&client, r#"{"action":"subscribe","topic":"coming-soon"}"#);
+    broker.update(Topic::ComingSoon, vec![]);
+    broker.receive(&client, r#"{"action":"unsubscribe","topic":"coming-soon"}"#);
+    broker.update(Topic::ComingSoon, vec![show(1, "sali 1")]);
+    drop(client);
+    drop(broker);
+
+    assert_eq!(
+      vec!["subscribed coming-soon ", "unsubscribed coming-soon "],
+      summary(&messages(receiver))
+    );
+  }
+
+  #[test]
+  fn test_invalid_messages() {
+    let broker = Broker::default();
+    let (client, receiver) = broker.connect();
+    broker.receive(&client, "subscribe");
+    broker.receive(&client, r#"{"action":"subscribe","topic":"films"}"#);
+    drop(client);
+    let received = messages(receiver);
+
+    assert_eq!(2, received.len());
+    assert_eq!("error", received[1]["type"]);
+    assert_eq!("400", received[1]["errors"][0]["status"]);
+    assert!(broker.watched().is_empty());
+  }
+
+  #[test]
+  fn test_lagging_client_is_disconnected() {
+    let broker = Broker::default();
+    let (client, receiver) = broker.connect();
+    broker.receive(&client, r#"{"action":"subscribe","topic":"area:1014"}"#);
+    broker.update(AREA, vec![]);
+    let shows: Vec<Resource> = (0..2 * BUFFER as u64)
+      .map(|id| show(id, "sali 1"))
+      .collect();
+    broker.update(AREA, shows);
+    let received = messages(receiver);
+
+    assert!(received.len() < 2 * BUFFER);
+    assert_eq!("added area:1014 0", summary(&received)[1]);
+    assert!(broker.watched().is_empty());
+  }
+
+  #[test]
+  fn test_topics_without_clients_are_not_watched() {
+    let broker = Broker::default();
+    let (client, receiver) = broker.connect();
+    broker.receive(&client, r#"{"action":"subscribe","topic":"event:303000"}"#);
+
+    assert_eq!(vec![Topic::Event(EventId(303000))], broker.watched());
+    drop(receiver);
+    assert!(broker.watched().is_empty());
+  }
+}
@@ -0,0 +1,589 @@
+//! The Finnkino feeds as both clients read them: through the XML cache, from
+//! the fixtures, the recorder or the configured upstream. Only sending the
+//! request is left to the HTTP client behind [`Transport`].
+
+use http::StatusCode;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+use std::vec::Vec;
+
+use crate::cache::Cache;
+use crate::events::{EventQuery, EventQueryBuilder, ListType};
+use crate::finnkino::{
+  from_xml, Error, ErrorBuilder, ErrorKind, Event, Events, News, NewsArticle, Schedule,
+  ScheduleDates, Show, TheatreArea, TheatreAreas, BASE_URL,
+};
+use crate::fixtures::Fixtures;
+use crate::id::{AreaId, EventId, ShowId};
+use crate::recording::{Exchange, Recorder};
+use crate::schedule::{schedule_dates_url, ShowFilter};
+use crate::time::{today, LocalDateTime};
+use crate::webhooks::Outgoing;
+
+/// An HTTP client that fetches the feeds and delivers the webhooks.
+pub trait Transport {
+  /// Sends a GET request to `url` and reads the whole response.
+  fn send(&self, url: &str) -> impl Future<Output = Result<Exchange, Error>>;
+
+  /// POSTs a webhook payload to `addr`, the resolved address of its host,
+  /// without following redirects. Answers the status of the response or why
+  /// there was none.
+  fn post(
+    &self,
+    outgoing: &Outgoing,
+    addr: SocketAddr,
+  ) -> impl Future<Output = Result<u16, String>>;
+}
+
+tokio::task_local! {
+  static UNCACHED: bool;
+}
+
+/// Runs `fetch` with every feed read from upstream, for the pollers: the XML
+/// cache outlives their interval, so they would see the same feed for
+/// minutes. The fresh feeds still refresh the cache.
+pub async fn uncached<F: Future>(fetch: F) -> F::Output {
+  UNCACHED.scope(true, fetch).await
+}
+
+/// The cached feeds over `transport`. Each client keeps its own, so that
+/// neither serves the other's cache.
+pub struct FeedClient<T> {
+  transport: T,
+  fixtures: OnceLock<Fixtures>,
+  recorder: OnceLock<Recorder>,
+  upstream: OnceLock<String>,
+  cache: Cache<String>,
+}
+
+impl<T: Transport> FeedClient<T> {
+  pub fn new(transport: T) -> Self {
+    FeedClient {
+      transport,
+      fixtures: OnceLock::new(),
+      recorder: OnceLock::new(),
+      upstream: OnceLock::new(),
+      cache: Cache::default(),
+    }
+  }
+
+  /// Serves the feeds from `fixtures` instead of Finnkino from now on. Only
+  /// the first call has an effect.
+  pub fn use_fixtures(&self, fixtures: Fixtures) {
+    let _ = self.fixtures.set(fixtures);
+  }
+
+  /// Records or replays the requests to Finnkino with `recorder` from now
+  /// on. Only the first call has an effect.
+  pub fn use_recorder(&self, recorder: Recorder) {
+    let _ = self.recorder.set(recorder);
+  }
+
+  /// Reads the feeds from `base_url` instead of Finnkino from now on. Only
+  /// the first call has an effect.
+  pub fn use_upstream(&self, base_url: String) {
+    let _ = self.upstream.set(base_url);
+  }
+
+  pub fn base_url(&self) -> &str {
+    self.upstream.get().map(String::as_str).unwrap_or(BASE_URL)
+  }
+
+  pub async fn get_areas(&self) -> Result<Vec<TheatreArea>, Error> {
+    self.fetch_areas(self.base_url()).await
+  }
+
+  pub async fn get_shows(&self, filter: &ShowFilter) -> Result<Vec<Show>, Error> {
+    self.fetch_shows(self.base_url(), filter).await
+  }
+
+  pub async fn get_schedule_dates(
+    &self,
+    area: Option<AreaId>,
+  ) -> Result<Vec<LocalDateTime>, Error> {
+    self.fetch_schedule_dates(self.base_url(), area).await
+  }
+
+  pub async fn get_event(&self, id: EventId) -> Result<Event, Error> {
+    self.fetch_event(self.base_url(), id).await
+  }
+
+  pub async fn get_upcoming_shows(&self, event_id: EventId) -> Result<Vec<Show>, Error> {
+    let filter = ShowFilter::upcoming(event_id, today());
+    self.fetch_shows(self.base_url(), &filter).await
+  }
+
+  pub async fn get_show(&self, id: ShowId) -> Result<Show, Error> {
+    self.fetch_show(self.base_url(), id).await
+  }
+
+  pub async fn get_events(&self, query: &EventQuery) -> Result<Vec<Event>, Error> {
+    self.fetch_events(self.base_url(), query).await
+  }
+
+  pub async fn get_coming_soon(&self) -> Result<Vec<Event>, Error> {
+    let query = EventQueryBuilder::default()
+      .list_type(ListType::ComingSoon)
+      .build()
+      .unwrap();
+    self.get_events(&query).await
+  }
+
+  pub async fn get_news(&self) -> Result<Vec<NewsArticle>, Error> {
+    self.fetch_news(self.base_url()).await
+  }
+
+  /// The areas from `base_url` instead of the configured upstream.
+  pub async fn fetch_areas(&self, base_url: &str) -> Result<Vec<TheatreArea>, Error> {
+    if let Some(fixtures) = self.fixtures.get() {
+      return fixtures.areas();
+    }
+    let xml = self
+      .get_cached_xml(format!("{}/TheatreAreas", base_url).as_str())
+      .await?;
+    from_xml::<TheatreAreas>(xml.as_str()).map(|areas| areas.theatre_areas)
+  }
+
+  pub async fn fetch_events(
+    &self,
+    base_url: &str,
+    query: &EventQuery,
+  ) -> Result<Vec<Event>, Error> {
+    if let Some(fixtures) = self.fixtures.get() {
+      return fixtures.events(query);
+    }
+    let xml = self
+      .get_cached_xml(query.events_url(base_url).as_str())
+      .await?;
+    from_xml::<Events>(xml.as_str()).map(|events| events.events)
+  }
+
+  pub async fn fetch_shows(&self, base_url: &str, filter: &ShowFilter) -> Result<Vec<Show>, Error> {
+    if let Some(fixtures) = self.fixtures.get() {
+      return fixtures.shows(filter);
+    }
+    let xml = self
+      .get_cached_xml(filter.schedule_url(base_url).as_str())
+      .await?;
+    from_xml::<Schedule>(xml.as_str()).map(|schedule| filter.apply(schedule.shows.shows))
+  }
+
+  pub async fn fetch_schedule_dates(
+    &self,
+    base_url: &str,
+    area: Option<AreaId>,
+  ) -> Result<Vec<LocalDateTime>, Error> {
+    if let Some(fixtures) = self.fixtures.get() {
+      return fixtures.schedule_dates(area);
+    }
+    let xml = self
+      .get_cached_xml(schedule_dates_url(base_url, area).as_str())
+      .await?;
+    from_xml::<ScheduleDates>(xml.as_str()).map(|dates| dates.dates)
+  }
+
+  async fn fetch_show(&self, base_url: &str, id: ShowId) -> Result<Show, Error> {
+    let shows = self
+      .fetch_shows(base_url, &ShowFilter::from_today(today()))
+      .await?;
+    match shows.into_iter().find(|show| show.id == id) {
+      None => Err(
+        ErrorBuilder::default()
+          .kind(ErrorKind::Status)
+          .status("404")
+          .title("Not Found")
+          .detail(format!("No upcoming show with id {}", id))
+          .build()
+          .unwrap(),
+      ),
+      Some(show) => Ok(show),
+    }
+  }
+
+  async fn fetch_event(&self, base_url: &str, id: EventId) -> Result<Event, Error> {
+    if let Some(fixtures) = self.fixtures.get() {
+      return fixtures.event(id);
+    }
+    let query = EventQueryBuilder::default().event(id).build().unwrap();
+    let xml = self
+      .get_cached_xml(query.events_url(base_url).as_str())
+      .await?;
+    let events = from_xml::<Events>(xml.as_str())?;
+    match events.events.into_iter().find(|event| event.id == id) {
+      None => Err(
+        ErrorBuilder::default()
+          .kind(ErrorKind::Status)
+          .status("404")
+          .title("Not Found")
+          .detail(format!("No event with id {}", id))
+          .build()
+          .unwrap(),
+      ),
+      Some(event) => Ok(event),
+    }
+  }
+
+  async fn fetch_news(&self, base_url: &str) -> Result<Vec<NewsArticle>, Error> {
+    if let Some(fixtures) = self.fixtures.get() {
+      return fixtures.news();
+    }
+    let xml = self
+      .get_cached_xml(format!("{}/News/", base_url).as_str())
+      .await?;
+    from_xml::<News>(xml.as_str()).map(|news| news.articles)
+  }
+
+  async fn get_cached_xml(&self, url: &str) -> Result<String, Error> {
+    let cached = match UNCACHED.try_with(|uncached| *uncached) {
+      Ok(true) => None,
+      _ => self.cache.get(url),
+    };
+    if let Some(xml) = cached {
+      return Ok(xml);
+    }
+    let xml = self.get_xml(url).await?;
+    self.cache.insert(url, xml.clone());
+    Ok(xml)
+  }
+
+  /// Fetches `url` past the cache, or replays it with the recorder.
+  pub async fn get_xml(&self, url: &str) -> Result<String, Error> {
+    self.fetch_xml(url, self.recorder.get()).await
+  }
+
+  /// Fetches `url` from Finnkino, or from `recorder` when it has recorded it.
+  async fn fetch_xml(&self, url: &str, recorder: Option<&Recorder>) -> Result<String, Error> {
+    let replayed = match recorder {
+      None => None,
+      Some(recorder) => recorder.replay(url)?,
+    };
+    if let Some(exchange) = replayed {
+      return to_xml(exchange);
+    }
+    let exchange = self.transport.send(url).await?;
+    if let Some(recorder) = recorder {
+      recorder.record(&exchange)?;
+    }
+    to_xml(exchange)
+  }
+}
+
+fn to_xml(exchange: Exchange) -> Result<String, Error> {
+  let status = StatusCode::from_u16(exchange.status);
+  if status.as_ref().is_ok_and(StatusCode::is_success) {
+    return Ok(exchange.body);
+  }
+  let error_builder = match status.ok().and_then(|status| status.canonical_reason()) {
+    None => ErrorBuilder::default()
+      .kind(ErrorKind::Status)
+      .status(exchange.status.to_string())
+      .title("Unknown response status")
+      .detail(format!("{:?}", exchange.status))
+      .build(),
+    Some(reason) => ErrorBuilder::default()
+      .kind(ErrorKind::Status)
+      .status(exchange.status.to_string())
+      .title(reason)
+      .build(),
+  };
+  Err(error_builder.unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::recording::{scrub_cookies, Mode};
+  use futures::executor::block_on;
+  use pretty_assertions::assert_eq;
+  use std::collections::HashMap;
+  use std::sync::Mutex;
+
+  /// Answers the requests from canned bodies by path, with 404 for the rest,
+  /// and keeps the requested URLs.
+  #[derive(Default)]
+  struct Canned {
+    responses: HashMap<String, (u16, String)>,
+    requests: Mutex<Vec<String>>,
+  }
+
+  impl Canned {
+    fn with(mut self, path: &str, status: u16, body: &str) -> Self {
+      let response = (status, body.to_string());
+      self
+        .responses
+        .insert(format!("{}{}", BASE_URL, path), response);
+      self
+    }
+  }
+
+  impl Transport for Canned {
+    async fn post(&self, _outgoing: &Outgoing, _addr: SocketAddr) -> Result<u16, String> {
+      unimplemented!()
+    }
+
+    async fn send(&self, url: &str) -> Result<Exchange, Error> {
+      self.requests.lock().unwrap().push(url.to_string());
+      let path = url.split('?').next().unwrap();
+      let (status, body) = self
+        .responses
+        .get(path)
+        .cloned()
+        .unwrap_or((404, String::new()));
+      Ok(Exchange {
+        url: url.to_string(),
+        status,
+        headers: vec![("Set-Cookie".to_string(), "session=secret".to_string())],
+        body,
+      })
+    }
+  }
+
+  fn requests(client: &FeedClient<Canned>) -> Vec<String> {
+    client.transport.requests.lock().unwrap().clone()
+  }
+
+  #[test]
+  fn test_get_xml_not_found() {
+    let client = FeedClient::new(Canned::default());
+    let error = ErrorBuilder::default()
+      .kind(ErrorKind::Status)
+      .status("404")
+      .title("Not Found")
+      .build()
+      .unwrap();
+
+    let url = format!("{}/TheatreAreas", BASE_URL);
+    assert_eq!(error, block_on(client.get_xml(&url)).unwrap_err());
+  }
+
+  #[test]
+  fn test_get_xml_unknown_status() {
+    let client = FeedClient::new(Canned::default().with("/TheatreAreas", 599, ""));
+    let error = ErrorBuilder::default()
+      .kind(ErrorKind::Status)
+      .status("599")
+      .title("Unknown response status")
+      .detail("599")
+      .build()
+      .unwrap();
+
+    let url = format!("{}/TheatreAreas", BASE_URL);
+    assert_eq!(error, block_on(client.get_xml(&url)).unwrap_err());
+  }
+
+  #[test]
+  fn test_fetch_xml_records_and_replays() {
+    let client = FeedClient::new(
+      Canned::default()
+        .with("/TheatreAreas", 200, "<TheatreAreas />")
+        .with("/News/", 503, ""),
+    );
+    let dir = std::env::temp_dir().join(format!("finnkino-transport-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let areas_url = format!("{}/TheatreAreas", BASE_URL);
+    let news_url = format!("{}/News/", BASE_URL);
+
+    let recorder = Recorder::new(&dir, Mode::Record).scrub(scrub_cookies);
+    assert_eq!(
+      "<TheatreAreas />",
+      block_on(client.fetch_xml(&areas_url, Some(&recorder))).unwrap()
+    );
+    let error = block_on(client.fetch_xml(&news_url, Some(&recorder))).unwrap_err();
+    assert_eq!(Some("503".to_string()), error.status);
+
+    let recorder = Recorder::new(&dir, Mode::Strict);
+    assert_eq!(
+      "<TheatreAreas />",
+      block_on(client.fetch_xml(&areas_url, Some(&recorder))).unwrap()
+    );
+    assert_eq!(
+      error,
+      block_on(client.fetch_xml(&news_url, Some(&recorder))).unwrap_err()
+    );
+    assert_eq!(2, requests(&client).len());
+    let recorded = recorder.replay(&areas_url).unwrap().unwrap();
+    assert!(recorded
+      .headers
+      .iter()
+      .all(|(name, _)| !name.eq_ignore_ascii_case("set-cookie")));
+    let unrecorded = format!("{}/Events/", BASE_URL);
+    assert_eq!(
+      Some("Unrecorded request".to_string()),
+      block_on(client.fetch_xml(&unrecorded, Some(&recorder)))
+        .unwrap_err()
+        .title
+    );
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_fetch_shows() {
+    let body = r#"<?xml version="1.0"?>
+    <Schedule>
+      <PubDate>2022-08-20T12:00:00</PubDate>
+      <Shows>
+        <Show>
+          <ID>1</ID>
+          <dttmShowStart>2022-08-20T16:00:00</dttmShowStart>
+          <EventID>303000</EventID>
+        </Show>
+        <Show>
+          <ID>2</ID>
+          <dttmShowStart>2022-08-20T20:00:00</dttmShowStart>
+          <EventID>303000</EventID>
+        </Show>
+      </Shows>
+    </Schedule>"#;
+    let client = FeedClient::new(Canned::default().with("/Schedule/", 200, body));
+
+    let filter = ShowFilter::from_query("area=1014&date=2022-08-20&after=18:00").unwrap();
+    let shows = block_on(client.fetch_shows(BASE_URL, &filter)).unwrap();
+    let ids: Vec<u64> = shows.iter().map(|show| show.id.0).collect();
+
+    assert_eq!(ids, vec![2]);
+    assert_eq!(vec![filter.schedule_url(BASE_URL)], requests(&client));
+  }
+
+  #[test]
+  fn test_fetch_schedule_dates() {
+    let body = r#"<?xml version="1.0"?>
+    <Dates>
+      <dateTime>2022-08-20T00:00:00</dateTime>
+      <dateTime>2022-08-21T00:00:00</dateTime>
+    </Dates>"#;
+    let client = FeedClient::new(Canned::default().with("/ScheduleDates/", 200, body));
+
+    let dates = block_on(client.fetch_schedule_dates(BASE_URL, Some(AreaId(1038)))).unwrap();
+    let days: Vec<String> = dates.iter().map(|date| date.to_rfc3339()).collect();
+
+    assert_eq!(
+      days,
+      vec!["2022-08-20T00:00:00+03:00", "2022-08-21T00:00:00+03:00"]
+    );
+    assert_eq!(
+      vec![schedule_dates_url(BASE_URL, Some(AreaId(1038)))],
+      requests(&client)
+    );
+  }
+
+  #[test]
+  fn test_fetch_event_is_cached() {
+    let body = r#"<?xml version="1.0"?>
+    <Events>
+      <Event>
+        <ID>303000</ID>
+        <Title>Minions: The Rise of Gru</Title>
+      </Event>
+    </Events>"#;
+    let client = FeedClient::new(Canned::default().with("/Events/", 200, body));
+
+    let event = block_on(client.fetch_event(BASE_URL, EventId(303000))).unwrap();
+    let cached_event = block_on(client.fetch_event(BASE_URL, EventId(303000))).unwrap();
+
+    assert_eq!(event.title, "Minions: The Rise of Gru");
+    assert_eq!(event, cached_event);
+    assert_eq!(1, requests(&client).len());
+  }
+
+  #[test]
+  fn test_uncached_fetch_skips_the_cache() {
+    let body = r#"<?xml version="1.0"?>
+    <Events>
+      <Event>
+        <ID>304000</ID>
+        <Title>Elvis</Title>
+      </Event>
+    </Events>"#;
+    let client = FeedClient::new(Canned::default().with("/Events/", 200, body));
+
+    for _ in 0..2 {
+      block_on(uncached(client.fetch_event(BASE_URL, EventId(304000)))).unwrap();
+    }
+    let cached_event = block_on(client.fetch_event(BASE_URL, EventId(304000))).unwrap();
+
+    assert_eq!(cached_event.title, "Elvis");
+    assert_eq!(2, requests(&client).len());
+  }
+
+  #[test]
+  fn test_fetch_event_not_found() {
+    let client = FeedClient::new(Canned::default().with("/Events/", 200, "<Events></Events>"));
+
+    let error = block_on(client.fetch_event(BASE_URL, EventId(1))).unwrap_err();
+
+    assert_eq!(error.status, Some("404".to_string()));
+  }
+
+  #[test]
+  fn test_fetch_show() {
+    let body = r#"<?xml version="1.0"?>
+    <Schedule>
+      <PubDate>2022-08-20T12:00:00</PubDate>
+      <Shows>
+        <Show>
+          <ID>1</ID>
+          <dttmShowStart>2022-08-20T16:00:00</dttmShowStart>
+        </Show>
+        <Show>
+          <ID>2</ID>
+          <dttmShowStart>2022-08-21T20:00:00</dttmShowStart>
+        </Show>
+      </Shows>
+    </Schedule>"#;
+    let client = FeedClient::new(Canned::default().with("/Schedule/", 200, body));
+
+    let show = block_on(client.fetch_show(BASE_URL, ShowId(2))).unwrap();
+    let error = block_on(client.fetch_show(BASE_URL, ShowId(3))).unwrap_err();
+
+    assert_eq!(show.id, ShowId(2));
+    assert_eq!(error.status, Some("404".to_string()));
+    assert!(requests(&client)[0].contains("nrOfDays=14"));
+  }
+
+  #[test]
+  fn test_fetch_news() {
+    let body = r#"<?xml version="1.0"?>
+    <News>
+      <NewsArticle>
+        <Title>Syksyn ensi-illat</Title>
+        <PublishDate>2022-08-19T09:00:00</PublishDate>
+        <ArticleURL>http://www.finnkino.fi/news/syksy/</ArticleURL>
+      </NewsArticle>
+    </News>"#;
+    let client = FeedClient::new(Canned::default().with("/News/", 200, body));
+
+    let articles = block_on(client.fetch_news(BASE_URL)).unwrap();
+
+    assert_eq!(articles.len(), 1);
+    assert_eq!(articles[0].title, "Syksyn ensi-illat");
+  }
+
+  #[test]
+  fn test_fixtures_are_served_without_a_request() {
+    let dir = std::env::temp_dir().join(format!(
+      "finnkino-transport-fixtures-{}",
+      std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let client = FeedClient::new(Canned::default());
+    client.use_fixtures(Fixtures::new(&dir));
+
+    let _ = block_on(client.get_areas());
+
+    assert!(requests(&client).is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn test_use_upstream() {
+    let client = FeedClient::new(Canned::default());
+    assert_eq!(BASE_URL, client.base_url());
+
+    client.use_upstream("http://localhost:8080/xml".to_string());
+    client.use_upstream("http://localhost:9090/xml".to_string());
+
+    assert_eq!("http://localhost:8080/xml", client.base_url());
+  }
+}
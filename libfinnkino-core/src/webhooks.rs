@@ -0,0 +1,843 @@
+//! Outgoing webhooks. A registration watches the Schedule of an area and is
+//! notified when a film coming soon gets its first shows there, and when new
+//! shows are added for the films it watches. Payloads are signed with the
+//! registration's secret, failed deliveries are retried with exponential
+//! backoff and every attempt is kept in a delivery log.
+
+use chrono::NaiveDate;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::Duration;
+use std::vec::Vec;
+use url::{Host, Url};
+
+use crate::api::v1;
+use crate::finnkino::Show;
+use crate::id::{AreaId, EventId, WebhookId};
+use crate::json;
+use crate::schedule::{self, ShowFilter};
+use crate::time::{now, LocalDateTime};
+use crate::transport::Transport;
+
+pub const CONTENT_TYPE: &str = "application/json";
+pub const SIGNATURE_HEADER: &str = "X-Finnkino-Signature";
+pub const TRIGGER_HEADER: &str = "X-Finnkino-Event";
+pub const DELIVERY_HEADER: &str = "X-Finnkino-Delivery";
+pub const POLL_INTERVAL_SECS: u64 = 60;
+pub const MAX_ATTEMPTS: u32 = 5;
+pub const INITIAL_BACKOFF_SECS: u64 = 2;
+pub const LOG_SIZE: usize = 100;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The shows of an area watched for webhooks.
+pub fn filter(area: AreaId, today: NaiveDate) -> ShowFilter {
+  ShowFilter {
+    area: Some(area),
+    ..ShowFilter::from_today(today)
+  }
+}
+
+pub fn not_found(id: WebhookId) -> json::Errors {
+  json::Errors {
+    errors: vec![json::ErrorBuilder::default()
+      .status("404")
+      .title("Not Found")
+      .detail(format!("No webhook with id {}", id))
+      .build()
+      .unwrap()],
+  }
+}
+
+/// The `sha256=<hex>` HMAC of a payload, sent in [`SIGNATURE_HEADER`].
+pub fn sign(secret: &str, body: &str) -> String {
+  let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+  mac.update(body.as_bytes());
+  format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Checks a signature in constant time, as a receiver should.
+pub fn verify(secret: &str, body: &str, signature: &str) -> bool {
+  let bytes = match signature.strip_prefix("sha256=").map(hex::decode) {
+    Some(Ok(bytes)) => bytes,
+    _ => return false,
+  };
+  let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+  mac.update(body.as_bytes());
+  mac.verify_slice(&bytes).is_ok()
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize)]
+pub enum Trigger {
+  /// A film coming soon has its first shows in the area.
+  #[serde(rename = "event.scheduled")]
+  EventScheduled,
+  /// New shows of a watched film were added in the area.
+  #[serde(rename = "shows.added")]
+  ShowsAdded,
+}
+
+impl Trigger {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Trigger::EventScheduled => "event.scheduled",
+      Trigger::ShowsAdded => "shows.added",
+    }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RegistrationRequest {
+  pub url: String,
+  pub secret: String,
+  pub area: AreaId,
+  #[serde(default)]
+  pub events: Vec<EventId>,
+}
+
+impl RegistrationRequest {
+  pub fn from_json(body: &[u8]) -> Result<Self, json::Errors> {
+    let request: RegistrationRequest =
+      serde_json::from_slice(body).map_err(|error| json::Errors {
+        errors: vec![json::Error::invalid_attribute("", error.to_string())],
+      })?;
+    let mut errors = Vec::new();
+    if let Some(detail) = url_error(&request.url) {
+      errors.push(json::Error::invalid_attribute("/url", detail));
+    }
+    if request.secret.is_empty() {
+      errors.push(json::Error::invalid_attribute(
+        "/secret",
+        "Must not be empty",
+      ));
+    }
+    if errors.is_empty() {
+      Ok(request)
+    } else {
+      Err(json::Errors { errors })
+    }
+  }
+}
+
+/// Why payloads can't be sent to `url`, if they can't. Only http and https
+/// URLs are taken, and not to loopback, private, shared or link-local
+/// addresses, so that registrations can't make the server POST into its own
+/// network. Names are resolved and checked again on every delivery.
+fn url_error(url: &str) -> Option<String> {
+  let host = Url::parse(url)
+    .ok()
+    .filter(|url| matches!(url.scheme(), "http" | "https"))
+    .and_then(|url| url.host().map(|host| host.to_owned()));
+  let internal = match host {
+    None => return Some(format!("'{}' is not an http or https URL", url)),
+    Some(Host::Domain(domain)) => {
+      let domain = domain.trim_end_matches('.');
+      domain == "localhost" || domain.ends_with(".localhost")
+    }
+    Some(Host::Ipv4(ip)) => is_internal(ip.into()),
+    Some(Host::Ipv6(ip)) => is_internal(ip.into()),
+  };
+  internal.then(|| format!("'{}' is not a public address", url))
+}
+
+fn is_internal(ip: IpAddr) -> bool {
+  match ip {
+    IpAddr::V4(ip) => is_internal_v4(ip),
+    IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+      None => is_internal_v6(ip),
+      Some(ip) => is_internal_v4(ip),
+    },
+  }
+}
+
+fn is_internal_v4(ip: Ipv4Addr) -> bool {
+  let [first, second, ..] = ip.octets();
+  ip.is_loopback()
+    || ip.is_private()
+    || ip.is_link_local()
+    || ip.is_unspecified()
+    || ip.is_broadcast()
+    // The shared address space of carrier-grade NAT, 100.64.0.0/10.
+    || (first == 100 && second & 0xc0 == 64)
+}
+
+fn is_internal_v6(ip: Ipv6Addr) -> bool {
+  let segments = ip.segments();
+  ip.is_loopback()
+    || ip.is_unique_local()
+    || ip.is_unicast_link_local()
+    || ip.is_unspecified()
+    // The deprecated site-local fec0::/10.
+    || segments[0] & 0xffc0 == 0xfec0
+    // NAT64, 64:ff9b::/96 and the local-use 64:ff9b:1::/48, which reach
+    // IPv4 addresses through a translator.
+    || segments[..2] == [0x64, 0xff9b]
+}
+
+/// The address to deliver to `url` at: its host resolved now, so that a name
+/// registered with a public address can't be pointed into the network later.
+async fn resolve(url: &str) -> Result<SocketAddr, String> {
+  let url = Url::parse(url).map_err(|error| error.to_string())?;
+  let host = match url.host() {
+    None => return Err(format!("'{}' has no host", url)),
+    Some(Host::Domain(domain)) => domain.to_string(),
+    Some(Host::Ipv4(ip)) => ip.to_string(),
+    Some(Host::Ipv6(ip)) => ip.to_string(),
+  };
+  let port = url.port_or_known_default().unwrap_or(80);
+  let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), port))
+    .await
+    .map_err(|error| format!("'{}' could not be resolved: {}", host, error))?
+    .collect();
+  if addrs.iter().any(|addr| is_internal(addr.ip())) {
+    return Err(format!("'{}' resolves to an internal address", host));
+  }
+  addrs
+    .into_iter()
+    .next()
+    .ok_or_else(|| format!("'{}' could not be resolved", host))
+}
+
+/// A registered webhook. The secret is never sent back.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Registration {
+  pub id: WebhookId,
+  pub url: String,
+  #[serde(skip)]
+  pub secret: String,
+  pub area: AreaId,
+  pub events: Vec<EventId>,
+  pub created_at: LocalDateTime,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Payload {
+  pub id: u64,
+  #[serde(rename = "type")]
+  pub trigger: Trigger,
+  pub area: AreaId,
+  pub event: EventId,
+  pub title: String,
+  pub shows: Vec<v1::Show>,
+  pub created_at: LocalDateTime,
+}
+
+/// A payload ready to be POSTed to a registration.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Outgoing {
+  pub delivery: u64,
+  pub registration: WebhookId,
+  pub trigger: Trigger,
+  pub url: String,
+  pub body: String,
+  pub signature: String,
+}
+
+impl Outgoing {
+  pub fn headers(&self) -> Vec<(&'static str, String)> {
+    vec![
+      ("Content-Type", CONTENT_TYPE.to_string()),
+      (SIGNATURE_HEADER, self.signature.clone()),
+      (TRIGGER_HEADER, self.trigger.as_str().to_string()),
+      (DELIVERY_HEADER, self.delivery.to_string()),
+    ]
+  }
+}
+
+/// One attempt to deliver a payload.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Delivery {
+  pub id: u64,
+  #[serde(rename = "type")]
+  pub trigger: Trigger,
+  pub attempt: u32,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub status: Option<u16>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+  pub delivered: bool,
+  pub at: LocalDateTime,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+  pub max_attempts: u32,
+  pub initial_backoff: Duration,
+}
+
+impl RetryPolicy {
+  /// How long to wait after a failed attempt, doubling every time. `None`
+  /// once the attempts are used up.
+  pub fn backoff(&self, attempt: u32) -> Option<Duration> {
+    if attempt >= self.max_attempts {
+      return None;
+    }
+    Some(self.initial_backoff * 2u32.saturating_pow(attempt.saturating_sub(1)))
+  }
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    RetryPolicy {
+      max_attempts: MAX_ATTEMPTS,
+      initial_backoff: Duration::from_secs(INITIAL_BACKOFF_SECS),
+    }
+  }
+}
+
+#[derive(Debug, Default)]
+struct Watcher {
+  shows: Option<Vec<Show>>,
+  scheduled: HashSet<EventId>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+  next_id: u64,
+  next_delivery: u64,
+  registrations: BTreeMap<WebhookId, Registration>,
+  watchers: HashMap<AreaId, Watcher>,
+  deliveries: HashMap<WebhookId, VecDeque<Delivery>>,
+}
+
+#[derive(Debug)]
+pub struct Webhooks {
+  retry: RetryPolicy,
+  log_size: usize,
+  state: Mutex<State>,
+}
+
+impl Webhooks {
+  pub fn new(retry: RetryPolicy, log_size: usize) -> Self {
+    Webhooks {
+      retry,
+      log_size,
+      state: Mutex::new(State::default()),
+    }
+  }
+
+  pub fn retry(&self) -> RetryPolicy {
+    self.retry
+  }
+
+  pub fn register(&self, request: RegistrationRequest, now: LocalDateTime) -> Registration {
+    let mut state = self.state.lock().unwrap();
+    state.next_id += 1;
+    let registration = Registration {
+      id: WebhookId(state.next_id),
+      url: request.url,
+      secret: request.secret,
+      area: request.area,
+      events: request.events,
+      created_at: now,
+    };
+    state
+      .registrations
+      .insert(registration.id, registration.clone());
+    state.deliveries.insert(registration.id, VecDeque::new());
+    registration
+  }
+
+  pub fn registrations(&self) -> Vec<Registration> {
+    let state = self.state.lock().unwrap();
+    state.registrations.values().cloned().collect()
+  }
+
+  pub fn registration(&self, id: WebhookId) -> Option<Registration> {
+    let state = self.state.lock().unwrap();
+    state.registrations.get(&id).cloned()
+  }
+
+  pub fn unregister(&self, id: WebhookId) -> bool {
+    let mut state = self.state.lock().unwrap();
+    state.deliveries.remove(&id);
+    state.registrations.remove(&id).is_some()
+  }
+
+  /// The delivery log of a registration, the latest attempt first.
+  pub fn deliveries(&self, id: WebhookId) -> Option<Vec<Delivery>> {
+    let state = self.state.lock().unwrap();
+    let deliveries = state.deliveries.get(&id)?;
+    Some(deliveries.iter().rev().cloned().collect())
+  }
+
+  /// The areas to poll. Areas without registrations are forgotten.
+  pub fn areas(&self) -> Vec<AreaId> {
+    let mut state = self.state.lock().unwrap();
+    let areas: HashSet<AreaId> = state
+      .registrations
+      .values()
+      .map(|registration| registration.area)
+      .collect();
+    state.watchers.retain(|area, _| areas.contains(area));
+    let mut areas: Vec<AreaId> = areas.into_iter().collect();
+    areas.sort_by_key(|area| area.0);
+    areas
+  }
+
+  /// Records the current Schedule of an area and returns the payloads to
+  /// deliver. `coming_soon` are the films currently coming soon. The first
+  /// Schedule of an area is only recorded.
+  pub fn update(
+    &self,
+    area: AreaId,
+    shows: Vec<Show>,
+    coming_soon: &[EventId],
+    now: LocalDateTime,
+  ) -> Vec<Outgoing> {
+    let mut state = self.state.lock().unwrap();
+    let State {
+      next_delivery,
+      registrations,
+      watchers,
+      ..
+    } = &mut *state;
+    let watcher = watchers.entry(area).or_default();
+    let previous = watcher.shows.replace(shows);
+    let current = watcher.shows.as_deref().unwrap_or_default();
+    let mut by_event: BTreeMap<EventId, Vec<&Show>> = BTreeMap::new();
+    for show in current {
+      by_event.entry(show.event_id).or_default().push(show);
+    }
+    let newly_scheduled: Vec<EventId> = coming_soon
+      .iter()
+      .filter(|event| by_event.contains_key(event) && !watcher.scheduled.contains(event))
+      .copied()
+      .collect();
+    watcher.scheduled.extend(newly_scheduled.iter().copied());
+    let previous = match previous {
+      None => return Vec::new(),
+      Some(previous) => previous,
+    };
+    let mut added: BTreeMap<EventId, Vec<Show>> = BTreeMap::new();
+    for show in schedule::diff(&previous, current).added {
+      added.entry(show.event_id).or_default().push(show);
+    }
+    let mut notifications: Vec<(Trigger, EventId, Vec<Show>)> = newly_scheduled
+      .iter()
+      .map(|event| {
+        let shows = by_event[event].iter().map(|show| (*show).clone()).collect();
+        (Trigger::EventScheduled, *event, shows)
+      })
+      .collect();
+    notifications.extend(
+      added
+        .into_iter()
+        .filter(|(event, _)| !newly_scheduled.contains(event))
+        .map(|(event, shows)| (Trigger::ShowsAdded, event, shows)),
+    );
+
+    let recipients: Vec<&Registration> = registrations
+      .values()
+      .filter(|registration| registration.area == area)
+      .collect();
+    let mut outgoing = Vec::new();
+    for (trigger, event, shows) in notifications {
+      for registration in &recipients {
+        if trigger == Trigger::ShowsAdded && !registration.events.contains(&event) {
+          continue;
+        }
+        *next_delivery += 1;
+        let payload = Payload {
+          id: *next_delivery,
+          trigger,
+          area,
+          event,
+          title: shows[0].title.clone(),
+          shows: shows.iter().cloned().map(v1::Show::from).collect(),
+          created_at: now,
+        };
+        let body = serde_json::to_string(&payload).unwrap();
+        outgoing.push(Outgoing {
+          delivery: payload.id,
+          registration: registration.id,
+          trigger,
+          url: registration.url.clone(),
+          signature: sign(&registration.secret, &body),
+          body,
+        });
+      }
+    }
+    outgoing
+  }
+
+  /// Logs an attempt to deliver a payload, with the status of the response
+  /// or why there was none. Returns whether the payload was delivered.
+  pub fn record(
+    &self,
+    outgoing: &Outgoing,
+    attempt: u32,
+    result: Result<u16, String>,
+    now: LocalDateTime,
+  ) -> bool {
+    let delivered = matches!(result, Ok(status) if (200..300).contains(&status));
+    let (status, error) = match result {
+      Ok(status) => (Some(status), None),
+      Err(error) => (None, Some(error)),
+    };
+    let mut state = self.state.lock().unwrap();
+    if let Some(deliveries) = state.deliveries.get_mut(&outgoing.registration) {
+      deliveries.push_back(Delivery {
+        id: outgoing.delivery,
+        trigger: outgoing.trigger,
+        attempt,
+        status,
+        error,
+        delivered,
+        at: now,
+      });
+      while deliveries.len() > self.log_size {
+        deliveries.pop_front();
+      }
+    }
+    delivered
+  }
+}
+
+impl Default for Webhooks {
+  fn default() -> Self {
+    Webhooks::new(RetryPolicy::default(), LOG_SIZE)
+  }
+}
+
+/// POSTs a payload over `transport` until it is accepted with a 2xx, the
+/// attempts of the retry policy are used up or the webhook is deleted,
+/// logging every attempt. An attempt fails without a request when the host
+/// resolves to an internal address.
+pub async fn deliver<T: Transport>(transport: &T, webhooks: &Webhooks, outgoing: Outgoing) {
+  let mut attempt = 1;
+  loop {
+    let result = match resolve(&outgoing.url).await {
+      Err(error) => Err(error),
+      Ok(addr) => transport.post(&outgoing, addr).await,
+    };
+    if webhooks.record(&outgoing, attempt, result, now()) {
+      return;
+    }
+    match webhooks.retry().backoff(attempt) {
+      None => return,
+      Some(delay) => tokio::time::sleep(delay).await,
+    }
+    if webhooks.registration(outgoing.registration).is_none() {
+      return;
+    }
+    attempt += 1;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::finnkino::Error;
+  use crate::id::ShowId;
+  use crate::recording::Exchange;
+  use pretty_assertions::assert_eq;
+
+  const AREA: AreaId = AreaId(1014);
+  const COMING: EventId = EventId(303000);
+  const WATCHED: EventId = EventId(302000);
+
+  fn show(id: u64, event: EventId) -> Show {
+    Show {
+      id: ShowId(id),
+      event_id: event,
+      title: format!("Film {}", event),
+      ..Show::default()
+    }
+  }
+
+  fn request(events: Vec<EventId>) -> RegistrationRequest {
+    RegistrationRequest {
+      url: "http://localhost:9000/hook".to_string(),
+      secret: "s3cret".to_string(),
+      area: AREA,
+      events,
+    }
+  }
+
+  fn summary(outgoing: &[Outgoing]) -> Vec<(WebhookId, &'static str, String)> {
+    outgoing
+      .iter()
+      .map(|outgoing| {
+        let payload: serde_json::Value = serde_json::from_str(&outgoing.body).unwrap();
+        let shows = payload["shows"]
+          .as_array()
+          .unwrap()
+          .iter()
+          .map(|show| show["id"].as_str().unwrap())
+          .collect::<Vec<_>>()
+          .join(",");
+        (outgoing.registration, outgoing.trigger.as_str(), shows)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn test_sign_and_verify() {
+    let signature = sign("s3cret", "{}");
+
+    assert!(signature.starts_with("sha256="));
+    assert!(verify("s3cret", "{}", &signature));
+    assert!(!verify("other", "{}", &signature));
+    assert!(!verify("s3cret", "[]", &signature));
+    assert!(!verify("s3cret", "{}", "sha256=zz"));
+  }
+
+  #[test]
+  fn test_coming_soon_event_gets_first_shows() {
+    let webhooks = Webhooks::default();
+    let watching = webhooks.register(request(vec![]), LocalDateTime::default());
+    let now = LocalDateTime::default();
+
+    assert!(webhooks
+      .update(AREA, vec![show(1, WATCHED)], &[COMING], now)
+      .is_empty());
+    let outgoing = webhooks.update(
+      AREA,
+      vec![show(1, WATCHED), show(2, COMING), show(3, COMING)],
+      &[COMING],
+      now,
+    );
+    assert_eq!(
+      vec![(watching.id, "event.scheduled", "2,3".to_string())],
+      summary(&outgoing)
+    );
+    assert!(webhooks
+      .update(
+        AREA,
+        vec![show(1, WATCHED), show(4, COMING)],
+        &[COMING],
+        now
+      )
+      .is_empty());
+  }
+
+  #[test]
+  fn test_new_shows_of_watched_event() {
+    let webhooks = Webhooks::default();
+    let watching = webhooks.register(request(vec![WATCHED]), LocalDateTime::default());
+    webhooks.register(request(vec![]), LocalDateTime::default());
+    let now = LocalDateTime::default();
+    webhooks.update(AREA, vec![show(1, WATCHED)], &[], now);
+    let outgoing = webhooks.update(
+      AREA,
+      vec![show(1, WATCHED), show(2, WATCHED), show(3, COMING)],
+      &[],
+      now,
+    );
+
+    assert_eq!(
+      vec![(watching.id, "shows.added", "2".to_string())],
+      summary(&outgoing)
+    );
+    assert_eq!(
+      sign("s3cret", &outgoing[0].body),
+      outgoing[0].headers()[1].1
+    );
+  }
+
+  #[test]
+  fn test_delivery_log() {
+    let webhooks = Webhooks::new(RetryPolicy::default(), 2);
+    let registration = webhooks.register(request(vec![WATCHED]), LocalDateTime::default());
+    let now = LocalDateTime::default();
+    webhooks.update(AREA, vec![], &[], now);
+    let outgoing = webhooks.update(AREA, vec![show(1, WATCHED)], &[], now);
+
+    assert!(!webhooks.record(&outgoing[0], 1, Err("refused".to_string()), now));
+    assert!(!webhooks.record(&outgoing[0], 2, Ok(500), now));
+    assert!(webhooks.record(&outgoing[0], 3, Ok(204), now));
+    let log = webhooks.deliveries(registration.id).unwrap();
+    assert_eq!(
+      vec![(3, Some(204), true), (2, Some(500), false)],
+      log
+        .iter()
+        .map(|delivery| (delivery.attempt, delivery.status, delivery.delivered))
+        .collect::<Vec<_>>()
+    );
+    assert!(webhooks.unregister(registration.id));
+    assert_eq!(None, webhooks.deliveries(registration.id));
+    assert!(webhooks.areas().is_empty());
+  }
+
+  #[test]
+  fn test_backoff_doubles() {
+    let retry = RetryPolicy::default();
+
+    assert_eq!(Some(Duration::from_secs(2)), retry.backoff(1));
+    assert_eq!(Some(Duration::from_secs(16)), retry.backoff(4));
+    assert_eq!(None, retry.backoff(MAX_ATTEMPTS));
+  }
+
+  #[test]
+  fn test_registration_request() {
+    let request = RegistrationRequest::from_json(
+      br#"{"url":"https://example.com/hook","secret":"s3cret","area":"1014","events":["302000"]}"#,
+    )
+    .unwrap();
+    assert_eq!(vec![WATCHED], request.events);
+
+    let errors = RegistrationRequest::from_json(br#"{"url":"ftp://x","secret":"","area":"1014"}"#)
+      .unwrap_err();
+    let pointers: Vec<_> = errors
+      .errors
+      .iter()
+      .map(|error| error.source.as_ref().unwrap().pointer.as_deref().unwrap())
+      .collect();
+    assert_eq!(vec!["/url", "/secret"], pointers);
+    assert!(RegistrationRequest::from_json(b"{}").is_err());
+  }
+
+  #[test]
+  fn test_registration_request_rejects_internal_addresses() {
+    let urls = [
+      "http://localhost:9000/hook",
+      "http://api.localhost/hook",
+      "http://127.0.0.1/hook",
+      "http://2130706433/hook",
+      "https://10.1.2.3/hook",
+      "http://172.16.0.1/hook",
+      "http://192.168.1.1/hook",
+      "http://169.254.169.254/latest/meta-data",
+      "http://0.0.0.0/hook",
+      "http://[::1]/hook",
+      "http://[fd00::1]/hook",
+      "http://[fe80::1]/hook",
+      "http://[::ffff:127.0.0.1]/hook",
+      "http://100.64.0.1/hook",
+      "http://100.127.255.254/hook",
+      "http://[fec0::1]/hook",
+      "http://[64:ff9b::a00:1]/hook",
+      "http://[64:ff9b:1::1]/hook",
+    ];
+    for url in urls {
+      let body = format!(r#"{{"url":"{}","secret":"s3cret","area":"1014"}}"#, url);
+      let errors = RegistrationRequest::from_json(body.as_bytes()).unwrap_err();
+      let detail = errors.errors[0].detail.as_deref().unwrap();
+      assert!(detail.ends_with("is not a public address"), "{}", url);
+    }
+    let body = br#"{"url":"http://93.184.216.34:8080/hook","secret":"s3cret","area":"1014"}"#;
+    assert!(RegistrationRequest::from_json(body).is_ok());
+  }
+
+  /// Answers the POSTs with `statuses` in turn, and keeps them.
+  struct Receiver {
+    statuses: Mutex<VecDeque<u16>>,
+    received: Mutex<Vec<SocketAddr>>,
+  }
+
+  impl Receiver {
+    fn new(statuses: Vec<u16>) -> Self {
+      Receiver {
+        statuses: Mutex::new(statuses.into()),
+        received: Mutex::new(vec![]),
+      }
+    }
+
+    fn received(&self) -> Vec<SocketAddr> {
+      self.received.lock().unwrap().clone()
+    }
+  }
+
+  impl Transport for Receiver {
+    async fn send(&self, _url: &str) -> Result<Exchange, Error> {
+      unimplemented!()
+    }
+
+    async fn post(&self, _outgoing: &Outgoing, addr: SocketAddr) -> Result<u16, String> {
+      self.received.lock().unwrap().push(addr);
+      let status = self.statuses.lock().unwrap().pop_front();
+      status.ok_or_else(|| "Connection refused".to_string())
+    }
+  }
+
+  fn outgoing(webhooks: &Webhooks, url: &str) -> Outgoing {
+    let request = RegistrationRequest {
+      url: url.to_string(),
+      ..request(vec![WATCHED])
+    };
+    webhooks.register(request, LocalDateTime::default());
+    webhooks.update(AREA, vec![], &[], LocalDateTime::default());
+    webhooks
+      .update(AREA, vec![show(1, WATCHED)], &[], LocalDateTime::default())
+      .remove(0)
+  }
+
+  const PUBLIC_URL: &str = "http://93.184.216.34:8080/hook";
+
+  fn retry(max_attempts: u32, initial_backoff: Duration) -> RetryPolicy {
+    RetryPolicy {
+      max_attempts,
+      initial_backoff,
+    }
+  }
+
+  #[tokio::test]
+  async fn test_deliver_retries_until_accepted() {
+    let receiver = Receiver::new(vec![503, 204]);
+    let webhooks = Webhooks::new(retry(3, Duration::ZERO), 10);
+    let outgoing = outgoing(&webhooks, PUBLIC_URL);
+
+    deliver(&receiver, &webhooks, outgoing.clone()).await;
+
+    let addr = SocketAddr::from(([93, 184, 216, 34], 8080));
+    assert_eq!(vec![addr, addr], receiver.received());
+    let log: Vec<_> = webhooks
+      .deliveries(outgoing.registration)
+      .unwrap()
+      .into_iter()
+      .map(|delivery| (delivery.attempt, delivery.status, delivery.delivered))
+      .collect();
+    assert_eq!(vec![(2, Some(204), true), (1, Some(503), false)], log);
+  }
+
+  #[tokio::test]
+  async fn test_deliver_gives_up() {
+    let receiver = Receiver::new(vec![500]);
+    let webhooks = Webhooks::new(retry(2, Duration::ZERO), 10);
+    let outgoing = outgoing(&webhooks, PUBLIC_URL);
+
+    deliver(&receiver, &webhooks, outgoing.clone()).await;
+
+    let log = webhooks.deliveries(outgoing.registration).unwrap();
+    assert_eq!(2, receiver.received().len());
+    assert_eq!(Some("Connection refused".to_string()), log[0].error);
+    assert!(log.iter().all(|delivery| !delivery.delivered));
+  }
+
+  #[tokio::test]
+  async fn test_deliver_stops_when_unregistered() {
+    let receiver = Receiver::new(vec![500, 500, 500]);
+    let webhooks = Webhooks::new(retry(3, Duration::from_millis(200)), 10);
+    let outgoing = outgoing(&webhooks, PUBLIC_URL);
+    let unregister = async {
+      tokio::time::sleep(Duration::from_millis(100)).await;
+      webhooks.unregister(outgoing.registration);
+    };
+
+    tokio::join!(deliver(&receiver, &webhooks, outgoing.clone()), unregister);
+
+    assert_eq!(1, receiver.received().len());
+  }
+
+  #[tokio::test]
+  async fn test_deliver_refuses_names_of_internal_addresses() {
+    let receiver = Receiver::new(vec![204]);
+    let webhooks = Webhooks::new(retry(1, Duration::ZERO), 10);
+    let outgoing = outgoing(&webhooks, "http://localhost:9000/hook");
+
+    deliver(&receiver, &webhooks, outgoing.clone()).await;
+
+    let log = webhooks.deliveries(outgoing.registration).unwrap();
+    assert!(receiver.received().is_empty());
+    assert_eq!(
+      Some("'localhost' resolves to an internal address".to_string()),
+      log[0].error
+    );
+  }
+}
@@ -0,0 +1,484 @@
+//! Finnkino's XML feeds written from the models with `quick_xml::se`, the
+//! inverse of [`from_xml`](crate::finnkino::from_xml). Elements the models do
+//! not read are left out, except for a few that make the feeds look like
+//! Finnkino's.
+//!
+//! The feeds are serialized from borrowed mirrors of the models because the
+//! models' own `Serialize` is the JSON of the API, with other names and
+//! shapes. Values are wrapped in [`Text`] since quick-xml writes bare
+//! primitives as attributes.
+
+use serde::Serialize;
+
+use crate::finnkino::{Event, Language, Person, Show, TheatreArea};
+use crate::time::{LocalDateTime, FINNKINO_DATETIME_FORMAT};
+
+const DECLARATION: &str = r#"<?xml version="1.0"?>"#;
+const XSD: &str = "http://www.w3.org/2001/XMLSchema";
+const XSI: &str = "http://www.w3.org/2001/XMLSchema-instance";
+
+/// The text content of an element.
+#[derive(Serialize)]
+struct Text<T> {
+  #[serde(rename = "$value")]
+  value: T,
+}
+
+fn text<T>(value: T) -> Text<T> {
+  Text { value }
+}
+
+fn datetime(value: &LocalDateTime) -> Text<String> {
+  text(value.format(FINNKINO_DATETIME_FORMAT).to_string())
+}
+
+fn to_xml<T: Serialize>(feed: &T) -> String {
+  let xml = quick_xml::se::to_string(feed).unwrap();
+  format!("{}\n{}", DECLARATION, xml)
+}
+
+#[derive(Serialize)]
+#[serde(rename = "TheatreAreas")]
+struct TheatreAreasXml<'a> {
+  #[serde(rename = "xmlns:xsd")]
+  xsd: &'static str,
+  #[serde(rename = "xmlns:xsi")]
+  xsi: &'static str,
+  #[serde(rename = "TheatreArea")]
+  areas: Vec<TheatreAreaXml<'a>>,
+}
+
+#[derive(Serialize)]
+struct TheatreAreaXml<'a> {
+  #[serde(rename = "ID")]
+  id: Text<u64>,
+  #[serde(rename = "Name")]
+  name: Text<&'a str>,
+}
+
+#[derive(Serialize)]
+#[serde(rename = "Dates")]
+struct DatesXml {
+  #[serde(rename = "xmlns:xsd")]
+  xsd: &'static str,
+  #[serde(rename = "xmlns:xsi")]
+  xsi: &'static str,
+  #[serde(rename = "dateTime")]
+  dates: Vec<Text<String>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename = "Schedule")]
+struct ScheduleXml<'a> {
+  #[serde(rename = "xmlns:xsd")]
+  xsd: &'static str,
+  #[serde(rename = "xmlns:xsi")]
+  xsi: &'static str,
+  #[serde(rename = "PubDate")]
+  pub_date: Text<String>,
+  #[serde(rename = "Shows")]
+  shows: ShowsXml<'a>,
+}
+
+#[derive(Serialize)]
+struct ShowsXml<'a> {
+  #[serde(rename = "Show")]
+  shows: Vec<ShowXml<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct ShowXml<'a> {
+  #[serde(rename = "ID")]
+  id: Text<u64>,
+  #[serde(rename = "dtAccounting")]
+  accounting: Text<String>,
+  #[serde(rename = "dttmShowStart")]
+  show_start: Text<String>,
+  #[serde(rename = "dttmShowEnd")]
+  show_end: Text<String>,
+  #[serde(rename = "EventID")]
+  event_id: Text<u64>,
+  title: Text<&'a str>,
+  original_title: Text<&'a str>,
+  production_year: Text<&'a str>,
+  length_in_minutes: Text<&'a str>,
+  #[serde(rename = "dtLocalRelease", skip_serializing_if = "Option::is_none")]
+  local_release: Option<Text<String>>,
+  rating: Text<&'a str>,
+  rating_label: Text<&'a str>,
+  event_type: Text<&'a str>,
+  genres: Text<&'a str>,
+  #[serde(rename = "TheatreID")]
+  theatre_id: Text<u64>,
+  #[serde(rename = "TheatreAuditriumID")]
+  auditorium_id: Text<&'a str>,
+  theatre_and_auditorium: Text<String>,
+  theatre: Text<&'a str>,
+  theatre_auditorium: Text<&'a str>,
+  presentation_method: Text<&'a str>,
+  #[serde(rename = "ShowURL")]
+  show_url: Text<&'a str>,
+  #[serde(rename = "EventURL")]
+  event_url: Text<&'a str>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  spoken_language: Option<LanguageXml<'a>>,
+  #[serde(rename = "SubtitleLanguage1", skip_serializing_if = "Option::is_none")]
+  subtitle_language_1: Option<LanguageXml<'a>>,
+  #[serde(rename = "SubtitleLanguage2", skip_serializing_if = "Option::is_none")]
+  subtitle_language_2: Option<LanguageXml<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct LanguageXml<'a> {
+  name: Text<&'a str>,
+  name_in_language: Text<&'a str>,
+  #[serde(rename = "ISOTwoLetterCode")]
+  iso_two_letter_code: Text<&'a str>,
+}
+
+impl<'a> LanguageXml<'a> {
+  fn new(language: &'a Option<Language>) -> Option<Self> {
+    language.as_ref().map(|language| LanguageXml {
+      name: text(&language.name),
+      name_in_language: text(&language.name_in_language),
+      iso_two_letter_code: text(&language.iso_two_letter_code),
+    })
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename = "Events")]
+struct EventsXml<'a> {
+  #[serde(rename = "xmlns:xsd")]
+  xsd: &'static str,
+  #[serde(rename = "xmlns:xsi")]
+  xsi: &'static str,
+  #[serde(rename = "Event")]
+  events: Vec<EventXml<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct EventXml<'a> {
+  #[serde(rename = "ID")]
+  id: Text<u64>,
+  title: Text<&'a str>,
+  original_title: Text<&'a str>,
+  production_year: Text<&'a str>,
+  length_in_minutes: Text<&'a str>,
+  #[serde(rename = "dtLocalRelease", skip_serializing_if = "Option::is_none")]
+  local_release: Option<Text<String>>,
+  rating: Text<&'a str>,
+  rating_label: Text<&'a str>,
+  local_distributor_name: Text<&'a str>,
+  event_type: Text<&'a str>,
+  genres: Text<&'a str>,
+  short_synopsis: Text<&'a str>,
+  synopsis: Text<&'a str>,
+  #[serde(rename = "EventURL")]
+  event_url: Text<&'a str>,
+  images: ImagesXml<'a>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  cast: Option<CastXml<'a>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  directors: Option<DirectorsXml<'a>>,
+}
+
+#[derive(Serialize)]
+struct ImagesXml<'a> {
+  #[serde(
+    rename = "EventSmallImagePortrait",
+    skip_serializing_if = "Option::is_none"
+  )]
+  small_portrait: Option<Text<&'a str>>,
+  #[serde(
+    rename = "EventMediumImagePortrait",
+    skip_serializing_if = "Option::is_none"
+  )]
+  medium_portrait: Option<Text<&'a str>>,
+  #[serde(
+    rename = "EventLargeImagePortrait",
+    skip_serializing_if = "Option::is_none"
+  )]
+  large_portrait: Option<Text<&'a str>>,
+  #[serde(
+    rename = "EventSmallImageLandscape",
+    skip_serializing_if = "Option::is_none"
+  )]
+  small_landscape: Option<Text<&'a str>>,
+  #[serde(
+    rename = "EventMediumImageLandscape",
+    skip_serializing_if = "Option::is_none"
+  )]
+  medium_landscape: Option<Text<&'a str>>,
+  #[serde(
+    rename = "EventLargeImageLandscape",
+    skip_serializing_if = "Option::is_none"
+  )]
+  large_landscape: Option<Text<&'a str>>,
+}
+
+#[derive(Serialize)]
+struct CastXml<'a> {
+  #[serde(rename = "Actor")]
+  actors: Vec<PersonXml<'a>>,
+}
+
+#[derive(Serialize)]
+struct DirectorsXml<'a> {
+  #[serde(rename = "Director")]
+  directors: Vec<PersonXml<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct PersonXml<'a> {
+  first_name: Text<&'a str>,
+  last_name: Text<&'a str>,
+}
+
+fn people(people: &[Person]) -> Vec<PersonXml<'_>> {
+  people
+    .iter()
+    .map(|person| PersonXml {
+      first_name: text(&person.first_name),
+      last_name: text(&person.last_name),
+    })
+    .collect()
+}
+
+fn image(url: &Option<String>) -> Option<Text<&str>> {
+  url.as_deref().map(text)
+}
+
+/// The TheatreAreas feed.
+pub fn theatre_areas(areas: &[TheatreArea]) -> String {
+  to_xml(&TheatreAreasXml {
+    xsd: XSD,
+    xsi: XSI,
+    areas: areas
+      .iter()
+      .map(|area| TheatreAreaXml {
+        id: text(area.id.0),
+        name: text(&area.name),
+      })
+      .collect(),
+  })
+}
+
+/// The ScheduleDates feed.
+pub fn schedule_dates(dates: &[LocalDateTime]) -> String {
+  to_xml(&DatesXml {
+    xsd: XSD,
+    xsi: XSI,
+    dates: dates.iter().map(datetime).collect(),
+  })
+}
+
+/// The Schedule feed.
+pub fn schedule(pub_date: &LocalDateTime, shows: &[Show]) -> String {
+  let shows = shows
+    .iter()
+    .map(|show| ShowXml {
+      id: text(show.id.0),
+      accounting: text(format!("{}T00:00:00", show.show_start.date_naive())),
+      show_start: datetime(&show.show_start),
+      show_end: datetime(&show.show_end),
+      event_id: text(show.event_id.0),
+      title: text(&show.title),
+      original_title: text(&show.original_title),
+      production_year: text(&show.production_year),
+      length_in_minutes: text(&show.length_in_minutes),
+      local_release: show.local_release.as_ref().map(datetime),
+      rating: text(&show.rating),
+      rating_label: text(&show.rating_label),
+      event_type: text(&show.event_type),
+      genres: text(&show.genres),
+      theatre_id: text(show.theatre_id.0),
+      auditorium_id: text(&show.auditorium_id),
+      theatre_and_auditorium: text(format!("{}, {}", show.theatre, show.auditorium)),
+      theatre: text(&show.theatre),
+      theatre_auditorium: text(&show.auditorium),
+      presentation_method: text(&show.presentation_method),
+      show_url: text(&show.show_url),
+      event_url: text(&show.event_url),
+      spoken_language: LanguageXml::new(&show.spoken_language),
+      subtitle_language_1: LanguageXml::new(&show.subtitle_language_1),
+      subtitle_language_2: LanguageXml::new(&show.subtitle_language_2),
+    })
+    .collect();
+  to_xml(&ScheduleXml {
+    xsd: XSD,
+    xsi: XSI,
+    pub_date: datetime(pub_date),
+    shows: ShowsXml { shows },
+  })
+}
+
+/// The Events feed.
+pub fn events(events: &[Event]) -> String {
+  let events = events
+    .iter()
+    .map(|event| EventXml {
+      id: text(event.id.0),
+      title: text(&event.title),
+      original_title: text(&event.original_title),
+      production_year: text(&event.production_year),
+      length_in_minutes: text(&event.length_in_minutes),
+      local_release: event.local_release.as_ref().map(datetime),
+      rating: text(&event.rating),
+      rating_label: text(&event.rating_label),
+      local_distributor_name: text(&event.local_distributor_name),
+      event_type: text(&event.event_type),
+      genres: text(&event.genres),
+      short_synopsis: text(&event.short_synopsis),
+      synopsis: text(&event.synopsis),
+      event_url: text(&event.event_url),
+      images: ImagesXml {
+        small_portrait: image(&event.images.small_portrait),
+        medium_portrait: image(&event.images.medium_portrait),
+        large_portrait: image(&event.images.large_portrait),
+        small_landscape: image(&event.images.small_landscape),
+        medium_landscape: image(&event.images.medium_landscape),
+        large_landscape: image(&event.images.large_landscape),
+      },
+      cast: (!event.cast.actors.is_empty()).then(|| CastXml {
+        actors: people(&event.cast.actors),
+      }),
+      directors: (!event.directors.directors.is_empty()).then(|| DirectorsXml {
+        directors: people(&event.directors.directors),
+      }),
+    })
+    .collect();
+  to_xml(&EventsXml {
+    xsd: XSD,
+    xsi: XSI,
+    events,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::finnkino::{
+    from_xml, Cast, Directors, Events, Images, Schedule, ScheduleDates, TheatreAreas,
+  };
+  use crate::id::{AreaId, EventId, ShowId};
+  use crate::time::parse_finnkino_datetime;
+  use pretty_assertions::assert_eq;
+
+  fn at(value: &str) -> LocalDateTime {
+    parse_finnkino_datetime(value).unwrap()
+  }
+
+  #[test]
+  fn test_areas_round_trip() {
+    let areas = vec![
+      TheatreArea {
+        id: AreaId(1029),
+        name: "Valitse alue/teatteri".to_string(),
+      },
+      TheatreArea {
+        id: AreaId(1038),
+        name: "Helsinki: TENNISPALATSI & <muut>".to_string(),
+      },
+    ];
+
+    assert_eq!(
+      areas,
+      from_xml::<TheatreAreas>(&theatre_areas(&areas))
+        .unwrap()
+        .theatre_areas
+    );
+  }
+
+  #[test]
+  fn test_dates_round_trip() {
+    let dates = vec![at("2022-08-20T00:00:00"), at("2022-08-21T00:00:00")];
+
+    assert_eq!(
+      concat!(
+        r#"<?xml version="1.0"?>"#,
+        "\n",
+        r#"<Dates xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">"#,
+        "<dateTime>2022-08-20T00:00:00</dateTime><dateTime>2022-08-21T00:00:00</dateTime></Dates>",
+      ),
+      schedule_dates(&dates)
+    );
+    assert_eq!(
+      dates,
+      from_xml::<ScheduleDates>(&schedule_dates(&dates))
+        .unwrap()
+        .dates
+    );
+  }
+
+  #[test]
+  fn test_schedule_round_trips() {
+    let shows = vec![Show {
+      id: ShowId(1794851),
+      show_start: at("2022-08-20T18:30:00"),
+      show_end: at("2022-08-20T20:10:00"),
+      event_id: EventId(303000),
+      title: "Minions: The Rise of Gru".to_string(),
+      local_release: Some(at("2022-07-01T00:00:00")),
+      genres: "Animaatio, Komedia".to_string(),
+      theatre_id: AreaId(1038),
+      theatre: "Tennispalatsi, Helsinki".to_string(),
+      auditorium: "sali 1".to_string(),
+      spoken_language: Some(Language {
+        name: "englanti".to_string(),
+        name_in_language: "English".to_string(),
+        iso_two_letter_code: "EN".to_string(),
+      }),
+      subtitle_language_1: Some(Language {
+        name: "suomi".to_string(),
+        name_in_language: "suomi".to_string(),
+        iso_two_letter_code: "FI".to_string(),
+      }),
+      ..Show::default()
+    }];
+    let pub_date = at("2022-08-20T09:00:00");
+    let parsed = from_xml::<Schedule>(&schedule(&pub_date, &shows)).unwrap();
+
+    assert_eq!(pub_date, parsed.pub_date);
+    assert_eq!(shows, parsed.shows.shows);
+    let empty = from_xml::<Schedule>(&schedule(&pub_date, &[])).unwrap();
+    assert!(empty.shows.shows.is_empty());
+  }
+
+  #[test]
+  fn test_events_round_trip() {
+    let events_ = vec![Event {
+      id: EventId(303000),
+      title: "Minions".to_string(),
+      local_release: Some(at("2022-07-01T00:00:00")),
+      synopsis: "Gru's \"origin\" story".to_string(),
+      images: Images {
+        small_portrait: Some("https://media.finnkino.fi/0.jpg".to_string()),
+        large_landscape: Some("https://media.finnkino.fi/1.jpg".to_string()),
+        ..Images::default()
+      },
+      cast: Cast {
+        actors: vec![Person {
+          first_name: "Steve".to_string(),
+          last_name: "Carell".to_string(),
+        }],
+      },
+      directors: Directors {
+        directors: vec![Person {
+          first_name: "Kyle".to_string(),
+          last_name: "Balda".to_string(),
+        }],
+      },
+      ..Event::default()
+    }];
+
+    assert_eq!(
+      events_,
+      from_xml::<Events>(&events(&events_)).unwrap().events
+    );
+  }
+}
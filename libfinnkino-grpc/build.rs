@@ -0,0 +1,19 @@
+use prost::Message;
+use protobuf::Message as _;
+
+const PROTO: &str = "proto/finnkino.proto";
+
+// The proto files are parsed with the pure Rust parser of rust-protobuf, so
+// building does not need protoc to be installed.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+  println!("cargo:rerun-if-changed={}", PROTO);
+  let descriptors = protobuf_parse::Parser::new()
+    .pure()
+    .include("proto")
+    .input(PROTO)
+    .file_descriptor_set()?;
+  let descriptors =
+    prost_types::FileDescriptorSet::decode(descriptors.write_to_bytes()?.as_slice())?;
+  tonic_prost_build::configure().compile_fds(descriptors)?;
+  Ok(())
+}
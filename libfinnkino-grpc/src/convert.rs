@@ -0,0 +1,130 @@
+//! Conversions from the Finnkino models to the protobuf messages.
+
+use libfinnkino_core::finnkino::{Event, Images, Language, Person, Show, TheatreArea};
+use libfinnkino_core::schedule::ScheduleDiff;
+use libfinnkino_core::time::LocalDateTime;
+
+use crate::proto;
+
+fn to_rfc3339(datetime: Option<LocalDateTime>) -> String {
+  datetime.map(LocalDateTime::to_rfc3339).unwrap_or_default()
+}
+
+impl From<TheatreArea> for proto::Area {
+  fn from(area: TheatreArea) -> Self {
+    proto::Area {
+      id: area.id.0,
+      name: area.name,
+    }
+  }
+}
+
+impl From<&Language> for proto::Language {
+  fn from(language: &Language) -> Self {
+    proto::Language {
+      name: language.name.clone(),
+      name_in_language: language.name_in_language.clone(),
+      iso_two_letter_code: language.iso_two_letter_code.clone(),
+    }
+  }
+}
+
+impl From<Show> for proto::Show {
+  fn from(show: Show) -> Self {
+    let subtitle_languages = show
+      .subtitle_languages()
+      .into_iter()
+      .map(proto::Language::from)
+      .collect();
+    proto::Show {
+      id: show.id.0,
+      event_id: show.event_id.0,
+      show_start: show.show_start.to_rfc3339(),
+      show_end: show.show_end.to_rfc3339(),
+      local_release: to_rfc3339(show.local_release),
+      spoken_language: show.spoken_language.as_ref().map(proto::Language::from),
+      subtitle_languages,
+      title: show.title,
+      original_title: show.original_title,
+      production_year: show.production_year,
+      length_in_minutes: show.length_in_minutes,
+      rating: show.rating,
+      rating_label: show.rating_label,
+      event_type: show.event_type,
+      genres: show.genres,
+      theatre_id: show.theatre_id.0,
+      theatre: show.theatre,
+      auditorium_id: show.auditorium_id,
+      auditorium: show.auditorium,
+      presentation_method: show.presentation_method,
+      show_url: show.show_url,
+      event_url: show.event_url,
+    }
+  }
+}
+
+impl From<Images> for proto::Images {
+  fn from(images: Images) -> Self {
+    proto::Images {
+      small_portrait: images.small_portrait.unwrap_or_default(),
+      medium_portrait: images.medium_portrait.unwrap_or_default(),
+      large_portrait: images.large_portrait.unwrap_or_default(),
+      small_landscape: images.small_landscape.unwrap_or_default(),
+      medium_landscape: images.medium_landscape.unwrap_or_default(),
+      large_landscape: images.large_landscape.unwrap_or_default(),
+    }
+  }
+}
+
+impl From<Person> for proto::Person {
+  fn from(person: Person) -> Self {
+    proto::Person {
+      first_name: person.first_name,
+      last_name: person.last_name,
+    }
+  }
+}
+
+impl From<Event> for proto::Event {
+  fn from(event: Event) -> Self {
+    proto::Event {
+      id: event.id.0,
+      title: event.title,
+      original_title: event.original_title,
+      production_year: event.production_year,
+      length_in_minutes: event.length_in_minutes,
+      local_release: to_rfc3339(event.local_release),
+      rating: event.rating,
+      rating_label: event.rating_label,
+      local_distributor_name: event.local_distributor_name,
+      event_type: event.event_type,
+      genres: event.genres,
+      short_synopsis: event.short_synopsis,
+      synopsis: event.synopsis,
+      event_url: event.event_url,
+      images: Some(event.images.into()),
+      actors: event
+        .cast
+        .actors
+        .into_iter()
+        .map(proto::Person::from)
+        .collect(),
+      directors: event
+        .directors
+        .directors
+        .into_iter()
+        .map(proto::Person::from)
+        .collect(),
+    }
+  }
+}
+
+impl From<ScheduleDiff> for proto::ScheduleUpdate {
+  fn from(diff: ScheduleDiff) -> Self {
+    proto::ScheduleUpdate {
+      added: diff.added.into_iter().map(proto::Show::from).collect(),
+      changed: diff.changed.into_iter().map(proto::Show::from).collect(),
+      removed: diff.removed.into_iter().map(proto::Show::from).collect(),
+    }
+  }
+}
@@ -0,0 +1,538 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use libfinnkino_client::graphql::Upstream;
+use libfinnkino_client::uncached;
+use libfinnkino_core::events::{EventQuery, EventQueryBuilder, ListType};
+use libfinnkino_core::finnkino::{self, Error, Show};
+use libfinnkino_core::graphql::Source;
+use libfinnkino_core::id::{AreaId, EventId, ShowId};
+use libfinnkino_core::json;
+use libfinnkino_core::schedule::{self, ShowFilter};
+use libfinnkino_core::time::today;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+use tonic::{Request, Response, Status};
+
+use crate::proto::finnkino_server::{Finnkino, FinnkinoServer};
+
+mod convert;
+
+pub mod proto {
+  tonic::include_proto!("finnkino.v1");
+}
+
+pub const DEFAULT_PORT: u16 = 50051;
+pub const POLL_INTERVAL_SECS: u64 = 60;
+
+/// Serves the Finnkino data over gRPC from any source of the GraphQL schema.
+#[derive(Clone)]
+pub struct Service {
+  source: Arc<dyn Source>,
+  poll_interval: Duration,
+}
+
+impl Service {
+  pub fn new(source: Arc<dyn Source>) -> Self {
+    Service {
+      source,
+      poll_interval: Duration::from_secs(POLL_INTERVAL_SECS),
+    }
+  }
+
+  /// How often `WatchSchedule` polls the schedule for changes.
+  pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+    self.poll_interval = poll_interval;
+    self
+  }
+
+  pub fn into_server(self) -> FinnkinoServer<Service> {
+    FinnkinoServer::new(self)
+  }
+}
+
+impl Default for Service {
+  fn default() -> Self {
+    Service::new(Arc::new(Upstream::default()))
+  }
+}
+
+type ScheduleStream = Pin<Box<dyn Stream<Item = Result<proto::ScheduleUpdate, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl Finnkino for Service {
+  async fn list_areas(
+    &self,
+    _request: Request<proto::ListAreasRequest>,
+  ) -> Result<Response<proto::ListAreasResponse>, Status> {
+    let areas = self.source.areas().await.map_err(upstream_status)?;
+    Ok(Response::new(proto::ListAreasResponse {
+      areas: areas.into_iter().map(proto::Area::from).collect(),
+    }))
+  }
+
+  async fn list_shows(
+    &self,
+    request: Request<proto::ListShowsRequest>,
+  ) -> Result<Response<proto::ListShowsResponse>, Status> {
+    let filter = show_filter(request.get_ref())?;
+    let matching = self.source.shows(filter).await.map_err(upstream_status)?;
+    Ok(Response::new(proto::ListShowsResponse {
+      shows: matching.into_iter().map(proto::Show::from).collect(),
+    }))
+  }
+
+  async fn get_show(
+    &self,
+    request: Request<proto::GetShowRequest>,
+  ) -> Result<Response<proto::Show>, Status> {
+    let id = ShowId(request.get_ref().id);
+    let upcoming = self
+      .source
+      .shows(ShowFilter::from_today(today()))
+      .await
+      .map_err(upstream_status)?;
+    match upcoming.into_iter().find(|show| show.id == id) {
+      None => Err(Status::not_found(format!(
+        "No upcoming show with id {}",
+        id
+      ))),
+      Some(show) => Ok(Response::new(show.into())),
+    }
+  }
+
+  async fn list_events(
+    &self,
+    request: Request<proto::ListEventsRequest>,
+  ) -> Result<Response<proto::ListEventsResponse>, Status> {
+    let request = request.get_ref();
+    let query = EventQuery {
+      event: None,
+      area: non_zero(request.area).map(AreaId),
+      list_type: match request.list_type() {
+        proto::ListType::Unspecified => None,
+        proto::ListType::NowInTheatres => Some(ListType::NowInTheatres),
+        proto::ListType::ComingSoon => Some(ListType::ComingSoon),
+      },
+    };
+    let events = self.source.events(query).await.map_err(upstream_status)?;
+    Ok(Response::new(proto::ListEventsResponse {
+      events: events.into_iter().map(proto::Event::from).collect(),
+    }))
+  }
+
+  async fn get_event(
+    &self,
+    request: Request<proto::GetEventRequest>,
+  ) -> Result<Response<proto::Event>, Status> {
+    let id = EventId(request.get_ref().id);
+    let query = EventQueryBuilder::default().event(id).build().unwrap();
+    let events = self.source.events(query).await.map_err(upstream_status)?;
+    match events.into_iter().find(|event| event.id == id) {
+      None => Err(Status::not_found(format!("No event with id {}", id))),
+      Some(event) => Ok(Response::new(event.into())),
+    }
+  }
+
+  type WatchScheduleStream = ScheduleStream;
+
+  async fn watch_schedule(
+    &self,
+    request: Request<proto::WatchScheduleRequest>,
+  ) -> Result<Response<Self::WatchScheduleStream>, Status> {
+    let request = request.into_inner();
+    let area = non_zero(request.area).map(AreaId);
+    let event = non_zero(request.event).map(EventId);
+    let source = self.source.clone();
+    let mut interval = tokio::time::interval(self.poll_interval);
+    let (sender, receiver) = mpsc::channel(4);
+
+    tokio::spawn(async move {
+      let mut published: Option<Vec<Show>> = None;
+      loop {
+        tokio::select! {
+          _ = interval.tick() => {}
+          _ = sender.closed() => return,
+        }
+        let filter = ShowFilter {
+          area,
+          event,
+          ..ShowFilter::from_today(today())
+        };
+        let update = match (uncached(source.shows(filter)).await, &published) {
+          // The watch fails only if the schedule can't be fetched at all,
+          // later errors are retried on the next poll.
+          (Err(error), None) => Err(upstream_status(error)),
+          (Err(_), Some(_)) => continue,
+          (Ok(current), previous) => {
+            let diff = schedule::diff(previous.as_deref().unwrap_or_default(), &current);
+            if previous.is_some() && diff.is_empty() {
+              continue;
+            }
+            published = Some(current);
+            Ok(diff.into())
+          }
+        };
+        let failed = update.is_err();
+        if sender.send(update).await.is_err() || failed {
+          return;
+        }
+      }
+    });
+
+    Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+  }
+}
+
+fn non_zero(id: u64) -> Option<u64> {
+  Some(id).filter(|id| *id != 0)
+}
+
+/// Validates the request the same way as the query parameters of the shows
+/// endpoint.
+fn show_filter(request: &proto::ListShowsRequest) -> Result<ShowFilter, Status> {
+  let ids = [("area", request.area), ("event", request.event)];
+  let values = [
+    ("date", &request.date),
+    ("until", &request.until),
+    ("genre", &request.genre),
+    ("presentation", &request.presentation),
+    ("language", &request.language),
+    ("subtitles", &request.subtitles),
+    ("rating", &request.rating),
+    ("after", &request.after),
+    ("before", &request.before),
+  ];
+  let params: Vec<(&str, String)> = ids
+    .iter()
+    .filter(|(_, id)| *id != 0)
+    .map(|(name, id)| (*name, id.to_string()))
+    .chain(
+      values
+        .iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(name, value)| (*name, value.to_string())),
+    )
+    .collect();
+  let query =
+    serde_urlencoded::to_string(params).map_err(|err| Status::invalid_argument(err.to_string()))?;
+  ShowFilter::from_query(&query).map_err(invalid_argument)
+}
+
+fn invalid_argument(errors: json::Errors) -> Status {
+  Status::invalid_argument(errors.invalid_arguments())
+}
+
+fn upstream_status(error: Error) -> Status {
+  match finnkino::error_status(&error) {
+    404 => Status::not_found(error.to_string()),
+    _ => Status::unavailable(error.to_string()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use libfinnkino_core::finnkino::{Event, TheatreArea};
+  use libfinnkino_core::graphql::SourceFuture;
+  use libfinnkino_core::time;
+  use pretty_assertions::assert_eq;
+  use std::collections::VecDeque;
+  use std::sync::Mutex;
+  use tokio::net::TcpListener;
+  use tokio_stream::wrappers::TcpListenerStream;
+  use tokio_stream::StreamExt;
+  use tonic::transport::{Channel, Server};
+  use tonic::Code;
+  use wiremock::matchers::{method, path, query_param};
+  use wiremock::{Mock, MockServer, ResponseTemplate};
+
+  use crate::proto::finnkino_client::FinnkinoClient;
+
+  async fn serve(service: Service) -> FinnkinoClient<Channel> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(
+      Server::builder()
+        .add_service(service.into_server())
+        .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    FinnkinoClient::connect(format!("http://{}", address))
+      .await
+      .unwrap()
+  }
+
+  async fn serve_upstream(mock_server: &MockServer) -> FinnkinoClient<Channel> {
+    let upstream = Upstream::new(&format!("{}/xml", mock_server.uri()));
+    serve(Service::new(Arc::new(upstream))).await
+  }
+
+  /// Returns the given schedules one poll at a time, repeating the last one.
+  struct Schedules(Mutex<VecDeque<Vec<Show>>>);
+
+  impl Source for Schedules {
+    fn areas(&self) -> SourceFuture<Vec<TheatreArea>> {
+      Box::pin(async { Ok(Vec::new()) })
+    }
+
+    fn shows(&self, _filter: ShowFilter) -> SourceFuture<Vec<Show>> {
+      let mut schedules = self.0.lock().unwrap();
+      let current = if schedules.len() > 1 {
+        schedules.pop_front().unwrap()
+      } else {
+        schedules.front().cloned().unwrap_or_default()
+      };
+      Box::pin(async move { Ok(current) })
+    }
+
+    fn events(&self, _query: EventQuery) -> SourceFuture<Vec<Event>> {
+      Box::pin(async { Ok(Vec::new()) })
+    }
+  }
+
+  fn show(id: u64, title: &str) -> Show {
+    Show {
+      id: ShowId(id),
+      title: title.to_string(),
+      ..Show::default()
+    }
+  }
+
+  fn ids(shows: &[proto::Show]) -> Vec<u64> {
+    shows.iter().map(|show| show.id).collect()
+  }
+
+  #[tokio::test]
+  async fn test_list_areas() {
+    // Start a background HTTP server on a random local port
+    let mock_server = MockServer::start().await;
+
+    // Arrange the behaviour of the MockServer adding a Mock:
+    // when it receives a GET request on '/xml/TheatreAreas' it will respond with one area.
+    let body = r#"<?xml version="1.0"?>
+    <TheatreAreas>
+      <TheatreArea>
+        <ID>1014</ID>
+        <Name>Pääkaupunkiseutu</Name>
+      </TheatreArea>
+    </TheatreAreas>"#;
+    Mock::given(method("GET"))
+      .and(path("/xml/TheatreAreas"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/xml"))
+      // Mounting the mock on the mock server - it's now effective!
+      .mount(&mock_server)
+      .await;
+
+    let mut client = serve_upstream(&mock_server).await;
+    let response = client
+      .list_areas(proto::ListAreasRequest {})
+      .await
+      .unwrap()
+      .into_inner();
+
+    assert_eq!(
+      vec![proto::Area {
+        id: 1014,
+        name: "Pääkaupunkiseutu".to_string(),
+      }],
+      response.areas
+    );
+  }
+
+  #[tokio::test]
+  async fn test_get_event() {
+    // Start a background HTTP server on a random local port
+    let mock_server = MockServer::start().await;
+
+    // Arrange the behaviour of the MockServer adding a Mock:
+    // only the event 303000 exists upstream.
+    let body = r#"<?xml version="1.0"?>
+    <Events>
+      <Event>
+        <ID>303000</ID>
+        <Title>Minions: The Rise of Gru</Title>
+        <Cast>
+          <Actor>
+            <FirstName>Steve</FirstName>
+            <LastName>Carell</LastName>
+          </Actor>
+        </Cast>
+      </Event>
+    </Events>"#;
+    Mock::given(method("GET"))
+      .and(path("/xml/Events/"))
+      .and(query_param("eventID", "303000"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/xml"))
+      .mount(&mock_server)
+      .await;
+    Mock::given(method("GET"))
+      .and(path("/xml/Events/"))
+      .and(query_param("eventID", "1"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw("<Events></Events>", "text/xml"))
+      .mount(&mock_server)
+      .await;
+
+    let mut client = serve_upstream(&mock_server).await;
+    let event = client
+      .get_event(proto::GetEventRequest { id: 303000 })
+      .await
+      .unwrap()
+      .into_inner();
+    let error = client
+      .get_event(proto::GetEventRequest { id: 1 })
+      .await
+      .unwrap_err();
+
+    assert_eq!("Minions: The Rise of Gru", event.title);
+    assert_eq!(
+      vec![proto::Person {
+        first_name: "Steve".to_string(),
+        last_name: "Carell".to_string(),
+      }],
+      event.actors
+    );
+    assert_eq!(Code::NotFound, error.code());
+  }
+
+  #[tokio::test]
+  async fn test_upstream_error_is_unavailable() {
+    // Start a background HTTP server on a random local port
+    let mock_server = MockServer::start().await;
+
+    // Arrange the behaviour of the MockServer adding a Mock:
+    // the upstream fails every request.
+    Mock::given(method("GET"))
+      .respond_with(ResponseTemplate::new(503))
+      .mount(&mock_server)
+      .await;
+
+    let mut client = serve_upstream(&mock_server).await;
+    let error = client
+      .list_events(proto::ListEventsRequest {
+        list_type: proto::ListType::ComingSoon.into(),
+        area: 0,
+      })
+      .await
+      .unwrap_err();
+
+    assert_eq!(Code::Unavailable, error.code());
+  }
+
+  #[tokio::test]
+  async fn test_list_shows_with_invalid_filter() {
+    let mock_server = MockServer::start().await;
+
+    let mut client = serve_upstream(&mock_server).await;
+    let error = client
+      .list_shows(proto::ListShowsRequest {
+        after: "6pm".to_string(),
+        ..proto::ListShowsRequest::default()
+      })
+      .await
+      .unwrap_err();
+
+    assert_eq!(Code::InvalidArgument, error.code());
+    assert_eq!(
+      "Invalid arguments: after: Must be a time formatted as HH:MM",
+      error.message()
+    );
+  }
+
+  #[tokio::test]
+  async fn test_watch_schedule_starts_with_upcoming_shows() {
+    // Start a background HTTP server on a random local port
+    let mock_server = MockServer::start().await;
+
+    // Arrange the behaviour of the MockServer adding a Mock:
+    // the area has one show tomorrow.
+    let tomorrow = today().succ_opt().unwrap();
+    let body = format!(
+      r#"<?xml version="1.0"?>
+    <Schedule>
+      <PubDate>{0}T12:00:00</PubDate>
+      <Shows>
+        <Show>
+          <ID>1</ID>
+          <dttmShowStart>{0}T18:00:00</dttmShowStart>
+          <Title>Minions: The Rise of Gru</Title>
+        </Show>
+      </Shows>
+    </Schedule>"#,
+      tomorrow
+    );
+    Mock::given(method("GET"))
+      .and(path("/xml/Schedule/"))
+      .and(query_param("area", "1014"))
+      .and(query_param("nrOfDays", "14"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/xml"))
+      .mount(&mock_server)
+      .await;
+
+    let mut client = serve_upstream(&mock_server).await;
+    let mut updates = client
+      .watch_schedule(proto::WatchScheduleRequest {
+        area: 1014,
+        event: 0,
+      })
+      .await
+      .unwrap()
+      .into_inner();
+    let update = updates.next().await.unwrap().unwrap();
+
+    assert_eq!(vec![1], ids(&update.added));
+    let show_start = time::parse_finnkino_datetime(&format!("{}T18:00:00", tomorrow)).unwrap();
+    assert_eq!(show_start.to_rfc3339(), update.added[0].show_start);
+    assert!(update.changed.is_empty());
+    assert!(update.removed.is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_watch_schedule_fails_if_upstream_fails() {
+    // Start a background HTTP server on a random local port
+    let mock_server = MockServer::start().await;
+
+    // Arrange the behaviour of the MockServer adding a Mock:
+    // the upstream fails every request.
+    Mock::given(method("GET"))
+      .respond_with(ResponseTemplate::new(500))
+      .mount(&mock_server)
+      .await;
+
+    let mut client = serve_upstream(&mock_server).await;
+    let mut updates = client
+      .watch_schedule(proto::WatchScheduleRequest::default())
+      .await
+      .unwrap()
+      .into_inner();
+    let error = updates.next().await.unwrap().unwrap_err();
+
+    assert_eq!(Code::Unavailable, error.code());
+    assert!(updates.next().await.is_none());
+  }
+
+  #[tokio::test]
+  async fn test_watch_schedule_sends_changes() {
+    let schedules = Schedules(Mutex::new(VecDeque::from(vec![
+      vec![show(1, "Minions"), show(2, "Elvis")],
+      vec![show(1, "Minions"), show(2, "Elvis")],
+      vec![show(1, "Minions: The Rise of Gru"), show(3, "Nope")],
+    ])));
+    let service = Service::new(Arc::new(schedules)).poll_interval(Duration::from_millis(10));
+
+    let mut client = serve(service).await;
+    let mut updates = client
+      .watch_schedule(proto::WatchScheduleRequest::default())
+      .await
+      .unwrap()
+      .into_inner();
+    let first = updates.next().await.unwrap().unwrap();
+    let second = updates.next().await.unwrap().unwrap();
+
+    assert_eq!(vec![1, 2], ids(&first.added));
+    assert_eq!(vec![3], ids(&second.added));
+    assert_eq!(vec![1], ids(&second.changed));
+    assert_eq!(vec![2], ids(&second.removed));
+  }
+}
@@ -0,0 +1,173 @@
+//! Runs every scenario against both clients, libfinnkino-actix and
+//! libfinnkino-client, which must fail the same way.
+
+use chrono::NaiveDate;
+use pretty_assertions::assert_eq;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use libfinnkino_core::events::EventQuery;
+use libfinnkino_core::finnkino::{Error, ErrorKind};
+use libfinnkino_core::id::AreaId;
+use libfinnkino_core::schedule::ShowFilter;
+
+use crate::{Feed, Mock, Options};
+
+/// Serves the mock on a new port and returns its base URL. The replies are
+/// written out byte for byte, which HTTP servers would not do for e.g. a
+/// body shorter than its `Content-Length`.
+fn serve(mock: Mock) -> String {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let base_url = format!("http://{}/xml", listener.local_addr().unwrap());
+  let mock = Arc::new(mock);
+  thread::spawn(move || {
+    for stream in listener.incoming().flatten() {
+      let mock = mock.clone();
+      thread::spawn(move || reply(&mock, stream));
+    }
+  });
+  base_url
+}
+
+fn reply(mock: &Mock, mut stream: TcpStream) {
+  let mut request = Vec::new();
+  let mut buffer = [0; 1024];
+  while !request.ends_with(b"\r\n\r\n") {
+    match stream.read(&mut buffer) {
+      Ok(0) | Err(_) => return,
+      Ok(read) => request.extend_from_slice(&buffer[..read]),
+    }
+  }
+  let request = String::from_utf8_lossy(&request);
+  let target = request.split(' ').nth(1).unwrap_or_default();
+  let (path, query) = target.split_once('?').unwrap_or((target, ""));
+  let reply = mock.respond(path, query);
+  thread::sleep(reply.delay);
+
+  let mut head = format!("HTTP/1.1 {} Mock\r\n", reply.status);
+  for (name, value) in &reply.headers {
+    head += &format!("{}: {}\r\n", name, value);
+  }
+  if !reply
+    .headers
+    .iter()
+    .any(|(name, _)| name.eq_ignore_ascii_case("Content-Length"))
+  {
+    head += &format!("Content-Length: {}\r\n", reply.body.len());
+  }
+  head += "\r\n";
+  let _ = stream
+    .write_all(head.as_bytes())
+    .and_then(|_| stream.write_all(&reply.body));
+}
+
+fn start() -> NaiveDate {
+  NaiveDate::from_ymd_opt(2022, 9, 1).unwrap()
+}
+
+fn kind<T>(result: Result<T, Error>) -> Option<ErrorKind> {
+  result.err().map(|error| error.kind)
+}
+
+/// The kind of error of each feed, from each client in turn.
+async fn fetch(feed: Feed, base_url: &str) -> [Option<ErrorKind>; 2] {
+  let filter = ShowFilter {
+    area: Some(AreaId(1038)),
+    date: Some(start()),
+    ..ShowFilter::default()
+  };
+  let query = EventQuery::default();
+  let area = Some(AreaId(1014));
+  match feed {
+    Feed::TheatreAreas => futures::join!(
+      async { kind(libfinnkino_actix::fetch_areas(base_url).await) },
+      async { kind(libfinnkino_client::fetch_areas(base_url).await) },
+    ),
+    Feed::Schedule => futures::join!(
+      async { kind(libfinnkino_actix::fetch_shows(base_url, &filter).await) },
+      async { kind(libfinnkino_client::fetch_shows(base_url, &filter).await) },
+    ),
+    Feed::Events => futures::join!(
+      async { kind(libfinnkino_actix::fetch_events(base_url, &query).await) },
+      async { kind(libfinnkino_client::fetch_events(base_url, &query).await) },
+    ),
+    Feed::ScheduleDates => futures::join!(
+      async { kind(libfinnkino_actix::fetch_schedule_dates(base_url, area).await) },
+      async { kind(libfinnkino_client::fetch_schedule_dates(base_url, area).await) },
+    ),
+  }
+  .into()
+}
+
+/// Serves the mock with `scenario` and checks that each feed fails with the
+/// kind `expected` gives it, `None` for success, in both clients.
+async fn run(scenario: &str, expected: impl Fn(Feed) -> Option<ErrorKind>) {
+  let base_url = serve(Mock::new(Options {
+    start: start(),
+    scenario: scenario.parse().unwrap(),
+    ..Options::default()
+  }));
+
+  for feed in Feed::ALL {
+    let [actix, client] = fetch(feed, &base_url).await;
+    assert_eq!(
+      (feed, expected(feed), expected(feed)),
+      (feed, actix, client),
+      "{}",
+      scenario
+    );
+  }
+}
+
+#[actix_rt::test]
+async fn test_no_faults() {
+  run("", |_| None).await;
+}
+
+#[actix_rt::test]
+async fn test_short_delay() {
+  run("*: delay 50ms", |_| None).await;
+}
+
+#[actix_rt::test]
+async fn test_timeout() {
+  run("Events: delay 6000ms", |feed| {
+    (feed == Feed::Events).then_some(ErrorKind::Timeout)
+  })
+  .await;
+}
+
+#[actix_rt::test]
+async fn test_status() {
+  run("*: status 503", |_| Some(ErrorKind::Status)).await;
+}
+
+#[actix_rt::test]
+async fn test_status_of_one_feed() {
+  run("Schedule: status 500", |feed| {
+    (feed == Feed::Schedule).then_some(ErrorKind::Status)
+  })
+  .await;
+}
+
+#[actix_rt::test]
+async fn test_truncated_body() {
+  run("*: truncate", |_| Some(ErrorKind::Body)).await;
+}
+
+#[actix_rt::test]
+async fn test_corrupt_gzip() {
+  run("*: corrupt gzip", |_| Some(ErrorKind::Body)).await;
+}
+
+#[actix_rt::test]
+async fn test_invalid_utf8() {
+  run("*: invalid utf-8", |_| Some(ErrorKind::Encoding)).await;
+}
+
+#[actix_rt::test]
+async fn test_malformed_xml() {
+  run("*: malformed xml", |_| Some(ErrorKind::Xml)).await;
+}
@@ -0,0 +1,496 @@
+//! The synthetic areas, films and shows of the mock, generated from a seed
+//! so that the same seed and start date always give the same feeds.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeSet;
+use std::vec::Vec;
+
+use libfinnkino_core::finnkino::{
+  Cast, Directors, Event, Images, Language, Person, Show, TheatreArea,
+};
+use libfinnkino_core::id::{AreaId, EventId, ShowId};
+use libfinnkino_core::time::{from_local, LocalDateTime};
+
+/// The area of every theatre, Finnkino's default.
+pub const ALL_AREAS: AreaId = AreaId(1029);
+
+const THEATRES: &[(u64, &str, &str, u64)] = &[
+  (1012, "Espoo", "OMENA", 4),
+  (1039, "Espoo", "SELLO", 3),
+  (1038, "Helsinki", "TENNISPALATSI", 4),
+  (1031, "Helsinki", "KINOPALATSI", 3),
+  (1013, "Vantaa", "FLAMINGO", 3),
+  (1015, "Jyväskylä", "FANTASIA", 2),
+  (1016, "Kuopio", "SCALA", 2),
+  (1017, "Lahti", "KUVAPALATSI", 2),
+  (1034, "Tampere", "CINE ATLAS", 3),
+  (1035, "Tampere", "PLEVNA", 3),
+  (1022, "Turku", "KINOPALATSI", 3),
+];
+
+const REGIONS: &[(u64, &str, &[u64])] = &[
+  (1014, "Pääkaupunkiseutu", &[1012, 1039, 1038, 1031, 1013]),
+  (1021, "Tampere", &[1034, 1035]),
+];
+
+const TITLES: &[(&str, &str)] = &[
+  ("Viimeinen", "The Last"),
+  ("Pimeä", "Dark"),
+  ("Kadonnut", "Lost"),
+  ("Hiljainen", "Silent"),
+  ("Punainen", "Red"),
+  ("Ikuinen", "Eternal"),
+  ("Salainen", "Secret"),
+  ("Villi", "Wild"),
+  ("Kylmä", "Cold"),
+  ("Kultainen", "Golden"),
+];
+
+const SUBJECTS: &[(&str, &str)] = &[
+  ("kesä", "Summer"),
+  ("ranta", "Shore"),
+  ("metsä", "Forest"),
+  ("kaupunki", "City"),
+  ("matka", "Journey"),
+  ("yö", "Night"),
+  ("talvi", "Winter"),
+  ("saari", "Island"),
+  ("tehtävä", "Mission"),
+  ("perintö", "Legacy"),
+];
+
+const GENRES: &[&str] = &[
+  "Draama",
+  "Komedia",
+  "Toiminta",
+  "Jännitys",
+  "Kauhu",
+  "Animaatio",
+  "Seikkailu",
+  "Romantiikka",
+  "Dokumentti",
+  "Fantasia",
+  "Scifi",
+  "Perhe",
+];
+
+const RATINGS: &[&str] = &["S", "7", "12", "16", "18"];
+
+const DISTRIBUTORS: &[&str] = &[
+  "Finnkino",
+  "SF Studios",
+  "Nordisk Film",
+  "Walt Disney Studios",
+  "Warner Bros",
+  "Sony Pictures",
+  "Universal Pictures",
+];
+
+const FIRST_NAMES: &[&str] = &[
+  "Aino", "Eero", "Laura", "Mikko", "Sofia", "Juha", "Emma", "Ville", "Anna", "Pekka", "Kate",
+  "John", "Maria", "David",
+];
+
+const LAST_NAMES: &[&str] = &[
+  "Virtanen",
+  "Korhonen",
+  "Nieminen",
+  "Mäkinen",
+  "Hämäläinen",
+  "Smith",
+  "Johnson",
+  "Laine",
+  "Heikkinen",
+  "Brown",
+  "García",
+  "Koskinen",
+];
+
+const PRESENTATIONS: &[&str] = &["2D", "2D", "2D", "3D", "IMAX"];
+
+/// The films in theatres and coming soon.
+const NOW_SHOWING: usize = 12;
+const COMING_SOON: usize = 5;
+
+fn language(code: &str) -> Language {
+  let (name, name_in_language) = match code {
+    "FI" => ("suomi", "suomi"),
+    "SV" => ("ruotsi", "svenska"),
+    _ => ("englanti", "English"),
+  };
+  Language {
+    name: name.to_string(),
+    name_in_language: name_in_language.to_string(),
+    iso_two_letter_code: code.to_string(),
+  }
+}
+
+/// `CINE ATLAS` as `Cine Atlas`, the way the Schedule names theatres.
+fn title_case(name: &str) -> String {
+  name
+    .split(' ')
+    .map(|word| {
+      let mut chars = word.chars();
+      match chars.next() {
+        Some(first) => first.to_string() + &chars.as_str().to_lowercase(),
+        None => String::new(),
+      }
+    })
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+fn midnight(date: NaiveDate) -> LocalDateTime {
+  from_local(&date.and_time(NaiveTime::MIN))
+}
+
+fn person(rng: &mut StdRng) -> Person {
+  Person {
+    first_name: FIRST_NAMES.choose(rng).unwrap().to_string(),
+    last_name: LAST_NAMES.choose(rng).unwrap().to_string(),
+  }
+}
+
+fn images(id: EventId, slug: &str) -> Images {
+  let url = |kind: &str, size: &str| {
+    Some(format!(
+      "https://media.finnkino.fi/1012/Event_{}/{}/{}_{}.jpg",
+      id, kind, slug, size
+    ))
+  };
+  Images {
+    small_portrait: url("portrait_small", "99"),
+    medium_portrait: url("portrait_medium", "320"),
+    large_portrait: url("portrait_large", "640"),
+    small_landscape: url("landscape_small", "160"),
+    medium_landscape: url("landscape_medium", "640"),
+    large_landscape: url("landscape_large", "1280"),
+  }
+}
+
+fn event(rng: &mut StdRng, id: EventId, title: (usize, usize), release: NaiveDate) -> Event {
+  let (adjective, subject) = (TITLES[title.0], SUBJECTS[title.1]);
+  let title = format!("{} {}", adjective.0, subject.0);
+  let original_title = format!("{} {}", adjective.1, subject.1);
+  let count = rng.gen_range(1..=3);
+  let mut genres: Vec<&str> = GENRES.choose_multiple(rng, count).copied().collect();
+  genres.sort_unstable();
+  let genres = genres.join(", ");
+  let rating = RATINGS.choose(rng).unwrap().to_string();
+  let production_year = (release.year() - rng.gen_range(0..2)).to_string();
+  let directors = vec![person(rng)];
+  let actors = (0..rng.gen_range(2..=4)).map(|_| person(rng)).collect();
+  let short_synopsis = format!(
+    "{} on {} vuodelta {}.",
+    title,
+    genres.to_lowercase(),
+    production_year
+  );
+  let synopsis = format!(
+    "{} Ohjaus {}. Elokuvan levittää {}.",
+    short_synopsis,
+    directors[0].full_name(),
+    DISTRIBUTORS.choose(rng).unwrap()
+  );
+  let slug = original_title.to_lowercase().replace(' ', "_");
+  Event {
+    id,
+    original_title,
+    production_year,
+    length_in_minutes: rng.gen_range(80..=170).to_string(),
+    local_release: Some(midnight(release)),
+    rating_label: rating.clone(),
+    rating,
+    local_distributor_name: DISTRIBUTORS.choose(rng).unwrap().to_string(),
+    event_type: "Movie".to_string(),
+    genres,
+    short_synopsis,
+    synopsis,
+    event_url: format!("http://www.finnkino.fi/event/{}/", id),
+    images: images(id, &slug),
+    cast: Cast { actors },
+    directors: Directors { directors },
+    title,
+  }
+}
+
+/// The languages of an event's shows: the spoken language and up to two
+/// subtitle languages.
+type Languages = (Language, Option<Language>, Option<Language>);
+
+fn languages(rng: &mut StdRng) -> Languages {
+  match rng.gen_range(0..10) {
+    0..=6 => (
+      language("EN"),
+      Some(language("FI")),
+      rng.gen_bool(0.5).then(|| language("SV")),
+    ),
+    7 | 8 => (language("FI"), None, None),
+    _ => (language("SV"), Some(language("FI")), None),
+  }
+}
+
+fn show(event: &Event, languages: &Languages, start: NaiveDateTime) -> Show {
+  let length = event.length_in_minutes.parse().unwrap_or(120);
+  Show {
+    show_start: from_local(&start),
+    show_end: from_local(&(start + Duration::minutes(length))),
+    event_id: event.id,
+    title: event.title.clone(),
+    original_title: event.original_title.clone(),
+    production_year: event.production_year.clone(),
+    length_in_minutes: event.length_in_minutes.clone(),
+    local_release: event.local_release,
+    rating: event.rating.clone(),
+    rating_label: event.rating_label.clone(),
+    event_type: event.event_type.clone(),
+    genres: event.genres.clone(),
+    event_url: event.event_url.clone(),
+    spoken_language: Some(languages.0.clone()),
+    subtitle_language_1: languages.1.clone(),
+    subtitle_language_2: languages.2.clone(),
+    ..Show::default()
+  }
+}
+
+/// Rounds up to the next quarter hour, when shows start.
+fn quarter(time: NaiveDateTime) -> NaiveDateTime {
+  let minutes = (15 - time.minute() % 15) % 15;
+  time.with_second(0).unwrap() + Duration::minutes(minutes.into())
+}
+
+#[derive(Debug)]
+pub struct Catalog {
+  pub areas: Vec<TheatreArea>,
+  pub events: Vec<Event>,
+  pub coming_soon: Vec<Event>,
+  /// Every show of every theatre, by start time.
+  pub shows: Vec<Show>,
+  pub pub_date: LocalDateTime,
+  regions: Vec<(AreaId, Vec<AreaId>)>,
+}
+
+impl Catalog {
+  /// The films of `days` days from `start` on, their shows in every theatre
+  /// and the films released later.
+  pub fn generate(seed: u64, start: NaiveDate, days: u32) -> Catalog {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut areas = vec![TheatreArea {
+      id: ALL_AREAS,
+      name: "Valitse alue/teatteri".to_string(),
+    }];
+    for &(id, name, members) in REGIONS {
+      areas.push(TheatreArea {
+        id: AreaId(id),
+        name: name.to_string(),
+      });
+      for member in members {
+        let (_, city, theatre, _) = THEATRES.iter().find(|each| each.0 == *member).unwrap();
+        areas.push(TheatreArea {
+          id: AreaId(*member),
+          name: format!("{}: {}", city, theatre),
+        });
+      }
+    }
+    for &(id, city, theatre, _) in THEATRES {
+      if !areas.iter().any(|area| area.id == AreaId(id)) {
+        areas.push(TheatreArea {
+          id: AreaId(id),
+          name: format!("{}: {}", city, theatre),
+        });
+      }
+    }
+
+    let mut titles: Vec<(usize, usize)> = (0..TITLES.len())
+      .flat_map(|adjective| (0..SUBJECTS.len()).map(move |subject| (adjective, subject)))
+      .collect();
+    titles.shuffle(&mut rng);
+    let mut id = 300000;
+    let mut events = Vec::new();
+    let mut coming_soon = Vec::new();
+    for (index, title) in titles
+      .into_iter()
+      .take(NOW_SHOWING + COMING_SOON)
+      .enumerate()
+    {
+      id += rng.gen_range(1..500);
+      let release = match index < NOW_SHOWING {
+        true => start - Duration::days(rng.gen_range(0..60)),
+        false => start + Duration::days(rng.gen_range(i64::from(days)..i64::from(days) + 90)),
+      };
+      let event = event(&mut rng, EventId(id), title, release);
+      match index < NOW_SHOWING {
+        true => events.push(event),
+        false => coming_soon.push(event),
+      }
+    }
+    let languages: Vec<Languages> = events.iter().map(|_| languages(&mut rng)).collect();
+
+    let mut shows = Vec::new();
+    for day in 0..days {
+      let date = start + Duration::days(day.into());
+      let last_start = date.and_hms_opt(22, 30, 0).unwrap();
+      for &(theatre_id, city, theatre, auditoriums) in THEATRES {
+        for auditorium in 1..=auditoriums {
+          let opening = date
+            .and_hms_opt(rng.gen_range(10..14), 15 * rng.gen_range(0..4), 0)
+            .unwrap();
+          let mut start_time = opening;
+          while start_time <= last_start {
+            let index = rng.gen_range(0..events.len());
+            let mut show = show(&events[index], &languages[index], start_time);
+            show.theatre_id = AreaId(theatre_id);
+            show.theatre = format!("{}, {}", title_case(theatre), city);
+            show.auditorium_id = (theatre_id * 10 + auditorium).to_string();
+            show.auditorium = format!("sali {}", auditorium);
+            show.presentation_method = PRESENTATIONS.choose(&mut rng).unwrap().to_string();
+            start_time = quarter(show.show_end.naive_local() + Duration::minutes(15));
+            shows.push(show);
+          }
+        }
+      }
+    }
+    shows.sort_by_key(|show| (show.show_start, show.theatre_id));
+    for (index, show) in shows.iter_mut().enumerate() {
+      show.id = ShowId(1800000 + index as u64);
+      show.show_url = format!("http://www.finnkino.fi/websales/show/{}/", show.id);
+    }
+
+    Catalog {
+      areas,
+      events,
+      coming_soon,
+      shows,
+      pub_date: from_local(&start.and_hms_opt(9, 0, 0).unwrap()),
+      regions: REGIONS
+        .iter()
+        .map(|(id, _, members)| (AreaId(*id), members.iter().map(|id| AreaId(*id)).collect()))
+        .collect(),
+    }
+  }
+
+  /// The theatres of an area, none if there is no such area.
+  pub fn theatres(&self, area: AreaId) -> Vec<AreaId> {
+    if area == ALL_AREAS {
+      return THEATRES.iter().map(|theatre| AreaId(theatre.0)).collect();
+    }
+    if let Some((_, members)) = self.regions.iter().find(|(id, _)| *id == area) {
+      return members.clone();
+    }
+    match THEATRES.iter().any(|theatre| AreaId(theatre.0) == area) {
+      true => vec![area],
+      false => Vec::new(),
+    }
+  }
+
+  /// The shows of the area's theatres on `days` days from `from` on.
+  pub fn shows(
+    &self,
+    area: AreaId,
+    from: NaiveDate,
+    days: u32,
+    event: Option<EventId>,
+  ) -> Vec<Show> {
+    let theatres = self.theatres(area);
+    let until = from + Duration::days(days.into());
+    self
+      .shows
+      .iter()
+      .filter(|show| theatres.contains(&show.theatre_id))
+      .filter(|show| event.is_none_or(|event| show.event_id == event))
+      .filter(|show| {
+        let date = show.show_start.date_naive();
+        date >= from && date < until
+      })
+      .cloned()
+      .collect()
+  }
+
+  /// The days with shows in the area, at midnight.
+  pub fn dates(&self, area: AreaId) -> Vec<LocalDateTime> {
+    let theatres = self.theatres(area);
+    let dates: BTreeSet<NaiveDate> = self
+      .shows
+      .iter()
+      .filter(|show| theatres.contains(&show.theatre_id))
+      .map(|show| show.show_start.date_naive())
+      .collect();
+    dates.into_iter().map(midnight).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  fn start() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2022, 8, 20).unwrap()
+  }
+
+  #[test]
+  fn test_same_seed_gives_same_catalog() {
+    let catalog = Catalog::generate(7, start(), 2);
+    let again = Catalog::generate(7, start(), 2);
+    let other = Catalog::generate(8, start(), 2);
+
+    assert_eq!(catalog.events, again.events);
+    assert_eq!(catalog.shows, again.shows);
+    assert_ne!(catalog.shows, other.shows);
+    assert_eq!(NOW_SHOWING, catalog.events.len());
+    assert_eq!(COMING_SOON, catalog.coming_soon.len());
+  }
+
+  #[test]
+  fn test_shows_are_plausible() {
+    let catalog = Catalog::generate(1, start(), 3);
+
+    assert!(!catalog.shows.is_empty());
+    for show in &catalog.shows {
+      let event = catalog
+        .events
+        .iter()
+        .find(|event| event.id == show.event_id)
+        .unwrap();
+      assert_eq!(event.title, show.title);
+      assert!(show.show_start < show.show_end);
+      assert!(show.show_start.minute() % 15 == 0);
+      assert!(event.local_release.unwrap() <= show.show_start);
+    }
+    let releases = catalog
+      .coming_soon
+      .iter()
+      .map(|event| event.local_release.unwrap().date_naive());
+    assert!(releases
+      .into_iter()
+      .all(|release| release >= start() + Duration::days(3)));
+  }
+
+  #[test]
+  fn test_areas_and_regions() {
+    let catalog = Catalog::generate(1, start(), 2);
+
+    assert_eq!(ALL_AREAS, catalog.areas[0].id);
+    assert_eq!("Helsinki: TENNISPALATSI", catalog.areas[4].name);
+    assert_eq!(
+      vec![AreaId(1034), AreaId(1035)],
+      catalog.theatres(AreaId(1021))
+    );
+    assert_eq!(Vec::<AreaId>::new(), catalog.theatres(AreaId(1)));
+
+    let shows = catalog.shows(AreaId(1014), start() + Duration::days(1), 1, None);
+    assert!(!shows.is_empty());
+    assert!(shows.iter().all(|show| {
+      show.show_start.date_naive() == start() + Duration::days(1)
+        && catalog.theatres(AreaId(1014)).contains(&show.theatre_id)
+    }));
+    assert_eq!(
+      vec![midnight(start()), midnight(start() + Duration::days(1))],
+      catalog.dates(AreaId(1038))
+    );
+    assert!(catalog.dates(AreaId(1)).is_empty());
+  }
+}
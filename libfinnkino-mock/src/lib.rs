@@ -0,0 +1,387 @@
+//! A stand-in for Finnkino's XML feeds with synthetic data, for developing
+//! against without the network. The feeds are written with
+//! [`libfinnkino_core::xml`] from a seeded [`Catalog`], and requests can be
+//! slowed down or fail at random, also reproducibly, or as a [`Scenario`]
+//! says.
+
+use chrono::NaiveDate;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::sync::Mutex;
+use std::time::Duration;
+use std::vec::Vec;
+
+use libfinnkino_core::id::{AreaId, EventId};
+use libfinnkino_core::time::{today, FINNKINO_DATE_FORMAT};
+use libfinnkino_core::xml;
+
+#[cfg(test)]
+mod backends;
+pub mod catalog;
+pub mod scenario;
+
+pub use catalog::{Catalog, ALL_AREAS};
+pub use scenario::{Fault, Scenario};
+
+const XML_CONTENT_TYPE: &str = "text/xml; charset=utf-8";
+const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+
+/// The feeds the mock serves, under `/xml/`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Feed {
+  TheatreAreas,
+  Schedule,
+  Events,
+  ScheduleDates,
+}
+
+impl Feed {
+  pub const ALL: [Feed; 4] = [
+    Feed::TheatreAreas,
+    Feed::Schedule,
+    Feed::Events,
+    Feed::ScheduleDates,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Feed::TheatreAreas => "TheatreAreas",
+      Feed::Schedule => "Schedule",
+      Feed::Events => "Events",
+      Feed::ScheduleDates => "ScheduleDates",
+    }
+  }
+
+  /// The feed of a request path such as `/xml/Schedule/`, ignoring case like
+  /// Finnkino does.
+  pub fn from_path(path: &str) -> Option<Feed> {
+    let name = path
+      .trim_end_matches('/')
+      .strip_prefix("/xml/")
+      .unwrap_or_default();
+    Feed::ALL
+      .into_iter()
+      .find(|feed| feed.as_str().eq_ignore_ascii_case(name))
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+  pub seed: u64,
+  /// The first day with shows, which is also "today" for the Schedule.
+  pub start: NaiveDate,
+  pub days: u32,
+  /// How long every response is delayed.
+  pub latency: Duration,
+  /// The most a response is delayed at random on top of `latency`.
+  pub jitter: Duration,
+  /// The share of requests, from 0 to 1, that fail with `error_status`.
+  pub error_rate: f64,
+  pub error_status: u16,
+  pub scenario: Scenario,
+}
+
+impl Default for Options {
+  fn default() -> Self {
+    Options {
+      seed: 0,
+      start: today(),
+      days: 7,
+      latency: Duration::ZERO,
+      jitter: Duration::ZERO,
+      error_rate: 0.0,
+      error_status: 503,
+      scenario: Scenario::default(),
+    }
+  }
+}
+
+/// A response of the mock, to send after `delay`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Reply {
+  pub status: u16,
+  pub headers: Vec<(String, String)>,
+  pub body: Vec<u8>,
+  pub delay: Duration,
+}
+
+impl Reply {
+  fn xml(body: String) -> Self {
+    Reply {
+      status: 200,
+      headers: vec![("Content-Type".to_string(), XML_CONTENT_TYPE.to_string())],
+      body: body.into_bytes(),
+      delay: Duration::ZERO,
+    }
+  }
+
+  fn error(status: u16, message: &str) -> Self {
+    let body = format!(
+      "<html><head><title>{}</title></head><body><h1>{}</h1></body></html>",
+      message, message
+    );
+    Reply {
+      status,
+      headers: vec![("Content-Type".to_string(), HTML_CONTENT_TYPE.to_string())],
+      body: body.into_bytes(),
+      delay: Duration::ZERO,
+    }
+  }
+
+  fn set_header(&mut self, name: &str, value: String) {
+    self
+      .headers
+      .retain(|(header, _)| !header.eq_ignore_ascii_case(name));
+    self.headers.push((name.to_string(), value));
+  }
+}
+
+pub struct Mock {
+  catalog: Catalog,
+  options: Options,
+  /// Draws the delays and failures, separately from the catalog so that they
+  /// do not change the data.
+  rng: Mutex<StdRng>,
+}
+
+fn param<'a>(params: &'a [(String, String)], name: &str) -> Option<&'a str> {
+  params
+    .iter()
+    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+    .map(|(_, value)| value.as_str())
+}
+
+/// Parses a query parameter, `Err` if it is set but invalid.
+fn parse<T>(
+  params: &[(String, String)],
+  name: &str,
+  parse: impl Fn(&str) -> Option<T>,
+) -> Result<Option<T>, Reply> {
+  match param(params, name) {
+    None => Ok(None),
+    Some(value) => parse(value)
+      .map(Some)
+      .ok_or_else(|| Reply::error(400, "Bad Request")),
+  }
+}
+
+impl Mock {
+  pub fn new(options: Options) -> Self {
+    Mock {
+      catalog: Catalog::generate(options.seed, options.start, options.days),
+      rng: Mutex::new(StdRng::seed_from_u64(options.seed.wrapping_add(1))),
+      options,
+    }
+  }
+
+  pub fn catalog(&self) -> &Catalog {
+    &self.catalog
+  }
+
+  /// The response to a GET of `path` with `query`, after the configured
+  /// latency, possibly failed and with the faults of the scenario.
+  pub fn respond(&self, path: &str, query: &str) -> Reply {
+    let (delay, fail) = {
+      let mut rng = self.rng.lock().unwrap();
+      let jitter = match self.options.jitter.is_zero() {
+        true => Duration::ZERO,
+        false => rng.gen_range(Duration::ZERO..=self.options.jitter),
+      };
+      let fail = rng.gen_bool(self.options.error_rate.clamp(0.0, 1.0));
+      (self.options.latency + jitter, fail)
+    };
+    let feed = Feed::from_path(path);
+    let reply = match (fail, feed) {
+      (true, _) => Reply::error(self.options.error_status, "Injected error"),
+      (false, None) => Reply::error(404, "Not Found"),
+      (false, Some(feed)) => self
+        .feed(feed, query)
+        .map_or_else(|reply| reply, Reply::xml),
+    };
+    let reply = Reply { delay, ..reply };
+    match feed {
+      None => reply,
+      Some(feed) => self.options.scenario.apply(feed, reply),
+    }
+  }
+
+  /// The XML of a feed, filtered by the query like Finnkino does.
+  fn feed(&self, feed: Feed, query: &str) -> Result<String, Reply> {
+    let params: Vec<(String, String)> = serde_urlencoded::from_str(query).unwrap_or_default();
+    let area = parse(&params, "area", |value| value.parse::<AreaId>().ok())?;
+    let area = area.unwrap_or(ALL_AREAS);
+    let event = parse(&params, "eventID", |value| value.parse::<EventId>().ok())?;
+    let body = match feed {
+      Feed::TheatreAreas => xml::theatre_areas(&self.catalog.areas),
+      Feed::ScheduleDates => xml::schedule_dates(&self.catalog.dates(area)),
+      Feed::Schedule => {
+        let date = parse(&params, "dt", |value| {
+          NaiveDate::parse_from_str(value, FINNKINO_DATE_FORMAT).ok()
+        })?;
+        let days = parse(&params, "nrOfDays", |value| {
+          value
+            .parse::<u32>()
+            .ok()
+            .filter(|days| (1..=31).contains(days))
+        })?;
+        let shows = self.catalog.shows(
+          area,
+          date.unwrap_or(self.options.start),
+          days.unwrap_or(1),
+          event,
+        );
+        xml::schedule(&self.catalog.pub_date, &shows)
+      }
+      Feed::Events => {
+        let coming_soon = match param(&params, "listType") {
+          None => false,
+          Some(list) if list.eq_ignore_ascii_case("NowInTheatres") => false,
+          Some(list) if list.eq_ignore_ascii_case("ComingSoon") => true,
+          Some(_) => return Err(Reply::error(400, "Bad Request")),
+        };
+        let theatres = self.catalog.theatres(area);
+        let events: Vec<_> = match event {
+          Some(id) => self
+            .catalog
+            .events
+            .iter()
+            .chain(&self.catalog.coming_soon)
+            .filter(|each| each.id == id)
+            .cloned()
+            .collect(),
+          None if coming_soon => self.catalog.coming_soon.clone(),
+          None => self
+            .catalog
+            .events
+            .iter()
+            .filter(|each| {
+              self
+                .catalog
+                .shows
+                .iter()
+                .any(|show| show.event_id == each.id && theatres.contains(&show.theatre_id))
+            })
+            .cloned()
+            .collect(),
+        };
+        xml::events(&events)
+      }
+    };
+    Ok(body)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use libfinnkino_core::finnkino::{from_xml, Events, Schedule, ScheduleDates, TheatreAreas};
+  use pretty_assertions::assert_eq;
+
+  fn body(reply: &Reply) -> &str {
+    std::str::from_utf8(&reply.body).unwrap()
+  }
+
+  fn mock(options: Options) -> Mock {
+    Mock::new(Options {
+      start: NaiveDate::from_ymd_opt(2022, 8, 20).unwrap(),
+      days: 3,
+      ..options
+    })
+  }
+
+  #[test]
+  fn test_feed_from_path() {
+    assert_eq!(Some(Feed::Schedule), Feed::from_path("/xml/Schedule/"));
+    assert_eq!(
+      Some(Feed::TheatreAreas),
+      Feed::from_path("/xml/theatreareas")
+    );
+    assert_eq!(None, Feed::from_path("/xml/News/"));
+    assert_eq!(None, Feed::from_path("/Schedule/"));
+  }
+
+  #[test]
+  fn test_feeds_are_valid_xml() {
+    let mock = mock(Options::default());
+
+    let areas = from_xml::<TheatreAreas>(body(&mock.respond("/xml/TheatreAreas", ""))).unwrap();
+    assert_eq!(mock.catalog().areas, areas.theatre_areas);
+
+    let reply = mock.respond("/xml/Schedule/", "area=1038&dt=21.08.2022");
+    assert_eq!(200, reply.status);
+    let shows = from_xml::<Schedule>(body(&reply)).unwrap().shows.shows;
+    assert_eq!(
+      mock.catalog().shows(
+        AreaId(1038),
+        NaiveDate::from_ymd_opt(2022, 8, 21).unwrap(),
+        1,
+        None
+      ),
+      shows
+    );
+
+    let reply = mock.respond("/xml/ScheduleDates/", "area=1014");
+    let dates = from_xml::<ScheduleDates>(body(&reply)).unwrap().dates;
+    assert_eq!(3, dates.len());
+
+    let reply = mock.respond("/xml/Events/", "listType=ComingSoon");
+    let events = from_xml::<Events>(body(&reply)).unwrap().events;
+    assert_eq!(mock.catalog().coming_soon, events);
+    let id = events[0].id;
+    let reply = mock.respond("/xml/Events/", &format!("eventID={}", id));
+    let events = from_xml::<Events>(body(&reply)).unwrap().events;
+    assert_eq!(
+      vec![id],
+      events.iter().map(|event| event.id).collect::<Vec<_>>()
+    );
+  }
+
+  #[test]
+  fn test_schedule_filters() {
+    let mock = mock(Options::default());
+    let event = mock.catalog().shows[0].event_id;
+
+    let reply = mock.respond(
+      "/xml/Schedule/",
+      &format!("area=1014&dt=20.08.2022&nrOfDays=3&eventID={}", event),
+    );
+    let shows = from_xml::<Schedule>(body(&reply)).unwrap().shows.shows;
+    assert!(!shows.is_empty());
+    assert!(shows.iter().all(|show| show.event_id == event));
+    let reply = mock.respond("/xml/Schedule/", "area=1");
+    assert!(from_xml::<Schedule>(body(&reply))
+      .unwrap()
+      .shows
+      .shows
+      .is_empty());
+    assert_eq!(400, mock.respond("/xml/Schedule/", "dt=2022-08-20").status);
+    assert_eq!(404, mock.respond("/xml/News/", "").status);
+  }
+
+  #[test]
+  fn test_latency_and_errors() {
+    let mock = mock(Options {
+      latency: Duration::from_millis(100),
+      jitter: Duration::from_millis(50),
+      error_rate: 1.0,
+      error_status: 502,
+      ..Options::default()
+    });
+
+    for _ in 0..10 {
+      let reply = mock.respond("/xml/TheatreAreas", "");
+      assert_eq!(502, reply.status);
+      assert_eq!(
+        ("Content-Type".to_string(), HTML_CONTENT_TYPE.to_string()),
+        reply.headers[0]
+      );
+      assert!(reply.delay >= Duration::from_millis(100));
+      assert!(reply.delay <= Duration::from_millis(150));
+    }
+    let healthy = Mock::new(Options::default());
+    assert_eq!(
+      Duration::ZERO,
+      healthy.respond("/xml/TheatreAreas", "").delay
+    );
+  }
+}
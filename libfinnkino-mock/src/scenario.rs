@@ -0,0 +1,262 @@
+//! Faults injected into the mock's responses, feed by feed, to reproduce how
+//! the clients fail. A scenario is a list of rules separated by `;` or new
+//! lines, each a feed (or `*` for every feed) and its faults:
+//!
+//! ```text
+//! Schedule: delay 6000ms; Events: status 503
+//! *: truncate, delay 100ms
+//! ```
+//!
+//! The faults are `delay <N>ms`, `status <X>`, `truncate` (the body is cut
+//! in half after its full length was announced), `corrupt gzip`,
+//! `invalid utf-8` and `malformed xml`. They apply in order, those of every
+//! matching rule.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use std::vec::Vec;
+
+use crate::{Feed, Reply};
+
+/// A gzip header followed by a deflate block of the reserved type, which
+/// every decoder rejects.
+const CORRUPT_GZIP: &[u8] = &[0x1f, 0x8b, 0x08, 0x00, 0, 0, 0, 0, 0x00, 0xff, 0xff];
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Fault {
+  Delay(Duration),
+  Status(u16),
+  Truncate,
+  CorruptGzip,
+  InvalidUtf8,
+  MalformedXml,
+}
+
+impl fmt::Display for Fault {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Fault::Delay(delay) => write!(f, "delay {}ms", delay.as_millis()),
+      Fault::Status(status) => write!(f, "status {}", status),
+      Fault::Truncate => f.write_str("truncate"),
+      Fault::CorruptGzip => f.write_str("corrupt gzip"),
+      Fault::InvalidUtf8 => f.write_str("invalid utf-8"),
+      Fault::MalformedXml => f.write_str("malformed xml"),
+    }
+  }
+}
+
+impl FromStr for Fault {
+  type Err = String;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    let words: Vec<String> = value
+      .split_whitespace()
+      .map(|word| word.to_lowercase())
+      .collect();
+    let words: Vec<&str> = words.iter().map(String::as_str).collect();
+    match words[..] {
+      ["delay", delay] => delay
+        .strip_suffix("ms")
+        .unwrap_or(delay)
+        .parse()
+        .map(|millis| Fault::Delay(Duration::from_millis(millis)))
+        .map_err(|_| format!("'{}' is not a delay in milliseconds", delay)),
+      ["status", status] => status
+        .parse()
+        .ok()
+        .filter(|status| (100..600).contains(status))
+        .map(Fault::Status)
+        .ok_or_else(|| format!("'{}' is not an HTTP status", status)),
+      ["truncate"] => Ok(Fault::Truncate),
+      ["corrupt", "gzip"] => Ok(Fault::CorruptGzip),
+      ["invalid", "utf-8" | "utf8"] => Ok(Fault::InvalidUtf8),
+      ["malformed", "xml"] => Ok(Fault::MalformedXml),
+      _ => Err(format!("Unknown fault '{}'", value.trim())),
+    }
+  }
+}
+
+impl Fault {
+  pub fn apply(&self, reply: Reply) -> Reply {
+    let mut reply = reply;
+    match self {
+      Fault::Delay(delay) => reply.delay += *delay,
+      Fault::Status(status) => {
+        let message = format!("Status {}", status);
+        reply = Reply {
+          delay: reply.delay,
+          ..Reply::error(*status, &message)
+        };
+      }
+      Fault::Truncate => {
+        let length = reply.body.len();
+        reply.set_header("Content-Length", length.to_string());
+        reply.body.truncate(length / 2);
+      }
+      Fault::CorruptGzip => {
+        reply.set_header("Content-Encoding", "gzip".to_string());
+        reply.body = CORRUPT_GZIP.to_vec();
+      }
+      Fault::InvalidUtf8 => {
+        // Inside the root element, past the declaration.
+        let root = find(&reply.body, b"?>").map_or(0, |end| end + 2);
+        let at = find(&reply.body[root..], b">").map_or(0, |end| root + end + 1);
+        reply.body.splice(at..at, [0xc3, 0x28]);
+      }
+      Fault::MalformedXml => {
+        reply.body.truncate(reply.body.len() / 2);
+        reply.body.extend_from_slice(b"</Malformed>");
+      }
+    }
+    reply
+  }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+  haystack
+    .windows(needle.len())
+    .position(|window| window == needle)
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Scenario {
+  /// The faults of a feed, or of every feed for `None`.
+  rules: Vec<(Option<Feed>, Vec<Fault>)>,
+}
+
+impl Scenario {
+  pub fn faults(&self, feed: Feed) -> impl Iterator<Item = &Fault> {
+    self
+      .rules
+      .iter()
+      .filter(move |(rule, _)| rule.is_none_or(|rule| rule == feed))
+      .flat_map(|(_, faults)| faults)
+  }
+
+  pub fn apply(&self, feed: Feed, reply: Reply) -> Reply {
+    self
+      .faults(feed)
+      .fold(reply, |reply, fault| fault.apply(reply))
+  }
+}
+
+impl fmt::Display for Scenario {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let rules: Vec<String> = self
+      .rules
+      .iter()
+      .map(|(feed, faults)| {
+        let faults: Vec<String> = faults.iter().map(Fault::to_string).collect();
+        format!(
+          "{}: {}",
+          feed.map_or("*", |feed| feed.as_str()),
+          faults.join(", ")
+        )
+      })
+      .collect();
+    f.write_str(&rules.join("; "))
+  }
+}
+
+impl FromStr for Scenario {
+  type Err = String;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    let mut rules = Vec::new();
+    for rule in value
+      .split([';', '\n'])
+      .filter(|rule| !rule.trim().is_empty())
+    {
+      let (feed, faults) = rule
+        .split_once(':')
+        .ok_or_else(|| format!("'{}' has no feed, e.g. 'Schedule: status 503'", rule.trim()))?;
+      let feed = match feed.trim() {
+        "*" => None,
+        name => Some(
+          Feed::ALL
+            .into_iter()
+            .find(|feed| feed.as_str().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Unknown feed '{}'", name))?,
+        ),
+      };
+      let faults = faults
+        .split(',')
+        .map(str::parse)
+        .collect::<Result<Vec<Fault>, String>>()?;
+      rules.push((feed, faults));
+    }
+    Ok(Scenario { rules })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  fn xml() -> Reply {
+    Reply::xml("<?xml version=\"1.0\"?>\n<Dates><dateTime /></Dates>".to_string())
+  }
+
+  #[test]
+  fn test_parse_scenario() {
+    let scenario: Scenario =
+      "Schedule: delay 6000ms, status 503\n*: truncate; events: invalid UTF-8"
+        .parse()
+        .unwrap();
+
+    assert_eq!(
+      vec![
+        &Fault::Delay(Duration::from_secs(6)),
+        &Fault::Status(503),
+        &Fault::Truncate
+      ],
+      scenario.faults(Feed::Schedule).collect::<Vec<_>>()
+    );
+    assert_eq!(
+      vec![&Fault::Truncate, &Fault::InvalidUtf8],
+      scenario.faults(Feed::Events).collect::<Vec<_>>()
+    );
+    assert_eq!(
+      "Schedule: delay 6000ms, status 503; *: truncate; Events: invalid utf-8",
+      scenario.to_string()
+    );
+    assert_eq!(Ok(Scenario::default()), "".parse());
+    assert_eq!(
+      Err("Unknown feed 'News'".to_string()),
+      "News: truncate".parse::<Scenario>()
+    );
+    assert_eq!(
+      Err("Unknown fault 'explode'".to_string()),
+      "*: explode".parse::<Scenario>()
+    );
+    assert!("*: status 999".parse::<Scenario>().is_err());
+    assert!("truncate".parse::<Scenario>().is_err());
+  }
+
+  #[test]
+  fn test_apply_faults() {
+    let length = xml().body.len();
+
+    let truncated = Fault::Truncate.apply(xml());
+    assert_eq!(length / 2, truncated.body.len());
+    assert!(truncated
+      .headers
+      .contains(&("Content-Length".to_string(), length.to_string())));
+
+    let invalid = Fault::InvalidUtf8.apply(xml());
+    assert!(invalid
+      .body
+      .starts_with(b"<?xml version=\"1.0\"?>\n<Dates>\xc3("));
+    assert!(String::from_utf8(invalid.body).is_err());
+
+    let failed = Fault::Status(502).apply(Fault::Delay(Duration::from_millis(5)).apply(xml()));
+    assert_eq!(502, failed.status);
+    assert_eq!(Duration::from_millis(5), failed.delay);
+    assert!(Fault::MalformedXml
+      .apply(xml())
+      .body
+      .ends_with(b"</Malformed>"));
+  }
+}
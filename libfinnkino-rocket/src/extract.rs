@@ -0,0 +1,122 @@
+use rocket::http::Status;
+use rocket::request::{FromParam, FromRequest, Outcome, Request};
+use std::convert::Infallible;
+use std::str::FromStr;
+
+use libfinnkino_core::ical;
+use libfinnkino_core::id::ParseIdError;
+use libfinnkino_core::json;
+use libfinnkino_core::live;
+
+/// A typed id taken from a path segment. Take it as
+/// `Result<PathId<T>, json::Errors>` to answer malformed ids with a 400.
+#[derive(Debug, Eq, PartialEq)]
+pub struct PathId<T>(pub T);
+
+impl<'a, T: FromStr<Err = ParseIdError>> FromParam<'a> for PathId<T> {
+  type Error = json::Errors;
+
+  fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+    param.parse::<T>().map(PathId).map_err(|err| json::Errors {
+      errors: vec![json::Error::invalid_path_parameter("id", err.to_string())],
+    })
+  }
+}
+
+/// A rejected path segment with the status to answer with.
+#[derive(Debug)]
+pub struct ApiError {
+  pub status: Status,
+  pub errors: json::Errors,
+}
+
+/// A typed id taken from a `{id}.ics` path segment, as Rocket can only match
+/// whole segments. Without the extension the path is not found, as with a
+/// router that can match it.
+#[derive(Debug, Eq, PartialEq)]
+pub struct CalendarId<T>(pub T);
+
+impl<'a, T: FromStr<Err = ParseIdError>> FromParam<'a> for CalendarId<T> {
+  type Error = ApiError;
+
+  fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+    match param.strip_suffix(ical::EXTENSION) {
+      None => Err(ApiError {
+        status: Status::NotFound,
+        errors: json::Errors {
+          errors: vec![json::Error::from_status(404, "Not Found")],
+        },
+      }),
+      Some(id) => PathId::from_param(id)
+        .map(|PathId(id)| CalendarId(id))
+        .map_err(|errors| ApiError {
+          status: Status::BadRequest,
+          errors,
+        }),
+    }
+  }
+}
+
+/// The `Last-Event-ID` header of a reconnecting Server-Sent Events client.
+/// Missing and malformed ids are both `None`.
+#[derive(Debug, Eq, PartialEq)]
+pub struct LastEventId(pub Option<u64>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LastEventId {
+  type Error = Infallible;
+
+  async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+    let id = req
+      .headers()
+      .get_one(live::LAST_EVENT_ID)
+      .and_then(live::parse_last_event_id);
+    Outcome::Success(LastEventId(id))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use libfinnkino_core::id::{EventId, ShowId};
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_path_id_from_param() {
+    assert_eq!(
+      PathId::<EventId>::from_param("303000").unwrap(),
+      PathId(EventId(303000))
+    );
+  }
+
+  #[test]
+  fn test_invalid_path_id_from_param() {
+    let errors = PathId::<EventId>::from_param("abc").unwrap_err();
+
+    assert_eq!(errors.errors[0].status, Some("400".to_string()));
+    assert_eq!(
+      errors.errors[0].source.as_ref().unwrap().parameter,
+      Some("id".to_string())
+    );
+  }
+
+  #[test]
+  fn test_calendar_id_from_param() {
+    assert_eq!(
+      CalendarId::<ShowId>::from_param("1794851.ics").unwrap(),
+      CalendarId(ShowId(1794851))
+    );
+    assert_eq!(
+      Status::NotFound,
+      CalendarId::<ShowId>::from_param("1794851")
+        .unwrap_err()
+        .status
+    );
+    assert_eq!(
+      Status::BadRequest,
+      CalendarId::<ShowId>::from_param("abc.ics")
+        .unwrap_err()
+        .status
+    );
+  }
+}
@@ -0,0 +1,181 @@
+//! WebSocket connections over Rocket's connection upgrades. Take a
+//! [`WebSocket`] guard in a route and answer with the [`Channel`] that runs
+//! the session once the handshake is done.
+
+use rocket::data::{IoHandler, IoStream};
+use rocket::futures::future::BoxFuture;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::{self, Responder, Response};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::WebSocketStream;
+
+pub use tokio_tungstenite::tungstenite::Message;
+
+pub type Stream = WebSocketStream<IoStream>;
+
+const PROTOCOL: &str = "websocket";
+const VERSION: &str = "13";
+
+/// The handshake of a WebSocket client, checked as in RFC 6455 §4.2.1.
+/// Requests that do not ask for an upgrade to WebSocket are answered with a
+/// 400, and handshakes for a version other than 13 with a 426.
+#[derive(Debug, Eq, PartialEq)]
+pub struct WebSocket {
+  key: String,
+}
+
+/// Why a request is not a WebSocket handshake the server can take.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HandshakeError {
+  /// The Upgrade, Connection or Sec-WebSocket-Key header is missing.
+  Invalid,
+  /// The client does not speak version 13.
+  Version,
+}
+
+impl WebSocket {
+  pub fn channel<F, Fut>(self, handler: F) -> Channel
+  where
+    F: FnOnce(Stream) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+  {
+    Channel {
+      key: self.key,
+      handler: Box::new(move |stream| Box::pin(handler(stream))),
+    }
+  }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for WebSocket {
+  type Error = HandshakeError;
+
+  async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+    let headers = req.headers();
+    let upgrade = headers
+      .get("Upgrade")
+      .any(|value| value.eq_ignore_ascii_case(PROTOCOL));
+    let connection = headers
+      .get("Connection")
+      .flat_map(|value| value.split(','))
+      .any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+    if !upgrade || !connection {
+      return Outcome::Error((Status::BadRequest, HandshakeError::Invalid));
+    }
+    if headers.get_one("Sec-WebSocket-Version") != Some(VERSION) {
+      return Outcome::Error((Status::UpgradeRequired, HandshakeError::Version));
+    }
+    match headers.get_one("Sec-WebSocket-Key") {
+      Some(key) => Outcome::Success(WebSocket {
+        key: key.to_string(),
+      }),
+      None => Outcome::Error((Status::BadRequest, HandshakeError::Invalid)),
+    }
+  }
+}
+
+pub struct Channel {
+  key: String,
+  handler: Box<dyn FnOnce(Stream) -> BoxFuture<'static, ()> + Send>,
+}
+
+impl<'r> Responder<'r, 'static> for Channel {
+  fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+    let accept = derive_accept_key(self.key.as_bytes());
+    Response::build()
+      .raw_header("Sec-WebSocket-Accept", accept)
+      .upgrade(PROTOCOL, self)
+      .ok()
+  }
+}
+
+#[rocket::async_trait]
+impl IoHandler for Channel {
+  async fn io(self: Pin<Box<Self>>, io: IoStream) -> io::Result<()> {
+    let channel = Pin::into_inner(self);
+    let stream = WebSocketStream::from_raw_socket(io, Role::Server, None).await;
+    (channel.handler)(stream).await;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+  use rocket::http::Header;
+  use rocket::local::blocking::Client;
+
+  #[rocket::get("/ws")]
+  fn echo(socket: WebSocket) -> Channel {
+    socket.channel(|_| async {})
+  }
+
+  fn client() -> Client {
+    Client::tracked(rocket::build().mount("/", rocket::routes![echo])).unwrap()
+  }
+
+  #[test]
+  fn test_handshake_accept_key() {
+    let client = client();
+    let response = client
+      .get("/ws")
+      .header(Header::new("Connection", "Upgrade"))
+      .header(Header::new("Upgrade", "websocket"))
+      .header(Header::new("Sec-WebSocket-Version", "13"))
+      .header(Header::new("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+      .dispatch();
+
+    assert_eq!(
+      Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="),
+      response.headers().get_one("Sec-WebSocket-Accept")
+    );
+  }
+
+  #[test]
+  fn test_request_without_upgrade_is_rejected() {
+    let client = client();
+    let response = client
+      .get("/ws")
+      .header(Header::new("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+      .dispatch();
+
+    assert_eq!(Status::BadRequest, response.status());
+  }
+
+  #[test]
+  fn test_request_without_connection_upgrade_is_rejected() {
+    let client = client();
+    let response = client
+      .get("/ws")
+      .header(Header::new("Connection", "keep-alive"))
+      .header(Header::new("Upgrade", "websocket"))
+      .header(Header::new("Sec-WebSocket-Version", "13"))
+      .header(Header::new("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="))
+      .dispatch();
+
+    assert_eq!(Status::BadRequest, response.status());
+  }
+
+  #[test]
+  fn test_other_version_needs_an_upgrade() {
+    let client = client();
+    for version in [None, Some("8")] {
+      let mut request = client
+        .get("/ws")
+        .header(Header::new("Connection", "keep-alive, Upgrade"))
+        .header(Header::new("Upgrade", "websocket"))
+        .header(Header::new("Sec-WebSocket-Key", "dGhlIHNhbXBsZSBub25jZQ=="));
+      if let Some(version) = version {
+        request = request.header(Header::new("Sec-WebSocket-Version", version));
+      }
+
+      assert_eq!(Status::UpgradeRequired, request.dispatch().status());
+    }
+  }
+}
@@ -0,0 +1,274 @@
+use chrono::NaiveDate;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::widgets::ListState;
+use std::vec::Vec;
+
+use libfinnkino_core::finnkino::{Error, Event, Show, TheatreArea};
+use libfinnkino_core::id::{AreaId, EventId};
+use libfinnkino_core::schedule::ShowFilter;
+use libfinnkino_core::time::LocalDateTime;
+
+const PAGE: usize = 10;
+
+/// The data the app needs next. The caller loads it and hands the result
+/// back to the matching `set_` method, or to [`App::set_error`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Request {
+  /// The ScheduleDates of an area, for [`App::set_dates`].
+  Dates(AreaId),
+  /// The shows of a day, for [`App::set_shows`].
+  Shows(ShowFilter),
+  /// A film, for [`App::set_event`].
+  Event(EventId),
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Focus {
+  #[default]
+  Areas,
+  Shows,
+}
+
+#[derive(Debug, Default)]
+pub struct App {
+  pub areas: Vec<TheatreArea>,
+  pub area_list: ListState,
+  /// The area whose shows are listed, not necessarily the highlighted one.
+  pub area: Option<AreaId>,
+  pub dates: Vec<NaiveDate>,
+  pub day: usize,
+  pub shows: Vec<Show>,
+  pub show_list: ListState,
+  /// Case-insensitive text the title, genres or theatre must contain.
+  pub search: String,
+  pub presentation: Option<String>,
+  pub searching: bool,
+  pub event: Option<Event>,
+  pub focus: Focus,
+  pub error: Option<String>,
+  pub quit: bool,
+}
+
+impl App {
+  /// Lists the areas and loads the first one.
+  pub fn set_areas(&mut self, areas: Vec<TheatreArea>) -> Option<Request> {
+    self.error = None;
+    self.areas = areas;
+    self.area_list.select(match self.areas.is_empty() {
+      true => None,
+      false => Some(0),
+    });
+    self.select_area()
+  }
+
+  /// Shows the days of the area as tabs, staying on the same day if it is
+  /// one of them, and loads the day's shows.
+  pub fn set_dates(&mut self, dates: Vec<LocalDateTime>) -> Option<Request> {
+    self.error = None;
+    let current = self.date();
+    let mut dates: Vec<NaiveDate> = dates.iter().map(|date| date.date_naive()).collect();
+    dates.sort();
+    dates.dedup();
+    self.dates = dates;
+    self.day = current
+      .and_then(|current| self.dates.iter().position(|date| *date == current))
+      .unwrap_or_default();
+    match self.date() {
+      None => {
+        self.set_shows(Vec::new());
+        None
+      }
+      Some(_) => self.shows_request(),
+    }
+  }
+
+  pub fn set_shows(&mut self, shows: Vec<Show>) {
+    self.error = None;
+    self.shows = shows;
+    self.reset_show_list();
+  }
+
+  pub fn set_event(&mut self, event: Event) {
+    self.error = None;
+    self.event = Some(event);
+  }
+
+  pub fn set_error(&mut self, error: Error) {
+    self.error = Some(error.to_string());
+  }
+
+  pub fn date(&self) -> Option<NaiveDate> {
+    self.dates.get(self.day).copied()
+  }
+
+  pub fn area_name(&self) -> Option<&str> {
+    self
+      .areas
+      .iter()
+      .find(|area| Some(area.id) == self.area)
+      .map(|area| area.name.as_str())
+  }
+
+  pub fn matches(&self, show: &Show) -> bool {
+    let search = self.search.to_lowercase();
+    let found = [
+      &show.title,
+      &show.original_title,
+      &show.genres,
+      &show.theatre,
+    ]
+    .iter()
+    .any(|field| field.to_lowercase().contains(&search));
+    found
+      && self
+        .presentation
+        .as_ref()
+        .is_none_or(|presentation| show.presentation_method == *presentation)
+  }
+
+  /// The shows that pass the search and presentation filters.
+  pub fn visible_shows(&self) -> Vec<&Show> {
+    self
+      .shows
+      .iter()
+      .filter(|show| self.matches(show))
+      .collect()
+  }
+
+  pub fn selected_show(&self) -> Option<&Show> {
+    let selected = self.show_list.selected()?;
+    self.visible_shows().into_iter().nth(selected)
+  }
+
+  /// The presentation methods of the day's shows, in order.
+  pub fn presentations(&self) -> Vec<&str> {
+    let mut presentations: Vec<&str> = self
+      .shows
+      .iter()
+      .map(|show| show.presentation_method.as_str())
+      .filter(|presentation| !presentation.is_empty())
+      .collect();
+    presentations.sort();
+    presentations.dedup();
+    presentations
+  }
+
+  pub fn on_key(&mut self, key: KeyEvent) -> Option<Request> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+      self.quit = true;
+      return None;
+    }
+    if self.searching {
+      match key.code {
+        KeyCode::Char(c) => self.search.push(c),
+        KeyCode::Backspace => {
+          self.search.pop();
+        }
+        KeyCode::Enter | KeyCode::Esc => self.searching = false,
+        _ => return None,
+      }
+      self.reset_show_list();
+      return None;
+    }
+    match key.code {
+      KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+      KeyCode::Tab | KeyCode::BackTab => {
+        self.focus = match self.focus {
+          Focus::Areas => Focus::Shows,
+          Focus::Shows => Focus::Areas,
+        }
+      }
+      KeyCode::Left | KeyCode::Char('h') if self.day > 0 => {
+        self.day -= 1;
+        return self.shows_request();
+      }
+      KeyCode::Right | KeyCode::Char('l') if self.day + 1 < self.dates.len() => {
+        self.day += 1;
+        return self.shows_request();
+      }
+      KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+      KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+      KeyCode::PageUp => self.move_selection(-(PAGE as isize)),
+      KeyCode::PageDown => self.move_selection(PAGE as isize),
+      KeyCode::Home | KeyCode::Char('g') => self.move_selection(isize::MIN),
+      KeyCode::End | KeyCode::Char('G') => self.move_selection(isize::MAX),
+      KeyCode::Enter => {
+        return match self.focus {
+          Focus::Areas => {
+            self.focus = Focus::Shows;
+            self.select_area()
+          }
+          Focus::Shows => {
+            let event = self.selected_show()?.event_id;
+            match &self.event {
+              Some(loaded) if loaded.id == event => None,
+              _ => Some(Request::Event(event)),
+            }
+          }
+        }
+      }
+      KeyCode::Char('/') => {
+        self.focus = Focus::Shows;
+        self.searching = true;
+      }
+      KeyCode::Char('p') => {
+        let presentations = self.presentations();
+        let next = match &self.presentation {
+          None => presentations.first(),
+          Some(current) => presentations
+            .iter()
+            .skip_while(|presentation| *presentation != current)
+            .nth(1),
+        };
+        self.presentation = next.map(|presentation| presentation.to_string());
+        self.reset_show_list();
+      }
+      KeyCode::Char('c') => {
+        self.search.clear();
+        self.presentation = None;
+        self.reset_show_list();
+      }
+      _ => {}
+    }
+    None
+  }
+
+  fn select_area(&mut self) -> Option<Request> {
+    let area = self.areas.get(self.area_list.selected()?)?.id;
+    self.area = Some(area);
+    Some(Request::Dates(area))
+  }
+
+  fn shows_request(&self) -> Option<Request> {
+    Some(Request::Shows(ShowFilter {
+      area: self.area,
+      date: Some(self.date()?),
+      ..ShowFilter::default()
+    }))
+  }
+
+  fn reset_show_list(&mut self) {
+    let visible = self.visible_shows().len();
+    self.show_list = ListState::default();
+    if visible > 0 {
+      self.show_list.select(Some(0));
+    }
+  }
+
+  fn move_selection(&mut self, by: isize) {
+    let len = match self.focus {
+      Focus::Areas => self.areas.len(),
+      Focus::Shows => self.visible_shows().len(),
+    };
+    let list = match self.focus {
+      Focus::Areas => &mut self.area_list,
+      Focus::Shows => &mut self.show_list,
+    };
+    if len == 0 {
+      return;
+    }
+    let selected = list.selected().unwrap_or_default() as isize;
+    let selected = selected.saturating_add(by).clamp(0, len as isize - 1);
+    list.select(Some(selected as usize));
+  }
+}
@@ -0,0 +1,12 @@
+//! A terminal UI for browsing showtimes. [`App`] holds the state and reacts
+//! to keys, [`draw`] renders it. Neither does any I/O, so the UI can be
+//! driven by any client and rendered to a headless backend in tests. [`load`]
+//! fetches what the app asks for from a [`Loader`], the client or fixtures.
+
+pub mod app;
+pub mod load;
+pub mod ui;
+
+pub use app::{App, Focus, Request};
+pub use load::{load, Loader};
+pub use ui::draw;
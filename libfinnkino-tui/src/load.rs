@@ -0,0 +1,65 @@
+use std::future::{self, Future};
+use std::vec::Vec;
+
+use libfinnkino_core::finnkino::{Error, Event, Show};
+use libfinnkino_core::fixtures::Fixtures;
+use libfinnkino_core::id::{AreaId, EventId};
+use libfinnkino_core::schedule::ShowFilter;
+use libfinnkino_core::time::LocalDateTime;
+
+use crate::app::{App, Request};
+
+/// Where the data of a [`Request`] comes from.
+pub trait Loader {
+  fn schedule_dates(&self, area: AreaId)
+    -> impl Future<Output = Result<Vec<LocalDateTime>, Error>>;
+  fn shows(&self, filter: &ShowFilter) -> impl Future<Output = Result<Vec<Show>, Error>>;
+  fn event(&self, id: EventId) -> impl Future<Output = Result<Event, Error>>;
+}
+
+impl Loader for Fixtures {
+  fn schedule_dates(
+    &self,
+    area: AreaId,
+  ) -> impl Future<Output = Result<Vec<LocalDateTime>, Error>> {
+    future::ready(Fixtures::schedule_dates(self, Some(area)))
+  }
+
+  fn shows(&self, filter: &ShowFilter) -> impl Future<Output = Result<Vec<Show>, Error>> {
+    future::ready(Fixtures::shows(self, filter))
+  }
+
+  fn event(&self, id: EventId) -> impl Future<Output = Result<Event, Error>> {
+    future::ready(Fixtures::event(self, id))
+  }
+}
+
+/// Loads what the app asks for, and whatever that in turn asks for.
+pub async fn load(app: &mut App, loader: &impl Loader, request: Option<Request>) {
+  let mut next = request;
+  while let Some(request) = next.take() {
+    next = match request {
+      Request::Dates(area) => match loader.schedule_dates(area).await {
+        Ok(dates) => app.set_dates(dates),
+        Err(error) => {
+          app.set_error(error);
+          None
+        }
+      },
+      Request::Shows(filter) => {
+        match loader.shows(&filter).await {
+          Ok(shows) => app.set_shows(shows),
+          Err(error) => app.set_error(error),
+        }
+        None
+      }
+      Request::Event(id) => {
+        match loader.event(id).await {
+          Ok(event) => app.set_event(event),
+          Err(error) => app.set_error(error),
+        }
+        None
+      }
+    }
+  }
+}
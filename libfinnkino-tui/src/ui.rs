@@ -0,0 +1,337 @@
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, Paragraph, Tabs, Wrap};
+use ratatui::Frame;
+use std::vec::Vec;
+
+use libfinnkino_core::finnkino::{Event, Show};
+use libfinnkino_core::schedule::TIME_FORMAT;
+
+use crate::app::{App, Focus};
+
+const DAY_FORMAT: &str = "%a %d.%m.";
+const HELP: &str =
+  "q quit  tab switch pane  ←/→ day  ↑/↓ move  enter select  / search  p presentation  c clear";
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+  let [tabs, body, status] = Layout::vertical([
+    Constraint::Length(3),
+    Constraint::Min(0),
+    Constraint::Length(1),
+  ])
+  .areas(frame.area());
+  let [areas, shows, detail] = Layout::horizontal([
+    Constraint::Length(28),
+    Constraint::Fill(3),
+    Constraint::Fill(2),
+  ])
+  .areas(body);
+
+  draw_tabs(frame, app, tabs);
+  draw_areas(frame, app, areas);
+  draw_shows(frame, app, shows);
+  draw_detail(frame, app, detail);
+  draw_status(frame, app, status);
+}
+
+fn pane(title: String, focused: bool) -> Block<'static> {
+  let block = Block::bordered().title(title);
+  match focused {
+    true => block.border_style(Style::new().fg(Color::Yellow)),
+    false => block,
+  }
+}
+
+fn highlight(focused: bool) -> Style {
+  match focused {
+    true => Style::new().add_modifier(Modifier::REVERSED),
+    false => Style::new().add_modifier(Modifier::BOLD),
+  }
+}
+
+fn draw_tabs(frame: &mut Frame, app: &App, area: Rect) {
+  let title = match app.area_name() {
+    Some(name) => format!(" Finnkino · {} ", name),
+    None => " Finnkino ".to_string(),
+  };
+  let block = Block::bordered().title(title);
+  if app.dates.is_empty() {
+    frame.render_widget(Paragraph::new("No shows").block(block), area);
+    return;
+  }
+  let days = app
+    .dates
+    .iter()
+    .map(|date| date.format(DAY_FORMAT).to_string());
+  let tabs = Tabs::new(days)
+    .select(app.day)
+    .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+    .block(block);
+  frame.render_widget(tabs, area);
+}
+
+fn draw_areas(frame: &mut Frame, app: &mut App, area: Rect) {
+  let focused = app.focus == Focus::Areas;
+  let items: Vec<ListItem> = app
+    .areas
+    .iter()
+    .map(|theatre_area| ListItem::new(theatre_area.name.as_str()))
+    .collect();
+  let list = List::new(items)
+    .block(pane(" Areas ".to_string(), focused))
+    .highlight_style(highlight(focused))
+    .highlight_symbol("> ");
+  frame.render_stateful_widget(list, area, &mut app.area_list);
+}
+
+fn draw_shows(frame: &mut Frame, app: &mut App, area: Rect) {
+  let focused = app.focus == Focus::Shows;
+  let mut title = format!(" Shows {}/{} ", app.visible_shows().len(), app.shows.len());
+  if app.searching || !app.search.is_empty() {
+    title.push_str(&format!("· /{} ", app.search));
+  }
+  if let Some(presentation) = &app.presentation {
+    title.push_str(&format!("· {} ", presentation));
+  }
+  let items: Vec<ListItem> = app
+    .visible_shows()
+    .into_iter()
+    .map(|show| {
+      ListItem::new(Line::from(vec![
+        Span::raw(show.show_start.format(TIME_FORMAT).to_string()).bold(),
+        Span::raw("  "),
+        Span::raw(show.title.clone()),
+        Span::raw("  "),
+        Span::raw(show.presentation_method.clone()).dim(),
+      ]))
+    })
+    .collect();
+  let list = List::new(items)
+    .block(pane(title, focused))
+    .highlight_style(highlight(focused))
+    .highlight_symbol("> ");
+  frame.render_stateful_widget(list, area, &mut app.show_list);
+}
+
+fn show_lines(show: &Show) -> Vec<Line<'static>> {
+  let mut place = show.theatre.clone();
+  if !show.auditorium.is_empty() {
+    place.push_str(&format!(", {}", show.auditorium));
+  }
+  let mut languages = vec![show.presentation_method.clone()];
+  if let Some(language) = &show.spoken_language {
+    languages.push(language.name_in_language.clone());
+  }
+  let subtitles: Vec<&str> = show
+    .subtitle_languages()
+    .iter()
+    .map(|language| language.name_in_language.as_str())
+    .collect();
+  if !subtitles.is_empty() {
+    languages.push(format!("subtitles {}", subtitles.join(", ")));
+  }
+  vec![
+    Line::from(show.title.clone()).bold(),
+    Line::from(format!(
+      "{}–{}  {}",
+      show.show_start.format(TIME_FORMAT),
+      show.show_end.format(TIME_FORMAT),
+      place
+    )),
+    Line::from(
+      languages
+        .into_iter()
+        .filter(|language| !language.is_empty())
+        .collect::<Vec<_>>()
+        .join(" · "),
+    ),
+  ]
+}
+
+fn event_lines(event: &Event) -> Vec<Line<'static>> {
+  let mut lines = Vec::new();
+  if event.original_title != event.title {
+    lines.push(Line::from(event.original_title.clone()).italic());
+  }
+  let facts: Vec<String> = [
+    event.production_year.clone(),
+    match event.length_in_minutes.is_empty() {
+      true => String::new(),
+      false => format!("{} min", event.length_in_minutes),
+    },
+    match event.rating_label.is_empty() {
+      true => String::new(),
+      false => format!("K{}", event.rating_label),
+    },
+    event.genres().join(", "),
+  ]
+  .into_iter()
+  .filter(|fact| !fact.is_empty())
+  .collect();
+  lines.push(Line::from(facts.join(" · ")));
+  let people = [
+    ("Director", &event.directors.directors),
+    ("Cast", &event.cast.actors),
+  ];
+  for (label, persons) in people {
+    if !persons.is_empty() {
+      let names: Vec<String> = persons.iter().map(|person| person.full_name()).collect();
+      lines.push(Line::from(vec![
+        Span::raw(format!("{}: ", label)).dim(),
+        Span::raw(names.join(", ")),
+      ]));
+    }
+  }
+  lines.push(Line::default());
+  lines.push(Line::from(event.synopsis.clone()));
+  lines
+}
+
+fn draw_detail(frame: &mut Frame, app: &App, area: Rect) {
+  let block = pane(" Details ".to_string(), false);
+  let show = match app.selected_show() {
+    None => {
+      frame.render_widget(Paragraph::new("No show selected").block(block), area);
+      return;
+    }
+    Some(show) => show,
+  };
+  let mut lines = show_lines(show);
+  lines.push(Line::default());
+  match &app.event {
+    Some(event) if event.id == show.event_id => lines.extend(event_lines(event)),
+    _ => lines.push(Line::from("Press enter for the film's details").dim()),
+  }
+  let paragraph = Paragraph::new(lines).block(block).wrap(Wrap { trim: true });
+  frame.render_widget(paragraph, area);
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+  let line = match (&app.error, app.searching) {
+    (_, true) => Line::from(format!("/{}_", app.search)),
+    (Some(error), false) => Line::from(error.clone()).fg(Color::Red),
+    (None, false) => Line::from(HELP).dim(),
+  };
+  frame.render_widget(Paragraph::new(line), area);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::app::Request;
+  use libfinnkino_core::fixtures::Fixtures;
+  use libfinnkino_core::id::EventId;
+  use ratatui::backend::TestBackend;
+  use ratatui::crossterm::event::{KeyCode, KeyEvent};
+  use ratatui::Terminal;
+
+  fn fixtures() -> Fixtures {
+    Fixtures::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../fixtures"))
+  }
+
+  /// Loads what the app asks for from the fixtures, like the client does.
+  fn load(app: &mut App, request: Option<Request>) {
+    futures::executor::block_on(crate::load(app, &fixtures(), request));
+  }
+
+  fn press(app: &mut App, codes: &[KeyCode]) {
+    for code in codes {
+      let request = app.on_key(KeyEvent::from(*code));
+      load(app, request);
+    }
+  }
+
+  fn render(app: &mut App) -> Terminal<TestBackend> {
+    let mut terminal = Terminal::new(TestBackend::new(120, 20)).unwrap();
+    terminal.draw(|frame| draw(frame, app)).unwrap();
+    terminal
+  }
+
+  fn app() -> App {
+    let mut app = App::default();
+    let request = app.set_areas(fixtures().areas().unwrap());
+    load(&mut app, request);
+    app
+  }
+
+  #[test]
+  fn test_browse_area() {
+    let mut app = app();
+    // Tennispalatsi is the third area.
+    press(&mut app, &[KeyCode::Down, KeyCode::Down, KeyCode::Enter]);
+
+    assert_eq!(Focus::Shows, app.focus);
+    assert_eq!(2, app.shows.len());
+    insta::assert_snapshot!(render(&mut app).backend());
+  }
+
+  #[test]
+  fn test_event_detail() {
+    let mut app = app();
+    press(
+      &mut app,
+      &[KeyCode::Down, KeyCode::Down, KeyCode::Enter, KeyCode::Down],
+    );
+    press(&mut app, &[KeyCode::Enter]);
+
+    assert_eq!(
+      Some(EventId(302000)),
+      app.event.as_ref().map(|event| event.id)
+    );
+    assert_eq!(None, app.on_key(KeyEvent::from(KeyCode::Enter)));
+    insta::assert_snapshot!(render(&mut app).backend());
+  }
+
+  #[test]
+  fn test_day_tabs_and_filters() {
+    let mut app = app();
+    press(&mut app, &[KeyCode::Down, KeyCode::Down, KeyCode::Enter]);
+    press(&mut app, &[KeyCode::Right]);
+
+    assert_eq!("2022-08-21", app.date().unwrap().to_string());
+    assert_eq!(1, app.shows.len());
+    press(&mut app, &[KeyCode::Left, KeyCode::Char('p')]);
+    assert_eq!(Some("2D".to_string()), app.presentation);
+    press(
+      &mut app,
+      &[
+        KeyCode::Char('/'),
+        KeyCode::Char('e'),
+        KeyCode::Char('l'),
+        KeyCode::Char('v'),
+      ],
+    );
+
+    assert!(app.searching);
+    assert_eq!(
+      vec!["Elvis"],
+      app
+        .visible_shows()
+        .iter()
+        .map(|show| show.title.as_str())
+        .collect::<Vec<_>>()
+    );
+    insta::assert_snapshot!(render(&mut app).backend());
+    press(&mut app, &[KeyCode::Enter, KeyCode::Char('c')]);
+    assert!(!app.searching);
+    assert_eq!(2, app.visible_shows().len());
+  }
+
+  #[test]
+  fn test_error_is_shown() {
+    let mut app = App::default();
+    let request = app.set_areas(fixtures().areas().unwrap());
+    app.set_error(Fixtures::new("missing").schedule_dates(None).unwrap_err());
+
+    assert_eq!(
+      Some(Request::Dates(libfinnkino_core::id::AreaId(1029))),
+      request
+    );
+    let terminal = render(&mut app);
+    let status = (0..120)
+      .map(|x| terminal.backend().buffer()[(x, 19)].symbol().to_string())
+      .collect::<String>();
+    assert!(status.starts_with("Fixture error: missing/ScheduleDates.xml"));
+  }
+}